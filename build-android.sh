@@ -133,6 +133,28 @@ if [ -f "apk_icon_xxxhdpi.png" ]; then
     echo "✅ 图标覆盖完成"
 fi
 
+# 交叉编译好的 libheif（HEIC/AVIF 解码）：
+# $LIBHEIF_ANDROID_DIR/<目标三元组>/lib 下需有 libheif.so 及其插件依赖和 pkgconfig/libheif.pc
+if [ -z "$LIBHEIF_ANDROID_DIR" ]; then
+    echo "❌ 未设置 LIBHEIF_ANDROID_DIR，发布构建需要为各目标架构交叉编译的 libheif（见 Android构建指南.md）"
+    exit 1
+fi
+export PKG_CONFIG_ALLOW_CROSS=1
+JNI_DIR="src-tauri/gen/android/app/src/main/jniLibs"
+for pair in aarch64-linux-android:arm64-v8a armv7-linux-androideabi:armeabi-v7a i686-linux-android:x86 x86_64-linux-android:x86_64; do
+    triple="${pair%%:*}"
+    abi="${pair##*:}"
+    prefix="$LIBHEIF_ANDROID_DIR/$triple"
+    if [ ! -f "$prefix/lib/pkgconfig/libheif.pc" ]; then
+        echo "❌ 缺少 $triple 的 libheif: $prefix/lib/pkgconfig/libheif.pc"
+        exit 1
+    fi
+    export "PKG_CONFIG_PATH_${triple//-/_}=$prefix/lib/pkgconfig"
+    mkdir -p "$JNI_DIR/$abi"
+    cp "$prefix"/lib/*.so "$JNI_DIR/$abi/"
+done
+echo "✅ libheif 已就绪"
+
 # 构建前端
 echo "📦 构建前端..."
 if ! build_frontend; then
@@ -144,7 +166,7 @@ echo "🔨 构建发布版 APK（使用正式签名）..."
 echo "📝 签名文件: release.keystore"
 
 # 使用 npx pnpm 避免 pnpm 未全局安装的问题
-npx pnpm tauri android build --apk true
+npx pnpm tauri android build --apk true --features heif
 
 if [ $? -ne 0 ]; then
     echo "❌ 发布版本构建失败"
@@ -12,6 +12,9 @@ echo "🚀 开始打包靓仔的app..."
 if ! check_environment; then
     exit 1
 fi
+if ! check_libheif; then
+    exit 1
+fi
 
 # 安装依赖
 if ! install_dependencies; then
@@ -23,9 +26,9 @@ if ! build_frontend; then
     exit 1
 fi
 
-# 打包应用
+# 打包应用（开启 heif 特性）
 echo "📱 开始打包桌面应用..."
-npm run tauri build
+npm run tauri:build
 
 if [ $? -eq 0 ]; then
     echo "🎉 打包成功！"
@@ -60,6 +60,17 @@ install_dependencies() {
   return 0
 }
 
+# 检查 libheif（发布构建开启 heif 特性，用于 HEIC/AVIF 解码）
+check_libheif() {
+  if ! pkg-config --atleast-version=1.18 libheif 2>/dev/null; then
+    echo -e "${RED}❌ 未找到 libheif >= 1.18，发布构建需要它来解码 HEIC/AVIF${NC}"
+    echo -e "${YELLOW}macOS: brew install libheif；Debian/Ubuntu 24.04+: apt install libheif-dev libheif-plugin-libde265 libheif-plugin-dav1d${NC}"
+    return 1
+  fi
+  echo -e "${GREEN}✅ libheif $(pkg-config --modversion libheif)${NC}"
+  return 0
+}
+
 # 构建前端
 build_frontend() {
   echo -e "${BLUE}🔨 构建前端应用...${NC}"
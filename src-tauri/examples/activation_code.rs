@@ -0,0 +1,92 @@
+//! 激活码生成工具（仅供发放方使用）
+//!
+//!   cargo run --example activation_code -- <功能,逗号分隔> <到期日期 YYYY-MM-DD | never> [设备标识]
+//!
+//! 功能名：videoTools、profileEditing、exportScope。
+//! 必须设置环境变量 ACTIVATION_CODE_SECRET，且与构建应用时的值一致；
+//! 调试构建未设置该变量时应用使用开发密钥 `renteng-sales/activation/dev`。
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{env, process};
+
+const CODE_VERSION: u8 = 1;
+const FLAG_DEVICE_BOUND: u8 = 0x01;
+// 与应用中功能位图的位序号一致
+const FEATURES: [(&str, u8); 3] = [("videoTools", 0), ("profileEditing", 1), ("exportScope", 2)];
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn feature_bits(list: &str) -> u8 {
+    list.split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            FEATURES
+                .iter()
+                .find(|(known, _)| *known == name.trim())
+                .map(|(_, bit)| 1u8 << bit)
+                .unwrap_or_else(|| fail(&format!("未知功能: {}", name)))
+        })
+        .fold(0, |bits, bit| bits | bit)
+}
+
+/// 以 2020-01-01 起的天数表示，到期日当天 00:00 (UTC+8) 失效
+fn expiry_days(date: &str) -> u16 {
+    if date == "never" {
+        return 0;
+    }
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .unwrap_or_else(|_| fail("到期日期格式应为 YYYY-MM-DD"));
+    let epoch = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap_or_default();
+    let days = (date - epoch).num_days();
+    u16::try_from(days)
+        .ok()
+        .filter(|days| *days > 0)
+        .unwrap_or_else(|| fail("到期日期超出范围"))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 3 {
+        fail("用法: activation_code <功能,逗号分隔> <YYYY-MM-DD | never> [设备标识]");
+    }
+    let device_id = args.get(2).map(String::as_str);
+
+    let flags = if device_id.is_some() {
+        FLAG_DEVICE_BOUND
+    } else {
+        0
+    };
+    let days = expiry_days(&args[1]).to_be_bytes();
+    let payload = [
+        (CODE_VERSION << 4) | flags,
+        feature_bits(&args[0]),
+        days[0],
+        days[1],
+    ];
+
+    let secret = env::var("ACTIVATION_CODE_SECRET")
+        .unwrap_or_else(|_| fail("请设置环境变量 ACTIVATION_CODE_SECRET（与构建应用时一致）"));
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap_or_else(|_| fail("密钥无效"));
+    mac.update(b"activation:");
+    mac.update(&payload);
+    if let Some(device_id) = device_id {
+        mac.update(device_id.as_bytes());
+    }
+    let digest = mac.finalize().into_bytes();
+
+    let mut bytes = payload.to_vec();
+    bytes.extend_from_slice(&digest[..6]);
+    let code = BASE32_NOPAD.encode(&bytes);
+    let grouped: Vec<&str> = code
+        .as_bytes()
+        .chunks(4)
+        .filter_map(|c| std::str::from_utf8(c).ok())
+        .collect();
+    println!("{}", grouped.join("-"));
+}
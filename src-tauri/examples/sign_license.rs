@@ -0,0 +1,67 @@
+//! 许可证签发工具（仅供发放方使用，私钥不要提交到仓库）
+//!
+//! 生成密钥对：
+//!   cargo run --example sign_license -- keygen
+//! 签发许可证（payload.json 为许可证内容，输出可直接导入的许可证文件）：
+//!   cargo run --example sign_license -- sign <私钥文件> <payload.json> > license.lic
+//!
+//! payload.json 示例：
+//!   {"version":1,"licenseId":"L-0001","licensee":"某某商行","deviceId":"<设备标识>",
+//!    "issuedAt":1767196800,"expiresAt":1798732800,"features":["videoTools"]}
+
+use ed25519_dalek::{Signer, SigningKey};
+use std::{env, fs, process};
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn keygen() {
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).unwrap_or_else(|e| fail(&format!("生成随机数失败: {}", e)));
+    let key = SigningKey::from_bytes(&seed);
+    println!("私钥（妥善保管）: {}", hex::encode(seed));
+    println!(
+        "公钥（构建时设置 LICENSE_PUBLIC_KEY）: {}",
+        hex::encode(key.verifying_key().to_bytes())
+    );
+}
+
+fn sign(key_path: &str, payload_path: &str) {
+    let seed: [u8; 32] = fs::read_to_string(key_path)
+        .ok()
+        .and_then(|content| hex::decode(content.trim()).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or_else(|| fail("私钥文件应为 64 位十六进制字符串"));
+    let raw = fs::read_to_string(payload_path)
+        .unwrap_or_else(|e| fail(&format!("读取 payload 失败: {}", e)));
+    // 统一为紧凑 JSON，签名对象即写入文件的字符串本身
+    let payload: serde_json::Value = serde_json::from_str(&raw)
+        .unwrap_or_else(|e| fail(&format!("payload 不是合法 JSON: {}", e)));
+    let payload = payload.to_string();
+
+    let signature = SigningKey::from_bytes(&seed).sign(payload.as_bytes());
+    let file = serde_json::json!({
+        "payload": payload,
+        "signature": hex::encode(signature.to_bytes()),
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&file).unwrap_or_default()
+    );
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["keygen"] => keygen(),
+        ["sign", key_path, payload_path] => sign(key_path, payload_path),
+        _ => fail("用法: sign_license keygen | sign_license sign <私钥文件> <payload.json>"),
+    }
+}
@@ -1,64 +1,325 @@
+use crate::activation::{self, Activation};
+use crate::clock_guard;
+use crate::entitlements::{self, Entitlements, Feature};
+use crate::install_stamp::{self, InstallStamp, InstallState};
+use crate::license::{self, License};
 use serde::Serialize;
-use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Manager, Runtime};
+use tauri::{AppHandle, Runtime};
 
-// 允许安装时间窗口截止到 2026-03-13 23:59:59 (UTC+8)，Unix 时间戳：1773417599
+// 未导入许可证时沿用的旧规则：允许安装时间窗口截止到 2026-03-13 23:59:59 (UTC+8)，Unix 时间戳：1773417599
 const THRESHOLD_TIME: u64 = 1773417599;
-// 存储安装时间的文件名
-const INSTALL_TIME_FILENAME: &str = ".install_time";
+// 到期前多少天开始提醒
+const EXPIRY_WARNING_DAYS: i64 = 7;
+// 到期后仍保留全部功能的宽限天数，之后只能只读访问业务数据
+const GRACE_PERIOD_DAYS: u64 = 7;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
 
 #[derive(Serialize)]
 pub struct AccessStatus {
     pub allowed: bool,
     pub install_time: u64,
+    /// 安装时间记录的校验结果，`tampered` 表示记录被删改
+    pub install_state: InstallState,
     pub threshold_time: u64,
+    /// 已导入且校验通过的许可证
+    pub license: Option<License>,
+    /// 已导入的许可证校验失败的原因（签名无效、不属于本设备等）
+    pub license_error: Option<String>,
+    /// 已激活且校验通过的激活码
+    pub activation: Option<Activation>,
+    pub activation_error: Option<String>,
+    /// 系统时钟是否可信：回拨超过容差或时间记录被删改时为 false
+    pub clock_trusted: bool,
+    /// 此前记录到的最晚时间，有效期按它与系统时间中较晚者判断
+    pub last_seen_time: u64,
+    /// 系统时间相对 last_seen_time 倒退的秒数
+    pub clock_rollback_secs: u64,
+    pub clock_tolerance_secs: u64,
+    /// 许可证与激活码中最晚的到期时间，为空表示永久有效或未授权
+    pub expires_at: Option<u64>,
+    /// 距到期的天数（不足一天按一天计），已到期为 0 或负数
+    pub days_remaining: Option<i64>,
+    /// 到期前 7 天内为 true，前端据此提醒续期
+    pub expiry_warning: bool,
+    pub grace_period_days: u64,
+    /// 已到期但仍在宽限期内，功能照常可用
+    pub in_grace_period: bool,
+    /// 宽限期已过：仍可进入应用查看业务数据，但不能修改，且不开通任何功能
+    pub read_only: bool,
+    /// 已开通的功能，无法访问应用或只读时为空
+    pub entitlements: Entitlements,
 }
 
-pub fn get_install_time<R: Runtime>(app: &AppHandle<R>) -> Result<u64, String> {
-    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+/// 一项授权（许可证或激活码）
+struct Grant {
+    features: Vec<Feature>,
+    expires_at: Option<u64>,
+}
 
-    if !data_dir.exists() {
-        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+impl Grant {
+    /// 未到期或仍在宽限期内
+    fn is_usable(&self, now: u64) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| now < expires_at + GRACE_PERIOD_DAYS * SECS_PER_DAY)
     }
+}
 
-    let file_path = data_dir.join(INSTALL_TIME_FILENAME);
+/// 由安装时间记录与授权得出的访问结论
+struct Decision {
+    allowed: bool,
+    read_only: bool,
+    expires_at: Option<u64>,
+    days_remaining: Option<i64>,
+    expiry_warning: bool,
+    in_grace_period: bool,
+    entitlements: Entitlements,
+}
 
-    if file_path.exists() {
-        let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
-        let time = content
-            .trim()
-            .parse::<u64>()
-            .map_err(|_| "Invalid timestamp format".to_string())?;
-        Ok(time)
+/// 权限判断逻辑：
+/// 有许可证或激活码时以其为准，到期后经过宽限期转为只读，不会直接拦截；
+/// 没有有效授权时（包括已导入但校验失败）沿用安装时间规则：
+/// install_time <= threshold_time 允许访问，安装时间记录被篡改时直接禁止访问
+fn decide(stamp: &InstallStamp, grants: &[Grant], now: u64) -> Decision {
+    let expires_at = if grants.is_empty() || grants.iter().any(|g| g.expires_at.is_none()) {
+        None
     } else {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| e.to_string())?;
-        let time = since_the_epoch.as_secs();
-
-        // 尝试写入文件
-        if let Err(e) = fs::write(&file_path, time.to_string()) {
-            return Err(format!("Failed to write install time: {}", e));
-        }
+        grants.iter().filter_map(|g| g.expires_at).max()
+    };
+    let read_only = !grants.is_empty() && !grants.iter().any(|g| g.is_usable(now));
+    let allowed = if !grants.is_empty() {
+        true
+    } else {
+        stamp.state != InstallState::Tampered && stamp.install_time <= THRESHOLD_TIME
+    };
+
+    let days_remaining = expires_at.map(|expires_at| days_until(expires_at, now));
+    let expiry_warning = days_remaining.is_some_and(|days| days > 0 && days <= EXPIRY_WARNING_DAYS);
+    let in_grace_period = !read_only && expires_at.is_some_and(|expires_at| expires_at <= now);
+
+    let entitlements = if allowed {
+        Entitlements::from_features(
+            grants
+                .iter()
+                .filter(|grant| grant.is_usable(now))
+                .flat_map(|grant| grant.features.iter().copied()),
+        )
+    } else {
+        Entitlements::default()
+    };
 
-        Ok(time)
+    Decision {
+        allowed,
+        read_only,
+        expires_at,
+        days_remaining,
+        expiry_warning,
+        in_grace_period,
+        entitlements,
     }
 }
 
-#[tauri::command]
-pub async fn check_access_status<R: Runtime>(app: AppHandle<R>) -> Result<AccessStatus, String> {
-    let install_time = get_install_time(&app)?;
+fn days_until(expires_at: u64, now: u64) -> i64 {
+    let diff = expires_at as i64 - now as i64;
+    if diff > 0 {
+        (diff + SECS_PER_DAY as i64 - 1) / SECS_PER_DAY as i64
+    } else {
+        diff / SECS_PER_DAY as i64
+    }
+}
 
-    // 权限判断逻辑：
-    // 若 install_time > threshold_time：禁止访问
-    // 若 install_time <= threshold_time：允许访问
-    let allowed = install_time <= THRESHOLD_TIME;
+/// 计算访问状态，供 `check_access_status` 与 `get_entitlements` 共用
+pub fn evaluate_access<R: Runtime>(app: &AppHandle<R>) -> Result<AccessStatus, String> {
+    let stamp = install_stamp::load_install_stamp(app)?;
+    let clock = clock_guard::check_clock(app, stamp.install_time)?;
+    // 有效期使用防回拨后的时间，把系统时间调回过去无法延长有效期
+    let now = clock.effective_now();
+
+    let (license, license_error) = match license::load_license(app) {
+        Ok(license) => (license, None),
+        Err(e) => (None, Some(e)),
+    };
+    let (activation, activation_error) = match activation::load_activation(app) {
+        Ok(activation) => (activation, None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let mut grants = Vec::new();
+    if let Some(license) = &license {
+        grants.push(Grant {
+            features: entitlements::license_features(license),
+            expires_at: license.expires_at,
+        });
+    }
+    if let Some(activation) = &activation {
+        grants.push(Grant {
+            features: activation.features.clone(),
+            expires_at: activation.expires_at,
+        });
+    }
+
+    let decision = decide(&stamp, &grants, now);
+
+    let clock_tolerance_secs = license
+        .as_ref()
+        .and_then(|license| license.clock_tolerance_secs)
+        .unwrap_or(clock_guard::DEFAULT_ROLLBACK_TOLERANCE_SECS);
 
     Ok(AccessStatus {
-        allowed,
-        install_time,
+        allowed: decision.allowed,
+        install_time: stamp.install_time,
+        install_state: stamp.state,
         threshold_time: THRESHOLD_TIME,
+        license,
+        license_error,
+        activation,
+        activation_error,
+        clock_trusted: clock.is_trusted(clock_tolerance_secs),
+        last_seen_time: clock.last_seen,
+        clock_rollback_secs: clock.rollback_secs(),
+        clock_tolerance_secs,
+        expires_at: decision.expires_at,
+        days_remaining: decision.days_remaining,
+        expiry_warning: decision.expiry_warning,
+        grace_period_days: GRACE_PERIOD_DAYS,
+        in_grace_period: decision.in_grace_period,
+        read_only: decision.read_only,
+        entitlements: decision.entitlements,
     })
 }
+
+/// 后端写入业务数据前调用：授权到期进入只读模式，或无法访问应用时拒绝修改
+pub(crate) fn ensure_writable<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let status = evaluate_access(app)?;
+    if !status.allowed {
+        return Err("当前版本已停止服务，无法保存修改".to_string());
+    }
+    if status.read_only {
+        return Err(
+            "授权已过期，当前为只读模式，无法保存修改。请输入新的激活码或导入许可证后再试。"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn check_access_status<R: Runtime>(app: AppHandle<R>) -> Result<AccessStatus, String> {
+    evaluate_access(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_800_000_000;
+    const DAY: u64 = SECS_PER_DAY;
+
+    fn stamp(install_time: u64, state: InstallState) -> InstallStamp {
+        InstallStamp {
+            install_time,
+            state,
+        }
+    }
+
+    fn grant(features: &[Feature], expires_at: Option<u64>) -> Grant {
+        Grant {
+            features: features.to_vec(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_install_time_rule_without_grants() {
+        let early = decide(&stamp(THRESHOLD_TIME, InstallState::Verified), &[], NOW);
+        assert!(early.allowed);
+        assert!(!early.read_only);
+        assert_eq!(early.expires_at, None);
+        assert!(early.entitlements.features.is_empty());
+
+        let late = decide(&stamp(THRESHOLD_TIME + 1, InstallState::FirstRun), &[], NOW);
+        assert!(!late.allowed);
+
+        let tampered = decide(&stamp(THRESHOLD_TIME - 1, InstallState::Tampered), &[], NOW);
+        assert!(!tampered.allowed);
+    }
+
+    #[test]
+    fn grants_override_the_install_time_rule() {
+        let late = stamp(THRESHOLD_TIME + 1, InstallState::Tampered);
+        let decision = decide(&late, &[grant(&[Feature::VideoTools], None)], NOW);
+        assert!(decision.allowed);
+        assert_eq!(decision.expires_at, None);
+        assert_eq!(decision.entitlements.features, vec![Feature::VideoTools]);
+    }
+
+    #[test]
+    fn warns_before_expiry_and_keeps_features_during_grace() {
+        let ok = stamp(0, InstallState::Verified);
+
+        let soon = decide(
+            &ok,
+            &[grant(&[Feature::ExportScope], Some(NOW + 3 * DAY - 10))],
+            NOW,
+        );
+        assert_eq!(soon.days_remaining, Some(3));
+        assert!(soon.expiry_warning);
+        assert!(!soon.in_grace_period);
+
+        let later = decide(&ok, &[grant(&[], Some(NOW + 30 * DAY))], NOW);
+        assert!(!later.expiry_warning);
+
+        let grace = decide(
+            &ok,
+            &[grant(&[Feature::ExportScope], Some(NOW - 2 * DAY))],
+            NOW,
+        );
+        assert!(grace.in_grace_period);
+        assert!(!grace.read_only);
+        assert_eq!(grace.days_remaining, Some(-2));
+        assert_eq!(grace.entitlements.features, vec![Feature::ExportScope]);
+    }
+
+    #[test]
+    fn turns_read_only_after_the_grace_period() {
+        let ok = stamp(0, InstallState::Verified);
+        let expired = NOW - GRACE_PERIOD_DAYS * DAY;
+        let decision = decide(&ok, &[grant(&[Feature::VideoTools], Some(expired))], NOW);
+        assert!(decision.allowed);
+        assert!(decision.read_only);
+        assert!(!decision.in_grace_period);
+        assert!(decision.entitlements.features.is_empty());
+
+        // 另一项授权仍可用时不进入只读，只开通可用授权中的功能
+        let decision = decide(
+            &ok,
+            &[
+                grant(&[Feature::VideoTools], Some(expired)),
+                grant(&[Feature::ProfileEditing], Some(NOW + 100 * DAY)),
+            ],
+            NOW,
+        );
+        assert!(!decision.read_only);
+        assert_eq!(decision.expires_at, Some(NOW + 100 * DAY));
+        assert_eq!(
+            decision.entitlements.features,
+            vec![Feature::ProfileEditing]
+        );
+    }
+
+    #[test]
+    fn grant_usability_ends_exactly_at_the_end_of_grace() {
+        let g = grant(&[], Some(NOW));
+        assert!(g.is_usable(NOW + GRACE_PERIOD_DAYS * DAY - 1));
+        assert!(!g.is_usable(NOW + GRACE_PERIOD_DAYS * DAY));
+        assert!(grant(&[], None).is_usable(u64::MAX / 2));
+    }
+
+    #[test]
+    fn counts_partial_days_as_whole_days() {
+        assert_eq!(days_until(NOW + 1, NOW), 1);
+        assert_eq!(days_until(NOW + DAY, NOW), 1);
+        assert_eq!(days_until(NOW + DAY + 1, NOW), 2);
+        assert_eq!(days_until(NOW, NOW), 0);
+        assert_eq!(days_until(NOW - DAY - 1, NOW), -1);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::entitlements::Feature;
+use crate::license;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use tauri::{AppHandle, Runtime};
+
+// 激活码校验密钥，发布构建必须通过环境变量 ACTIVATION_CODE_SECRET 提供，未设置时编译失败；
+// 密钥由发布负责人保管，生成激活码时使用同一密钥
+#[cfg(not(debug_assertions))]
+const CODE_SECRET: &str = env!(
+    "ACTIVATION_CODE_SECRET",
+    "发布构建需要设置环境变量 ACTIVATION_CODE_SECRET（激活码校验密钥）"
+);
+// 调试构建未设置时使用开发密钥，仅供本地测试
+#[cfg(debug_assertions)]
+const CODE_SECRET: &str = match option_env!("ACTIVATION_CODE_SECRET") {
+    Some(secret) => secret,
+    None => "renteng-sales/activation/dev",
+};
+// 已激活的激活码文件名
+const ACTIVATION_FILENAME: &str = "activation.code";
+// 激活码格式版本
+const CODE_VERSION: u8 = 1;
+// 有效期以天计，从 2020-01-01 00:00:00 (UTC+8) 起算
+const CODE_EPOCH: u64 = 1577808000;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+// 内容 4 字节 + 校验 6 字节，base32 编码后正好 16 个字符
+const PAYLOAD_LEN: usize = 4;
+const CHECKSUM_LEN: usize = 6;
+// 标志位：激活码绑定设备
+const FLAG_DEVICE_BOUND: u8 = 0x01;
+
+/// 已激活的激活码
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activation {
+    /// 规范化后的激活码（XXXX-XXXX-XXXX-XXXX）
+    pub code: String,
+    pub features: Vec<Feature>,
+    /// 到期时间（Unix 秒），为空表示永久有效
+    pub expires_at: Option<u64>,
+    pub device_bound: bool,
+}
+
+/// 激活码布局（10 字节）：
+/// `[版本(高 4 位) | 标志(低 4 位)] [功能位图] [到期天数 u16 BE，0 为永久] [HMAC-SHA256 前 6 字节]`。
+/// 绑定设备时设备标识参与校验但不占激活码长度。
+fn checksum(payload: &[u8], device_id: Option<&str>) -> [u8; CHECKSUM_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(CODE_SECRET.as_bytes())
+        .expect("HMAC 可以接受任意长度的密钥");
+    mac.update(b"activation:");
+    mac.update(payload);
+    if let Some(device_id) = device_id {
+        mac.update(device_id.as_bytes());
+    }
+    let digest = mac.finalize().into_bytes();
+    let mut out = [0u8; CHECKSUM_LEN];
+    out.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    out
+}
+
+/// 去掉分隔符并纠正容易看错的字符（0→O、1→I、8→B）
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| match c.to_ascii_uppercase() {
+            '0' => 'O',
+            '1' => 'I',
+            '8' => 'B',
+            c => c,
+        })
+        .collect()
+}
+
+fn format_code(normalized: &str) -> String {
+    normalized
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// 解析并校验激活码（不检查是否过期）
+pub fn decode_code(code: &str, device_id: &str) -> Result<Activation, String> {
+    let normalized = normalize(code);
+    let bytes = BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .map_err(|_| "激活码格式错误".to_string())?;
+    if bytes.len() != PAYLOAD_LEN + CHECKSUM_LEN {
+        return Err("激活码长度不正确".to_string());
+    }
+    let (payload, mac) = bytes.split_at(PAYLOAD_LEN);
+
+    let version = payload[0] >> 4;
+    if version != CODE_VERSION {
+        return Err(format!("不支持的激活码版本: {}", version));
+    }
+    let device_bound = payload[0] & FLAG_DEVICE_BOUND != 0;
+    let expected = checksum(payload, device_bound.then_some(device_id));
+    if mac != expected {
+        return Err(if device_bound {
+            "激活码无效或不属于本设备".to_string()
+        } else {
+            "激活码无效".to_string()
+        });
+    }
+
+    let days = u16::from_be_bytes([payload[2], payload[3]]) as u64;
+    Ok(Activation {
+        code: format_code(&normalized),
+        features: Feature::from_bits(payload[1]),
+        expires_at: (days > 0).then_some(CODE_EPOCH + days * SECS_PER_DAY),
+        device_bound,
+    })
+}
+
+/// 读取并校验已激活的激活码；未激活时返回 None
+pub fn load_activation<R: Runtime>(app: &AppHandle<R>) -> Result<Option<Activation>, String> {
+    let path = license::data_dir(app)?.join(ACTIVATION_FILENAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let code = fs::read_to_string(&path).map_err(|e| format!("读取激活码失败: {}", e))?;
+    decode_code(&code, &license::device_id(app)?).map(Some)
+}
+
+/// 输入激活码，校验通过且未过期时保存
+#[tauri::command]
+pub fn activate_code<R: Runtime>(app: AppHandle<R>, code: String) -> Result<Activation, String> {
+    let activation = decode_code(&code, &license::device_id(&app)?)?;
+    let now = crate::clock_guard::effective_now(&app)?;
+    if activation
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return Err("激活码已过期".to_string());
+    }
+    let path = license::data_dir(&app)?.join(ACTIVATION_FILENAME);
+    fs::write(&path, &activation.code).map_err(|e| format!("保存激活码失败: {}", e))?;
+    Ok(activation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(flags: u8, features: u8, days: u16, device_id: Option<&str>) -> String {
+        let mut bytes = vec![(CODE_VERSION << 4) | flags, features];
+        bytes.extend_from_slice(&days.to_be_bytes());
+        let mac = checksum(&bytes, device_id);
+        bytes.extend_from_slice(&mac);
+        format_code(&BASE32_NOPAD.encode(&bytes))
+    }
+
+    #[test]
+    fn decodes_features_and_expiry() {
+        let code = encode(0, 0b101, 10, None);
+        let activation = decode_code(&code, "any-device").unwrap();
+        assert_eq!(activation.code, code);
+        assert_eq!(activation.code.len(), 19);
+        assert_eq!(
+            activation.features,
+            vec![Feature::VideoTools, Feature::ExportScope]
+        );
+        assert_eq!(activation.expires_at, Some(CODE_EPOCH + 10 * SECS_PER_DAY));
+        assert!(!activation.device_bound);
+
+        let permanent = decode_code(&encode(0, 0b010, 0, None), "d").unwrap();
+        assert_eq!(permanent.expires_at, None);
+        assert_eq!(permanent.features, vec![Feature::ProfileEditing]);
+    }
+
+    #[test]
+    fn normalizes_case_separators_and_lookalike_characters() {
+        let code = encode(0, 0b111, 365, None);
+        let typed = code
+            .to_lowercase()
+            .replace('-', " ")
+            .replace('o', "0")
+            .replace('i', "1")
+            .replace('b', "8");
+        assert_eq!(decode_code(&typed, "d").unwrap().code, code);
+    }
+
+    #[test]
+    fn binds_codes_to_a_device() {
+        let code = encode(FLAG_DEVICE_BOUND, 0b001, 30, Some("device-a"));
+        assert!(decode_code(&code, "device-a").unwrap().device_bound);
+        assert_eq!(
+            decode_code(&code, "device-b").unwrap_err(),
+            "激活码无效或不属于本设备"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_and_malformed_codes() {
+        // 改动功能位图但保留原校验值
+        let mut bytes = BASE32_NOPAD
+            .decode(normalize(&encode(0, 0b001, 30, None)).as_bytes())
+            .unwrap();
+        bytes[1] = 0b111;
+        let tampered = format_code(&BASE32_NOPAD.encode(&bytes));
+        assert_eq!(decode_code(&tampered, "d").unwrap_err(), "激活码无效");
+
+        let short = BASE32_NOPAD.encode(&[CODE_VERSION << 4, 0, 0, 0, 0]);
+        assert_eq!(decode_code(&short, "d").unwrap_err(), "激活码长度不正确");
+
+        let mut future = vec![2 << 4, 0, 0, 0];
+        future.extend_from_slice(&[0; CHECKSUM_LEN]);
+        assert_eq!(
+            decode_code(&BASE32_NOPAD.encode(&future), "d").unwrap_err(),
+            "不支持的激活码版本: 2"
+        );
+
+        assert_eq!(decode_code("!!!!", "d").unwrap_err(), "激活码长度不正确");
+        assert_eq!(decode_code("ABC", "d").unwrap_err(), "激活码格式错误");
+    }
+}
@@ -0,0 +1,536 @@
+//! 自动备份：后台定时把业务数据加密保存为 v2 快照（`snapshot-YYYYMMDD-HHMMSS-mmm.cbin`），
+//! 默认放在应用数据目录的 `snapshots/` 下，也可以指定其他文件夹（如网盘同步目录）。
+//!
+//! 距上次快照满设定的小时数，或业务数据累计变更达到设定次数时生成快照，没有任何变更时不生成；
+//! 变更次数由数据库触发器累计在 `change_counter` 表中。
+//! 快照按“每日 / 每周 / 每月各保留若干份”轮换，每个周期保留其中最新的一份。
+//! 自动备份的密码保存在系统钥匙串 / Android Keystore 中（见 `secret_store`），不写入数据库；
+//! 快照复制到其他设备后仍需输入密码才能恢复。
+
+use crate::business_db::{self, db_err, with_db};
+use crate::encrypted_backup::{self, BackupRestoreResult};
+use crate::license;
+use crate::secret_store;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+const SNAPSHOT_DIRNAME: &str = "snapshots";
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_SUFFIX: &str = ".cbin";
+// 快照时间精确到毫秒，同一秒内的多份快照（如恢复前自动生成的那份）不会互相覆盖
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+// 旧版本的快照时间只精确到秒
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+// 偏好设置中的键
+const SETTINGS_KEY: &str = "auto_backup_settings";
+// 安全存储中的密码键；旧版本曾以同名键明文保存在偏好设置中，读取时迁移
+const PASSWORD_KEY: &str = "auto_backup_password";
+const LAST_SNAPSHOT_KEY: &str = "auto_backup_last_at";
+const LAST_ERROR_KEY: &str = "auto_backup_last_error";
+// 启动后稍等再检查，避免与首屏加载争抢
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 自动备份设置
+/// - interval_hours: 距上次快照满多少小时生成新快照（默认 24，即每天一次）
+/// - change_threshold: 累计变更达到多少次时提前生成快照，0 表示不按变更次数触发
+/// - directory: 快照保存的文件夹，省略时为应用数据目录下的 `snapshots/`
+/// - keep_daily / keep_weekly / keep_monthly: 轮换时每日、每周、每月各保留的份数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoBackupSettings {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub change_threshold: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    pub keep_daily: u32,
+    pub keep_weekly: u32,
+    pub keep_monthly: u32,
+    pub include_attachments: bool,
+}
+
+impl Default for AutoBackupSettings {
+    fn default() -> Self {
+        AutoBackupSettings {
+            enabled: false,
+            interval_hours: 24,
+            change_threshold: 50,
+            directory: None,
+            keep_daily: 7,
+            keep_weekly: 4,
+            keep_monthly: 12,
+            include_attachments: false,
+        }
+    }
+}
+
+/// 自动备份状态：设置、是否已设置密码、快照目录、未备份的变更次数、上次快照时间与上次失败原因
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoBackupStatus {
+    pub settings: AutoBackupSettings,
+    pub has_password: bool,
+    pub directory: String,
+    pub pending_changes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_snapshot_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub file_name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size: u64,
+}
+
+/// 自动备份所需的全部状态：密码来自安全存储，其余从数据库读出
+struct State {
+    settings: AutoBackupSettings,
+    password: Option<String>,
+    pending_changes: i64,
+    last_snapshot_at: Option<String>,
+    last_error: Option<String>,
+}
+
+fn load_db_state(conn: &Connection) -> Result<State, String> {
+    let settings = business_db::preference(conn, SETTINGS_KEY)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let pending_changes = conn
+        .query_row(
+            "SELECT changes FROM change_counter WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+    Ok(State {
+        settings,
+        password: None,
+        pending_changes,
+        last_snapshot_at: business_db::preference(conn, LAST_SNAPSHOT_KEY)?,
+        last_error: business_db::preference(conn, LAST_ERROR_KEY)?,
+    })
+}
+
+/// 读取自动备份密码；旧版本明文保存在偏好设置中的密码转存到安全存储后删除
+fn load_password<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    let legacy = with_db(app, |conn| business_db::preference(conn, PASSWORD_KEY))?;
+    if let Some(legacy) = legacy {
+        secret_store::store(app, PASSWORD_KEY, Some(&legacy))?;
+        with_db(app, |conn| {
+            conn.execute("DELETE FROM preferences WHERE key = ?", [PASSWORD_KEY])
+                .map(|_| ())
+                .map_err(db_err)
+        })?;
+        return Ok(Some(legacy));
+    }
+    secret_store::load(app, PASSWORD_KEY)
+}
+
+fn load_state<R: Runtime>(app: &AppHandle<R>) -> Result<State, String> {
+    let mut state = with_db(app, |conn| load_db_state(conn))?;
+    state.password = load_password(app)?;
+    Ok(state)
+}
+
+fn snapshot_dir<R: Runtime>(
+    app: &AppHandle<R>,
+    settings: &AutoBackupSettings,
+) -> Result<PathBuf, String> {
+    let dir = match settings.directory.as_deref().filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => license::data_dir(app)?.join(SNAPSHOT_DIRNAME),
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("备份文件夹不可用: {}", e))?;
+    Ok(dir)
+}
+
+fn snapshot_time(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name
+        .strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(SNAPSHOT_SUFFIX)?;
+    NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(stamp, LEGACY_TIMESTAMP_FORMAT))
+        .ok()
+}
+
+/// 新快照的文件名；同一毫秒内已有快照时顺延到下一毫秒
+fn snapshot_file_name(dir: &Path, mut time: NaiveDateTime) -> String {
+    loop {
+        let file_name = format!(
+            "{}{}{}",
+            SNAPSHOT_PREFIX,
+            time.format(TIMESTAMP_FORMAT),
+            SNAPSHOT_SUFFIX
+        );
+        if !dir.join(&file_name).exists() {
+            return file_name;
+        }
+        time += chrono::Duration::milliseconds(1);
+    }
+}
+
+/// 目录中的快照，按时间从新到旧排列；其他文件忽略
+fn list_snapshots(dir: &Path) -> Result<Vec<(NaiveDateTime, SnapshotInfo)>, String> {
+    let mut snapshots: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("读取备份文件夹失败: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_str()?.to_string();
+            let time = snapshot_time(&file_name)?;
+            let size = entry.metadata().ok()?.len();
+            let created_at = time
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default();
+            Some((
+                time,
+                SnapshotInfo {
+                    file_name,
+                    path: entry.path().to_string_lossy().into_owned(),
+                    created_at,
+                    size,
+                },
+            ))
+        })
+        .collect();
+    snapshots.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    Ok(snapshots)
+}
+
+/// 按每日 / 每周 / 每月规则标记需要保留的快照（`times` 从新到旧），最新的一份总是保留
+fn retained(times: &[NaiveDateTime], settings: &AutoBackupSettings) -> Vec<bool> {
+    type Period = fn(&NaiveDateTime) -> (i32, u32);
+    let rules: [(u32, Period); 3] = [
+        (settings.keep_daily, |t| (t.year(), t.ordinal())),
+        (settings.keep_weekly, |t| {
+            (t.iso_week().year(), t.iso_week().week())
+        }),
+        (settings.keep_monthly, |t| (t.year(), t.month())),
+    ];
+    let mut keep = vec![false; times.len()];
+    for (limit, period) in rules {
+        let mut seen = HashSet::new();
+        for (i, time) in times.iter().enumerate() {
+            if seen.len() >= limit as usize {
+                break;
+            }
+            if seen.insert(period(time)) {
+                keep[i] = true;
+            }
+        }
+    }
+    if let Some(newest) = keep.first_mut() {
+        *newest = true;
+    }
+    keep
+}
+
+fn rotate(dir: &Path, settings: &AutoBackupSettings) -> Result<(), String> {
+    let snapshots = list_snapshots(dir)?;
+    let times: Vec<_> = snapshots.iter().map(|(time, _)| *time).collect();
+    for ((_, snapshot), keep) in snapshots.iter().zip(retained(&times, settings)) {
+        if !keep {
+            let _ = fs::remove_file(&snapshot.path);
+        }
+    }
+    Ok(())
+}
+
+/// 生成一份快照并扣减已备份的变更次数；`rotate_after` 为 false 时不做轮换
+fn take_snapshot<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &State,
+    rotate_after: bool,
+) -> Result<SnapshotInfo, String> {
+    let password = state
+        .password
+        .as_deref()
+        .ok_or_else(|| "请先设置自动备份密码".to_string())?;
+    let dir = snapshot_dir(app, &state.settings)?;
+    let now = Local::now();
+    let file_name = snapshot_file_name(&dir, now.naive_local());
+    let summary = encrypted_backup::write_v2_backup(
+        app,
+        &dir.join(&file_name),
+        password,
+        None,
+        state.settings.include_attachments,
+    )?;
+
+    with_db(app, |conn| {
+        // 生成快照期间新增的变更留到下一次
+        conn.execute(
+            "UPDATE change_counter SET changes = MAX(changes - ?, 0) WHERE id = 1",
+            [state.pending_changes],
+        )
+        .map_err(db_err)?;
+        business_db::set_preference(conn, LAST_SNAPSHOT_KEY, &business_db::now_iso())?;
+        conn.execute("DELETE FROM preferences WHERE key = ?", [LAST_ERROR_KEY])
+            .map_err(db_err)?;
+        Ok(())
+    })?;
+    if rotate_after {
+        rotate(&dir, &state.settings)?;
+    }
+    Ok(SnapshotInfo {
+        file_name,
+        path: summary.path,
+        created_at: now.to_rfc3339(),
+        size: summary.size,
+    })
+}
+
+fn is_due(state: &State) -> bool {
+    let settings = &state.settings;
+    if !settings.enabled || state.password.is_none() || state.pending_changes <= 0 {
+        return false;
+    }
+    if settings.change_threshold > 0 && state.pending_changes >= settings.change_threshold as i64 {
+        return true;
+    }
+    let last = state
+        .last_snapshot_at
+        .as_deref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok());
+    match last {
+        Some(last) => {
+            let elapsed = Local::now().signed_duration_since(last);
+            elapsed.num_hours() >= settings.interval_hours as i64
+        }
+        None => true,
+    }
+}
+
+/// 检查一次，到期则生成快照；失败原因记录下来，在设置页显示
+fn run_once<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let state = load_state(app)?;
+    if !is_due(&state) {
+        return Ok(());
+    }
+    if let Err(err) = take_snapshot(app, &state, true) {
+        with_db(app, |conn| {
+            business_db::set_preference(conn, LAST_ERROR_KEY, &err)
+        })?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// 后台线程定时检查是否需要生成快照
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            let _ = run_once(&app);
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+fn status<R: Runtime>(app: &AppHandle<R>, state: State) -> Result<AutoBackupStatus, String> {
+    let directory = snapshot_dir(app, &state.settings)?;
+    Ok(AutoBackupStatus {
+        has_password: state.password.is_some(),
+        directory: directory.to_string_lossy().into_owned(),
+        pending_changes: state.pending_changes,
+        last_snapshot_at: state.last_snapshot_at,
+        last_error: state.last_error,
+        settings: state.settings,
+    })
+}
+
+#[tauri::command]
+pub async fn get_auto_backup_status<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<AutoBackupStatus, String> {
+    let state = load_state(&app)?;
+    status(&app, state)
+}
+
+/// 保存自动备份设置；`password` 省略时沿用已保存的密码
+#[tauri::command]
+pub async fn save_auto_backup_settings<R: Runtime>(
+    app: AppHandle<R>,
+    settings: AutoBackupSettings,
+    password: Option<String>,
+) -> Result<AutoBackupStatus, String> {
+    if settings.interval_hours == 0 {
+        return Err("备份间隔至少 1 小时".to_string());
+    }
+    let password = password.map(|password| password.trim().to_string());
+    if let Some(password) = &password {
+        encrypted_backup::check_v2_password(password)?;
+    }
+    // 提前创建并检查文件夹，避免到后台备份时才发现不可用
+    snapshot_dir(&app, &settings)?;
+
+    match &password {
+        Some(password) => secret_store::store(&app, PASSWORD_KEY, Some(password))?,
+        None if settings.enabled && load_password(&app)?.is_none() => {
+            return Err("启用自动备份前请先设置备份密码".to_string());
+        }
+        None => {}
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("序列化失败: {}", e))?;
+    with_db(&app, |conn| {
+        business_db::set_preference(conn, SETTINGS_KEY, &json)
+    })?;
+    status(&app, load_state(&app)?)
+}
+
+/// 立即生成一份快照（不论是否到期、是否有变更）
+#[tauri::command]
+pub async fn create_backup_snapshot<R: Runtime>(app: AppHandle<R>) -> Result<SnapshotInfo, String> {
+    let state = load_state(&app)?;
+    take_snapshot(&app, &state, true)
+}
+
+/// 列出快照，按时间从新到旧
+#[tauri::command]
+pub async fn list_backup_snapshots<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<SnapshotInfo>, String> {
+    let state = load_state(&app)?;
+    let dir = snapshot_dir(&app, &state.settings)?;
+    Ok(list_snapshots(&dir)?
+        .into_iter()
+        .map(|(_, snapshot)| snapshot)
+        .collect())
+}
+
+/// 从快照恢复业务数据。
+/// 默认覆盖现有数据，使数据回到快照时的状态；恢复前若有未备份的变更，先为当前数据生成一份快照。
+/// `password` 省略时使用自动备份密码（快照来自其他设备时需手动输入）
+#[tauri::command]
+pub async fn restore_backup_snapshot<R: Runtime>(
+    app: AppHandle<R>,
+    file_name: String,
+    password: Option<String>,
+    overwrite: Option<bool>,
+) -> Result<BackupRestoreResult, String> {
+    // 只接受快照文件名，不接受路径
+    if snapshot_time(&file_name).is_none() || file_name.contains(['/', '\\']) {
+        return Err("快照文件名无效".to_string());
+    }
+    crate::access_control::ensure_writable(&app)?;
+    let state = load_state(&app)?;
+    let path = snapshot_dir(&app, &state.settings)?.join(&file_name);
+    if !path.is_file() {
+        return Err("快照不存在".to_string());
+    }
+    let password = password
+        .or_else(|| state.password.clone())
+        .ok_or_else(|| "请输入快照密码".to_string())?;
+
+    if state.pending_changes > 0 && state.password.is_some() {
+        // 不轮换，以免把要恢复的快照删掉
+        take_snapshot(&app, &state, false)?;
+    }
+    let overwrite = overwrite.unwrap_or(true);
+    let result = encrypted_backup::restore_file(&app, &path, &password, overwrite)?;
+    if overwrite {
+        // 数据与快照一致，恢复写入的记录不算作新的变更
+        with_db(&app, |conn| {
+            conn.execute("UPDATE change_counter SET changes = 0 WHERE id = 1", [])
+                .map(|_| ())
+                .map_err(db_err)
+        })?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn keeps_daily_weekly_and_monthly_snapshots() {
+        let newest = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        // 一年多来每天两份快照，从新到旧
+        let times: Vec<_> = (0..800).map(|i| newest - Duration::hours(12 * i)).collect();
+        let keep = retained(&times, &AutoBackupSettings::default());
+        let kept: Vec<_> = times
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(time, _)| *time)
+            .collect();
+
+        // 最近 7 天每天一份，且是当天最新的那份（21:00 而不是 09:00）
+        assert!(kept.contains(&newest));
+        for day in 1..7 {
+            assert!(kept.contains(&(newest - Duration::hours(24 * day - 12))));
+            assert!(!kept.contains(&(newest - Duration::days(day))));
+        }
+        // 每日、每周、每月的保留会重叠，总数不超过 7 + 4 + 12
+        assert!(kept.len() <= 23);
+        // 最旧的一份保留在约 11 个月前
+        let oldest = *kept.last().unwrap();
+        assert_eq!((oldest.year(), oldest.month()), (2025, 11));
+    }
+
+    #[test]
+    fn always_keeps_newest_snapshot() {
+        let settings = AutoBackupSettings {
+            keep_daily: 0,
+            keep_weekly: 0,
+            keep_monthly: 0,
+            ..AutoBackupSettings::default()
+        };
+        let now = Local::now().naive_local();
+        let keep = retained(&[now, now - Duration::days(1)], &settings);
+        assert_eq!(keep, vec![true, false]);
+    }
+
+    #[test]
+    fn parses_only_snapshot_file_names() {
+        let time = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_milli_opt(9, 0, 0, 250)
+            .unwrap();
+        assert_eq!(
+            snapshot_time("snapshot-20261019-090000-250.cbin"),
+            Some(time)
+        );
+        assert!(snapshot_time("snapshot-20261019-090000.cbin").is_some());
+        assert!(snapshot_time("snapshot-20261019-090000-250.cbin.tmp").is_none());
+        assert!(snapshot_time("snapshot-20261019-090000.cbin.tmp").is_none());
+        assert!(snapshot_time("pre-migration-v3-20261019-090000.db").is_none());
+        assert!(snapshot_time("export.cbin").is_none());
+    }
+
+    #[test]
+    fn snapshots_taken_in_the_same_millisecond_get_distinct_names() {
+        let dir = std::env::temp_dir().join(format!("snapshots-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_milli_opt(9, 0, 0, 999)
+            .unwrap();
+        let first = snapshot_file_name(&dir, now);
+        assert_eq!(first, "snapshot-20261019-090000-999.cbin");
+        fs::write(dir.join(&first), b"").unwrap();
+        let second = snapshot_file_name(&dir, now);
+        assert_eq!(second, "snapshot-20261019-090001-000.cbin");
+        assert!(snapshot_time(&second) > snapshot_time(&first));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
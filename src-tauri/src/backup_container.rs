@@ -0,0 +1,444 @@
+//! 备份容器 v2（`CYPRIDINA2`）：
+//!
+//! `[magic "CYPRIDINA2"] + [头部长度 u32 LE] + [头部 JSON] + [AES-256-GCM 密文（末尾 16 字节为认证标签）]`
+//!
+//! 头部明文记录格式版本、Argon2id 参数、salt、nonce 与压缩算法，整段作为附加认证数据参与加密，
+//! 改动任何一个字节都会导致解密失败。密文解开后是 zstd 压缩的负载：
+//! `[清单长度 u32 LE] + [清单 JSON] + [各段内容依次排列]`，
+//! 清单按顺序记录每一段（其余数据、各张表、附件）的长度与 SHA-256，表另外记录条数。
+
+use aes_gcm::aead::consts::U12;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+pub const MAGIC: &[u8] = b"CYPRIDINA2";
+pub const FORMAT_VERSION: u32 = 2;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER_ALGORITHM: &str = "aes-256-gcm";
+const COMPRESSION: &str = "zstd";
+const ZSTD_LEVEL: i32 = 9;
+// 读取时的上限，防止损坏或恶意文件耗尽内存
+const MAX_HEADER_LEN: usize = 64 * 1024;
+// 头部未经认证，内存上限在验证密码之前就会生效；256 MiB 为默认值的 4 倍，手机上也能分配
+const MAX_KDF_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 16;
+const MAX_PAYLOAD_LEN: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Argon2id 参数，导出时写入头部，导入时按头部中的参数派生密钥
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfCost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// 默认参数：64 MiB 内存、3 轮，手机上约一两秒
+pub const DEFAULT_KDF_COST: KdfCost = KdfCost {
+    memory_kib: 64 * 1024,
+    iterations: 3,
+    parallelism: 1,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfHeader {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CipherHeader {
+    algorithm: String,
+    nonce: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    format_version: u32,
+    kdf: KdfHeader,
+    cipher: CipherHeader,
+    compression: String,
+}
+
+/// 清单中的一段：名称、字节数与 SHA-256（十六进制）；表记录条数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Section {
+    pub name: String,
+    pub length: u64,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format_version: u32,
+    pub created_at: String,
+    pub app_version: String,
+    pub extra: Section,
+    pub tables: Vec<Section>,
+    #[serde(default)]
+    pub attachments: Vec<Section>,
+}
+
+/// 容器中的一段内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub count: Option<usize>,
+    pub data: Vec<u8>,
+}
+
+impl Entry {
+    pub fn new(name: impl Into<String>, count: Option<usize>, data: Vec<u8>) -> Self {
+        Entry {
+            name: name.into(),
+            count,
+            data,
+        }
+    }
+
+    fn section(&self) -> Section {
+        Section {
+            name: self.name.clone(),
+            length: self.data.len() as u64,
+            sha256: sha256_hex(&self.data),
+            count: self.count,
+        }
+    }
+}
+
+/// 备份内容：数据库之外的其余数据（JSON）、各张表（JSON 数组）与附件原始字节
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contents {
+    pub extra: Vec<u8>,
+    pub tables: Vec<Entry>,
+    pub attachments: Vec<Entry>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("生成随机数失败: {}", e))?;
+    Ok(bytes)
+}
+
+fn derive_key(password: &str, salt: &[u8], cost: KdfCost) -> Result<[u8; KEY_LEN], String> {
+    let params = Params::new(
+        cost.memory_kib,
+        cost.iterations,
+        cost.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| format!("密钥派生参数无效: {}", e))?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("密钥派生失败: {}", e))?;
+    Ok(key)
+}
+
+fn cipher(key: &[u8; KEY_LEN]) -> Result<Aes256Gcm, String> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| "密钥长度错误".to_string())
+}
+
+fn nonce(bytes: &[u8]) -> Result<Nonce<U12>, String> {
+    <[u8; NONCE_LEN]>::try_from(bytes)
+        .map(Nonce::from)
+        .map_err(|_| "备份头部中的 nonce 长度错误".to_string())
+}
+
+fn push_framed(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// 读取 `[长度 u32 LE] + [内容]`，返回内容与剩余部分
+fn split_framed(data: &[u8], max_len: usize) -> Option<(&[u8], &[u8])> {
+    let len_bytes: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    if len > max_len {
+        return None;
+    }
+    let rest = &data[4..];
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+pub fn is_container(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 使用默认参数加密打包
+pub fn seal(contents: &Contents, password: &str, app_version: &str) -> Result<Vec<u8>, String> {
+    seal_with(contents, password, app_version, DEFAULT_KDF_COST)
+}
+
+pub fn seal_with(
+    contents: &Contents,
+    password: &str,
+    app_version: &str,
+    cost: KdfCost,
+) -> Result<Vec<u8>, String> {
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        created_at: crate::business_db::now_iso(),
+        app_version: app_version.to_string(),
+        extra: Section {
+            name: "extra".to_string(),
+            length: contents.extra.len() as u64,
+            sha256: sha256_hex(&contents.extra),
+            count: None,
+        },
+        tables: contents.tables.iter().map(Entry::section).collect(),
+        attachments: contents.attachments.iter().map(Entry::section).collect(),
+    };
+    let manifest_json =
+        serde_json::to_vec(&manifest).map_err(|e| format!("序列化清单失败: {}", e))?;
+
+    let mut payload = Vec::new();
+    push_framed(&mut payload, &manifest_json);
+    payload.extend_from_slice(&contents.extra);
+    for entry in contents.tables.iter().chain(&contents.attachments) {
+        payload.extend_from_slice(&entry.data);
+    }
+    let compressed =
+        zstd::bulk::compress(&payload, ZSTD_LEVEL).map_err(|e| format!("压缩失败: {}", e))?;
+
+    let salt = random_bytes::<SALT_LEN>()?;
+    let iv = random_bytes::<NONCE_LEN>()?;
+    let header = Header {
+        format_version: FORMAT_VERSION,
+        kdf: KdfHeader {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib: cost.memory_kib,
+            iterations: cost.iterations,
+            parallelism: cost.parallelism,
+            salt: hex::encode(salt),
+        },
+        cipher: CipherHeader {
+            algorithm: CIPHER_ALGORITHM.to_string(),
+            nonce: hex::encode(iv),
+        },
+        compression: COMPRESSION.to_string(),
+    };
+    let header_json = serde_json::to_vec(&header).map_err(|e| format!("序列化头部失败: {}", e))?;
+
+    let mut out = MAGIC.to_vec();
+    push_framed(&mut out, &header_json);
+    let key = derive_key(password, &salt, cost)?;
+    let ciphertext = cipher(&key)?
+        .encrypt(
+            &nonce(&iv)?,
+            Payload {
+                msg: &compressed,
+                aad: &out,
+            },
+        )
+        .map_err(|_| "加密失败".to_string())?;
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn parse_header(header_json: &[u8]) -> Result<(Header, KdfCost), String> {
+    let header: Header =
+        serde_json::from_slice(header_json).map_err(|_| "备份头部格式错误".to_string())?;
+    if header.format_version != FORMAT_VERSION {
+        return Err(format!(
+            "不支持的备份格式版本：{}，请升级应用后再导入",
+            header.format_version
+        ));
+    }
+    if header.kdf.algorithm != KDF_ALGORITHM
+        || header.cipher.algorithm != CIPHER_ALGORITHM
+        || header.compression != COMPRESSION
+    {
+        return Err("不支持的备份加密或压缩算法".to_string());
+    }
+    let cost = KdfCost {
+        memory_kib: header.kdf.memory_kib,
+        iterations: header.kdf.iterations,
+        parallelism: header.kdf.parallelism,
+    };
+    if cost.memory_kib > MAX_KDF_MEMORY_KIB
+        || cost.iterations > MAX_KDF_ITERATIONS
+        || cost.parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err("备份头部中的密钥派生参数超出范围".to_string());
+    }
+    Ok((header, cost))
+}
+
+fn take_section(data: &mut &[u8], section: &Section) -> Result<Vec<u8>, String> {
+    let len = usize::try_from(section.length)
+        .ok()
+        .filter(|len| *len <= data.len())
+        .ok_or_else(|| format!("备份内容不完整：{}", section.name))?;
+    let (content, rest) = data.split_at(len);
+    if sha256_hex(content) != section.sha256 {
+        return Err(format!("备份内容校验失败：{}", section.name));
+    }
+    *data = rest;
+    Ok(content.to_vec())
+}
+
+/// 解密并校验容器，返回清单与各段内容
+pub fn open(data: &[u8], password: &str) -> Result<(Manifest, Contents), String> {
+    let framed = data
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| "文件格式错误或非加密导出文件".to_string())?;
+    let (header_json, ciphertext) =
+        split_framed(framed, MAX_HEADER_LEN).ok_or_else(|| "备份头部格式错误".to_string())?;
+    let (header, cost) = parse_header(header_json)?;
+    let salt = hex::decode(&header.kdf.salt).map_err(|_| "备份头部格式错误".to_string())?;
+    let iv = hex::decode(&header.cipher.nonce).map_err(|_| "备份头部格式错误".to_string())?;
+
+    let aad = &data[..data.len() - ciphertext.len()];
+    let key = derive_key(password, &salt, cost)?;
+    let compressed = cipher(&key)?
+        .decrypt(
+            &nonce(&iv)?,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| "密码错误或文件已损坏".to_string())?;
+
+    let mut payload = Vec::new();
+    zstd::stream::read::Decoder::new(compressed.as_slice())
+        .and_then(|decoder| decoder.take(MAX_PAYLOAD_LEN).read_to_end(&mut payload))
+        .map_err(|e| format!("解压备份失败: {}", e))?;
+
+    let (manifest_json, mut rest) =
+        split_framed(&payload, payload.len()).ok_or_else(|| "备份清单缺失".to_string())?;
+    let manifest: Manifest =
+        serde_json::from_slice(manifest_json).map_err(|_| "备份清单格式错误".to_string())?;
+    let extra = take_section(&mut rest, &manifest.extra)?;
+    let mut read_entries = |sections: &[Section]| -> Result<Vec<Entry>, String> {
+        sections
+            .iter()
+            .map(|section| {
+                take_section(&mut rest, section)
+                    .map(|data| Entry::new(section.name.clone(), section.count, data))
+            })
+            .collect()
+    };
+    let tables = read_entries(&manifest.tables)?;
+    let attachments = read_entries(&manifest.attachments)?;
+    if !rest.is_empty() {
+        return Err("备份内容与清单不一致".to_string());
+    }
+    Ok((
+        manifest,
+        Contents {
+            extra,
+            tables,
+            attachments,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用较小的参数，避免 Argon2 在调试构建下过慢
+    const TEST_COST: KdfCost = KdfCost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn sample() -> Contents {
+        Contents {
+            extra: br#"{"version":"2.0.0"}"#.to_vec(),
+            tables: vec![
+                Entry::new("customers", Some(1), br#"[{"id":"c1"}]"#.to_vec()),
+                Entry::new("products", Some(0), b"[]".to_vec()),
+            ],
+            attachments: vec![Entry::new("/tmp/a.png", None, vec![0x89, b'P', b'N', b'G'])],
+        }
+    }
+
+    #[test]
+    fn round_trips_contents_and_manifest() {
+        let contents = sample();
+        let sealed = seal_with(&contents, "correct horse", "1.0.0", TEST_COST).unwrap();
+        assert!(is_container(&sealed));
+
+        let (manifest, opened) = open(&sealed, "correct horse").unwrap();
+        assert_eq!(opened, contents);
+        assert_eq!(manifest.format_version, FORMAT_VERSION);
+        assert_eq!(manifest.tables[0].count, Some(1));
+        assert_eq!(manifest.tables[0].sha256, sha256_hex(br#"[{"id":"c1"}]"#));
+        assert_eq!(manifest.attachments[0].length, 4);
+    }
+
+    #[test]
+    fn rejects_wrong_password_and_tampered_header() {
+        let sealed = seal_with(&sample(), "correct horse", "1.0.0", TEST_COST).unwrap();
+        assert_eq!(
+            open(&sealed, "wrong horse").unwrap_err(),
+            "密码错误或文件已损坏"
+        );
+
+        // 头部不加密但参与认证：在头部 JSON 末尾追加空格，解析结果不变，但认证应失败
+        let framed = &sealed[MAGIC.len()..];
+        let (header_json, ciphertext) = split_framed(framed, MAX_HEADER_LEN).unwrap();
+        let mut tampered = MAGIC.to_vec();
+        push_framed(&mut tampered, &[header_json, b" "].concat());
+        tampered.extend_from_slice(ciphertext);
+        assert_eq!(
+            open(&tampered, "correct horse").unwrap_err(),
+            "密码错误或文件已损坏"
+        );
+    }
+
+    #[test]
+    fn rejects_excessive_kdf_cost() {
+        let cost = KdfCost {
+            memory_kib: MAX_KDF_MEMORY_KIB + 1,
+            ..TEST_COST
+        };
+        let header = Header {
+            format_version: FORMAT_VERSION,
+            kdf: KdfHeader {
+                algorithm: KDF_ALGORITHM.to_string(),
+                memory_kib: cost.memory_kib,
+                iterations: cost.iterations,
+                parallelism: cost.parallelism,
+                salt: hex::encode([0u8; SALT_LEN]),
+            },
+            cipher: CipherHeader {
+                algorithm: CIPHER_ALGORITHM.to_string(),
+                nonce: hex::encode([0u8; NONCE_LEN]),
+            },
+            compression: COMPRESSION.to_string(),
+        };
+        let mut data = MAGIC.to_vec();
+        push_framed(&mut data, &serde_json::to_vec(&header).unwrap());
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(
+            open(&data, "correct horse").unwrap_err(),
+            "备份头部中的密钥派生参数超出范围"
+        );
+    }
+}
@@ -0,0 +1,1396 @@
+use crate::business_models::{
+    Customer, CustomerProductHistory, Invoice, InvoiceItem, InvoiceStatus, InvoiceType,
+    LastPurchase, PaymentStatus, Product, Quotation, QuotationProduct, SearchFilter,
+};
+use crate::db_migrations;
+use crate::license;
+use crate::money;
+use crate::numbering::{self, DocumentKind};
+use crate::receivables;
+use rusqlite::types::{Type, Value as SqlValue};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+// 业务数据库文件名，位于应用数据目录
+const DB_FILENAME: &str = "business.db";
+// 迁移前备份所在目录（应用数据目录下）
+const MIGRATION_BACKUP_DIRNAME: &str = "backups";
+
+// 各表的列顺序，查询、写入与行映射共用
+const CUSTOMER_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "category",
+    "initial_debt",
+    "phone",
+    "backup_phone",
+    "address",
+    "fax",
+    "email",
+    "notes",
+    "images",
+    "attachments",
+    "created_at",
+    "updated_at",
+    "is_active",
+];
+const PRODUCT_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "barcode",
+    "category",
+    "specifications",
+    "attributes",
+    "unit",
+    "prices",
+    "tags",
+    "notes",
+    "images",
+    "created_at",
+    "updated_at",
+    "is_active",
+];
+const INVOICE_COLUMNS: &[&str] = &[
+    "id",
+    "invoice_number",
+    "date",
+    "customer_id",
+    "customer_info",
+    "company_info",
+    "total_amount",
+    "created_by",
+    "notes",
+    "status",
+    "type",
+    "delivery_date",
+    "payment_status",
+    "paid_amount",
+    "created_at",
+    "updated_at",
+    "rounding",
+];
+const QUOTATION_PRODUCT_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "specification",
+    "default_price",
+    "notes",
+    "created_at",
+    "updated_at",
+];
+const QUOTATION_COLUMNS: &[&str] = &[
+    "id",
+    "quotation_number",
+    "custom_name",
+    "header_info",
+    "columns",
+    "table_width",
+    "header_font_size",
+    "note1_font_size",
+    "note2_font_size",
+    "items",
+    "footer_info",
+    "created_by",
+    "created_at",
+    "updated_at",
+    "notes",
+];
+
+/// 业务数据库连接，首次使用时打开
+#[derive(Default)]
+pub struct BusinessDb {
+    conn: Mutex<Option<Connection>>,
+}
+
+pub(crate) fn db_err(e: rusqlite::Error) -> String {
+    format!("数据库操作失败: {}", e)
+}
+
+fn open<R: Runtime>(app: &AppHandle<R>) -> Result<Connection, String> {
+    let path = license::data_dir(app)?.join(DB_FILENAME);
+    let mut conn = Connection::open(&path).map_err(|e| format!("打开数据库失败: {}", e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA busy_timeout = 5000;")
+        .map_err(db_err)?;
+    db_migrations::migrate(
+        &mut conn,
+        &license::data_dir(app)?.join(MIGRATION_BACKUP_DIRNAME),
+    )?;
+    // 迁移中可能重建表，外键约束在迁移完成后再开启
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .map_err(db_err)?;
+    Ok(conn)
+}
+
+/// 在业务数据库连接上执行操作，连接在各命令间共享并串行使用
+pub(crate) fn with_db<R: Runtime, T>(
+    app: &AppHandle<R>,
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    let db = app.state::<BusinessDb>();
+    let mut conn = db.conn.lock().map_err(|_| "数据库连接不可用".to_string())?;
+    if conn.is_none() {
+        *conn = Some(open(app)?);
+    }
+    match conn.as_mut() {
+        Some(conn) => f(conn),
+        None => Err("数据库连接不可用".to_string()),
+    }
+}
+
+/// 修改业务数据的命令使用：先检查授权是否允许写入（只读模式下拒绝），再访问数据库
+pub(crate) fn with_db_write<R: Runtime, T>(
+    app: &AppHandle<R>,
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    crate::access_control::ensure_writable(app)?;
+    with_db(app, f)
+}
+
+/// 启动时打开业务数据库并执行迁移
+pub fn init<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    with_db(app, |_| Ok(()))
+}
+
+/// 与前端 `new Date().toISOString()` 格式一致的当前时间
+pub(crate) fn now_iso() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("序列化失败: {}", e))
+}
+
+fn json_column<T: DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(idx)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+pub(crate) fn upsert_sql(table: &str, columns: &[&str]) -> String {
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let updates = columns
+        .iter()
+        .filter(|column| **column != "id")
+        .map(|column| format!("{0} = excluded.{0}", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+        table,
+        columns.join(", "),
+        placeholders,
+        updates
+    )
+}
+
+/// 已保存记录的更新时间，用于导入时判断哪一份较新；`table` 只能是本模块中的表名
+pub(crate) fn stored_updated_at(
+    conn: &Connection,
+    table: &str,
+    id: &str,
+) -> Result<Option<String>, String> {
+    conn.prepare_cached(&format!("SELECT updated_at FROM {} WHERE id = ?", table))
+        .and_then(|mut stmt| stmt.query_row([id], |row| row.get(0)).optional())
+        .map_err(db_err)
+}
+
+pub(crate) fn select_sql(table: &str, columns: &[&str]) -> String {
+    format!("SELECT {} FROM {}", columns.join(", "), table)
+}
+
+/// 按筛选条件拼接的 WHERE / ORDER BY / LIMIT 子句，取值全部走参数绑定
+#[derive(Default)]
+pub(crate) struct Query {
+    clauses: Vec<String>,
+    pub(crate) values: Vec<SqlValue>,
+    order: String,
+}
+
+impl Query {
+    pub(crate) fn filter(&mut self, clause: &str, values: impl IntoIterator<Item = SqlValue>) {
+        self.clauses.push(clause.to_string());
+        self.values.extend(values);
+    }
+
+    /// 关键字在任一列中出现即匹配（不区分大小写，仅限 ASCII）
+    pub(crate) fn keyword(&mut self, keyword: Option<&str>, columns: &[&str]) {
+        let Some(keyword) = keyword.map(str::trim).filter(|k| !k.is_empty()) else {
+            return;
+        };
+        let escaped = keyword
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("%{}%", escaped);
+        let clause = columns
+            .iter()
+            .map(|column| format!("{} LIKE ? ESCAPE '\\'", column))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        self.filter(
+            &format!("({})", clause),
+            columns.iter().map(|_| SqlValue::Text(pattern.clone())),
+        );
+    }
+
+    /// `sort_by` 只接受白名单中的字段，避免拼接任意 SQL
+    pub(crate) fn order_by(
+        &mut self,
+        filter: &SearchFilter,
+        allowed: &[(&str, &str)],
+        default: &str,
+    ) {
+        let column = filter
+            .sort_by
+            .as_deref()
+            .and_then(|key| allowed.iter().find(|(name, _)| *name == key))
+            .map(|(_, column)| *column);
+        let direction = match filter.sort_order.as_deref() {
+            Some("asc") => "ASC",
+            _ => "DESC",
+        };
+        self.order = match column {
+            Some(column) => format!(" ORDER BY {} {}", column, direction),
+            None => format!(" ORDER BY {}", default),
+        };
+    }
+
+    pub(crate) fn sql(&self, select: &str, filter: &SearchFilter) -> String {
+        let mut sql = select.to_string();
+        if !self.clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.clauses.join(" AND "));
+        }
+        sql.push_str(&self.order);
+        let limit = filter.limit.map(i64::from).unwrap_or(-1);
+        sql.push_str(&format!(
+            " LIMIT {} OFFSET {}",
+            limit,
+            filter.offset.unwrap_or(0)
+        ));
+        sql
+    }
+}
+
+pub(crate) fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+pub(crate) fn query_rows<T>(
+    conn: &Connection,
+    sql: &str,
+    values: Vec<SqlValue>,
+    map: impl FnMut(&Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>, String> {
+    let mut stmt = conn.prepare(sql).map_err(db_err)?;
+    let rows = stmt
+        .query_map(params_from_iter(values), map)
+        .map_err(db_err)?;
+    rows.collect::<rusqlite::Result<Vec<T>>>().map_err(db_err)
+}
+
+// ---------------------------------------------------------------- 客户
+
+fn customer_from_row(row: &Row) -> rusqlite::Result<Customer> {
+    Ok(Customer {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: row.get(2)?,
+        initial_debt: money::column(row, 3)?,
+        phone: row.get(4)?,
+        backup_phone: row.get(5)?,
+        address: row.get(6)?,
+        fax: row.get(7)?,
+        email: row.get(8)?,
+        notes: row.get(9)?,
+        images: json_column(row, 10)?,
+        attachments: json_column(row, 11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+        is_active: row.get(14)?,
+    })
+}
+
+/// 按原样写入客户（已存在则覆盖），时间戳由调用方负责
+pub(crate) fn upsert_customer(conn: &Connection, customer: &Customer) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("customers", CUSTOMER_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        customer.id,
+        customer.name,
+        customer.category,
+        money::to_f64(customer.initial_debt),
+        customer.phone,
+        customer.backup_phone,
+        customer.address,
+        customer.fax,
+        customer.email,
+        customer.notes,
+        to_json(&customer.images)?,
+        to_json(&customer.attachments)?,
+        customer.created_at,
+        customer.updated_at,
+        customer.is_active,
+    ])
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+pub(crate) fn get_customer_by_id(conn: &Connection, id: &str) -> Result<Option<Customer>, String> {
+    conn.query_row(
+        &format!("{} WHERE id = ?", select_sql("customers", CUSTOMER_COLUMNS)),
+        [id],
+        customer_from_row,
+    )
+    .optional()
+    .map_err(db_err)
+}
+
+/// 查询客户：关键字匹配名称、电话、地址与备注，`category` 为客户分类
+#[tauri::command]
+pub async fn list_customers<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<Customer>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(
+        non_empty(&filter.keyword),
+        &["name", "phone", "backup_phone", "address", "notes"],
+    );
+    if let Some(category) = non_empty(&filter.category) {
+        query.filter("category = ?", [SqlValue::Text(category.to_string())]);
+    }
+    if !filter.include_inactive {
+        query.filter("is_active = 1", []);
+    }
+    query.order_by(
+        &filter,
+        &[
+            ("name", "name"),
+            ("createdAt", "created_at"),
+            ("updatedAt", "updated_at"),
+            ("initialDebt", "initial_debt"),
+        ],
+        "updated_at DESC",
+    );
+    let sql = query.sql(&select_sql("customers", CUSTOMER_COLUMNS), &filter);
+    with_db(&app, |conn| {
+        query_rows(conn, &sql, query.values, customer_from_row)
+    })
+}
+
+#[tauri::command]
+pub async fn get_customer<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<Customer>, String> {
+    with_db(&app, |conn| get_customer_by_id(conn, &id))
+}
+
+/// 新增或更新客户，返回写入后的记录（更新时间由后端填写）
+#[tauri::command]
+pub async fn save_customer<R: Runtime>(
+    app: AppHandle<R>,
+    mut customer: Customer,
+) -> Result<Customer, String> {
+    if customer.id.trim().is_empty() {
+        return Err("客户 ID 不能为空".to_string());
+    }
+    if customer.name.trim().is_empty() {
+        return Err("客户名称不能为空".to_string());
+    }
+    let now = now_iso();
+    if customer.created_at.is_empty() {
+        customer.created_at = now.clone();
+    }
+    customer.updated_at = now;
+    with_db_write(&app, |conn| upsert_customer(conn, &customer))?;
+    Ok(customer)
+}
+
+/// 删除客户，返回是否确有记录被删除；关联的销售单保留
+#[tauri::command]
+pub async fn delete_customer<R: Runtime>(app: AppHandle<R>, id: String) -> Result<bool, String> {
+    with_db_write(&app, |conn| {
+        conn.execute("DELETE FROM customers WHERE id = ?", [&id])
+            .map(|n| n > 0)
+            .map_err(db_err)
+    })
+}
+
+// ---------------------------------------------------------------- 产品
+
+fn product_from_row(row: &Row) -> rusqlite::Result<Product> {
+    Ok(Product {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        barcode: row.get(2)?,
+        category: row.get(3)?,
+        specifications: json_column(row, 4)?,
+        attributes: json_column(row, 5)?,
+        unit: row.get(6)?,
+        prices: json_column(row, 7)?,
+        tags: json_column(row, 8)?,
+        notes: row.get(9)?,
+        images: json_column(row, 10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+        is_active: row.get(13)?,
+    })
+}
+
+pub(crate) fn upsert_product(conn: &Connection, product: &Product) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("products", PRODUCT_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        product.id,
+        product.name,
+        product.barcode,
+        product.category,
+        to_json(&product.specifications)?,
+        to_json(&product.attributes)?,
+        product.unit,
+        to_json(&product.prices)?,
+        to_json(&product.tags)?,
+        product.notes,
+        to_json(&product.images)?,
+        product.created_at,
+        product.updated_at,
+        product.is_active,
+    ])
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+/// 查询产品：关键字匹配名称、条形码、标签与备注，`category` 为产品分类
+#[tauri::command]
+pub async fn list_products<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<Product>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(
+        non_empty(&filter.keyword),
+        &["name", "barcode", "tags", "notes"],
+    );
+    if let Some(category) = non_empty(&filter.category) {
+        query.filter("category = ?", [SqlValue::Text(category.to_string())]);
+    }
+    if !filter.include_inactive {
+        query.filter("is_active = 1", []);
+    }
+    query.order_by(
+        &filter,
+        &[
+            ("name", "name"),
+            ("createdAt", "created_at"),
+            ("updatedAt", "updated_at"),
+        ],
+        "updated_at DESC",
+    );
+    let sql = query.sql(&select_sql("products", PRODUCT_COLUMNS), &filter);
+    with_db(&app, |conn| {
+        query_rows(conn, &sql, query.values, product_from_row)
+    })
+}
+
+#[tauri::command]
+pub async fn get_product<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<Product>, String> {
+    with_db(&app, |conn| {
+        conn.query_row(
+            &format!("{} WHERE id = ?", select_sql("products", PRODUCT_COLUMNS)),
+            [&id],
+            product_from_row,
+        )
+        .optional()
+        .map_err(db_err)
+    })
+}
+
+#[tauri::command]
+pub async fn save_product<R: Runtime>(
+    app: AppHandle<R>,
+    mut product: Product,
+) -> Result<Product, String> {
+    if product.id.trim().is_empty() {
+        return Err("产品 ID 不能为空".to_string());
+    }
+    if product.name.trim().is_empty() {
+        return Err("产品名称不能为空".to_string());
+    }
+    let now = now_iso();
+    if product.created_at.is_empty() {
+        product.created_at = now.clone();
+    }
+    product.updated_at = now;
+    with_db_write(&app, |conn| upsert_product(conn, &product))?;
+    Ok(product)
+}
+
+#[tauri::command]
+pub async fn delete_product<R: Runtime>(app: AppHandle<R>, id: String) -> Result<bool, String> {
+    with_db_write(&app, |conn| {
+        conn.execute("DELETE FROM products WHERE id = ?", [&id])
+            .map(|n| n > 0)
+            .map_err(db_err)
+    })
+}
+
+// ---------------------------------------------------------------- 销售单
+
+fn invoice_from_row(row: &Row) -> rusqlite::Result<Invoice> {
+    let status: String = row.get(9)?;
+    let invoice_type: String = row.get(10)?;
+    let payment_status: String = row.get(12)?;
+    Ok(Invoice {
+        id: row.get(0)?,
+        invoice_number: row.get(1)?,
+        date: row.get(2)?,
+        customer_id: row.get(3)?,
+        customer_info: json_column(row, 4)?,
+        company_info: json_column(row, 5)?,
+        items: Vec::new(),
+        total_amount: money::column(row, 6)?,
+        created_by: row.get(7)?,
+        notes: row.get(8)?,
+        status: InvoiceStatus::parse(&status).unwrap_or_default(),
+        invoice_type: InvoiceType::parse(&invoice_type).unwrap_or_default(),
+        delivery_date: row.get(11)?,
+        payment_status: PaymentStatus::parse(&payment_status).unwrap_or_default(),
+        paid_amount: money::column(row, 13)?,
+        created_at: row.get(14)?,
+        updated_at: row.get(15)?,
+        rounding: row
+            .get::<_, Option<String>>(16)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+fn item_from_row(row: &Row) -> rusqlite::Result<InvoiceItem> {
+    Ok(InvoiceItem {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        product_name: row.get(2)?,
+        specification: row.get(3)?,
+        unit: row.get(4)?,
+        quantity: money::column(row, 5)?,
+        delivery_quantity: row.get(6)?,
+        unit_price: money::column(row, 7)?,
+        amount: money::column(row, 8)?,
+        note: row.get(9)?,
+    })
+}
+
+fn load_items(conn: &Connection, invoice: &mut Invoice) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id, product_id, product_name, specification, unit, quantity, \
+             delivery_quantity, unit_price, amount, note \
+             FROM invoice_items WHERE invoice_id = ? ORDER BY position",
+        )
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([&invoice.id], item_from_row)
+        .map_err(db_err)?;
+    invoice.items = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(db_err)?;
+    Ok(())
+}
+
+/// 写入销售单及其商品行（已存在则整体替换），调用方负责事务
+pub(crate) fn upsert_invoice(conn: &Connection, invoice: &Invoice) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("invoices", INVOICE_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        invoice.id,
+        invoice.invoice_number,
+        invoice.date,
+        invoice.customer_id,
+        to_json(&invoice.customer_info)?,
+        to_json(&invoice.company_info)?,
+        money::to_f64(invoice.total_amount),
+        invoice.created_by,
+        invoice.notes,
+        invoice.status.as_str(),
+        invoice.invoice_type.as_str(),
+        invoice.delivery_date,
+        invoice.payment_status.as_str(),
+        money::to_f64(invoice.paid_amount),
+        invoice.created_at,
+        invoice.updated_at,
+        invoice.rounding.as_ref().map(to_json).transpose()?,
+    ])
+    .map_err(db_err)?;
+
+    conn.execute(
+        "DELETE FROM invoice_items WHERE invoice_id = ?",
+        [&invoice.id],
+    )
+    .map_err(db_err)?;
+    let mut insert = conn
+        .prepare_cached(
+            "INSERT INTO invoice_items (invoice_id, position, id, product_id, product_name, \
+             specification, unit, quantity, delivery_quantity, unit_price, amount, note) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .map_err(db_err)?;
+    for (position, item) in invoice.items.iter().enumerate() {
+        insert
+            .execute(params![
+                invoice.id,
+                position as i64,
+                item.id,
+                item.product_id,
+                item.product_name,
+                item.specification,
+                item.unit,
+                money::to_f64(item.quantity),
+                item.delivery_quantity,
+                money::to_f64(item.unit_price),
+                money::to_f64(item.amount),
+                item.note,
+            ])
+            .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn get_invoice_by_id(conn: &Connection, id: &str) -> Result<Option<Invoice>, String> {
+    let invoice = conn
+        .query_row(
+            &format!("{} WHERE id = ?", select_sql("invoices", INVOICE_COLUMNS)),
+            [id],
+            invoice_from_row,
+        )
+        .optional()
+        .map_err(db_err)?;
+    match invoice {
+        Some(mut invoice) => {
+            load_items(conn, &mut invoice)?;
+            Ok(Some(invoice))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 查询销售单（含商品行）：关键字匹配单号、客户名称与备注，
+/// `category` 为单据类型，`status` 同时匹配单据状态与付款状态，日期范围按开单日期
+#[tauri::command]
+pub async fn list_invoices<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<Invoice>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(
+        non_empty(&filter.keyword),
+        &[
+            "invoice_number",
+            "json_extract(customer_info, '$.name')",
+            "notes",
+        ],
+    );
+    if let Some(customer_id) = non_empty(&filter.customer_id) {
+        query.filter("customer_id = ?", [SqlValue::Text(customer_id.to_string())]);
+    }
+    if let Some(invoice_type) = non_empty(&filter.category) {
+        query.filter("type = ?", [SqlValue::Text(invoice_type.to_string())]);
+    }
+    if let Some(status) = non_empty(&filter.status) {
+        query.filter(
+            "(status = ? OR payment_status = ?)",
+            [
+                SqlValue::Text(status.to_string()),
+                SqlValue::Text(status.to_string()),
+            ],
+        );
+    }
+    if let Some(date_from) = non_empty(&filter.date_from) {
+        query.filter("date >= ?", [SqlValue::Text(date_from.to_string())]);
+    }
+    if let Some(date_to) = non_empty(&filter.date_to) {
+        query.filter("date <= ?", [SqlValue::Text(date_to.to_string())]);
+    }
+    query.order_by(
+        &filter,
+        &[
+            ("date", "date"),
+            ("invoiceNumber", "invoice_number"),
+            ("totalAmount", "total_amount"),
+            ("createdAt", "created_at"),
+            ("updatedAt", "updated_at"),
+        ],
+        "date DESC, created_at DESC",
+    );
+    let sql = query.sql(&select_sql("invoices", INVOICE_COLUMNS), &filter);
+    with_db(&app, |conn| {
+        let mut invoices = query_rows(conn, &sql, query.values, invoice_from_row)?;
+        for invoice in &mut invoices {
+            load_items(conn, invoice)?;
+        }
+        Ok(invoices)
+    })
+}
+
+#[tauri::command]
+pub async fn get_invoice<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<Invoice>, String> {
+    with_db(&app, |conn| get_invoice_by_id(conn, &id))
+}
+
+#[tauri::command]
+pub async fn save_invoice<R: Runtime>(
+    app: AppHandle<R>,
+    mut invoice: Invoice,
+) -> Result<Invoice, String> {
+    if invoice.id.trim().is_empty() {
+        return Err("销售单 ID 不能为空".to_string());
+    }
+    if invoice.invoice_number.trim().is_empty() {
+        return Err("销售单号不能为空".to_string());
+    }
+    let now = now_iso();
+    if invoice.created_at.is_empty() {
+        invoice.created_at = now.clone();
+    }
+    invoice.updated_at = now;
+    // 行金额、合计与付款状态以后端计算为准
+    money::apply(&mut invoice)?;
+    with_db_write(&app, |conn| {
+        let tx = conn.transaction().map_err(db_err)?;
+        let kind = DocumentKind::of_invoice(invoice.invoice_type);
+        if numbering::is_taken(&tx, kind, &invoice.invoice_number, Some(&invoice.id))? {
+            return Err(format!("单号已存在：{}", invoice.invoice_number));
+        }
+        // 已付金额由收款分配得出，忽略前端传入的值
+        receivables::settle(&tx, &mut invoice)?;
+        receivables::check_invoice(&tx, &invoice)?;
+        upsert_invoice(&tx, &invoice)?;
+        tx.commit().map_err(db_err)
+    })?;
+    Ok(invoice)
+}
+
+#[tauri::command]
+pub async fn delete_invoice<R: Runtime>(app: AppHandle<R>, id: String) -> Result<bool, String> {
+    with_db_write(&app, |conn| {
+        conn.execute("DELETE FROM invoices WHERE id = ?", [&id])
+            .map(|n| n > 0)
+            .map_err(db_err)
+    })
+}
+
+// ---------------------------------------------------------------- 报价单
+
+fn quotation_from_row(row: &Row) -> rusqlite::Result<Quotation> {
+    Ok(Quotation {
+        id: row.get(0)?,
+        quotation_number: row.get(1)?,
+        custom_name: row.get(2)?,
+        header_info: json_column(row, 3)?,
+        columns: json_column(row, 4)?,
+        table_width: row.get(5)?,
+        header_font_size: row.get(6)?,
+        note1_font_size: row.get(7)?,
+        note2_font_size: row.get(8)?,
+        items: json_column(row, 9)?,
+        footer_info: json_column(row, 10)?,
+        created_by: row.get(11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+        notes: row.get(14)?,
+    })
+}
+
+pub(crate) fn upsert_quotation(conn: &Connection, quotation: &Quotation) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("quotations", QUOTATION_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        quotation.id,
+        quotation.quotation_number,
+        quotation.custom_name,
+        to_json(&quotation.header_info)?,
+        to_json(&quotation.columns)?,
+        quotation.table_width,
+        quotation.header_font_size,
+        quotation.note1_font_size,
+        quotation.note2_font_size,
+        to_json(&quotation.items)?,
+        to_json(&quotation.footer_info)?,
+        quotation.created_by,
+        quotation.created_at,
+        quotation.updated_at,
+        quotation.notes,
+    ])
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+/// 查询报价单：关键字匹配编号、自定义名称、标题与备注
+#[tauri::command]
+pub async fn list_quotations<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<Quotation>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(
+        non_empty(&filter.keyword),
+        &[
+            "quotation_number",
+            "custom_name",
+            "json_extract(header_info, '$.title')",
+            "notes",
+        ],
+    );
+    query.order_by(
+        &filter,
+        &[
+            ("quotationNumber", "quotation_number"),
+            ("createdAt", "created_at"),
+            ("updatedAt", "updated_at"),
+        ],
+        "updated_at DESC",
+    );
+    let sql = query.sql(&select_sql("quotations", QUOTATION_COLUMNS), &filter);
+    with_db(&app, |conn| {
+        query_rows(conn, &sql, query.values, quotation_from_row)
+    })
+}
+
+#[tauri::command]
+pub async fn get_quotation<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<Quotation>, String> {
+    with_db(&app, |conn| {
+        conn.query_row(
+            &format!(
+                "{} WHERE id = ?",
+                select_sql("quotations", QUOTATION_COLUMNS)
+            ),
+            [&id],
+            quotation_from_row,
+        )
+        .optional()
+        .map_err(db_err)
+    })
+}
+
+#[tauri::command]
+pub async fn save_quotation<R: Runtime>(
+    app: AppHandle<R>,
+    mut quotation: Quotation,
+) -> Result<Quotation, String> {
+    if quotation.id.trim().is_empty() {
+        return Err("报价单 ID 不能为空".to_string());
+    }
+    let now = now_iso();
+    if quotation.created_at.is_empty() {
+        quotation.created_at = now.clone();
+    }
+    quotation.updated_at = now;
+    with_db_write(&app, |conn| {
+        let number = quotation.quotation_number.trim();
+        if !number.is_empty()
+            && numbering::is_taken(conn, DocumentKind::Quotation, number, Some(&quotation.id))?
+        {
+            return Err(format!("报价单编号已存在：{}", number));
+        }
+        upsert_quotation(conn, &quotation)
+    })?;
+    Ok(quotation)
+}
+
+#[tauri::command]
+pub async fn delete_quotation<R: Runtime>(app: AppHandle<R>, id: String) -> Result<bool, String> {
+    with_db_write(&app, |conn| {
+        conn.execute("DELETE FROM quotations WHERE id = ?", [&id])
+            .map(|n| n > 0)
+            .map_err(db_err)
+    })
+}
+
+// ---------------------------------------------------------------- 报价产品
+
+fn quotation_product_from_row(row: &Row) -> rusqlite::Result<QuotationProduct> {
+    Ok(QuotationProduct {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        specification: row.get(2)?,
+        default_price: row.get(3)?,
+        notes: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+pub(crate) fn upsert_quotation_product(
+    conn: &Connection,
+    product: &QuotationProduct,
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("quotation_products", QUOTATION_PRODUCT_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        product.id,
+        product.name,
+        product.specification,
+        product.default_price,
+        product.notes,
+        product.created_at,
+        product.updated_at,
+    ])
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+#[tauri::command]
+pub async fn list_quotation_products<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<QuotationProduct>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(
+        non_empty(&filter.keyword),
+        &["name", "specification", "notes"],
+    );
+    query.order_by(
+        &filter,
+        &[
+            ("name", "name"),
+            ("createdAt", "created_at"),
+            ("updatedAt", "updated_at"),
+        ],
+        "updated_at DESC",
+    );
+    let sql = query.sql(
+        &select_sql("quotation_products", QUOTATION_PRODUCT_COLUMNS),
+        &filter,
+    );
+    with_db(&app, |conn| {
+        query_rows(conn, &sql, query.values, quotation_product_from_row)
+    })
+}
+
+#[tauri::command]
+pub async fn save_quotation_product<R: Runtime>(
+    app: AppHandle<R>,
+    mut product: QuotationProduct,
+) -> Result<QuotationProduct, String> {
+    if product.id.trim().is_empty() {
+        return Err("报价产品 ID 不能为空".to_string());
+    }
+    if product.name.trim().is_empty() {
+        return Err("报价产品名称不能为空".to_string());
+    }
+    let now = now_iso();
+    if product.created_at.is_empty() {
+        product.created_at = now.clone();
+    }
+    product.updated_at = now;
+    with_db_write(&app, |conn| upsert_quotation_product(conn, &product))?;
+    Ok(product)
+}
+
+#[tauri::command]
+pub async fn delete_quotation_product<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<bool, String> {
+    with_db_write(&app, |conn| {
+        conn.execute("DELETE FROM quotation_products WHERE id = ?", [&id])
+            .map(|n| n > 0)
+            .map_err(db_err)
+    })
+}
+
+// ---------------------------------------------------------------- 客户购买历史
+
+/// 写入客户购买历史，已有记录时只在日期更新时覆盖；返回是否写入
+pub(crate) fn upsert_customer_product_history(
+    conn: &Connection,
+    history: &CustomerProductHistory,
+) -> Result<bool, String> {
+    let purchase = &history.last_purchase;
+    conn.prepare_cached(
+        "INSERT INTO customer_product_history \
+         (customer_id, product_id, unit_price, unit, specification, quantity, date) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(customer_id, product_id) DO UPDATE SET \
+         unit_price = excluded.unit_price, unit = excluded.unit, \
+         specification = excluded.specification, quantity = excluded.quantity, \
+         date = excluded.date WHERE excluded.date > customer_product_history.date",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute(params![
+            history.customer_id,
+            history.product_id,
+            purchase.unit_price,
+            purchase.unit,
+            purchase.specification,
+            purchase.quantity,
+            purchase.date,
+        ])
+    })
+    .map(|n| n > 0)
+    .map_err(db_err)
+}
+
+/// 客户对某个产品的最后一次购买信息，开单时用于带出上次的单价与规格
+#[tauri::command]
+pub async fn get_customer_product_history<R: Runtime>(
+    app: AppHandle<R>,
+    customer_id: String,
+    product_id: String,
+) -> Result<Option<LastPurchase>, String> {
+    with_db(&app, |conn| {
+        conn.query_row(
+            "SELECT unit_price, unit, specification, quantity, date \
+             FROM customer_product_history WHERE customer_id = ? AND product_id = ?",
+            [&customer_id, &product_id],
+            |row| {
+                Ok(LastPurchase {
+                    unit_price: row.get(0)?,
+                    unit: row.get(1)?,
+                    specification: row.get(2)?,
+                    quantity: row.get(3)?,
+                    date: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(db_err)
+    })
+}
+
+/// 全部客户购买历史，恢复备份后前端用它替换本地副本
+#[tauri::command]
+pub async fn list_customer_product_history<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<CustomerProductHistory>, String> {
+    with_db(&app, |conn| all_customer_product_history(conn))
+}
+
+// ---------------------------------------------------------------- 偏好设置
+
+pub(crate) fn set_preference(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO preferences (key, value) VALUES (?, ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+pub(crate) fn preference(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT value FROM preferences WHERE key = ?",
+        [key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(db_err)
+}
+
+/// 读取偏好设置，如 `last_selected_sales_company`
+#[tauri::command]
+pub async fn get_preference<R: Runtime>(
+    app: AppHandle<R>,
+    key: String,
+) -> Result<Option<String>, String> {
+    with_db(&app, |conn| preference(conn, &key))
+}
+
+// ---------------------------------------------------------------- 全量读写（备份与恢复）
+
+pub(crate) fn all_customers(conn: &Connection) -> Result<Vec<Customer>, String> {
+    let sql = format!(
+        "{} ORDER BY created_at",
+        select_sql("customers", CUSTOMER_COLUMNS)
+    );
+    query_rows(conn, &sql, Vec::new(), customer_from_row)
+}
+
+pub(crate) fn all_products(conn: &Connection) -> Result<Vec<Product>, String> {
+    let sql = format!(
+        "{} ORDER BY created_at",
+        select_sql("products", PRODUCT_COLUMNS)
+    );
+    query_rows(conn, &sql, Vec::new(), product_from_row)
+}
+
+pub(crate) fn all_invoices(conn: &Connection) -> Result<Vec<Invoice>, String> {
+    let sql = format!(
+        "{} ORDER BY date, created_at",
+        select_sql("invoices", INVOICE_COLUMNS)
+    );
+    let mut invoices = query_rows(conn, &sql, Vec::new(), invoice_from_row)?;
+    for invoice in &mut invoices {
+        load_items(conn, invoice)?;
+    }
+    Ok(invoices)
+}
+
+pub(crate) fn all_quotations(conn: &Connection) -> Result<Vec<Quotation>, String> {
+    let sql = format!(
+        "{} ORDER BY created_at",
+        select_sql("quotations", QUOTATION_COLUMNS)
+    );
+    query_rows(conn, &sql, Vec::new(), quotation_from_row)
+}
+
+pub(crate) fn all_quotation_products(conn: &Connection) -> Result<Vec<QuotationProduct>, String> {
+    let sql = format!(
+        "{} ORDER BY created_at",
+        select_sql("quotation_products", QUOTATION_PRODUCT_COLUMNS)
+    );
+    query_rows(conn, &sql, Vec::new(), quotation_product_from_row)
+}
+
+pub(crate) fn all_customer_product_history(
+    conn: &Connection,
+) -> Result<Vec<CustomerProductHistory>, String> {
+    query_rows(
+        conn,
+        "SELECT customer_id, product_id, unit_price, unit, specification, quantity, date \
+         FROM customer_product_history ORDER BY customer_id, product_id",
+        Vec::new(),
+        |row| {
+            Ok(CustomerProductHistory {
+                customer_id: row.get(0)?,
+                product_id: row.get(1)?,
+                last_purchase: LastPurchase {
+                    unit_price: row.get(2)?,
+                    unit: row.get(3)?,
+                    specification: row.get(4)?,
+                    quantity: row.get(5)?,
+                    date: row.get(6)?,
+                },
+            })
+        },
+    )
+}
+
+/// 清空全部业务数据（偏好设置保留），用于覆盖式恢复，调用方负责事务
+pub(crate) fn clear_business_data(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "DELETE FROM payment_allocations; DELETE FROM payments; \
+         DELETE FROM invoice_items; DELETE FROM invoices; DELETE FROM customers; \
+         DELETE FROM products; DELETE FROM quotations; DELETE FROM quotation_products; \
+         DELETE FROM customer_product_history;",
+    )
+    .map_err(db_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // 空库不做迁移前备份，目录不会被创建
+        db_migrations::migrate(&mut conn, Path::new("unused")).unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        conn
+    }
+
+    fn from_json<T: DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn to_value<T: Serialize>(value: &T) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    fn customer_ids(conn: &Connection, filter: Value, keyword_columns: &[&str]) -> Vec<String> {
+        let filter: SearchFilter = from_json(filter);
+        let mut query = Query::default();
+        query.keyword(non_empty(&filter.keyword), keyword_columns);
+        query.order_by(&filter, &[("name", "name")], "created_at");
+        let sql = query.sql(&select_sql("customers", CUSTOMER_COLUMNS), &filter);
+        query_rows(conn, &sql, query.values, customer_from_row)
+            .unwrap()
+            .into_iter()
+            .map(|customer| customer.id)
+            .collect()
+    }
+
+    #[test]
+    fn customer_round_trip_update_and_delete() {
+        let conn = setup();
+        let customer: Customer = from_json(json!({
+            "id": "c1", "name": "张三", "category": "批发", "initialDebt": 123.45,
+            "phone": "13800000000", "address": "上海", "images": ["a.jpg"],
+            "attachments": [], "createdAt": "2026-01-01T00:00:00.000Z",
+            "updatedAt": "2026-01-01T00:00:00.000Z", "isActive": true,
+        }));
+        upsert_customer(&conn, &customer).unwrap();
+        let stored = get_customer_by_id(&conn, "c1").unwrap().unwrap();
+        assert_eq!(to_value(&stored), to_value(&customer));
+
+        let mut updated = customer.clone();
+        updated.name = "张三（新）".to_string();
+        updated.address = None;
+        updated.is_active = false;
+        upsert_customer(&conn, &updated).unwrap();
+        let stored = get_customer_by_id(&conn, "c1").unwrap().unwrap();
+        assert_eq!(to_value(&stored), to_value(&updated));
+        assert_eq!(all_customers(&conn).unwrap().len(), 1);
+
+        conn.execute("DELETE FROM customers WHERE id = ?", ["c1"])
+            .unwrap();
+        assert!(get_customer_by_id(&conn, "c1").unwrap().is_none());
+    }
+
+    #[test]
+    fn invoice_items_are_replaced_and_kept_in_order() {
+        let conn = setup();
+        let mut invoice: Invoice = from_json(json!({
+            "id": "s1", "invoiceNumber": "XS20260101-0001", "date": "2026-01-01",
+            "customerInfo": { "name": "张三" }, "type": "sale", "status": "sent",
+            "items": [
+                { "id": "b", "productName": "螺丝", "quantity": 3, "unitPrice": 0.1 },
+                { "id": "a", "productName": "螺母", "quantity": 2.5, "unitPrice": 1.2,
+                  "deliveryQuantity": 2 },
+            ],
+            "paidAmount": 1, "rounding": { "level": "total" },
+        }));
+        money::apply(&mut invoice).unwrap();
+        upsert_invoice(&conn, &invoice).unwrap();
+        let stored = get_invoice_by_id(&conn, "s1").unwrap().unwrap();
+        assert_eq!(to_value(&stored), to_value(&invoice));
+        assert_eq!(stored.items[0].id, "b");
+        assert_eq!(stored.total_amount.to_string(), "3.3");
+
+        invoice.items.truncate(1);
+        money::apply(&mut invoice).unwrap();
+        upsert_invoice(&conn, &invoice).unwrap();
+        let stored = get_invoice_by_id(&conn, "s1").unwrap().unwrap();
+        assert_eq!(stored.items.len(), 1);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM invoice_items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn products_quotations_history_and_preferences_round_trip() {
+        let conn = setup();
+        let product: Product = from_json(json!({
+            "id": "p1", "name": "螺丝", "category": "五金", "unit": "个",
+            "specifications": [{ "id": "s", "name": "M6", "isDefault": true }],
+            "prices": [{ "id": "pr", "type": "sale", "price": 0.5, "isDefault": true }], "tags": ["常用"],
+        }));
+        upsert_product(&conn, &product).unwrap();
+        assert_eq!(
+            to_value(&all_products(&conn).unwrap()),
+            to_value(&vec![product])
+        );
+
+        let quotation: Quotation = from_json(json!({
+            "id": "q1", "quotationNumber": "BJ-1", "items": [{ "id": "r", "name": "螺丝", "price": 1 }],
+            "headerInfo": { "title": "报价单" }, "columns": [],
+        }));
+        upsert_quotation(&conn, &quotation).unwrap();
+        assert_eq!(
+            to_value(&all_quotations(&conn).unwrap()),
+            to_value(&vec![quotation])
+        );
+
+        let quotation_product: QuotationProduct =
+            from_json(json!({ "id": "qp1", "name": "螺母", "defaultPrice": 2.5 }));
+        upsert_quotation_product(&conn, &quotation_product).unwrap();
+        assert_eq!(
+            to_value(&all_quotation_products(&conn).unwrap()),
+            to_value(&vec![quotation_product])
+        );
+
+        let history = |date: &str, price: f64| -> CustomerProductHistory {
+            from_json(json!({
+                "customerId": "c1", "productId": "p1",
+                "lastPurchase": { "unitPrice": price, "date": date },
+            }))
+        };
+        assert!(upsert_customer_product_history(&conn, &history("2026-02-01", 1.0)).unwrap());
+        // 较早的记录不会覆盖较新的
+        assert!(!upsert_customer_product_history(&conn, &history("2026-01-01", 0.8)).unwrap());
+        let stored = all_customer_product_history(&conn).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].last_purchase.date, "2026-02-01");
+
+        set_preference(&conn, "last_selected_sales_company", "A").unwrap();
+        set_preference(&conn, "last_selected_sales_company", "B").unwrap();
+        assert_eq!(
+            preference(&conn, "last_selected_sales_company")
+                .unwrap()
+                .as_deref(),
+            Some("B")
+        );
+
+        clear_business_data(&conn).unwrap();
+        assert!(all_products(&conn).unwrap().is_empty());
+        assert!(all_customer_product_history(&conn).unwrap().is_empty());
+        assert!(preference(&conn, "last_selected_sales_company")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn keyword_wildcards_are_literal_and_sort_uses_whitelist() {
+        let conn = setup();
+        for (id, name) in [
+            ("c1", "折扣50%"),
+            ("c2", "五十"),
+            ("c3", "a_b"),
+            ("c4", "axb"),
+        ] {
+            let customer: Customer = from_json(json!({
+                "id": id, "name": name, "createdAt": id,
+            }));
+            upsert_customer(&conn, &customer).unwrap();
+        }
+        assert_eq!(
+            customer_ids(&conn, json!({ "keyword": "50%" }), &["name"]),
+            ["c1"]
+        );
+        assert_eq!(
+            customer_ids(&conn, json!({ "keyword": "a_b" }), &["name"]),
+            ["c3"]
+        );
+        assert_eq!(
+            customer_ids(&conn, json!({ "keyword": " " }), &["name"]).len(),
+            4
+        );
+        // 不在白名单中的排序字段按默认顺序
+        assert_eq!(
+            customer_ids(&conn, json!({ "sortBy": "id; DROP TABLE customers" }), &[]),
+            ["c1", "c2", "c3", "c4"]
+        );
+        assert_eq!(
+            customer_ids(
+                &conn,
+                json!({ "sortBy": "name", "sortOrder": "asc", "limit": 2, "offset": 1 }),
+                &[]
+            ),
+            ["c4", "c2"]
+        );
+    }
+}
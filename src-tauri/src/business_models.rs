@@ -0,0 +1,534 @@
+//! 业务数据模型，字段与 `src/lib/types/invoice.ts` 中的同名接口一一对应，
+//! 序列化为 camelCase 以便前端直接使用。
+//! 销售单金额使用定点小数 `Decimal`，JSON 中仍是数字（见 `money`）。
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 客户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Customer {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub category: String,
+    /// 期初欠款
+    #[serde(default, with = "crate::money::number")]
+    pub initial_debt: Decimal,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fax: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+/// 产品规格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductSpecification {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// 产品属性
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductAttribute {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// 价格类型：销售价、采购价、批发价
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceType {
+    Sale,
+    Purchase,
+    Wholesale,
+}
+
+/// 产品价格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductPrice {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub price_type: PriceType,
+    pub price: f64,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// 产品
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Product {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub barcode: Option<String>,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub specifications: Vec<ProductSpecification>,
+    #[serde(default)]
+    pub attributes: Vec<ProductAttribute>,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default)]
+    pub prices: Vec<ProductPrice>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+/// 公司信息（销售单抬头）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanyInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_id: Option<String>,
+}
+
+/// 销售单上的客户信息快照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// 销售单商品行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceItem {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<String>,
+    #[serde(default)]
+    pub product_name: String,
+    #[serde(default)]
+    pub specification: String,
+    #[serde(default)]
+    pub unit: String,
+    /// 销售数量
+    #[serde(default, with = "crate::money::number")]
+    pub quantity: Decimal,
+    /// 送货数量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_quantity: Option<f64>,
+    #[serde(default, with = "crate::money::number")]
+    pub unit_price: Decimal,
+    #[serde(default, with = "crate::money::number")]
+    pub amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvoiceStatus {
+    #[default]
+    Draft,
+    Sent,
+    Paid,
+    Cancelled,
+}
+
+/// 单据类型：销售单、采购单、退货单
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvoiceType {
+    #[default]
+    Sale,
+    Purchase,
+    Return,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    #[default]
+    Unpaid,
+    Partial,
+    Paid,
+}
+
+/// 合计的舍入层级：逐行四舍五入到分后相加，或按未舍入的行金额相加后再舍入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingLevel {
+    #[default]
+    Line,
+    Total,
+}
+
+/// 抹零单位：不抹零、抹到角、抹到元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WipeUnit {
+    #[default]
+    None,
+    Jiao,
+    Yuan,
+}
+
+/// 抹零方式：直接舍去或四舍五入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WipeMode {
+    #[default]
+    Truncate,
+    Round,
+}
+
+/// 销售单金额的舍入规则，省略时逐行舍入、不抹零
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoundingRule {
+    pub level: RoundingLevel,
+    pub wipe: WipeUnit,
+    pub wipe_mode: WipeMode,
+}
+
+/// 销售单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    pub id: String,
+    pub invoice_number: String,
+    #[serde(default)]
+    pub date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer_id: Option<String>,
+    #[serde(default)]
+    pub customer_info: CustomerInfo,
+    #[serde(default)]
+    pub company_info: CompanyInfo,
+    #[serde(default)]
+    pub items: Vec<InvoiceItem>,
+    #[serde(default, with = "crate::money::number")]
+    pub total_amount: Decimal,
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub status: InvoiceStatus,
+    #[serde(default, rename = "type")]
+    pub invoice_type: InvoiceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_date: Option<String>,
+    #[serde(default)]
+    pub payment_status: PaymentStatus,
+    #[serde(default, with = "crate::money::number")]
+    pub paid_amount: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<RoundingRule>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// 收款分配：一笔收款冲抵某张销售单的金额
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentAllocation {
+    pub invoice_id: String,
+    #[serde(with = "crate::money::number")]
+    pub amount: Decimal,
+    /// 销售单号，读取时由后端填写
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice_number: Option<String>,
+}
+
+/// 收款记录；未分配到销售单的部分作为预收款
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    pub id: String,
+    pub customer_id: String,
+    /// 收款日期 YYYY-MM-DD
+    #[serde(default)]
+    pub date: String,
+    #[serde(default, with = "crate::money::number")]
+    pub amount: Decimal,
+    /// 收款方式，如现金、微信、支付宝、银行转账
+    #[serde(default)]
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub allocations: Vec<PaymentAllocation>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// 报价单列定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotationColumn {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub field_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_sequence: Option<bool>,
+}
+
+/// 报价单行，除 id 外的字段由列配置决定，原样保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotationItem {
+    pub id: String,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotationHeaderInfo {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotationFooterInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note1: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note2: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_line: Option<String>,
+    /// Base64 或 URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_code_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_code_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_code_height: Option<f64>,
+}
+
+/// 报价单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quotation {
+    pub id: String,
+    #[serde(default)]
+    pub quotation_number: String,
+    /// 自定义名称，仅用于列表显示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_name: Option<String>,
+    #[serde(default)]
+    pub header_info: QuotationHeaderInfo,
+    #[serde(default)]
+    pub columns: Vec<QuotationColumn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_width: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_font_size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note1_font_size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note2_font_size: Option<f64>,
+    #[serde(default)]
+    pub items: Vec<QuotationItem>,
+    #[serde(default)]
+    pub footer_info: QuotationFooterInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// 报价产品（报价单独立使用的产品库）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotationProduct {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub specification: Option<String>,
+    /// 参考单价
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// 客户对某个产品的最后一次购买信息，对应 `src/lib/utils/customerHistory.ts`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerProductHistory {
+    pub customer_id: String,
+    pub product_id: String,
+    pub last_purchase: LastPurchase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastPurchase {
+    #[serde(default)]
+    pub unit_price: f64,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default)]
+    pub specification: String,
+    #[serde(default)]
+    pub quantity: f64,
+    /// ISO 8601
+    #[serde(default)]
+    pub date: String,
+}
+
+/// 列表查询条件，对应前端的 `SearchFilter`，另加客户筛选与分页
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilter {
+    pub keyword: Option<String>,
+    pub category: Option<String>,
+    /// 销售单日期范围（含），格式 YYYY-MM-DD
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    /// 销售单状态或付款状态
+    pub status: Option<String>,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<String>,
+    pub customer_id: Option<String>,
+    /// 默认只返回启用的客户与产品
+    #[serde(default)]
+    pub include_inactive: bool,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl InvoiceStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<InvoiceStatus> {
+        match value {
+            "draft" => Some(InvoiceStatus::Draft),
+            "sent" => Some(InvoiceStatus::Sent),
+            "paid" => Some(InvoiceStatus::Paid),
+            "cancelled" => Some(InvoiceStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl InvoiceType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvoiceType::Sale => "sale",
+            InvoiceType::Purchase => "purchase",
+            InvoiceType::Return => "return",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<InvoiceType> {
+        match value {
+            "sale" => Some(InvoiceType::Sale),
+            "purchase" => Some(InvoiceType::Purchase),
+            "return" => Some(InvoiceType::Return),
+            _ => None,
+        }
+    }
+}
+
+impl PaymentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaymentStatus::Unpaid => "unpaid",
+            PaymentStatus::Partial => "partial",
+            PaymentStatus::Paid => "paid",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<PaymentStatus> {
+        match value {
+            "unpaid" => Some(PaymentStatus::Unpaid),
+            "partial" => Some(PaymentStatus::Partial),
+            "paid" => Some(PaymentStatus::Paid),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::entitlements::Feature;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+#[cfg(desktop)]
+use std::time::Duration;
+#[cfg(target_os = "android")]
+use tauri_plugin_android_permissions::AndroidPermissionsExt;
+
+/// 检测到分享链接时向前端广播的事件名
+pub const SHARE_LINK_EVENT: &str = "share-link-detected";
+// 桌面端轮询剪贴板的间隔
+#[cfg(desktop)]
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+// 记住最近提示过的链接数量，同一链接只提示一次
+const MAX_REMEMBERED_LINKS: usize = 32;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareLink {
+    pub platform: String,
+    pub url: String,
+}
+
+#[derive(Default)]
+struct Offers {
+    recent: VecDeque<String>,
+    /// 已广播但前端尚未取走的链接（事件可能在页面挂载监听前发出）
+    pending: Option<ShareLink>,
+}
+
+/// 剪贴板监听状态，由 `run()` 中 `manage` 注册。
+/// 开通视频去水印后由前端调用 `start_clipboard_watch` 才开始监听
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    offers: Mutex<Offers>,
+    enabled: AtomicBool,
+}
+
+/// 从分享文案中提取第一个受支持平台的链接
+/// 例如 "7.43 复制打开抖音，看看【xx的作品】 https://v.douyin.com/abc/ a@b.cn 02/11"
+pub(crate) fn detect_share_link(text: &str) -> Option<ShareLink> {
+    let re = regex::Regex::new(r"https?://[^\s\u{3000}，。！？、；：“”‘’（）【】《》]+").ok()?;
+    let link = re.find_iter(text).find_map(|m| {
+        let url = m
+            .as_str()
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '"', '\''])
+            .to_string();
+        let platform = crate::detect_platform(&url);
+        (platform != "未知").then_some(ShareLink { platform, url })
+    });
+    link
+}
+
+impl ClipboardWatcher {
+    /// 记录新链接；最近已提示过的链接返回 false
+    fn remember(&self, link: &ShareLink) -> bool {
+        let Ok(mut offers) = self.offers.lock() else {
+            return false;
+        };
+        if offers.recent.iter().any(|url| url == &link.url) {
+            return false;
+        }
+        if offers.recent.len() >= MAX_REMEMBERED_LINKS {
+            offers.recent.pop_front();
+        }
+        offers.recent.push_back(link.url.clone());
+        offers.pending = Some(link.clone());
+        true
+    }
+
+    fn take_pending(&self) -> Option<ShareLink> {
+        self.offers.lock().ok()?.pending.take()
+    }
+}
+
+/// 对剪贴板文本做分享链接检测，新链接会广播 `share-link-detected` 事件
+fn offer<R: Runtime>(app: &AppHandle<R>, text: &str) -> Option<ShareLink> {
+    let link = detect_share_link(text)?;
+    if !app.state::<ClipboardWatcher>().remember(&link) {
+        return None;
+    }
+    let _ = app.emit(SHARE_LINK_EVENT, &link);
+    Some(link)
+}
+
+#[cfg(desktop)]
+fn read_text<R: Runtime>(_app: &AppHandle<R>) -> Result<Option<String>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("无法访问剪贴板: {}", e))?;
+    // 剪贴板为空或内容不是文本时 arboard 返回错误，这里视为没有内容
+    Ok(clipboard.get_text().ok())
+}
+
+#[cfg(target_os = "android")]
+fn read_text<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    app.android_permissions()
+        .read_clipboard()
+        .map(|response| response.text)
+        .map_err(|e| format!("读取剪贴板失败: {}", e))
+}
+
+#[cfg(target_os = "ios")]
+fn read_text<R: Runtime>(_app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Err("当前平台暂不支持读取剪贴板".to_string())
+}
+
+/// 桌面端：后台线程轮询剪贴板，内容变化时做链接检测
+#[cfg(desktop)]
+fn start<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // 无图形会话等情况下无法访问剪贴板，直接放弃监听
+        let Ok(mut clipboard) = arboard::Clipboard::new() else {
+            return;
+        };
+        let mut last_text: Option<String> = None;
+        loop {
+            if let Ok(text) = clipboard.get_text() {
+                if last_text.as_deref() != Some(text.as_str()) {
+                    offer(&app, &text);
+                    last_text = Some(text);
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// 移动端：窗口获得焦点时检查一次剪贴板。
+/// Android 10 起只有持有输入焦点的应用才能读到剪贴板，`Resumed` 时尚未获得焦点，读到的总是空内容
+#[cfg(mobile)]
+pub fn on_focus<R: Runtime>(app: &AppHandle<R>) {
+    if !app
+        .state::<ClipboardWatcher>()
+        .enabled
+        .load(Ordering::SeqCst)
+    {
+        return;
+    }
+    // 插件调用会等待原生侧返回，放到独立线程避免阻塞事件循环
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Ok(Some(text)) = read_text(&app) {
+            offer(&app, &text);
+        }
+    });
+}
+
+/// 开始监听剪贴板中的分享链接，仅在开通视频去水印时生效，返回是否已在监听。
+/// 重复调用不会启动多个轮询线程
+#[tauri::command]
+pub fn start_clipboard_watch<R: Runtime>(app: AppHandle<R>) -> Result<bool, String> {
+    let status = crate::access_control::evaluate_access(&app)?;
+    if !status.entitlements.features.contains(&Feature::VideoTools) {
+        return Ok(false);
+    }
+    if app
+        .state::<ClipboardWatcher>()
+        .enabled
+        .swap(true, Ordering::SeqCst)
+    {
+        return Ok(true);
+    }
+
+    #[cfg(desktop)]
+    start(&app);
+    // 启动时窗口已有焦点，不会再收到获得焦点事件，这里先检查一次
+    #[cfg(mobile)]
+    on_focus(&app);
+    Ok(true)
+}
+
+/// 读取剪贴板纯文本，供前端“粘贴”按钮使用
+#[tauri::command]
+pub fn read_clipboard_text<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
+    Ok(read_text(&app)?.unwrap_or_default())
+}
+
+/// 立即检查剪贴板，发现新的分享链接时广播事件并返回
+#[tauri::command]
+pub fn check_clipboard<R: Runtime>(app: AppHandle<R>) -> Result<Option<ShareLink>, String> {
+    Ok(read_text(&app)?.and_then(|text| offer(&app, &text)))
+}
+
+/// 取走尚未处理的分享链接，页面挂载后调用以补收之前发出的事件
+#[tauri::command]
+pub fn take_share_link(watcher: State<'_, ClipboardWatcher>) -> Option<ShareLink> {
+    watcher.take_pending()
+}
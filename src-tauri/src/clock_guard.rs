@@ -0,0 +1,185 @@
+use crate::install_stamp::{self, Record};
+use crate::license;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+
+// 允许的时钟回拨幅度（秒），可在许可证中通过 clockToleranceSecs 调整
+pub const DEFAULT_ROLLBACK_TOLERANCE_SECS: u64 = 6 * 60 * 60;
+// 最近一次看到的系统时间，只增不减
+const LAST_SEEN_FILENAME: &str = ".last_seen";
+// 记录的签名用途
+const PURPOSE: &str = "last_seen";
+
+/// 一次时钟检查的结果
+#[derive(Debug, Clone, Copy)]
+pub struct ClockCheck {
+    /// 当前系统时间
+    pub now: u64,
+    /// 此前记录到的最晚时间（含安装时间），作为可信时间的下限
+    pub last_seen: u64,
+    /// 时间记录签名无效（被删改），无法判断回拨幅度
+    pub record_tampered: bool,
+}
+
+impl ClockCheck {
+    /// 系统时间比已知最晚时间倒退了多少秒
+    pub fn rollback_secs(&self) -> u64 {
+        self.last_seen.saturating_sub(self.now)
+    }
+
+    /// 用于有效期判断的时间：系统时间被回拨时以已知最晚时间为准
+    pub fn effective_now(&self) -> u64 {
+        self.now.max(self.last_seen)
+    }
+
+    pub fn is_trusted(&self, tolerance_secs: u64) -> bool {
+        !self.record_tampered && self.rollback_secs() <= tolerance_secs
+    }
+}
+
+/// 本次启动第一次检查时得出的时间下限；之后的检查沿用它，不再读写记录文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LaunchRecord {
+    last_seen: u64,
+    tampered: bool,
+}
+
+static LAUNCH_RECORD: Mutex<Option<LaunchRecord>> = Mutex::new(None);
+
+fn last_seen_path<R: Runtime>(app: &AppHandle<R>) -> Result<(PathBuf, String), String> {
+    let device_id = license::device_id(app)?;
+    let path = license::data_dir(app)?.join(LAST_SEEN_FILENAME);
+    Ok((path, device_id))
+}
+
+/// 由记录得出本次启动的时间下限，以及需要写回记录的时间：
+/// 系统时间不早于下限时记录前进到当前时间；记录被删改时本次仍按不可信处理，
+/// 但只要时钟没有早于安装时间就重新签名，下次启动恢复正常
+fn launch_record(recorded: &Record, floor: u64, now: u64) -> (LaunchRecord, Option<u64>) {
+    match recorded {
+        Record::Invalid => (
+            LaunchRecord {
+                last_seen: floor,
+                tampered: true,
+            },
+            (now >= floor).then_some(now),
+        ),
+        Record::Valid(time) => {
+            let last_seen = (*time).max(floor);
+            let write = (now >= last_seen).then_some(now);
+            (
+                LaunchRecord {
+                    last_seen: write.unwrap_or(last_seen),
+                    tampered: false,
+                },
+                write,
+            )
+        }
+        Record::Missing => (
+            LaunchRecord {
+                last_seen: now.max(floor),
+                tampered: false,
+            },
+            (now >= floor).then_some(now),
+        ),
+    }
+}
+
+/// 启动检查：与上次记录的时间（不早于安装时间）比较，系统时间前进时更新记录。
+/// 记录每次启动只读写一次，之后的调用（如每次写入业务数据前的授权检查）直接使用缓存。
+/// 检测到回拨时不更新，回拨前看到的时间继续作为下限。
+pub fn check_clock<R: Runtime>(
+    app: &AppHandle<R>,
+    install_time: u64,
+) -> Result<ClockCheck, String> {
+    let now = license::now_secs()?;
+    let mut cached = LAUNCH_RECORD
+        .lock()
+        .map_err(|_| "时钟记录不可用".to_string())?;
+    let record = match *cached {
+        Some(record) => record,
+        None => {
+            let (path, device_id) = last_seen_path(app)?;
+            let recorded = install_stamp::read_signed(&path, PURPOSE, &device_id);
+            let (record, write) = launch_record(&recorded, install_time, now);
+            if let Some(time) = write {
+                install_stamp::write_signed(&path, PURPOSE, &device_id, time)?;
+            }
+            *cached = Some(record);
+            record
+        }
+    };
+    Ok(ClockCheck {
+        now,
+        last_seen: record.last_seen,
+        record_tampered: record.tampered,
+    })
+}
+
+/// 只读地获取可信的当前时间（不更新记录），用于导入许可证等操作
+pub fn effective_now<R: Runtime>(app: &AppHandle<R>) -> Result<u64, String> {
+    let now = license::now_secs()?;
+    let cached = *LAUNCH_RECORD
+        .lock()
+        .map_err(|_| "时钟记录不可用".to_string())?;
+    let last_seen = match cached {
+        Some(record) => record.last_seen,
+        None => {
+            let (path, device_id) = last_seen_path(app)?;
+            match install_stamp::read_signed(&path, PURPOSE, &device_id) {
+                Record::Valid(time) => time,
+                _ => 0,
+            }
+        }
+    };
+    Ok(now.max(last_seen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTALL: u64 = 1_700_000_000;
+
+    #[test]
+    fn advances_the_record_when_the_clock_moves_forward() {
+        let (record, write) = launch_record(&Record::Valid(INSTALL + 100), INSTALL, INSTALL + 500);
+        assert_eq!(write, Some(INSTALL + 500));
+        assert_eq!(record.last_seen, INSTALL + 500);
+        assert!(!record.tampered);
+
+        let (record, write) = launch_record(&Record::Missing, INSTALL, INSTALL + 10);
+        assert_eq!(write, Some(INSTALL + 10));
+        assert_eq!(record.last_seen, INSTALL + 10);
+    }
+
+    #[test]
+    fn keeps_the_floor_when_the_clock_is_rolled_back() {
+        let (record, write) = launch_record(&Record::Valid(INSTALL + 500), INSTALL, INSTALL + 100);
+        assert_eq!(write, None);
+        assert_eq!(record.last_seen, INSTALL + 500);
+
+        let check = ClockCheck {
+            now: INSTALL + 100,
+            last_seen: record.last_seen,
+            record_tampered: record.tampered,
+        };
+        assert_eq!(check.rollback_secs(), 400);
+        assert_eq!(check.effective_now(), INSTALL + 500);
+        assert!(check.is_trusted(400));
+        assert!(!check.is_trusted(399));
+    }
+
+    #[test]
+    fn re_signs_a_tampered_record_once_the_clock_is_past_the_floor() {
+        let (record, write) = launch_record(&Record::Invalid, INSTALL, INSTALL + 100);
+        assert!(record.tampered);
+        assert_eq!(write, Some(INSTALL + 100));
+
+        let (record, write) = launch_record(&Record::Invalid, INSTALL, INSTALL - 100);
+        assert!(record.tampered);
+        assert_eq!(record.last_seen, INSTALL);
+        assert_eq!(write, None);
+    }
+}
@@ -0,0 +1,580 @@
+//! 业务数据库的版本化迁移。
+//!
+//! 迁移按版本号顺序只进不退：已发布的迁移不能修改，表结构变化一律追加新的迁移。
+//! 每个迁移在独立事务中执行并写入 `schema_version`；执行前先把数据库完整备份一份。
+
+use crate::business_db::{db_err, now_iso};
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+// 迁移前备份文件名前缀，只保留最近几份
+const BACKUP_PREFIX: &str = "pre-migration-";
+const MAX_BACKUPS: usize = 5;
+
+struct Migration {
+    version: u32,
+    description: &'static str,
+    sql: &'static str,
+}
+
+// 版本 1、2 使用 IF NOT EXISTS，以便接管迁移机制引入之前创建的数据库
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "客户、产品、销售单与报价单",
+        sql: "
+            CREATE TABLE IF NOT EXISTS customers (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                category TEXT NOT NULL DEFAULT '',
+                initial_debt REAL NOT NULL DEFAULT 0,
+                phone TEXT NOT NULL DEFAULT '',
+                backup_phone TEXT,
+                address TEXT,
+                fax TEXT,
+                email TEXT,
+                notes TEXT,
+                images TEXT NOT NULL DEFAULT '[]',
+                attachments TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                is_active INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_customers_name ON customers(name);
+
+            CREATE TABLE IF NOT EXISTS products (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                barcode TEXT,
+                category TEXT NOT NULL DEFAULT '',
+                specifications TEXT NOT NULL DEFAULT '[]',
+                attributes TEXT NOT NULL DEFAULT '[]',
+                unit TEXT NOT NULL DEFAULT '',
+                prices TEXT NOT NULL DEFAULT '[]',
+                tags TEXT NOT NULL DEFAULT '[]',
+                notes TEXT,
+                images TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                is_active INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_products_name ON products(name);
+
+            CREATE TABLE IF NOT EXISTS invoices (
+                id TEXT PRIMARY KEY,
+                invoice_number TEXT NOT NULL,
+                date TEXT NOT NULL,
+                customer_id TEXT,
+                customer_info TEXT NOT NULL,
+                company_info TEXT NOT NULL,
+                total_amount REAL NOT NULL DEFAULT 0,
+                created_by TEXT NOT NULL DEFAULT '',
+                notes TEXT,
+                status TEXT NOT NULL,
+                type TEXT NOT NULL,
+                delivery_date TEXT,
+                payment_status TEXT NOT NULL,
+                paid_amount REAL NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_invoices_date ON invoices(date);
+            CREATE INDEX IF NOT EXISTS idx_invoices_customer ON invoices(customer_id);
+            CREATE INDEX IF NOT EXISTS idx_invoices_number ON invoices(invoice_number);
+
+            CREATE TABLE IF NOT EXISTS invoice_items (
+                invoice_id TEXT NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                id TEXT NOT NULL,
+                product_id TEXT,
+                product_name TEXT NOT NULL DEFAULT '',
+                specification TEXT NOT NULL DEFAULT '',
+                unit TEXT NOT NULL DEFAULT '',
+                quantity REAL NOT NULL DEFAULT 0,
+                delivery_quantity REAL,
+                unit_price REAL NOT NULL DEFAULT 0,
+                amount REAL NOT NULL DEFAULT 0,
+                note TEXT,
+                PRIMARY KEY (invoice_id, position)
+            );
+            CREATE INDEX IF NOT EXISTS idx_invoice_items_product ON invoice_items(product_id);
+
+            CREATE TABLE IF NOT EXISTS quotations (
+                id TEXT PRIMARY KEY,
+                quotation_number TEXT NOT NULL DEFAULT '',
+                custom_name TEXT,
+                header_info TEXT NOT NULL,
+                columns TEXT NOT NULL DEFAULT '[]',
+                table_width TEXT,
+                header_font_size REAL,
+                note1_font_size REAL,
+                note2_font_size REAL,
+                items TEXT NOT NULL DEFAULT '[]',
+                footer_info TEXT NOT NULL,
+                created_by TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                notes TEXT
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "报价产品、客户购买历史与偏好设置",
+        sql: "
+            CREATE TABLE IF NOT EXISTS quotation_products (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                specification TEXT,
+                default_price REAL,
+                notes TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS customer_product_history (
+                customer_id TEXT NOT NULL,
+                product_id TEXT NOT NULL,
+                unit_price REAL NOT NULL DEFAULT 0,
+                unit TEXT NOT NULL DEFAULT '',
+                specification TEXT NOT NULL DEFAULT '',
+                quantity REAL NOT NULL DEFAULT 0,
+                date TEXT NOT NULL,
+                PRIMARY KEY (customer_id, product_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS preferences (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "业务数据变更计数（自动备份）",
+        sql: "
+            CREATE TABLE change_counter (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                changes INTEGER NOT NULL DEFAULT 0
+            );
+            INSERT INTO change_counter (id, changes) VALUES (1, 0);
+
+            CREATE TRIGGER customers_count_insert AFTER INSERT ON customers
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER customers_count_update AFTER UPDATE ON customers
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER customers_count_delete AFTER DELETE ON customers
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER products_count_insert AFTER INSERT ON products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER products_count_update AFTER UPDATE ON products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER products_count_delete AFTER DELETE ON products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER invoices_count_insert AFTER INSERT ON invoices
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER invoices_count_update AFTER UPDATE ON invoices
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER invoices_count_delete AFTER DELETE ON invoices
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotations_count_insert AFTER INSERT ON quotations
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotations_count_update AFTER UPDATE ON quotations
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotations_count_delete AFTER DELETE ON quotations
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotation_products_count_insert AFTER INSERT ON quotation_products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotation_products_count_update AFTER UPDATE ON quotation_products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER quotation_products_count_delete AFTER DELETE ON quotation_products
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "销售单金额舍入规则",
+        sql: "ALTER TABLE invoices ADD COLUMN rounding TEXT;",
+    },
+    Migration {
+        version: 5,
+        description: "单据编号流水号",
+        sql: "
+            CREATE TABLE number_sequences (
+                kind TEXT NOT NULL,
+                period TEXT NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (kind, period)
+            );
+            CREATE INDEX IF NOT EXISTS idx_quotations_number ON quotations(quotation_number);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "收款记录与收款分配",
+        sql: "
+            CREATE TABLE payments (
+                id TEXT PRIMARY KEY,
+                customer_id TEXT NOT NULL,
+                date TEXT NOT NULL,
+                amount REAL NOT NULL,
+                method TEXT NOT NULL DEFAULT '',
+                note TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX idx_payments_customer ON payments(customer_id, date);
+
+            CREATE TABLE payment_allocations (
+                payment_id TEXT NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+                invoice_id TEXT NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                amount REAL NOT NULL,
+                PRIMARY KEY (payment_id, invoice_id)
+            );
+            CREATE INDEX idx_payment_allocations_invoice ON payment_allocations(invoice_id);
+
+            CREATE TRIGGER payments_count_insert AFTER INSERT ON payments
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER payments_count_update AFTER UPDATE ON payments
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+            CREATE TRIGGER payments_count_delete AFTER DELETE ON payments
+            BEGIN UPDATE change_counter SET changes = changes + 1 WHERE id = 1; END;
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "销售单已收金额转为开单收款记录",
+        // 此后销售单的已付金额只由收款分配得出；与 receivables::settle_imported 的规则一致
+        sql: "
+            CREATE TEMP TABLE legacy_paid AS
+            SELECT i.id AS invoice_id, COALESCE(i.customer_id, '') AS customer_id, i.date,
+                   ROUND(i.paid_amount - COALESCE(
+                       (SELECT SUM(a.amount) FROM payment_allocations a WHERE a.invoice_id = i.id), 0
+                   ), 2) AS amount,
+                   i.created_at, i.updated_at
+            FROM invoices i
+            WHERE i.type = 'sale' AND i.status != 'cancelled';
+            DELETE FROM legacy_paid WHERE amount <= 0;
+
+            INSERT INTO payments (id, customer_id, date, amount, method, note, created_at, updated_at)
+            SELECT 'legacy-paid-' || invoice_id, customer_id, date, amount, '开单收款', NULL,
+                   created_at, updated_at
+            FROM legacy_paid;
+            INSERT INTO payment_allocations (payment_id, invoice_id, position, amount)
+            SELECT 'legacy-paid-' || invoice_id, invoice_id, 0, amount FROM legacy_paid;
+            DROP TABLE legacy_paid;
+        ",
+    },
+];
+
+/// 当前应用支持的最高数据库版本
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn current_version(conn: &Connection) -> Result<u32, String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );",
+    )
+    .map_err(db_err)?;
+    conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+        row.get::<_, Option<u32>>(0)
+    })
+    .map(|version| version.unwrap_or(0))
+    .map_err(db_err)
+}
+
+/// 数据库中是否已有业务表（全新的空库不需要备份）
+fn has_user_tables(conn: &Connection) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name != 'schema_version')",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(db_err)
+}
+
+/// 用 `VACUUM INTO` 生成一致的备份副本（WAL 模式下同样完整），并清理较早的备份
+fn backup(conn: &Connection, dir: &Path, version: u32) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    let name = format!(
+        "{}v{}-{}.db",
+        BACKUP_PREFIX,
+        version,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = dir.join(name);
+    conn.execute("VACUUM INTO ?", [path.to_string_lossy()])
+        .map_err(|e| format!("迁移前备份数据库失败: {}", e))?;
+
+    let mut backups: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("读取备份目录失败: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            Some((backup_sort_key(&name)?, entry.path()))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(MAX_BACKUPS);
+    for (_, old) in backups.into_iter().take(excess) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// 备份文件名 `pre-migration-v{版本}-{时间戳}.db` 的排序键：先按时间戳，同一秒内再按版本。
+/// 不能直接比较文件名，否则 v10 会排在 v9 之前
+fn backup_sort_key(name: &str) -> Option<(String, u32)> {
+    let rest = name.strip_prefix(BACKUP_PREFIX)?.strip_prefix('v')?;
+    let (version, timestamp) = rest.split_once('-')?;
+    Some((timestamp.to_string(), version.parse().ok()?))
+}
+
+/// 把数据库升级到最新版本。数据库版本高于应用时拒绝打开，避免旧版应用写坏新格式的数据
+pub fn migrate(conn: &mut Connection, backup_dir: &Path) -> Result<(), String> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(format!(
+            "数据库版本（{}）高于当前应用支持的版本（{}），请升级应用后再打开",
+            current, latest
+        ));
+    }
+    if current == latest {
+        return Ok(());
+    }
+
+    if has_user_tables(conn)? {
+        backup(conn, backup_dir, current)?;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let fail = |e: String| {
+            format!(
+                "数据库迁移到版本 {}（{}）失败: {}",
+                migration.version, migration.description, e
+            )
+        };
+        let tx = conn.transaction().map_err(|e| fail(e.to_string()))?;
+        tx.execute_batch(migration.sql)
+            .map_err(|e| fail(e.to_string()))?;
+        // 迁移期间外键约束未开启，提交前统一检查一次
+        let violations: i64 = tx
+            .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| fail(e.to_string()))?;
+        if violations > 0 {
+            return Err(fail(format!("存在 {} 条外键不一致的记录", violations)));
+        }
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+            (migration.version, migration.description, now_iso()),
+        )
+        .map_err(|e| fail(e.to_string()))?;
+        tx.commit().map_err(|e| fail(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("db-migrations-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn versions(conn: &Connection) -> Vec<u32> {
+        let mut stmt = conn
+            .prepare("SELECT version FROM schema_version ORDER BY version")
+            .unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+        rows.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    fn backups(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+    }
+
+    #[test]
+    fn fresh_database_is_migrated_without_backup() {
+        let dir = temp_dir();
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, &dir).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert_eq!(versions(&conn), (1..=latest_version()).collect::<Vec<_>>());
+        assert!(!dir.exists());
+
+        // 已是最新版本时不再执行任何迁移
+        migrate(&mut conn, &dir).unwrap();
+        assert_eq!(versions(&conn).len(), MIGRATIONS.len());
+        let counter: i64 = conn
+            .query_row(
+                "SELECT changes FROM change_counter WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(counter, 0);
+    }
+
+    #[test]
+    fn upgrade_keeps_data_and_backs_up_first() {
+        let dir = temp_dir();
+        let mut conn = Connection::open_in_memory().unwrap();
+        current_version(&conn).unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (1, '', '')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO customers (id, name, created_at, updated_at) VALUES ('c1', '张三', '', '')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&mut conn, &dir).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let name: String = conn
+            .query_row("SELECT name FROM customers WHERE id = 'c1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(name, "张三");
+
+        let names = backups(&dir);
+        assert_eq!(names.len(), 1);
+        assert!(names[0].starts_with("pre-migration-v1-"));
+        let backup = Connection::open(dir.join(&names[0])).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn converts_paid_amounts_of_sales_into_payments() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        current_version(&conn).unwrap();
+        for migration in &MIGRATIONS[..6] {
+            conn.execute_batch(migration.sql).unwrap();
+            conn.execute(
+                "INSERT INTO schema_version (version, description, applied_at) VALUES (?, '', '')",
+                [migration.version],
+            )
+            .unwrap();
+        }
+        for (id, invoice_type, customer) in [
+            ("s1", "sale", Some("c1")),
+            ("s2", "sale", None),
+            ("r1", "return", Some("c1")),
+        ] {
+            let invoice: crate::business_models::Invoice =
+                serde_json::from_value(serde_json::json!({
+                    "id": id, "invoiceNumber": id, "date": "2026-01-05", "type": invoice_type,
+                    "customerId": customer, "totalAmount": 100, "paidAmount": 40,
+                }))
+                .unwrap();
+            crate::business_db::upsert_invoice(&conn, &invoice).unwrap();
+        }
+
+        let dir = temp_dir();
+        migrate(&mut conn, &dir).unwrap();
+        let payments: Vec<(String, String, f64)> = {
+            let mut stmt = conn
+                .prepare("SELECT id, customer_id, amount FROM payments ORDER BY id")
+                .unwrap();
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap();
+            rows.collect::<rusqlite::Result<_>>().unwrap()
+        };
+        assert_eq!(
+            payments,
+            [
+                ("legacy-paid-s1".to_string(), "c1".to_string(), 40.0),
+                ("legacy-paid-s2".to_string(), String::new(), 40.0),
+            ]
+        );
+        let allocated: f64 = conn
+            .query_row(
+                "SELECT SUM(amount) FROM payment_allocations WHERE invoice_id = 's1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(allocated, 40.0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_database_from_newer_app() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, Path::new("unused")).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?, '', '')",
+            [latest_version() + 1],
+        )
+        .unwrap();
+        let err = migrate(&mut conn, Path::new("unused")).unwrap_err();
+        assert!(err.contains("请升级应用"));
+    }
+
+    #[test]
+    fn keeps_only_latest_backups() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        // 最新的一份版本号为两位数，按文件名排序会排到最前面
+        for day in 1..=6 {
+            let version = if day == 6 { 10 } else { 9 };
+            fs::write(
+                dir.join(format!(
+                    "pre-migration-v{}-2020010{}-000000.db",
+                    version, day
+                )),
+                b"",
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("other.db"), b"").unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (x INTEGER);").unwrap();
+        backup(&conn, &dir, 2).unwrap();
+
+        let names = backups(&dir);
+        assert_eq!(names.len(), MAX_BACKUPS + 1);
+        assert!(names.contains(&"other.db".to_string()));
+        assert!(!names.contains(&"pre-migration-v9-20200101-000000.db".to_string()));
+        assert!(!names.contains(&"pre-migration-v9-20200102-000000.db".to_string()));
+        assert!(names.contains(&"pre-migration-v10-20200106-000000.db".to_string()));
+        assert!(names
+            .iter()
+            .any(|name| name.starts_with("pre-migration-v2-")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
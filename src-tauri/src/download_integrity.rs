@@ -0,0 +1,714 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// 下载过程中使用的临时文件后缀，校验通过后才重命名为正式文件
+const PARTIAL_SUFFIX: &str = ".part";
+// 校验失败的文件移入的隔离目录名
+const QUARANTINE_DIRNAME: &str = "quarantine";
+// 下载记录日志，每行一条 JSON，保存在应用数据目录中
+const DOWNLOAD_LOG_FILENAME: &str = "downloads.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Mp4,
+    Heif,
+    Jpeg,
+    Png,
+    Webp,
+    Gif,
+    Mp3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaCategory {
+    Video,
+    Image,
+    Audio,
+}
+
+/// 一次下载的最终记录：保存路径、实际字节数与 SHA-256
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRecord {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub media_kind: MediaKind,
+    /// 写入来源信息失败时的原因（不影响下载结果）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_error: Option<String>,
+    /// 图片转码失败时的原因，此时保存的是原始格式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversion_error: Option<String>,
+}
+
+impl MediaKind {
+    pub fn extension(self) -> &'static str {
+        match self {
+            MediaKind::Mp4 => "mp4",
+            MediaKind::Heif => "heic",
+            MediaKind::Jpeg => "jpg",
+            MediaKind::Png => "png",
+            MediaKind::Webp => "webp",
+            MediaKind::Gif => "gif",
+            MediaKind::Mp3 => "mp3",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            MediaKind::Mp4 => "video/mp4",
+            MediaKind::Heif => "image/heif",
+            MediaKind::Jpeg => "image/jpeg",
+            MediaKind::Png => "image/png",
+            MediaKind::Webp => "image/webp",
+            MediaKind::Gif => "image/gif",
+            MediaKind::Mp3 => "audio/mpeg",
+        }
+    }
+
+    pub(crate) fn matches(self, expected: MediaCategory) -> bool {
+        match expected {
+            MediaCategory::Video => self == MediaKind::Mp4,
+            MediaCategory::Image => matches!(
+                self,
+                MediaKind::Jpeg
+                    | MediaKind::Png
+                    | MediaKind::Webp
+                    | MediaKind::Gif
+                    | MediaKind::Heif
+            ),
+            MediaCategory::Audio => matches!(self, MediaKind::Mp3 | MediaKind::Mp4),
+        }
+    }
+}
+
+/// 根据文件名后缀推断期望的媒体类别，未知后缀不做类别限制
+pub fn expected_category(filename: &str) -> Option<MediaCategory> {
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())?;
+
+    match ext.as_str() {
+        "mp4" | "mov" | "m4v" => Some(MediaCategory::Video),
+        "jpg" | "jpeg" | "png" | "webp" | "gif" | "heic" | "heif" | "avif" => {
+            Some(MediaCategory::Image)
+        }
+        "mp3" | "m4a" | "aac" => Some(MediaCategory::Audio),
+        _ => None,
+    }
+}
+
+/// 通过文件头识别容器格式
+pub fn sniff_media_kind(head: &[u8]) -> Option<MediaKind> {
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return match &head[8..12] {
+            b"heic" | b"heix" | b"heim" | b"heis" | b"mif1" | b"msf1" | b"avif" | b"avis" => {
+                Some(MediaKind::Heif)
+            }
+            _ => Some(MediaKind::Mp4),
+        };
+    }
+    // 部分 QuickTime 文件没有 ftyp，直接以 moov/mdat 等盒子开头
+    // DASH 媒体分段（.m4s）以 styp 开头
+    if head.len() >= 8
+        && matches!(
+            &head[4..8],
+            b"moov" | b"mdat" | b"wide" | b"free" | b"skip" | b"styp"
+        )
+    {
+        return Some(MediaKind::Mp4);
+    }
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(MediaKind::Jpeg);
+    }
+    if head.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some(MediaKind::Png);
+    }
+    if head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return Some(MediaKind::Webp);
+    }
+    if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        return Some(MediaKind::Gif);
+    }
+    if head.starts_with(b"ID3") || (head.len() >= 2 && head[0] == 0xFF && head[1] & 0xE0 == 0xE0) {
+        return Some(MediaKind::Mp3);
+    }
+    None
+}
+
+fn read_tail<F: Read + Seek>(file: &mut F, len: u64, count: u64) -> Result<Vec<u8>, String> {
+    let count = count.min(len);
+    file.seek(SeekFrom::Start(len - count))
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let mut buf = vec![0u8; count as usize];
+    file.read_exact(&mut buf)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(buf)
+}
+
+/// 逐个遍历 MP4 顶层盒子，确认盒子长度与文件长度严格吻合
+fn verify_mp4_boxes<F: Read + Seek>(
+    file: &mut F,
+    len: u64,
+    require_moov: bool,
+) -> Result<(), String> {
+    let mut offset = 0u64;
+    let mut has_moov = false;
+    let mut has_moof = false;
+    let mut is_segment = false;
+
+    while offset < len {
+        if len - offset < 8 {
+            return Err(format!(
+                "MP4 结构不完整: 偏移 {} 处剩余数据不足一个盒子头",
+                offset
+            ));
+        }
+
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        let mut header = [0u8; 16];
+        let available = (len - offset).min(16) as usize;
+        file.read_exact(&mut header[..available])
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+
+        let box_type = &header[4..8];
+        if !box_type.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            return Err(format!("MP4 结构损坏: 偏移 {} 处盒子类型无效", offset));
+        }
+        match box_type {
+            b"moov" => has_moov = true,
+            b"moof" => has_moof = true,
+            b"styp" if offset == 0 => is_segment = true,
+            _ => {}
+        }
+
+        let size32 = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let box_size = match size32 {
+            0 => len - offset,
+            1 => {
+                if available < 16 {
+                    return Err("MP4 结构不完整: 扩展盒子头被截断".to_string());
+                }
+                let mut large = [0u8; 8];
+                large.copy_from_slice(&header[8..16]);
+                u64::from_be_bytes(large)
+            }
+            size => size,
+        };
+
+        let header_len = if size32 == 1 { 16 } else { 8 };
+        if box_size < header_len {
+            return Err(format!("MP4 结构损坏: 偏移 {} 处盒子长度无效", offset));
+        }
+        let Some(box_end) = offset.checked_add(box_size).filter(|end| *end <= len) else {
+            return Err(format!(
+                "MP4 结构不完整: {} 盒子需要 {} 字节，文件仅剩 {} 字节",
+                String::from_utf8_lossy(box_type),
+                box_size,
+                len - offset
+            ));
+        };
+
+        offset = box_end;
+    }
+
+    // 媒体分段没有 moov，样本信息在 moof 中
+    if is_segment {
+        if !has_moof {
+            return Err("MP4 分段不完整: 缺少 moof 盒子".to_string());
+        }
+    } else if require_moov && !has_moov {
+        return Err("MP4 结构不完整: 缺少 moov 盒子".to_string());
+    }
+
+    Ok(())
+}
+
+/// 校验已落盘文件的容器头与结尾标记，返回识别出的格式
+pub fn verify_media_file(
+    path: &Path,
+    expected: Option<MediaCategory>,
+) -> Result<MediaKind, String> {
+    let mut file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+
+    verify_media(&mut file, len, expected)
+}
+
+/// 校验内存中的完整数据，规则与 [`verify_media_file`] 相同
+pub fn verify_media_bytes(
+    data: &[u8],
+    expected: Option<MediaCategory>,
+) -> Result<MediaKind, String> {
+    verify_media(&mut Cursor::new(data), data.len() as u64, expected)
+}
+
+fn verify_media<F: Read + Seek>(
+    file: &mut F,
+    len: u64,
+    expected: Option<MediaCategory>,
+) -> Result<MediaKind, String> {
+    if len == 0 {
+        return Err("文件为空".to_string());
+    }
+
+    let mut head = [0u8; 32];
+    let head_len = (len as usize).min(head.len());
+    file.read_exact(&mut head[..head_len])
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+
+    let kind = sniff_media_kind(&head[..head_len])
+        .ok_or_else(|| "文件内容不是可识别的媒体格式（可能是错误页面）".to_string())?;
+
+    if let Some(expected) = expected {
+        if !kind.matches(expected) {
+            return Err(format!(
+                "文件格式与期望不符: 期望 {:?}，实际为 {:?}",
+                expected, kind
+            ));
+        }
+    }
+
+    match kind {
+        MediaKind::Mp4 => verify_mp4_boxes(file, len, true)?,
+        MediaKind::Heif => verify_mp4_boxes(file, len, false)?,
+        MediaKind::Jpeg => {
+            // EOI 之后可能跟随少量填充字节，只在结尾附近查找
+            let tail = read_tail(file, len, 32)?;
+            if !tail.windows(2).any(|w| w == [0xFF, 0xD9]) {
+                return Err("JPEG 图片不完整: 缺少结束标记".to_string());
+            }
+        }
+        MediaKind::Png => {
+            let tail = read_tail(file, len, 12)?;
+            if tail != [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82] {
+                return Err("PNG 图片不完整: 缺少 IEND 块".to_string());
+            }
+        }
+        MediaKind::Webp => {
+            let riff_size = u32::from_le_bytes([head[4], head[5], head[6], head[7]]) as u64;
+            if riff_size + 8 > len {
+                return Err(format!(
+                    "WebP 图片不完整: 期望 {} 字节，实际 {} 字节",
+                    riff_size + 8,
+                    len
+                ));
+            }
+        }
+        MediaKind::Gif => {
+            let tail = read_tail(file, len, 1)?;
+            if tail != [0x3B] {
+                return Err("GIF 图片不完整: 缺少结束标记".to_string());
+            }
+        }
+        MediaKind::Mp3 => {}
+    }
+
+    Ok(kind)
+}
+
+/// 计算已落盘文件的 SHA-256（十六进制）
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn partial_path_for(dest: &Path) -> PathBuf {
+    let mut name = dest
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(PARTIAL_SUFFIX);
+    dest.with_file_name(name)
+}
+
+/// 将校验失败的文件移入隔离目录，无法移动时直接删除
+fn quarantine_file(path: &Path, quarantine_root: Option<&Path>) -> Option<PathBuf> {
+    if let Some(root) = quarantine_root {
+        let dir = root.join(QUARANTINE_DIRNAME);
+        if fs::create_dir_all(&dir).is_ok() {
+            if let Some(name) = path.file_name() {
+                let target = dir.join(name);
+                if fs::rename(path, &target).is_ok() {
+                    return Some(target);
+                }
+            }
+        }
+    }
+    let _ = fs::remove_file(path);
+    None
+}
+
+/// 以流式方式把响应写入 `<dest>.part`，同时计算 SHA-256。
+/// 长度与 `Content-Length` 不符、写入失败时删除临时文件；容器校验失败时移入隔离目录。
+/// 全部通过后才重命名为 `dest`；`dest` 已存在时改用追加序号的文件名，返回记录中的 `path` 为实际位置。
+pub async fn save_response_verified(
+    mut response: reqwest::Response,
+    dest: &Path,
+    quarantine_root: Option<&Path>,
+) -> Result<DownloadRecord, String> {
+    let expected_len = response.content_length();
+    let part_path = partial_path_for(dest);
+
+    let mut file = File::create(&part_path).map_err(|e| format!("创建文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut received: u64 = 0;
+
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                drop(file);
+                let _ = fs::remove_file(&part_path);
+                return Err(format!("读取数据失败: {}", e));
+            }
+        };
+
+        if let Err(e) = file.write_all(&chunk) {
+            drop(file);
+            let _ = fs::remove_file(&part_path);
+            return Err(format!("保存文件失败: {}", e));
+        }
+        hasher.update(&chunk);
+        received += chunk.len() as u64;
+    }
+
+    if let Err(e) = file.sync_all() {
+        drop(file);
+        let _ = fs::remove_file(&part_path);
+        return Err(format!("保存文件失败: {}", e));
+    }
+    drop(file);
+
+    if let Some(expected) = expected_len {
+        if received != expected {
+            let _ = fs::remove_file(&part_path);
+            return Err(format!(
+                "下载不完整: 已接收 {} 字节，服务器声明 {} 字节",
+                received, expected
+            ));
+        }
+    }
+
+    let file_name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let media_kind = match verify_media_file(&part_path, expected_category(&file_name)) {
+        Ok(kind) => kind,
+        Err(err) => {
+            return Err(match quarantine_file(&part_path, quarantine_root) {
+                Some(target) => format!("{}（已隔离至 {}）", err, target.to_string_lossy()),
+                None => err,
+            });
+        }
+    };
+
+    // 下载期间出现了同名文件（例如用户另存了一份）时不覆盖
+    let dest = match (dest.exists(), dest.parent()) {
+        (true, Some(dir)) => crate::media_store::unique_path(dir, &file_name),
+        _ => dest.to_path_buf(),
+    };
+    if let Err(e) = fs::rename(&part_path, &dest) {
+        let _ = fs::remove_file(&part_path);
+        return Err(format!("保存文件失败: {}", e));
+    }
+
+    Ok(DownloadRecord {
+        path: dest.to_string_lossy().to_string(),
+        size: received,
+        sha256: hex::encode(hasher.finalize()),
+        media_kind,
+        metadata_error: None,
+        conversion_error: None,
+    })
+}
+
+/// 下载日志中的一条记录：最终路径（桌面端为文件路径，Android 为 content:// URI）、字节数与 SHA-256
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedDownload {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub saved_at: String,
+}
+
+/// 重新校验的结果；`intact` 为 false 表示文件在保存后被修改或损坏
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadVerification {
+    pub path: String,
+    pub saved_at: String,
+    pub expected_size: u64,
+    pub expected_sha256: String,
+    pub actual_size: u64,
+    pub actual_sha256: String,
+    pub intact: bool,
+}
+
+/// 在 `dir` 下的下载日志末尾追加一条记录
+pub fn append_download_log(dir: &Path, entry: &LoggedDownload) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("序列化下载记录失败: {}", e))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(DOWNLOAD_LOG_FILENAME))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("写入下载记录失败: {}", e))
+}
+
+/// 查找 `path` 最近一次的下载记录；同一路径被覆盖保存时以最后一条为准，无法解析的行跳过
+pub fn find_download_log(dir: &Path, path: &str) -> Result<Option<LoggedDownload>, String> {
+    let file = match File::open(dir.join(DOWNLOAD_LOG_FILENAME)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("读取下载记录失败: {}", e)),
+    };
+
+    let mut found = None;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("读取下载记录失败: {}", e))?;
+        if let Ok(entry) = serde_json::from_str::<LoggedDownload>(&line) {
+            if entry.path == path {
+                found = Some(entry);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// 用本地文件（Android 上为媒体库文件的临时副本）与下载记录比对
+pub fn verify_against_log(
+    entry: LoggedDownload,
+    local: &Path,
+) -> Result<DownloadVerification, String> {
+    let actual_size = fs::metadata(local)
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let actual_sha256 = sha256_file(local)?;
+    Ok(DownloadVerification {
+        intact: actual_size == entry.size && actual_sha256 == entry.sha256,
+        path: entry.path,
+        saved_at: entry.saved_at,
+        expected_size: entry.size,
+        expected_sha256: entry.sha256,
+        actual_size,
+        actual_sha256,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(box_type);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn sample_mp4() -> Vec<u8> {
+        let mut data = mp4_box(b"ftyp", b"isom\0\0\x02\0isomiso2mp41");
+        data.extend(mp4_box(b"moov", &[0; 24]));
+        data.extend(mp4_box(b"mdat", &[7; 100]));
+        data
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn sniffs_container_headers() {
+        assert_eq!(sniff_media_kind(&sample_mp4()), Some(MediaKind::Mp4));
+        assert_eq!(
+            sniff_media_kind(b"\0\0\0\x18ftypheic\0\0\0\0"),
+            Some(MediaKind::Heif)
+        );
+        assert_eq!(
+            sniff_media_kind(b"\0\0\0\x18ftypavif\0\0\0\0"),
+            Some(MediaKind::Heif)
+        );
+        assert_eq!(
+            sniff_media_kind(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some(MediaKind::Jpeg)
+        );
+        assert_eq!(
+            sniff_media_kind(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(MediaKind::Webp)
+        );
+        assert_eq!(sniff_media_kind(b"GIF89a"), Some(MediaKind::Gif));
+        assert_eq!(sniff_media_kind(b"ID3\x04"), Some(MediaKind::Mp3));
+        assert_eq!(sniff_media_kind(b"<!DOCTYPE html><html>"), None);
+    }
+
+    #[test]
+    fn maps_extensions_to_categories() {
+        assert_eq!(expected_category("a.MP4"), Some(MediaCategory::Video));
+        assert_eq!(expected_category("a.webp"), Some(MediaCategory::Image));
+        assert_eq!(expected_category("a.m4a"), Some(MediaCategory::Audio));
+        assert_eq!(expected_category("a.zip"), None);
+        assert_eq!(expected_category("noext"), None);
+        // 音频类别接受 MP4 容器（m4a）
+        assert!(MediaKind::Mp4.matches(MediaCategory::Audio));
+        assert!(!MediaKind::Jpeg.matches(MediaCategory::Video));
+    }
+
+    #[test]
+    fn accepts_complete_mp4_and_rejects_truncated() {
+        let data = sample_mp4();
+        assert_eq!(
+            verify_media_bytes(&data, Some(MediaCategory::Video)),
+            Ok(MediaKind::Mp4)
+        );
+
+        let truncated = &data[..data.len() - 10];
+        let err = verify_media_bytes(truncated, None).unwrap_err();
+        assert!(err.contains("mdat"), "{}", err);
+
+        let mut no_moov = mp4_box(b"ftyp", b"isom\0\0\x02\0");
+        no_moov.extend(mp4_box(b"mdat", &[0; 16]));
+        assert!(verify_media_bytes(&no_moov, None)
+            .unwrap_err()
+            .contains("moov"));
+
+        // 64 位长度接近 u64::MAX 时偏移相加会溢出
+        let mut huge = mp4_box(b"ftyp", b"isom\0\0\x02\0");
+        huge.extend_from_slice(&1u32.to_be_bytes());
+        huge.extend_from_slice(b"mdat");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(verify_media_bytes(&huge, None)
+            .unwrap_err()
+            .contains("mdat"));
+    }
+
+    #[test]
+    fn keeps_existing_destination_file() {
+        let dir = temp_dir("verified-save");
+        let dest = dir.join("clip.mp4");
+        fs::write(&dest, b"user file").unwrap();
+
+        let response = reqwest::Response::from(tauri::http::Response::new(sample_mp4()));
+        let record = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(save_response_verified(response, &dest, None))
+            .unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"user file");
+        assert_eq!(PathBuf::from(&record.path), dir.join("clip (1).mp4"));
+        assert_eq!(fs::read(&record.path).unwrap(), sample_mp4());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn accepts_dash_media_segments() {
+        let mut segment = mp4_box(b"styp", b"msdh\0\0\0\0msdhmsix");
+        segment.extend(mp4_box(b"sidx", &[0; 24]));
+        segment.extend(mp4_box(b"moof", &[0; 16]));
+        segment.extend(mp4_box(b"mdat", &[3; 32]));
+        assert_eq!(verify_media_bytes(&segment, None), Ok(MediaKind::Mp4));
+
+        let without_moof = &segment[..segment.len() - 32 - 8 - 24];
+        assert!(verify_media_bytes(without_moof, None)
+            .unwrap_err()
+            .contains("moof"));
+    }
+
+    #[test]
+    fn checks_image_end_markers() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 1, 2, 3];
+        assert!(verify_media_bytes(&jpeg, None).is_err());
+        jpeg.extend_from_slice(&[0xFF, 0xD9, 0, 0]);
+        assert_eq!(verify_media_bytes(&jpeg, None), Ok(MediaKind::Jpeg));
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        assert!(verify_media_bytes(&png, None).is_err());
+        png.extend_from_slice(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+        assert_eq!(verify_media_bytes(&png, None), Ok(MediaKind::Png));
+
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&12u32.to_le_bytes());
+        webp.extend_from_slice(b"WEBPVP8 ");
+        assert!(verify_media_bytes(&webp, None).is_err());
+        webp.extend_from_slice(&[0; 4]);
+        assert_eq!(verify_media_bytes(&webp, None), Ok(MediaKind::Webp));
+
+        assert!(verify_media_bytes(b"GIF89a\0\0", None).is_err());
+        assert_eq!(verify_media_bytes(b"GIF89a\0;", None), Ok(MediaKind::Gif));
+    }
+
+    #[test]
+    fn rejects_error_pages_and_mismatched_kinds() {
+        assert!(verify_media_bytes(b"", None).is_err());
+        assert!(verify_media_bytes(b"<html>403 Forbidden</html>", None).is_err());
+        let gif = b"GIF89a\0;";
+        assert!(verify_media_bytes(gif, Some(MediaCategory::Video))
+            .unwrap_err()
+            .contains("期望"));
+    }
+
+    #[test]
+    fn logs_downloads_and_detects_changes() {
+        let dir = temp_dir("download-log");
+        let file = dir.join("video.mp4");
+        fs::write(&file, sample_mp4()).unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        assert_eq!(find_download_log(&dir, &path), Ok(None));
+        let stale = LoggedDownload {
+            path: path.clone(),
+            size: 1,
+            sha256: "old".to_string(),
+            saved_at: "2026-10-18T00:00:00.000Z".to_string(),
+        };
+        let entry = LoggedDownload {
+            size: sample_mp4().len() as u64,
+            sha256: sha256_file(&file).unwrap(),
+            saved_at: "2026-10-19T00:00:00.000Z".to_string(),
+            ..stale.clone()
+        };
+        append_download_log(&dir, &stale).unwrap();
+        append_download_log(&dir, &entry).unwrap();
+
+        // 同一路径以最后一条记录为准
+        let found = find_download_log(&dir, &path).unwrap().unwrap();
+        assert_eq!(found, entry);
+        assert!(verify_against_log(found.clone(), &file).unwrap().intact);
+
+        fs::write(&file, b"tampered").unwrap();
+        let result = verify_against_log(found, &file).unwrap();
+        assert!(!result.intact);
+        assert_eq!(result.actual_size, 8);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
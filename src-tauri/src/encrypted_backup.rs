@@ -0,0 +1,530 @@
+//! 加密备份（`.cbin`），与前端 `src/lib/utils/crypto.ts` 读写同一种格式：
+//!
+//! `[magic "CYPRIDINA1"] + [salt(16)] + [iv(12)] + [AES-256-GCM 密文（末尾 16 字节为认证标签）]`，
+//! 密钥由密码经 PBKDF2-SHA256 迭代 120000 次派生。明文是与“我的 -> 导出数据”相同结构的 JSON，
+//! 因此应用内导出的文件与这里生成的文件可以互相导入。
+//!
+//! 后端另有 v2 格式（见 `backup_container`）：Argon2id 派生密钥、zstd 压缩、带校验清单，
+//! 可选附带客户与产品引用的本地图片。导入时按文件头自动识别两种格式。
+
+use crate::backup_container::{self, Contents, Entry};
+use crate::business_db::{self, with_db};
+use crate::legacy_import::{self, Collection, ImportReport};
+use crate::license;
+use crate::receivables;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Runtime};
+
+const MAGIC: &[u8] = b"CYPRIDINA1";
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 120_000;
+// 与前端导出时的密码要求一致
+const MIN_PASSWORD_LEN: usize = 4;
+// v2 格式要求更长的密码
+const MIN_V2_PASSWORD_LEN: usize = 8;
+// 从 v2 备份恢复的附件保存在应用数据目录下的该子目录
+const ATTACHMENT_DIRNAME: &str = "attachments";
+// 前端导入导出备份文件时的中转目录（应用数据目录下），前端只能读写该目录中的文件
+const TRANSFER_DIRNAME: &str = "transfer";
+// 客户与产品中可能引用本地文件的字段
+const ATTACHMENT_FIELDS: [(&str, &[&str]); 2] = [
+    ("customers", &["images", "attachments"]),
+    ("products", &["images"]),
+];
+// 与前端导出数据中的 version 一致
+const BACKUP_VERSION: &str = "1.0.0";
+// 与前端 filterCustomersForTransfer 一致：该客户不随备份导入导出
+const HIDDEN_CUSTOMER_NAME: &str = "291769418@张总最帅";
+// 备份 JSON 中的业务数据键，按导入顺序排列
+const BACKUP_KEYS: [(Collection, &str); 7] = [
+    (Collection::Customers, "customers"),
+    (Collection::Products, "products"),
+    (Collection::QuotationProducts, "quotationProducts"),
+    (Collection::Invoices, "invoices"),
+    (Collection::Payments, "payments"),
+    (Collection::Quotations, "quotations"),
+    (Collection::CustomerHistory, "customerHistory"),
+];
+// 前端导入时要求必须存在的键
+const REQUIRED_KEYS: [&str; 3] = ["customers", "products", "invoices"];
+
+fn derive_key(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+    key
+}
+
+fn nonce(iv: &[u8]) -> Result<Nonce<aes_gcm::aead::consts::U12>, String> {
+    <[u8; IV_LEN]>::try_from(iv)
+        .map(Nonce::from)
+        .map_err(|_| "IV 长度错误".to_string())
+}
+
+fn encrypt_with(plain: &[u8], password: &str, salt: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(password, salt))
+        .map_err(|_| "密钥长度错误".to_string())?;
+    let ciphertext = cipher
+        .encrypt(&nonce(iv)?, plain)
+        .map_err(|_| "加密失败".to_string())?;
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + IV_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(salt);
+    out.extend_from_slice(iv);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// 使用随机 salt 与 IV 加密
+pub fn encrypt(plain: &[u8], password: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut iv = [0u8; IV_LEN];
+    getrandom::getrandom(&mut salt)
+        .and_then(|_| getrandom::getrandom(&mut iv))
+        .map_err(|e| format!("生成随机数失败: {}", e))?;
+    encrypt_with(plain, password, &salt, &iv)
+}
+
+pub fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>, String> {
+    if !data.starts_with(MAGIC) || data.len() < MAGIC.len() + SALT_LEN + IV_LEN + TAG_LEN {
+        return Err("文件格式错误或非加密导出文件".to_string());
+    }
+    let (salt, rest) = data[MAGIC.len()..].split_at(SALT_LEN);
+    let (iv, ciphertext) = rest.split_at(IV_LEN);
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(password, salt))
+        .map_err(|_| "密钥长度错误".to_string())?;
+    cipher
+        .decrypt(&nonce(iv)?, ciphertext)
+        .map_err(|_| "密码错误或文件已损坏".to_string())
+}
+
+fn is_hidden_customer(customer: &Value) -> bool {
+    customer.get("name").and_then(Value::as_str) == Some(HIDDEN_CUSTOMER_NAME)
+}
+
+/// 依次访问客户与产品中的图片、附件引用
+fn for_each_attachment(backup: &mut Map<String, Value>, mut f: impl FnMut(&mut String)) {
+    for (key, fields) in ATTACHMENT_FIELDS {
+        let Some(Value::Array(records)) = backup.get_mut(key) else {
+            continue;
+        };
+        for record in records {
+            for field in fields {
+                if let Some(Value::Array(refs)) = record.get_mut(*field) {
+                    refs.iter_mut()
+                        .filter_map(|value| match value {
+                            Value::String(reference) => Some(reference),
+                            _ => None,
+                        })
+                        .for_each(&mut f);
+                }
+            }
+        }
+    }
+}
+
+/// 读取备份中引用的本地文件；data URL、网络地址与已不存在的文件不打包
+fn collect_attachments(backup: &mut Map<String, Value>) -> Result<Vec<Entry>, String> {
+    let mut paths = BTreeSet::new();
+    for_each_attachment(backup, |reference| {
+        let path = Path::new(reference.as_str());
+        if path.is_absolute() && path.is_file() {
+            paths.insert(reference.clone());
+        }
+    });
+    paths
+        .into_iter()
+        .map(|path| {
+            fs::read(&path)
+                .map(|data| Entry::new(path.clone(), None, data))
+                .map_err(|e| format!("读取附件失败（{}）: {}", path, e))
+        })
+        .collect()
+}
+
+/// 把附件写入应用数据目录（按内容哈希命名），并把记录中的原路径替换为新路径
+fn restore_attachments<R: Runtime>(
+    app: &AppHandle<R>,
+    backup: &mut Map<String, Value>,
+    attachments: &[Entry],
+) -> Result<usize, String> {
+    if attachments.is_empty() {
+        return Ok(0);
+    }
+    let dir = license::data_dir(app)?.join(ATTACHMENT_DIRNAME);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+    let mut restored = HashMap::new();
+    for entry in attachments {
+        let extension = Path::new(&entry.name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| ext.len() <= 8 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .map(|ext| format!(".{}", ext.to_ascii_lowercase()))
+            .unwrap_or_default();
+        let hash = hex::encode(Sha256::digest(&entry.data));
+        let path = dir.join(format!("{}{}", &hash[..16], extension));
+        if !path.exists() {
+            license::write_atomic(&path, &entry.data)?;
+        }
+        restored.insert(entry.name.clone(), path.to_string_lossy().into_owned());
+    }
+    for_each_attachment(backup, |reference| {
+        if let Some(path) = restored.get(reference.as_str()) {
+            *reference = path.clone();
+        }
+    });
+    Ok(restored.len())
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("序列化失败: {}", e))
+}
+
+/// 导出结果：各类记录的条数
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub path: String,
+    pub size: u64,
+    pub format_version: u32,
+    pub customers: usize,
+    pub products: usize,
+    pub invoices: usize,
+    pub quotations: usize,
+    pub quotation_products: usize,
+    pub customer_history: usize,
+    pub payments: usize,
+    pub attachments: usize,
+}
+
+/// 读取数据库中的全部业务数据，组装成与前端导出相同结构的备份 JSON
+pub(crate) fn build_backup(
+    conn: &rusqlite::Connection,
+    extra: Option<Map<String, Value>>,
+) -> Result<(Map<String, Value>, BackupSummary), String> {
+    let customers: Vec<_> = business_db::all_customers(conn)?
+        .into_iter()
+        .filter(|customer| customer.name != HIDDEN_CUSTOMER_NAME)
+        .collect();
+    let products = business_db::all_products(conn)?;
+    let invoices = business_db::all_invoices(conn)?;
+    let quotations = business_db::all_quotations(conn)?;
+    let quotation_products = business_db::all_quotation_products(conn)?;
+    let customer_history = business_db::all_customer_product_history(conn)?;
+    let payments = receivables::all_payments(conn)?;
+
+    let summary = BackupSummary {
+        path: String::new(),
+        size: 0,
+        format_version: 1,
+        customers: customers.len(),
+        products: products.len(),
+        invoices: invoices.len(),
+        quotations: quotations.len(),
+        quotation_products: quotation_products.len(),
+        customer_history: customer_history.len(),
+        payments: payments.len(),
+        attachments: 0,
+    };
+
+    // 数据库之外的内容（个人资料、设置、标签等）由调用方提供，业务数据以数据库为准
+    let mut backup = extra.unwrap_or_default();
+    backup.insert("customers".to_string(), to_value(&customers)?);
+    backup.insert("products".to_string(), to_value(&products)?);
+    backup.insert("invoices".to_string(), to_value(&invoices)?);
+    backup.insert("quotations".to_string(), to_value(&quotations)?);
+    backup.insert(
+        "quotationProducts".to_string(),
+        to_value(&quotation_products)?,
+    );
+    backup.insert("customerHistory".to_string(), to_value(&customer_history)?);
+    backup.insert("payments".to_string(), to_value(&payments)?);
+    backup.insert(
+        "exportTime".to_string(),
+        Value::String(business_db::now_iso()),
+    );
+    backup.insert(
+        "version".to_string(),
+        Value::String(BACKUP_VERSION.to_string()),
+    );
+    Ok((backup, summary))
+}
+
+/// 把备份 JSON 中的业务数据写入数据库，返回导入报告；`overwrite` 为 true 时先清空现有业务数据
+pub(crate) fn restore_backup(
+    conn: &mut rusqlite::Connection,
+    backup: &mut Map<String, Value>,
+    overwrite: bool,
+) -> Result<ImportReport, String> {
+    if !REQUIRED_KEYS
+        .iter()
+        .all(|key| backup.get(*key).is_some_and(Value::is_array))
+    {
+        return Err("数据格式不正确，请选择有效的导出文件".to_string());
+    }
+    if let Some(Value::Array(customers)) = backup.get_mut("customers") {
+        customers.retain(|customer| !is_hidden_customer(customer));
+    }
+
+    let tx = conn.transaction().map_err(business_db::db_err)?;
+    if overwrite {
+        business_db::clear_business_data(&tx)?;
+    }
+    let mut report = ImportReport::default();
+    for (collection, key) in BACKUP_KEYS {
+        legacy_import::import_collection(&tx, &mut report, collection, key, backup.get(key))?;
+    }
+    receivables::settle_imported(&tx)?;
+    tx.commit().map_err(business_db::db_err)?;
+    Ok(report)
+}
+
+/// 把数据库中的业务数据导出为加密备份文件。
+/// `extra` 为数据库之外需要一并备份的内容（如 `userInfo`、`settings`、`globalTags`），可省略
+#[tauri::command]
+pub async fn export_encrypted_backup<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    password: String,
+    extra: Option<Map<String, Value>>,
+) -> Result<BackupSummary, String> {
+    let password = password.trim();
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err("加密密码至少 4 位".to_string());
+    }
+    let (backup, mut summary) = with_db(&app, |conn| build_backup(conn, extra))?;
+    let plain = serde_json::to_vec(&backup).map_err(|e| format!("序列化失败: {}", e))?;
+    let encrypted = encrypt(&plain, password)?;
+    license::write_atomic(Path::new(&path), &encrypted)?;
+    summary.size = encrypted.len() as u64;
+    summary.path = path;
+    Ok(summary)
+}
+
+/// 检查 v2 备份密码长度
+pub(crate) fn check_v2_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_V2_PASSWORD_LEN {
+        return Err(format!("加密密码至少 {} 位", MIN_V2_PASSWORD_LEN));
+    }
+    Ok(())
+}
+
+/// 以 v2 格式把数据库中的业务数据写入 `path`
+pub(crate) fn write_v2_backup<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    password: &str,
+    extra: Option<Map<String, Value>>,
+    include_attachments: bool,
+) -> Result<BackupSummary, String> {
+    check_v2_password(password)?;
+    let (mut backup, mut summary) = with_db(app, |conn| build_backup(conn, extra))?;
+    let attachments = if include_attachments {
+        collect_attachments(&mut backup)?
+    } else {
+        Vec::new()
+    };
+
+    let mut tables = Vec::new();
+    for (_, key) in BACKUP_KEYS {
+        let value = backup
+            .remove(key)
+            .unwrap_or_else(|| Value::Array(Vec::new()));
+        let count = value.as_array().map_or(0, Vec::len);
+        let data = serde_json::to_vec(&value).map_err(|e| format!("序列化失败: {}", e))?;
+        tables.push(Entry::new(key, Some(count), data));
+    }
+    let contents = Contents {
+        extra: serde_json::to_vec(&backup).map_err(|e| format!("序列化失败: {}", e))?,
+        tables,
+        attachments,
+    };
+    let encrypted =
+        backup_container::seal(&contents, password, &app.package_info().version.to_string())?;
+    license::write_atomic(path, &encrypted)?;
+    summary.size = encrypted.len() as u64;
+    summary.format_version = backup_container::FORMAT_VERSION;
+    summary.attachments = contents.attachments.len();
+    summary.path = path.to_string_lossy().into_owned();
+    Ok(summary)
+}
+
+/// 备份文件中转目录：前端把选中的备份文件写到这里再导入，导出的文件从这里读出后交给用户保存
+#[tauri::command]
+pub async fn backup_transfer_dir<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
+    let dir = license::data_dir(&app)?.join(TRANSFER_DIRNAME);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建中转目录失败: {}", e))?;
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// 以 v2 格式导出加密备份；`include_attachments` 为 true 时一并打包客户与产品引用的本地图片
+#[tauri::command]
+pub async fn export_backup<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    password: String,
+    extra: Option<Map<String, Value>>,
+    include_attachments: Option<bool>,
+) -> Result<BackupSummary, String> {
+    write_v2_backup(
+        &app,
+        Path::new(&path),
+        password.trim(),
+        extra,
+        include_attachments.unwrap_or(false),
+    )
+}
+
+/// 把 v2 容器中的各段还原成与 v1 相同结构的备份 JSON，并核对每张表的条数
+fn backup_from_contents(contents: &Contents) -> Result<Map<String, Value>, String> {
+    let mut backup: Map<String, Value> = serde_json::from_slice(&contents.extra)
+        .map_err(|_| "备份内容不是有效的 JSON".to_string())?;
+    for table in &contents.tables {
+        let value: Value = serde_json::from_slice(&table.data)
+            .map_err(|_| format!("备份内容不是有效的 JSON：{}", table.name))?;
+        if table.count.is_some() && value.as_array().map(Vec::len) != table.count {
+            return Err(format!("备份内容与清单不一致：{}", table.name));
+        }
+        backup.insert(table.name.clone(), value);
+    }
+    Ok(backup)
+}
+
+/// 恢复结果：导入报告，以及备份中数据库之外的内容，由前端自行决定是否恢复
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRestoreResult {
+    pub report: ImportReport,
+    pub format_version: u32,
+    pub attachments: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_time: Option<String>,
+    pub extra: Map<String, Value>,
+}
+
+/// 读取加密备份文件（`CYPRIDINA1` 或 v2）并恢复到数据库
+pub(crate) fn restore_file<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    password: &str,
+    overwrite: bool,
+) -> Result<BackupRestoreResult, String> {
+    let data = fs::read(path).map_err(|e| format!("读取备份文件失败: {}", e))?;
+    let (format_version, mut backup, attachments) = if backup_container::is_container(&data) {
+        let (manifest, contents) = backup_container::open(&data, password)?;
+        let backup = backup_from_contents(&contents)?;
+        (manifest.format_version, backup, contents.attachments)
+    } else {
+        let plain = decrypt(&data, password)?;
+        let backup =
+            serde_json::from_slice(&plain).map_err(|_| "备份内容不是有效的 JSON".to_string())?;
+        (1, backup, Vec::new())
+    };
+    let attachments = restore_attachments(app, &mut backup, &attachments)?;
+
+    let report = with_db(app, |conn| restore_backup(conn, &mut backup, overwrite))?;
+
+    for (_, key) in BACKUP_KEYS {
+        backup.remove(key);
+    }
+    backup.remove("version");
+    let export_time = backup
+        .remove("exportTime")
+        .and_then(|value| value.as_str().map(str::to_string));
+    Ok(BackupRestoreResult {
+        report,
+        format_version,
+        attachments,
+        export_time,
+        extra: backup,
+    })
+}
+
+/// 从加密备份文件恢复业务数据，支持 `CYPRIDINA1` 与 v2 两种格式。
+/// 合并模式（默认）保留现有数据，同一 ID 只在备份中的记录更新时覆盖；覆盖模式先清空现有业务数据
+#[tauri::command]
+pub async fn import_encrypted_backup<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    password: String,
+    overwrite: Option<bool>,
+) -> Result<BackupRestoreResult, String> {
+    crate::access_control::ensure_writable(&app)?;
+    restore_file(
+        &app,
+        Path::new(&path),
+        &password,
+        overwrite.unwrap_or(false),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 由 tests/fixtures/make_cbin_fixture.mjs 调用 crypto.ts 的 encryptJsonToBinary 生成
+    const JS_EXPORT: &[u8] = include_bytes!("../tests/fixtures/js-export.cbin");
+    const JS_EXPORT_JSON: &str = include_str!("../tests/fixtures/js-export.json");
+    const JS_PASSWORD: &str = "cypridina-测试";
+
+    #[test]
+    fn decrypts_file_exported_by_js() {
+        let plain = decrypt(JS_EXPORT, JS_PASSWORD).expect("解密前端导出的文件");
+        let actual: Value = serde_json::from_slice(&plain).unwrap();
+        let expected: Value = serde_json::from_str(JS_EXPORT_JSON).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn reencrypting_js_export_reproduces_identical_bytes() {
+        let plain = decrypt(JS_EXPORT, JS_PASSWORD).unwrap();
+        let salt = &JS_EXPORT[MAGIC.len()..MAGIC.len() + SALT_LEN];
+        let iv = &JS_EXPORT[MAGIC.len() + SALT_LEN..MAGIC.len() + SALT_LEN + IV_LEN];
+        let encrypted = encrypt_with(&plain, JS_PASSWORD, salt, iv).unwrap();
+        assert_eq!(encrypted, JS_EXPORT);
+    }
+
+    #[test]
+    fn round_trips_with_random_salt_and_iv() {
+        let plain = r#"{"customers":[],"products":[],"invoices":[]}"#.as_bytes();
+        let first = encrypt(plain, "1234").unwrap();
+        let second = encrypt(plain, "1234").unwrap();
+        assert!(first.starts_with(MAGIC));
+        assert_eq!(
+            first.len(),
+            MAGIC.len() + SALT_LEN + IV_LEN + plain.len() + TAG_LEN
+        );
+        assert_ne!(first, second);
+        assert_eq!(decrypt(&first, "1234").unwrap(), plain);
+        assert_eq!(decrypt(&second, "1234").unwrap(), plain);
+    }
+
+    #[test]
+    fn rejects_wrong_password_and_tampered_data() {
+        assert!(decrypt(JS_EXPORT, "wrong-password").is_err());
+
+        let mut tampered = JS_EXPORT.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert!(decrypt(&tampered, JS_PASSWORD).is_err());
+    }
+
+    #[test]
+    fn rejects_files_without_magic() {
+        let mut data = JS_EXPORT.to_vec();
+        data[0] = b'X';
+        assert_eq!(
+            decrypt(&data, JS_PASSWORD).unwrap_err(),
+            "文件格式错误或非加密导出文件"
+        );
+        assert!(decrypt(MAGIC, JS_PASSWORD).is_err());
+    }
+}
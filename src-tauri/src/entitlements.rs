@@ -0,0 +1,130 @@
+use crate::license::License;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+
+/// 可单独开通的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Feature {
+    /// 视频去水印
+    VideoTools,
+    /// “我的”页面编辑资料
+    ProfileEditing,
+    /// 导出前选择是否包含客户与销售单
+    ExportScope,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::VideoTools,
+        Feature::ProfileEditing,
+        Feature::ExportScope,
+    ];
+
+    /// 许可证中使用的功能名，与序列化名称一致
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::VideoTools => "videoTools",
+            Feature::ProfileEditing => "profileEditing",
+            Feature::ExportScope => "exportScope",
+        }
+    }
+
+    /// 激活码中功能位图的位序号，已分配的位不能再改动
+    pub fn bit(self) -> u8 {
+        match self {
+            Feature::VideoTools => 0,
+            Feature::ProfileEditing => 1,
+            Feature::ExportScope => 2,
+        }
+    }
+
+    pub fn from_bits(bits: u8) -> Vec<Feature> {
+        Feature::ALL
+            .into_iter()
+            .filter(|feature| bits & (1 << feature.bit()) != 0)
+            .collect()
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Feature::ALL
+            .into_iter()
+            .find(|feature| feature.name() == name.trim())
+    }
+}
+
+/// 当前设备已开通的功能
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entitlements {
+    pub features: Vec<Feature>,
+}
+
+impl Entitlements {
+    pub fn from_features(features: impl IntoIterator<Item = Feature>) -> Entitlements {
+        let mut features: Vec<Feature> = features.into_iter().collect();
+        features.sort();
+        features.dedup();
+        Entitlements { features }
+    }
+}
+
+/// 许可证中的功能名；无法识别的功能名（新版本签发的许可证）直接忽略
+pub fn license_features(license: &License) -> Vec<Feature> {
+    license
+        .features
+        .iter()
+        .filter_map(|name| Feature::from_name(name))
+        .collect()
+}
+
+/// 获取当前设备已开通的功能；无法访问应用时不开通任何功能
+#[tauri::command]
+pub async fn get_entitlements<R: Runtime>(app: AppHandle<R>) -> Result<Entitlements, String> {
+    crate::access_control::evaluate_access(&app).map(|status| status.entitlements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_bits_and_names() {
+        assert_eq!(Feature::from_bits(0), vec![]);
+        assert_eq!(Feature::from_bits(0xFF), Feature::ALL.to_vec());
+        for feature in Feature::ALL {
+            assert_eq!(Feature::from_bits(1 << feature.bit()), vec![feature]);
+            assert_eq!(Feature::from_name(feature.name()), Some(feature));
+        }
+        assert_eq!(
+            Feature::from_name(" videoTools "),
+            Some(Feature::VideoTools)
+        );
+        assert_eq!(Feature::from_name("VideoTools"), None);
+    }
+
+    #[test]
+    fn merges_features_and_ignores_unknown_license_names() {
+        let merged = Entitlements::from_features([
+            Feature::ExportScope,
+            Feature::VideoTools,
+            Feature::ExportScope,
+        ]);
+        assert_eq!(
+            merged.features,
+            vec![Feature::VideoTools, Feature::ExportScope]
+        );
+
+        let license = License {
+            version: 1,
+            license_id: "L".to_string(),
+            licensee: "客户".to_string(),
+            device_id: None,
+            issued_at: 0,
+            expires_at: None,
+            clock_tolerance_secs: None,
+            features: vec!["profileEditing".to_string(), "futureFeature".to_string()],
+        };
+        assert_eq!(license_features(&license), vec![Feature::ProfileEditing]);
+    }
+}
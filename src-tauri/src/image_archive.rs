@@ -0,0 +1,359 @@
+use crate::download_integrity::{self, MediaCategory, MediaKind};
+use crate::image_convert::{self, ImageConversion};
+use crate::{build_referer, media_store, VideoInfo, MOBILE_USER_AGENT};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Runtime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+// 压缩包内的元数据文件名
+const METADATA_FILENAME: &str = "metadata.json";
+// 同时下载的图片数量上限，避免触发 CDN 限流
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageFailure {
+    pub index: usize,
+    pub url: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageArchiveResult {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub image_count: usize,
+    pub failures: Vec<ImageFailure>,
+}
+
+struct FetchedImage {
+    index: usize,
+    url: String,
+    data: Vec<u8>,
+    kind: MediaKind,
+    /// 转码失败时的原因，此时保留原始格式
+    conversion_error: Option<String>,
+}
+
+// 文件名主体（不含扩展名）的最大字符数
+const MAX_STEM_CHARS: usize = 100;
+
+/// 清理文件名中的非法字符（与前端 sanitizeFilename 规则一致）：
+/// 去掉 `<>:"/\|?*`，连续空白合并为一个 `_`，只截断主体部分，扩展名保持不变
+pub(crate) fn sanitize_filename(name: &str) -> String {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext))
+            if !stem.is_empty()
+                && !ext.is_empty()
+                && ext.len() <= 8
+                && ext.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            (stem, Some(ext))
+        }
+        _ => (name, None),
+    };
+
+    let mut cleaned = String::new();
+    let mut in_whitespace = false;
+    for c in stem
+        .chars()
+        .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'))
+    {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                cleaned.push('_');
+            }
+            in_whitespace = true;
+        } else {
+            cleaned.push(c);
+            in_whitespace = false;
+        }
+    }
+    let mut cleaned: String = cleaned.chars().take(MAX_STEM_CHARS).collect();
+
+    // 只有点的名称（"."、".."）拼到目录后会指向上级目录
+    if cleaned.chars().all(|c| c == '.') {
+        cleaned = "images".to_string();
+    }
+    if let Some(ext) = ext {
+        cleaned.push('.');
+        cleaned.push_str(ext);
+    }
+    cleaned
+}
+
+async fn fetch_image(client: &reqwest::Client, url: &str) -> Result<(Vec<u8>, MediaKind), String> {
+    let referer = build_referer(url);
+    let origin = referer.trim_end_matches('/').to_string();
+
+    let response = client
+        .get(url)
+        .header("Referer", &referer)
+        .header("Origin", &origin)
+        .header("User-Agent", MOBILE_USER_AGENT)
+        .send()
+        .await
+        .map_err(|e| format!("下载失败: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("下载失败: HTTP {}", response.status()));
+    }
+
+    let expected_len = response.content_length();
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("读取数据失败: {}", e))?;
+
+    if let Some(expected) = expected_len {
+        if bytes.len() as u64 != expected {
+            return Err(format!(
+                "下载不完整: 已接收 {} 字节，服务器声明 {} 字节",
+                bytes.len(),
+                expected
+            ));
+        }
+    }
+
+    let kind = download_integrity::verify_media_bytes(&bytes, Some(MediaCategory::Image))?;
+    Ok((bytes.to_vec(), kind))
+}
+
+/// 按需转码，失败时返回原图与失败原因
+async fn convert_image(
+    data: Vec<u8>,
+    kind: MediaKind,
+    conversion: Option<ImageConversion>,
+) -> (Vec<u8>, MediaKind, Option<String>) {
+    let Some(conversion) = conversion else {
+        return (data, kind, None);
+    };
+    if kind == conversion.target_kind() {
+        return (data, kind, None);
+    }
+
+    let source = data.clone();
+    match tokio::task::spawn_blocking(move || image_convert::convert_bytes(&source, &conversion))
+        .await
+    {
+        Ok(Ok((converted, converted_kind))) => (converted, converted_kind, None),
+        Ok(Err(err)) => (data, kind, Some(err)),
+        Err(e) => (data, kind, Some(format!("转码任务异常: {}", e))),
+    }
+}
+
+/// 并发下载全部图片，按原始顺序返回成功项与失败项
+async fn fetch_all_images(
+    urls: &[String],
+    conversion: Option<ImageConversion>,
+) -> Result<(Vec<FetchedImage>, Vec<ImageFailure>), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+    let mut tasks = JoinSet::new();
+
+    for (index, url) in urls.iter().enumerate() {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let url = url.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = match fetch_image(&client, &url).await {
+                Ok((data, kind)) => Ok(convert_image(data, kind, conversion).await),
+                Err(err) => Err(err),
+            };
+            (index, url, result)
+        });
+    }
+
+    let mut fetched = Vec::new();
+    let mut failures = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, url, Ok((data, kind, conversion_error)))) => fetched.push(FetchedImage {
+                index,
+                url,
+                data,
+                kind,
+                conversion_error,
+            }),
+            Ok((index, url, Err(error))) => failures.push(ImageFailure {
+                index: index + 1,
+                url,
+                error,
+            }),
+            Err(e) => return Err(format!("下载任务异常: {}", e)),
+        }
+    }
+
+    fetched.sort_by_key(|image| image.index);
+    failures.sort_by_key(|failure| failure.index);
+    Ok((fetched, failures))
+}
+
+fn write_archive(
+    path: &Path,
+    info: &VideoInfo,
+    source_url: Option<&str>,
+    images: &[FetchedImage],
+    failures: &[ImageFailure],
+    total: usize,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("创建压缩包失败: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    // 图片本身已经压缩过，直接存储即可
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let width = total.to_string().len().max(2);
+
+    let mut entries = Vec::new();
+    for image in images {
+        let entry_name = format!(
+            "{:0width$}.{}",
+            image.index + 1,
+            image.kind.extension(),
+            width = width
+        );
+        zip.start_file(entry_name.as_str(), stored)
+            .map_err(|e| format!("写入压缩包失败: {}", e))?;
+        zip.write_all(&image.data)
+            .map_err(|e| format!("写入压缩包失败: {}", e))?;
+
+        let mut entry = json!({
+            "index": image.index + 1,
+            "file": entry_name,
+            "url": image.url,
+            "size": image.data.len(),
+            "sha256": hex::encode(Sha256::digest(&image.data)),
+        });
+        if let Some(error) = &image.conversion_error {
+            entry["conversionError"] = json!(error);
+        }
+        entries.push(entry);
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let metadata = json!({
+        "title": info.title,
+        "author": info.author,
+        "platform": info.platform,
+        "sourceUrl": source_url,
+        "cover": info.cover,
+        "imageCount": total,
+        "createdAt": created_at,
+        "images": entries,
+        "failures": failures,
+    });
+    let metadata_text =
+        serde_json::to_string_pretty(&metadata).map_err(|e| format!("生成元数据失败: {}", e))?;
+
+    zip.start_file(METADATA_FILENAME, deflated)
+        .map_err(|e| format!("写入压缩包失败: {}", e))?;
+    zip.write_all(metadata_text.as_bytes())
+        .map_err(|e| format!("写入压缩包失败: {}", e))?;
+
+    let mut file = zip.finish().map_err(|e| format!("写入压缩包失败: {}", e))?;
+    file.flush().map_err(|e| format!("写入压缩包失败: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("写入压缩包失败: {}", e))?;
+    Ok(())
+}
+
+/// 将图文作品的全部图片按顺序打包为一个 ZIP，并附带 metadata.json。
+/// 单张图片失败不会中断打包，失败项会记录在结果与元数据中。
+/// 传入 `conversion` 时每张图片转为 JPEG/PNG，转码失败的图片保留原格式。
+#[tauri::command]
+pub async fn download_images_archive<R: Runtime>(
+    app: AppHandle<R>,
+    info: VideoInfo,
+    source_url: Option<String>,
+    filename: Option<String>,
+    conversion: Option<ImageConversion>,
+) -> Result<ImageArchiveResult, String> {
+    let urls = info.images.clone().unwrap_or_default();
+    if urls.is_empty() {
+        return Err("该作品没有可下载的图片".to_string());
+    }
+
+    let (images, failures) = fetch_all_images(&urls, conversion).await?;
+    if images.is_empty() {
+        let reasons: Vec<String> = failures
+            .iter()
+            .map(|f| format!("#{} {}", f.index, f.error))
+            .collect();
+        return Err(format!("全部图片下载失败: {}", reasons.join(" | ")));
+    }
+
+    let filename = match filename {
+        Some(name) if !name.trim().is_empty() => sanitize_filename(name.trim()),
+        _ => format!("{}.zip", sanitize_filename(&info.title)),
+    };
+    let staged = media_store::staging_path(&app, &filename)?;
+
+    if let Err(err) = write_archive(
+        &staged,
+        &info,
+        source_url.as_deref(),
+        &images,
+        &failures,
+        urls.len(),
+    ) {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+
+    let size = fs::metadata(&staged)
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let sha256 = download_integrity::sha256_file(&staged)?;
+    let path = media_store::publish(&app, &staged, &filename, "application/zip").await?;
+    media_store::remember(&app, &path, size, &sha256);
+
+    Ok(ImageArchiveResult {
+        path,
+        size,
+        sha256,
+        image_count: images.len(),
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_stem_and_keeps_extension() {
+        assert_eq!(sanitize_filename("a  b\tc.zip"), "a_b_c.zip");
+        assert_eq!(sanitize_filename("标题:<test>?.jpg"), "标题test.jpg");
+        assert_eq!(sanitize_filename("  .zip"), "_.zip");
+        assert_eq!(sanitize_filename("???"), "images");
+        assert_eq!(sanitize_filename(".."), "images");
+        assert_eq!(sanitize_filename("."), "images");
+        assert_eq!(sanitize_filename("..."), "images");
+        assert_eq!(sanitize_filename("v1.2 final"), "v1.2_final");
+
+        // 超长标题只截断主体，.zip 保留
+        let long = format!("{}.zip", "长".repeat(120));
+        let cleaned = sanitize_filename(&long);
+        assert!(cleaned.ends_with(".zip"));
+        assert_eq!(cleaned.chars().count(), MAX_STEM_CHARS + 4);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::download_integrity::{self, MediaKind};
+use crate::media_store;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbImage};
+use serde::Deserialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// 保存图片时的目标格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+}
+
+/// 图片下载时的转码选项，`quality` 仅对 JPEG 生效（1-100）
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageConversion {
+    pub format: ImageFormat,
+    pub quality: Option<u8>,
+}
+
+impl ImageConversion {
+    pub fn target_kind(&self) -> MediaKind {
+        match self.format {
+            ImageFormat::Jpeg => MediaKind::Jpeg,
+            ImageFormat::Png => MediaKind::Png,
+        }
+    }
+
+    fn jpeg_quality(&self) -> u8 {
+        self.quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100)
+    }
+}
+
+/// 通过 libheif 解码 HEIC/AVIF，libheif 会按 irot/imir 摆正图片
+#[cfg(feature = "heif")]
+fn decode_heif(data: &[u8]) -> Result<DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib = LibHeif::new();
+    let context =
+        HeifContext::read_from_bytes(data).map_err(|e| format!("读取 HEIC/AVIF 失败: {}", e))?;
+    let handle = context
+        .primary_image_handle()
+        .map_err(|e| format!("读取 HEIC/AVIF 失败: {}", e))?;
+    let decoded = lib
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| format!("解码 HEIC/AVIF 失败: {}", e))?;
+
+    let planes = decoded.planes();
+    let plane = planes
+        .interleaved
+        .ok_or("解码 HEIC/AVIF 失败: 缺少像素数据")?;
+    let row = plane.width as usize * 4;
+    let mut pixels = Vec::with_capacity(row * plane.height as usize);
+    for y in 0..plane.height as usize {
+        let start = y * plane.stride;
+        pixels.extend_from_slice(
+            plane
+                .data
+                .get(start..start + row)
+                .ok_or("解码 HEIC/AVIF 失败: 像素数据不完整")?,
+        );
+    }
+    image::RgbaImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "解码 HEIC/AVIF 失败: 像素数据不完整".to_string())
+}
+
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_data: &[u8]) -> Result<DynamicImage, String> {
+    Err("此版本未启用 HEIC/AVIF 解码（构建时需开启 heif 特性）".to_string())
+}
+
+/// 解码图片并按 EXIF 方向摆正
+pub fn decode_oriented(data: &[u8]) -> Result<DynamicImage, String> {
+    if download_integrity::sniff_media_kind(data) == Some(MediaKind::Heif) {
+        return decode_heif(data);
+    }
+
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| format!("读取图片失败: {}", e))?
+        .into_decoder()
+        .map_err(|e| format!("无法识别图片格式: {}", e))?;
+    let orientation = decoder.orientation().ok();
+    let mut image =
+        DynamicImage::from_decoder(decoder).map_err(|e| format!("解码图片失败: {}", e))?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
+
+/// JPEG 不支持透明通道，透明区域按白色背景合成
+fn flatten_on_white(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn encode(image: &DynamicImage, conversion: &ImageConversion) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    match conversion.format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut out, conversion.jpeg_quality())
+            .encode_image(&flatten_on_white(image))
+            .map_err(|e| format!("生成 JPEG 失败: {}", e))?,
+        ImageFormat::Png => image
+            .write_with_encoder(PngEncoder::new(&mut out))
+            .map_err(|e| format!("生成 PNG 失败: {}", e))?,
+    }
+    Ok(out)
+}
+
+/// 把图片转为目标格式；原本就是目标格式时原样返回，保留原始画质与元数据
+pub fn convert_bytes(
+    data: &[u8],
+    conversion: &ImageConversion,
+) -> Result<(Vec<u8>, MediaKind), String> {
+    let source = download_integrity::sniff_media_kind(data);
+    let target = conversion.target_kind();
+    if source == Some(target) {
+        return Ok((data.to_vec(), target));
+    }
+
+    let image = decode_oriented(data)?;
+    Ok((encode(&image, conversion)?, target))
+}
+
+/// 替换文件名的扩展名为目标格式
+pub fn converted_filename(filename: &str, kind: MediaKind) -> String {
+    Path::new(filename)
+        .with_extension(kind.extension())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 转码已落盘的图片文件，返回新文件路径（扩展名随格式变化，原文件会被删除）
+pub fn convert_file(path: &Path, conversion: &ImageConversion) -> Result<PathBuf, String> {
+    let data = fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let (converted, kind) = convert_bytes(&data, conversion)?;
+    let mut target = path.with_extension(kind.extension());
+    if target == path && converted == data {
+        return Ok(target);
+    }
+    // 换了扩展名时不覆盖目录中已有的同名文件
+    if target != path {
+        if let (Some(dir), Some(name)) = (target.parent(), target.file_name()) {
+            target = media_store::unique_path(dir, &name.to_string_lossy());
+        }
+    }
+
+    let temp = target.with_extension(format!("{}.tmp", kind.extension()));
+    fs::write(&temp, &converted).map_err(|e| format!("写入文件失败: {}", e))?;
+    if let Err(e) = fs::rename(&temp, &target) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("写入文件失败: {}", e));
+    }
+    if target != path {
+        let _ = fs::remove_file(path);
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(image: DynamicImage, format: image::ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, format).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn converts_webp_without_overwriting_existing_files() {
+        let dir = std::env::temp_dir().join(format!("image-convert-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let webp = encoded(
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                2,
+                2,
+                image::Rgba([0, 0, 0, 0]),
+            )),
+            image::ImageFormat::WebP,
+        );
+        let source = dir.join("photo.webp");
+        fs::write(&source, &webp).unwrap();
+        fs::write(dir.join("photo.jpg"), b"keep").unwrap();
+
+        let conversion = ImageConversion {
+            format: ImageFormat::Jpeg,
+            quality: Some(80),
+        };
+        let converted = convert_file(&source, &conversion).unwrap();
+        assert_eq!(converted, dir.join("photo (1).jpg"));
+        assert!(!source.exists());
+        assert_eq!(fs::read(dir.join("photo.jpg")).unwrap(), b"keep");
+
+        // 透明像素按白色背景合成
+        let decoded = image::open(&converted).unwrap().to_rgb8();
+        assert!(decoded.get_pixel(0, 0).0.iter().all(|c| *c > 240));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_bytes_when_already_in_target_format() {
+        let png = encoded(
+            DynamicImage::ImageRgb8(RgbImage::new(2, 2)),
+            image::ImageFormat::Png,
+        );
+        let conversion = ImageConversion {
+            format: ImageFormat::Png,
+            quality: None,
+        };
+        let (out, kind) = convert_bytes(&png, &conversion).unwrap();
+        assert_eq!(kind, MediaKind::Png);
+        assert_eq!(out, png);
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[test]
+    fn reports_missing_heif_support() {
+        let err = decode_oriented(b"\0\0\0\x18ftypheic\0\0\0\0mif1heic").unwrap_err();
+        assert!(err.contains("heif"), "{}", err);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{business_db, license};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+// 安装时间签名密钥（同时用于 .last_seen），发布构建必须通过环境变量 INSTALL_STAMP_SECRET 提供，
+// 未设置时编译失败；密钥由发布负责人保管，不进入仓库
+#[cfg(not(debug_assertions))]
+const STAMP_SECRET: &str = env!(
+    "INSTALL_STAMP_SECRET",
+    "发布构建需要设置环境变量 INSTALL_STAMP_SECRET（安装时间签名密钥）"
+);
+// 调试构建未设置时使用开发密钥，仅供本地测试
+#[cfg(debug_assertions)]
+const STAMP_SECRET: &str = match option_env!("INSTALL_STAMP_SECRET") {
+    Some(secret) => secret,
+    None => "renteng-sales/install-stamp/dev",
+};
+// 旧版本只在应用数据目录保存纯文本时间戳，新版本沿用该文件名并改为带签名的格式
+const LEGACY_FILENAME: &str = ".install_time";
+// 安装时间记录的签名用途
+const PURPOSE: &str = "install_time";
+// 写入过签名记录后在业务数据库中保存的标记，此后不再接受旧版本的纯文本时间戳。
+// 删除应用数据目录中的记录文件容易，删除数据库就会丢失全部业务数据
+const MARKER_KEY: &str = "install_stamp_marker";
+const MARKER_PURPOSE: &str = "install_marker";
+
+/// 安装时间记录的校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallState {
+    /// 首次运行，刚写入安装时间
+    FirstRun,
+    /// 所有位置的记录签名有效且一致
+    Verified,
+    /// 部分位置缺失，已用有效记录补齐
+    Repaired,
+    /// 从旧版本的纯文本时间戳迁移
+    Migrated,
+    /// 记录签名无效或各位置不一致
+    Tampered,
+}
+
+/// 安装时间检查结果；被篡改时 `install_time` 为仍然有效的最早记录，都无效时为 0
+#[derive(Debug, Clone, Copy)]
+pub struct InstallStamp {
+    pub install_time: u64,
+    pub state: InstallState,
+}
+
+pub(crate) enum Record {
+    Missing,
+    Valid(u64),
+    Invalid,
+}
+
+/// 保存位置：分散在不同目录且文件名各不相同，单独删改其中一处都能被发现
+fn locations<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<PathBuf>, String> {
+    let path = app.path();
+    let dirs = [
+        (path.app_data_dir(), LEGACY_FILENAME),
+        (path.app_config_dir(), ".install_stamp"),
+        (path.app_local_data_dir(), ".install_guard"),
+    ];
+    let mut files = Vec::new();
+    for (dir, filename) in dirs {
+        let dir = dir.map_err(|e| e.to_string())?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        files.push(dir.join(filename));
+    }
+    Ok(files)
+}
+
+/// 签名内容包含用途与设备标识，记录拷贝到其它设备或挪作它用都无法通过校验
+fn sign(purpose: &str, device_id: &str, value: u64) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(STAMP_SECRET.as_bytes())
+        .expect("HMAC 可以接受任意长度的密钥");
+    mac.update(format!("{}:{}:{}", purpose, device_id, value).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn encode(purpose: &str, device_id: &str, value: u64) -> String {
+    format!("{}:{}", value, sign(purpose, device_id, value))
+}
+
+/// 读取带签名的时间记录（格式为 `<value>:<hmac>`）
+pub(crate) fn read_signed(path: &Path, purpose: &str, device_id: &str) -> Record {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Record::Missing;
+    };
+    let parsed = content
+        .trim()
+        .split_once(':')
+        .and_then(|(value, mac)| Some((value.parse::<u64>().ok()?, mac)));
+    match parsed {
+        Some((value, mac)) if sign(purpose, device_id, value) == mac => Record::Valid(value),
+        _ => Record::Invalid,
+    }
+}
+
+/// 写入带签名的时间记录
+pub(crate) fn write_signed(
+    path: &Path,
+    purpose: &str,
+    device_id: &str,
+    value: u64,
+) -> Result<(), String> {
+    // 先写临时文件再替换，写到一半中断不会留下无法校验的记录
+    license::write_atomic(path, encode(purpose, device_id, value))
+        .map_err(|e| format!("写入校验记录失败: {}", e))
+}
+
+/// 读取旧版本的纯文本时间戳
+fn read_legacy(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse::<u64>().ok()
+}
+
+fn write_records(paths: &[PathBuf], device_id: &str, install_time: u64) -> Result<(), String> {
+    for path in paths {
+        write_signed(path, PURPOSE, device_id, install_time)?;
+    }
+    Ok(())
+}
+
+/// 读取并交叉校验各位置的安装时间记录，缺失的位置用有效记录补齐。
+/// 任一记录签名无效或记录之间不一致都视为被篡改，不会重新写入新的安装时间。
+pub fn load_install_stamp<R: Runtime>(app: &AppHandle<R>) -> Result<InstallStamp, String> {
+    let device_id = license::device_id(app)?;
+    let paths = locations(app)?;
+    let signed_before = business_db::with_db(app, |conn| {
+        Ok(business_db::preference(conn, MARKER_KEY)?.is_some())
+    })?;
+    let stamp = resolve(&paths, &device_id, signed_before, license::now_secs()?)?;
+    if !signed_before && stamp.state != InstallState::Tampered {
+        let marker = encode(MARKER_PURPOSE, &device_id, stamp.install_time);
+        business_db::with_db(app, |conn| {
+            business_db::set_preference(conn, MARKER_KEY, &marker)
+        })?;
+    }
+    Ok(stamp)
+}
+
+/// 按各位置的记录得出安装时间。`paths[0]` 为旧版本的纯文本文件所在位置；
+/// `signed_before` 表示本机已写入过签名记录，此时记录全部缺失或只剩纯文本都视为被篡改
+fn resolve(
+    paths: &[PathBuf],
+    device_id: &str,
+    signed_before: bool,
+    now: u64,
+) -> Result<InstallStamp, String> {
+    let records: Vec<Record> = paths
+        .iter()
+        .map(|path| read_signed(path, PURPOSE, device_id))
+        .collect();
+
+    let valid: Vec<u64> = records
+        .iter()
+        .filter_map(|record| match record {
+            Record::Valid(time) => Some(*time),
+            _ => None,
+        })
+        .collect();
+    let any_invalid = records
+        .iter()
+        .any(|record| matches!(record, Record::Invalid));
+    let earliest = valid.iter().min().copied();
+    let tampered = |install_time| InstallStamp {
+        install_time,
+        state: InstallState::Tampered,
+    };
+
+    if valid.is_empty() {
+        // 只有旧版本的纯文本文件、其它位置都没有记录、且从未写入过签名记录时才迁移；
+        // 纯文本无法解析时视为篡改
+        let only_legacy = matches!(records[0], Record::Invalid)
+            && records[1..]
+                .iter()
+                .all(|record| matches!(record, Record::Missing));
+        if only_legacy && !signed_before {
+            return match read_legacy(&paths[0]) {
+                Some(time) => {
+                    write_records(paths, device_id, time)?;
+                    Ok(InstallStamp {
+                        install_time: time,
+                        state: InstallState::Migrated,
+                    })
+                }
+                None => Ok(tampered(0)),
+            };
+        }
+        if any_invalid || signed_before {
+            return Ok(tampered(0));
+        }
+
+        write_records(paths, device_id, now)?;
+        return Ok(InstallStamp {
+            install_time: now,
+            state: InstallState::FirstRun,
+        });
+    }
+
+    let consistent = valid.iter().all(|time| Some(*time) == earliest);
+    let install_time = earliest.unwrap_or_default();
+    if any_invalid || !consistent {
+        return Ok(tampered(install_time));
+    }
+
+    if valid.len() < paths.len() {
+        write_records(paths, device_id, install_time)?;
+        return Ok(InstallStamp {
+            install_time,
+            state: InstallState::Repaired,
+        });
+    }
+
+    Ok(InstallStamp {
+        install_time,
+        state: InstallState::Verified,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVICE: &str = "device-1";
+    const NOW: u64 = 1_800_000_000;
+
+    fn temp_paths() -> Vec<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("install-stamp-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        [LEGACY_FILENAME, ".install_stamp", ".install_guard"]
+            .iter()
+            .map(|name| dir.join(name))
+            .collect()
+    }
+
+    fn cleanup(paths: &[PathBuf]) {
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    fn all_valid(paths: &[PathBuf], time: u64) -> bool {
+        paths
+            .iter()
+            .all(|path| matches!(read_signed(path, PURPOSE, DEVICE), Record::Valid(t) if t == time))
+    }
+
+    #[test]
+    fn first_run_writes_every_location_then_verifies() {
+        let paths = temp_paths();
+        let stamp = resolve(&paths, DEVICE, false, NOW).unwrap();
+        assert_eq!(stamp.state, InstallState::FirstRun);
+        assert_eq!(stamp.install_time, NOW);
+        assert!(all_valid(&paths, NOW));
+
+        let stamp = resolve(&paths, DEVICE, true, NOW + 100).unwrap();
+        assert_eq!(stamp.state, InstallState::Verified);
+        assert_eq!(stamp.install_time, NOW);
+        cleanup(&paths);
+    }
+
+    #[test]
+    fn repairs_missing_locations_from_valid_records() {
+        let paths = temp_paths();
+        write_records(&paths, DEVICE, NOW).unwrap();
+        fs::remove_file(&paths[2]).unwrap();
+
+        let stamp = resolve(&paths, DEVICE, true, NOW + 100).unwrap();
+        assert_eq!(stamp.state, InstallState::Repaired);
+        assert_eq!(stamp.install_time, NOW);
+        assert!(all_valid(&paths, NOW));
+        cleanup(&paths);
+    }
+
+    #[test]
+    fn migrates_plain_timestamp_only_before_any_signed_record() {
+        let paths = temp_paths();
+        fs::write(&paths[0], "1700000000\n").unwrap();
+        let stamp = resolve(&paths, DEVICE, false, NOW).unwrap();
+        assert_eq!(stamp.state, InstallState::Migrated);
+        assert_eq!(stamp.install_time, 1_700_000_000);
+        assert!(all_valid(&paths, 1_700_000_000));
+
+        // 已写入过签名记录：删掉其它记录并改写纯文本不会被当作旧版本迁移
+        fs::remove_file(&paths[1]).unwrap();
+        fs::remove_file(&paths[2]).unwrap();
+        fs::write(&paths[0], "1600000000").unwrap();
+        let stamp = resolve(&paths, DEVICE, true, NOW).unwrap();
+        assert_eq!(stamp.state, InstallState::Tampered);
+        assert_eq!(stamp.install_time, 0);
+        cleanup(&paths);
+    }
+
+    #[test]
+    fn detects_tampering() {
+        // 无法解析的纯文本
+        let paths = temp_paths();
+        fs::write(&paths[0], "not a timestamp").unwrap();
+        assert_eq!(
+            resolve(&paths, DEVICE, false, NOW).unwrap().state,
+            InstallState::Tampered
+        );
+        cleanup(&paths);
+
+        // 写入过签名记录后全部被删除
+        let paths = temp_paths();
+        assert_eq!(
+            resolve(&paths, DEVICE, true, NOW).unwrap().state,
+            InstallState::Tampered
+        );
+        cleanup(&paths);
+
+        // 签名无效、属于其他设备或记录之间不一致
+        let paths = temp_paths();
+        write_records(&paths, DEVICE, NOW).unwrap();
+        fs::write(&paths[1], encode(PURPOSE, DEVICE, NOW).replace(':', ":0")).unwrap();
+        let stamp = resolve(&paths, DEVICE, true, NOW).unwrap();
+        assert_eq!(stamp.state, InstallState::Tampered);
+        assert_eq!(stamp.install_time, NOW);
+
+        write_records(&paths, DEVICE, NOW).unwrap();
+        write_signed(&paths[2], PURPOSE, "device-2", NOW).unwrap();
+        assert_eq!(
+            resolve(&paths, DEVICE, true, NOW).unwrap().state,
+            InstallState::Tampered
+        );
+
+        write_records(&paths, DEVICE, NOW).unwrap();
+        write_signed(&paths[2], PURPOSE, DEVICE, NOW - 10).unwrap();
+        let stamp = resolve(&paths, DEVICE, true, NOW).unwrap();
+        assert_eq!(stamp.state, InstallState::Tampered);
+        assert_eq!(stamp.install_time, NOW - 10);
+        cleanup(&paths);
+    }
+}
@@ -0,0 +1,1282 @@
+//! 把旧版存放在 WebView localStorage 中的业务数据导入业务数据库。
+//!
+//! 前端把各个 localStorage 键的原始值（JSON 字符串或已解析的值）原样传入，
+//! 逐条校验并修复后在同一个事务中写入；数据库中已有相同或更新的记录时跳过，可以重复执行。
+
+use crate::business_db::{self, with_db_write};
+use crate::business_models::{
+    Customer, CustomerProductHistory, Invoice, Payment, Product, Quotation, QuotationProduct,
+};
+use crate::money;
+use crate::numbering::{self, DocumentKind};
+use crate::receivables;
+use rusqlite::{Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use tauri::{AppHandle, Runtime};
+
+// 导入完成后写入偏好设置的时间标记
+const IMPORTED_AT_KEY: &str = "legacy_import_completed_at";
+// localStorage 键名与数据集合的对应关系，按导入顺序排列
+const LEGACY_KEYS: [(Collection, &str); 6] = [
+    (Collection::Customers, "customers"),
+    (Collection::Products, "products"),
+    (Collection::QuotationProducts, "quotation_products"),
+    (Collection::Invoices, "invoice_history"),
+    (Collection::Quotations, "quotations"),
+    (Collection::CustomerHistory, "customer_product_history"),
+];
+// 以此为前缀的键（上次选择的公司、地址等）原样保存为偏好设置
+const LAST_SELECTED_PREFIX: &str = "last_selected_";
+
+/// 单个 localStorage 键的导入结果
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyReport {
+    pub key: String,
+    pub total: usize,
+    /// 写入的记录数（含修复后写入的）
+    pub imported: usize,
+    pub skipped: usize,
+    /// 经过修复后写入的记录数
+    pub repaired: usize,
+    /// 整个键无法解析时的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 被跳过或修复的单条记录
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    pub key: String,
+    /// 记录在原数组中的下标
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// true 为跳过，false 为修复后写入
+    pub skipped: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub keys: Vec<KeyReport>,
+    pub imported: usize,
+    pub skipped: usize,
+    pub repaired: usize,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    fn push_key(&mut self, key: KeyReport) {
+        self.imported += key.imported;
+        self.skipped += key.skipped;
+        self.repaired += key.repaired;
+        self.keys.push(key);
+    }
+}
+
+/// 一条待导入的记录，修复过程中记下每一处改动
+struct Record {
+    fields: Map<String, Value>,
+    repairs: Vec<String>,
+}
+
+impl Record {
+    fn new(value: Value) -> Result<Record, String> {
+        match value {
+            Value::Object(fields) => Ok(Record {
+                fields,
+                repairs: Vec::new(),
+            }),
+            _ => Err("记录不是对象".to_string()),
+        }
+    }
+
+    fn repair(&mut self, message: impl Into<String>) {
+        self.repairs.push(message.into());
+    }
+
+    fn text(&self, key: &str) -> Option<&str> {
+        self.fields
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+    }
+
+    /// 记录 ID：数字转为字符串，缺失时生成新的 UUID
+    fn id(&mut self, key: &str) -> String {
+        match self.fields.get(key) {
+            Some(Value::String(id)) if !id.trim().is_empty() => return id.trim().to_string(),
+            Some(Value::Number(id)) => {
+                let id = id.to_string();
+                self.fields
+                    .insert(key.to_string(), Value::String(id.clone()));
+                self.repair(format!("{} 为数字，已转为文本", key));
+                return id;
+            }
+            _ => {}
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        self.fields
+            .insert(key.to_string(), Value::String(id.clone()));
+        self.repair(format!("缺少 {}，已生成", key));
+        id
+    }
+
+    /// 文本字段：数字、布尔值转为文本，null 视为未填写
+    fn string(&mut self, key: &str) {
+        match self.fields.get(key) {
+            None | Some(Value::String(_)) => {}
+            Some(Value::Null) => {
+                self.fields.remove(key);
+            }
+            Some(Value::Number(_)) | Some(Value::Bool(_)) => {
+                let text = self.fields[key].to_string();
+                self.fields.insert(key.to_string(), Value::String(text));
+                self.repair(format!("{} 不是文本，已转换", key));
+            }
+            Some(_) => {
+                self.fields.remove(key);
+                self.repair(format!("{} 格式错误，已清空", key));
+            }
+        }
+    }
+
+    fn strings(&mut self, keys: &[&str]) {
+        for key in keys {
+            self.string(key);
+        }
+    }
+
+    /// 数值字段：接受 "12.5"、"¥1,200" 这类文本，无法识别时清空
+    fn number(&mut self, key: &str) {
+        match self.fields.get(key) {
+            None | Some(Value::Number(_)) => {}
+            Some(Value::Null) => {
+                self.fields.remove(key);
+            }
+            Some(Value::String(text)) => {
+                let cleaned: String = text
+                    .trim()
+                    .chars()
+                    .filter(|c| !matches!(c, ',' | '，' | '¥' | '￥' | ' '))
+                    .collect();
+                let parsed = cleaned
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number);
+                match parsed {
+                    Some(number) => {
+                        self.fields.insert(key.to_string(), number);
+                        self.repair(format!("{} 为文本，已转为数值", key));
+                    }
+                    None if cleaned.is_empty() => {
+                        self.fields.remove(key);
+                    }
+                    None => {
+                        self.fields.remove(key);
+                        self.repair(format!("{} 无法识别为数值，已清空", key));
+                    }
+                }
+            }
+            Some(_) => {
+                self.fields.remove(key);
+                self.repair(format!("{} 无法识别为数值，已清空", key));
+            }
+        }
+    }
+
+    fn numbers(&mut self, keys: &[&str]) {
+        for key in keys {
+            self.number(key);
+        }
+    }
+
+    fn float(&self, key: &str) -> Option<f64> {
+        self.fields.get(key).and_then(Value::as_f64)
+    }
+
+    fn bool(&mut self, key: &str) {
+        let fixed = match self.fields.get(key) {
+            None | Some(Value::Bool(_)) => return,
+            Some(Value::Null) => None,
+            Some(Value::String(text)) => match text.trim() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            },
+            Some(Value::Number(n)) => n.as_f64().map(|n| n != 0.0),
+            Some(_) => None,
+        };
+        match fixed {
+            Some(fixed) => {
+                self.fields.insert(key.to_string(), Value::Bool(fixed));
+                self.repair(format!("{} 不是布尔值，已转换", key));
+            }
+            None => {
+                self.fields.remove(key);
+            }
+        }
+    }
+
+    /// 文本数组：丢弃其中的非文本元素
+    fn string_array(&mut self, key: &str) {
+        match self.fields.get_mut(key) {
+            None => {}
+            Some(Value::Array(items)) => {
+                let before = items.len();
+                items.retain(Value::is_string);
+                if items.len() != before {
+                    self.repair(format!("{} 中有无效元素，已移除", key));
+                }
+            }
+            Some(Value::Null) => {
+                self.fields.remove(key);
+            }
+            Some(_) => {
+                self.fields.remove(key);
+                self.repair(format!("{} 不是数组，已清空", key));
+            }
+        }
+    }
+
+    /// 取值限定在 `allowed` 内，否则改回默认值
+    fn one_of(&mut self, key: &str, allowed: &[&str]) {
+        match self.fields.get(key) {
+            None => {}
+            Some(Value::String(value)) if allowed.contains(&value.as_str()) => {}
+            Some(Value::Null) => {
+                self.fields.remove(key);
+            }
+            Some(_) => {
+                self.fields.remove(key);
+                self.repair(format!("{} 取值无效，已改为默认值", key));
+            }
+        }
+    }
+
+    /// 嵌套对象，缺失或格式错误时按空对象处理
+    fn object(&mut self, key: &str, normalize: impl FnOnce(&mut Record) -> Result<(), String>) {
+        let mut nested = match self.fields.remove(key) {
+            Some(Value::Object(fields)) => Record {
+                fields,
+                repairs: Vec::new(),
+            },
+            None | Some(Value::Null) => return,
+            Some(_) => {
+                self.repair(format!("{} 不是对象，已清空", key));
+                return;
+            }
+        };
+        if let Err(reason) = normalize(&mut nested) {
+            self.repair(format!("{}：{}，已清空", key, reason));
+            return;
+        }
+        for repair in nested.repairs {
+            self.repair(format!("{}：{}", key, repair));
+        }
+        self.fields
+            .insert(key.to_string(), Value::Object(nested.fields));
+    }
+
+    /// 嵌套对象数组，无法修复的元素直接移除
+    fn objects(&mut self, key: &str, normalize: impl Fn(&mut Record) -> Result<(), String>) {
+        let items = match self.fields.remove(key) {
+            Some(Value::Array(items)) => items,
+            None | Some(Value::Null) => return,
+            Some(_) => {
+                self.repair(format!("{} 不是数组，已清空", key));
+                return;
+            }
+        };
+        let mut kept = Vec::with_capacity(items.len());
+        for (index, item) in items.into_iter().enumerate() {
+            let label = format!("{}[{}]", key, index);
+            let mut nested = match Record::new(item) {
+                Ok(nested) => nested,
+                Err(reason) => {
+                    self.repair(format!("{}：{}，已移除", label, reason));
+                    continue;
+                }
+            };
+            match normalize(&mut nested) {
+                Ok(()) => {
+                    for repair in nested.repairs {
+                        self.repair(format!("{}：{}", label, repair));
+                    }
+                    kept.push(Value::Object(nested.fields));
+                }
+                Err(reason) => self.repair(format!("{}：{}，已移除", label, reason)),
+            }
+        }
+        self.fields.insert(key.to_string(), Value::Array(kept));
+    }
+
+    /// 创建/更新时间：缺一个时互相补齐，都缺时用 `fallback`（如开单日期）或当前时间
+    fn timestamps(&mut self, fallback: Option<&str>) {
+        self.strings(&["createdAt", "updatedAt"]);
+        let created = self.text("createdAt").map(str::to_string);
+        let updated = self.text("updatedAt").map(str::to_string);
+        let (created, updated) = match (created, updated) {
+            (Some(_), Some(_)) => return,
+            (Some(created), None) => (created.clone(), created),
+            (None, Some(updated)) => (updated.clone(), updated),
+            (None, None) => {
+                let time = fallback
+                    .map(str::to_string)
+                    .unwrap_or_else(business_db::now_iso);
+                (time.clone(), time)
+            }
+        };
+        self.fields
+            .insert("createdAt".to_string(), Value::String(created));
+        self.fields
+            .insert("updatedAt".to_string(), Value::String(updated));
+        self.repair("缺少创建/更新时间，已补齐");
+    }
+
+    fn finish<T: DeserializeOwned>(self) -> Result<(T, Vec<String>), String> {
+        serde_json::from_value(Value::Object(self.fields))
+            .map(|record| (record, self.repairs))
+            .map_err(|e| format!("字段格式错误: {}", e))
+    }
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn require_name(record: &mut Record, message: &str) -> Result<(), String> {
+    record.string("name");
+    if record.text("name").is_none() {
+        return Err(message.to_string());
+    }
+    Ok(())
+}
+
+fn normalize_customer(record: &mut Record) -> Result<(), String> {
+    record.id("id");
+    require_name(record, "客户名称为空")?;
+    record.strings(&[
+        "category",
+        "phone",
+        "backupPhone",
+        "address",
+        "fax",
+        "email",
+        "notes",
+    ]);
+    record.number("initialDebt");
+    record.string_array("images");
+    record.string_array("attachments");
+    record.bool("isActive");
+    record.timestamps(None);
+    Ok(())
+}
+
+fn normalize_product(record: &mut Record) -> Result<(), String> {
+    record.id("id");
+    require_name(record, "产品名称为空")?;
+    record.strings(&["barcode", "category", "unit", "notes"]);
+    record.objects("specifications", |spec| {
+        spec.id("id");
+        require_name(spec, "规格名称为空")?;
+        spec.bool("isDefault");
+        Ok(())
+    });
+    record.objects("attributes", |attribute| {
+        attribute.id("id");
+        require_name(attribute, "属性名称为空")?;
+        attribute.string("value");
+        Ok(())
+    });
+    record.objects("prices", |price| {
+        price.id("id");
+        price.one_of("type", &["sale", "purchase", "wholesale"]);
+        if price.text("type").is_none() {
+            return Err("价格类型无效".to_string());
+        }
+        price.number("price");
+        if price.float("price").is_none() {
+            return Err("价格为空".to_string());
+        }
+        price.bool("isDefault");
+        Ok(())
+    });
+    record.string_array("tags");
+    record.string_array("images");
+    record.bool("isActive");
+    record.timestamps(None);
+    Ok(())
+}
+
+fn normalize_invoice(record: &mut Record) -> Result<(), String> {
+    let id = record.id("id");
+    record.strings(&[
+        "invoiceNumber",
+        "date",
+        "customerId",
+        "createdBy",
+        "notes",
+        "deliveryDate",
+    ]);
+    if record.text("invoiceNumber").is_none() {
+        let number: String = id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .take(8)
+            .collect();
+        let number = format!("LEGACY-{}", number.to_uppercase());
+        record
+            .fields
+            .insert("invoiceNumber".to_string(), Value::String(number));
+        record.repair("缺少单号，已生成");
+    }
+    if record.text("customerId").is_none() {
+        record.fields.remove("customerId");
+    }
+
+    // 开单日期统一为 YYYY-MM-DD，缺失时取创建时间的日期部分
+    let date = record
+        .text("date")
+        .or_else(|| record.text("createdAt"))
+        .map(str::to_string);
+    let normalized = date
+        .as_deref()
+        .and_then(|date| date.get(..10))
+        .filter(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+        .map(str::to_string);
+    match normalized {
+        Some(normalized) => {
+            if record.text("date") != Some(normalized.as_str()) {
+                record.repair("开单日期格式不规范，已修正");
+            }
+            record
+                .fields
+                .insert("date".to_string(), Value::String(normalized));
+        }
+        None => return Err("开单日期无效".to_string()),
+    }
+
+    record.object("customerInfo", |info| {
+        info.strings(&["name", "address", "phone", "email"]);
+        Ok(())
+    });
+    record.object("companyInfo", |info| {
+        info.strings(&["name", "address", "phone", "email", "taxId"]);
+        Ok(())
+    });
+    record.objects("items", |item| {
+        item.id("id");
+        item.strings(&["productId", "productName", "specification", "unit", "note"]);
+        item.numbers(&["quantity", "deliveryQuantity", "unitPrice", "amount"]);
+        if item.float("amount").is_none() {
+            let amount = round_cents(
+                item.float("quantity").unwrap_or(0.0) * item.float("unitPrice").unwrap_or(0.0),
+            );
+            if let Some(amount) = Number::from_f64(amount) {
+                item.fields
+                    .insert("amount".to_string(), Value::Number(amount));
+                item.repair("缺少金额，已按数量×单价计算");
+            }
+        }
+        Ok(())
+    });
+    record.numbers(&["totalAmount", "paidAmount"]);
+    if record.float("totalAmount").is_none() {
+        let total = record
+            .fields
+            .get("items")
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.get("amount").and_then(Value::as_f64))
+                    .sum::<f64>()
+            })
+            .unwrap_or(0.0);
+        if let Some(total) = Number::from_f64(round_cents(total)) {
+            record
+                .fields
+                .insert("totalAmount".to_string(), Value::Number(total));
+            record.repair("缺少总金额，已按商品行合计");
+        }
+    }
+    record.one_of("status", &["draft", "sent", "paid", "cancelled"]);
+    record.one_of("type", &["sale", "purchase", "return"]);
+    record.one_of("paymentStatus", &["unpaid", "partial", "paid"]);
+    let date = record
+        .text("date")
+        .map(|date| format!("{}T00:00:00.000Z", date));
+    record.timestamps(date.as_deref());
+    Ok(())
+}
+
+fn normalize_quotation(record: &mut Record) -> Result<(), String> {
+    record.id("id");
+    record.strings(&[
+        "quotationNumber",
+        "customName",
+        "tableWidth",
+        "createdBy",
+        "notes",
+    ]);
+    record.numbers(&["headerFontSize", "note1FontSize", "note2FontSize"]);
+    record.object("headerInfo", |info| {
+        info.strings(&["title", "company", "phone", "address", "email"]);
+        Ok(())
+    });
+    record.objects("columns", |column| {
+        column.id("id");
+        column.strings(&["label", "fieldKey", "width"]);
+        column.bool("isSequence");
+        Ok(())
+    });
+    record.objects("items", |item| {
+        item.id("id");
+        Ok(())
+    });
+    record.object("footerInfo", |info| {
+        info.strings(&["note1", "note2", "contactLine", "qrCodeImage"]);
+        info.numbers(&["qrCodeWidth", "qrCodeHeight"]);
+        Ok(())
+    });
+    record.timestamps(None);
+    Ok(())
+}
+
+fn normalize_quotation_product(record: &mut Record) -> Result<(), String> {
+    record.id("id");
+    require_name(record, "产品名称为空")?;
+    record.strings(&["specification", "notes"]);
+    record.number("defaultPrice");
+    record.timestamps(None);
+    Ok(())
+}
+
+fn normalize_payment(record: &mut Record) -> Result<(), String> {
+    record.id("id");
+    record.strings(&["customerId", "date", "method", "note"]);
+    if record.text("customerId").is_none() {
+        return Err("缺少客户 ID".to_string());
+    }
+    if record.text("date").is_none() {
+        return Err("缺少收款日期".to_string());
+    }
+    record.number("amount");
+    if record.float("amount").is_none() {
+        return Err("收款金额为空".to_string());
+    }
+    record.objects("allocations", |allocation| {
+        allocation.strings(&["invoiceId", "invoiceNumber"]);
+        if allocation.text("invoiceId").is_none() {
+            return Err("缺少销售单 ID".to_string());
+        }
+        allocation.number("amount");
+        if allocation.float("amount").is_none() {
+            return Err("分配金额为空".to_string());
+        }
+        Ok(())
+    });
+    let date = record
+        .text("date")
+        .map(|date| format!("{}T00:00:00.000Z", date));
+    record.timestamps(date.as_deref());
+    Ok(())
+}
+
+fn normalize_history(record: &mut Record) -> Result<(), String> {
+    record.strings(&["customerId", "productId"]);
+    if record.text("customerId").is_none() || record.text("productId").is_none() {
+        return Err("缺少客户或产品 ID".to_string());
+    }
+    let mut missing_date = false;
+    record.object("lastPurchase", |purchase| {
+        purchase.strings(&["unit", "specification", "date"]);
+        purchase.numbers(&["unitPrice", "quantity"]);
+        missing_date = purchase.text("date").is_none();
+        Ok(())
+    });
+    if !record.fields.contains_key("lastPurchase") {
+        return Err("缺少购买信息".to_string());
+    }
+    if missing_date {
+        return Err("缺少购买日期".to_string());
+    }
+    Ok(())
+}
+
+/// 可按 ID 覆盖写入的记录
+trait Stored: DeserializeOwned {
+    const TABLE: &'static str;
+    fn id(&self) -> &str;
+    fn updated_at(&self) -> &str;
+    /// 写入前按保存命令的规则校验并修正，修正内容记入 `repairs`，返回错误时跳过该记录
+    fn prepare(&mut self, _conn: &Connection, _repairs: &mut Vec<String>) -> Result<(), String> {
+        Ok(())
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String>;
+}
+
+impl Stored for Customer {
+    const TABLE: &'static str = "customers";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        business_db::upsert_customer(conn, self)
+    }
+}
+
+impl Stored for Product {
+    const TABLE: &'static str = "products";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        business_db::upsert_product(conn, self)
+    }
+}
+
+impl Stored for Invoice {
+    const TABLE: &'static str = "invoices";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    /// 与 save_invoice 相同：金额以后端计算为准，单号不能与其他单据重复，不能与收款分配冲突；
+    /// 已付金额与付款状态由收款维护，库中已有的销售单保留原值，新销售单的已付金额在导入结束时
+    /// 由 `receivables::settle_imported` 转为收款记录
+    fn prepare(&mut self, conn: &Connection, repairs: &mut Vec<String>) -> Result<(), String> {
+        let imported_total = self.total_amount;
+        let stored_paid = conn
+            .query_row(
+                "SELECT paid_amount FROM invoices WHERE id = ?",
+                [&self.id],
+                |row| money::column(row, 0),
+            )
+            .optional()
+            .map_err(business_db::db_err)?;
+        if let Some(paid) = stored_paid {
+            self.paid_amount = paid;
+        }
+        money::apply(self)?;
+        if self.total_amount != imported_total {
+            repairs.push(format!(
+                "总金额与商品行不一致，已按 {} 元重新计算",
+                self.total_amount
+            ));
+        }
+
+        let kind = DocumentKind::of_invoice(self.invoice_type);
+        if numbering::is_taken(conn, kind, &self.invoice_number, Some(&self.id))? {
+            let mut suffix = 2;
+            let renamed = loop {
+                let candidate = format!("{}-{}", self.invoice_number, suffix);
+                if !numbering::is_taken(conn, kind, &candidate, Some(&self.id))? {
+                    break candidate;
+                }
+                suffix += 1;
+            };
+            repairs.push(format!("单号与其他单据重复，已改为 {}", renamed));
+            self.invoice_number = renamed;
+        }
+        receivables::check_invoice(conn, self)
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        business_db::upsert_invoice(conn, self)
+    }
+}
+
+impl Stored for Quotation {
+    const TABLE: &'static str = "quotations";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        business_db::upsert_quotation(conn, self)
+    }
+}
+
+impl Stored for QuotationProduct {
+    const TABLE: &'static str = "quotation_products";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        business_db::upsert_quotation_product(conn, self)
+    }
+}
+
+impl Stored for Payment {
+    const TABLE: &'static str = "payments";
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+    fn write(&self, conn: &Connection) -> Result<(), String> {
+        receivables::upsert_payment(conn, self)
+    }
+}
+
+/// localStorage 中的值是 JSON 字符串，也接受前端已解析好的数组
+fn parse_array(value: &Value) -> Result<Vec<Value>, String> {
+    let parsed = match value {
+        Value::String(text) if text.trim().is_empty() => return Ok(Vec::new()),
+        Value::String(text) => {
+            serde_json::from_str(text).map_err(|e| format!("JSON 解析失败: {}", e))?
+        }
+        Value::Null => return Ok(Vec::new()),
+        other => other.clone(),
+    };
+    match parsed {
+        Value::Array(items) => Ok(items),
+        _ => Err("内容不是数组".to_string()),
+    }
+}
+
+/// 逐条导入一个键下的记录；只有数据库写入失败才中止（整个事务回滚）
+fn import_key(
+    report: &mut ImportReport,
+    key: &str,
+    value: Option<&Value>,
+    mut import: impl FnMut(Value) -> Result<Outcome, String>,
+) -> Result<(), String> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let mut summary = KeyReport {
+        key: key.to_string(),
+        ..KeyReport::default()
+    };
+    let items = match parse_array(value) {
+        Ok(items) => items,
+        Err(reason) => {
+            summary.error = Some(reason);
+            report.push_key(summary);
+            return Ok(());
+        }
+    };
+    summary.total = items.len();
+    for (index, item) in items.into_iter().enumerate() {
+        let fallback_id = item.get("id").and_then(Value::as_str).map(str::to_string);
+        let (skipped, id, reasons) = match import(item)? {
+            Outcome::Imported { id, repairs } => {
+                summary.imported += 1;
+                if repairs.is_empty() {
+                    continue;
+                }
+                summary.repaired += 1;
+                (false, id, repairs)
+            }
+            Outcome::Skipped { id, reasons } => {
+                summary.skipped += 1;
+                (true, id.or(fallback_id), reasons)
+            }
+        };
+        report.issues.push(ImportIssue {
+            key: key.to_string(),
+            index,
+            id,
+            skipped,
+            reasons,
+        });
+    }
+    report.push_key(summary);
+    Ok(())
+}
+
+enum Outcome {
+    Imported {
+        id: Option<String>,
+        repairs: Vec<String>,
+    },
+    Skipped {
+        id: Option<String>,
+        reasons: Vec<String>,
+    },
+}
+
+fn skipped(id: Option<String>, mut repairs: Vec<String>, reason: String) -> Outcome {
+    repairs.push(reason);
+    Outcome::Skipped {
+        id,
+        reasons: repairs,
+    }
+}
+
+fn import_stored<T: Stored>(
+    conn: &Connection,
+    value: Value,
+    normalize: fn(&mut Record) -> Result<(), String>,
+) -> Result<Outcome, String> {
+    let mut record = match Record::new(value) {
+        Ok(record) => record,
+        Err(reason) => return Ok(skipped(None, Vec::new(), reason)),
+    };
+    if let Err(reason) = normalize(&mut record) {
+        let id = record.text("id").map(str::to_string);
+        return Ok(skipped(id, record.repairs, reason));
+    }
+    let (mut item, mut repairs) = match record.finish::<T>() {
+        Ok(finished) => finished,
+        Err(reason) => return Ok(skipped(None, Vec::new(), reason)),
+    };
+    let id = Some(item.id().to_string());
+    if let Some(existing) = business_db::stored_updated_at(conn, T::TABLE, item.id())? {
+        if existing.as_str() >= item.updated_at() {
+            return Ok(skipped(
+                id,
+                repairs,
+                "数据库中已有相同或更新的记录".to_string(),
+            ));
+        }
+    }
+    if let Err(reason) = item.prepare(conn, &mut repairs) {
+        return Ok(skipped(id, repairs, reason));
+    }
+    item.write(conn)?;
+    Ok(Outcome::Imported { id, repairs })
+}
+
+fn import_history(conn: &Connection, value: Value) -> Result<Outcome, String> {
+    let mut record = match Record::new(value) {
+        Ok(record) => record,
+        Err(reason) => return Ok(skipped(None, Vec::new(), reason)),
+    };
+    if let Err(reason) = normalize_history(&mut record) {
+        return Ok(skipped(None, record.repairs, reason));
+    }
+    let (history, repairs) = match record.finish::<CustomerProductHistory>() {
+        Ok(finished) => finished,
+        Err(reason) => return Ok(skipped(None, Vec::new(), reason)),
+    };
+    let id = Some(format!("{}/{}", history.customer_id, history.product_id));
+    if !business_db::upsert_customer_product_history(conn, &history)? {
+        return Ok(skipped(
+            id,
+            repairs,
+            "数据库中已有相同或更新的记录".to_string(),
+        ));
+    }
+    Ok(Outcome::Imported { id, repairs })
+}
+
+/// 可导入的数据集合
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Collection {
+    Customers,
+    Products,
+    QuotationProducts,
+    Invoices,
+    Quotations,
+    CustomerHistory,
+    Payments,
+}
+
+/// 校验修复并导入一个集合（`key` 为来源中的键名，仅用于报告）。
+/// 销售单与购买历史引用客户和产品，应排在它们之后导入；收款引用销售单，排在销售单之后
+pub(crate) fn import_collection(
+    conn: &Connection,
+    report: &mut ImportReport,
+    collection: Collection,
+    key: &str,
+    value: Option<&Value>,
+) -> Result<(), String> {
+    import_key(report, key, value, |v| match collection {
+        Collection::Customers => import_stored::<Customer>(conn, v, normalize_customer),
+        Collection::Products => import_stored::<Product>(conn, v, normalize_product),
+        Collection::QuotationProducts => {
+            import_stored::<QuotationProduct>(conn, v, normalize_quotation_product)
+        }
+        Collection::Invoices => import_stored::<Invoice>(conn, v, normalize_invoice),
+        Collection::Quotations => import_stored::<Quotation>(conn, v, normalize_quotation),
+        Collection::CustomerHistory => import_history(conn, v),
+        Collection::Payments => import_stored::<Payment>(conn, v, normalize_payment),
+    })
+}
+
+/// 在一个事务中导入全部键，任一写入失败时整体回滚
+fn import_payload(
+    conn: &mut Connection,
+    payload: &Map<String, Value>,
+) -> Result<ImportReport, String> {
+    let tx = conn.transaction().map_err(business_db::db_err)?;
+    let mut report = ImportReport::default();
+
+    for (collection, key) in LEGACY_KEYS {
+        import_collection(&tx, &mut report, collection, key, payload.get(key))?;
+    }
+
+    for (key, value) in payload {
+        if !key.starts_with(LAST_SELECTED_PREFIX) {
+            continue;
+        }
+        let mut summary = KeyReport {
+            key: key.clone(),
+            total: 1,
+            ..KeyReport::default()
+        };
+        match value {
+            Value::String(text) => {
+                business_db::set_preference(&tx, key, text.trim())?;
+                summary.imported = 1;
+            }
+            _ => {
+                summary.skipped = 1;
+                summary.error = Some("内容不是文本".to_string());
+            }
+        }
+        report.push_key(summary);
+    }
+
+    receivables::settle_imported(&tx)?;
+    business_db::set_preference(&tx, IMPORTED_AT_KEY, &business_db::now_iso())?;
+    tx.commit().map_err(business_db::db_err)?;
+    Ok(report)
+}
+
+/// 导入旧版 localStorage 数据。`payload` 的键为 localStorage 键名：
+/// `customers`、`products`、`invoice_history`、`quotations`、`quotation_products`、
+/// `customer_product_history` 以及 `last_selected_*`；未提供的键直接跳过。
+#[tauri::command]
+pub async fn import_legacy_data<R: Runtime>(
+    app: AppHandle<R>,
+    payload: Map<String, Value>,
+) -> Result<ImportReport, String> {
+    with_db_write(&app, |conn| import_payload(conn, &payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_migrations;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // 空库不做迁移前备份，目录不会被创建
+        db_migrations::migrate(&mut conn, Path::new("unused")).unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        conn
+    }
+
+    fn payload(value: Value) -> Map<String, Value> {
+        // localStorage 中的值是 JSON 字符串
+        value
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| {
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), Value::String(text))
+            })
+            .collect()
+    }
+
+    fn key<'a>(report: &'a ImportReport, name: &str) -> &'a KeyReport {
+        report.keys.iter().find(|key| key.key == name).unwrap()
+    }
+
+    #[test]
+    fn reports_imported_skipped_and_repaired_records() {
+        let mut conn = setup();
+        let report = import_payload(
+            &mut conn,
+            &payload(json!({
+                "customers": [
+                    { "id": "c1", "name": "张三", "createdAt": "2026-01-01T00:00:00.000Z",
+                      "updatedAt": "2026-01-01T00:00:00.000Z" },
+                    { "id": 42, "name": "李四", "initialDebt": "¥1,200.5", "isActive": "0",
+                      "images": ["a.jpg", 3] },
+                    { "id": "c3", "name": "  " },
+                    "不是对象",
+                ],
+                "invoice_history": [
+                    { "id": "s1", "date": "2026-01-05T08:00:00Z", "customerId": "c1",
+                      "status": "archived",
+                      "items": [{ "id": "i1", "quantity": "2", "unitPrice": 1.25 }, 7] },
+                    { "id": "s2", "invoiceNumber": "XS-2" },
+                ],
+                "customer_product_history": [
+                    { "customerId": "c1", "productId": "p1",
+                      "lastPurchase": { "unitPrice": 1.25, "date": "2026-01-05" } },
+                    { "customerId": "c1" },
+                ],
+                "products": "{broken",
+                "last_selected_sales_company": "  公司A ",
+            })),
+        )
+        .unwrap();
+
+        let customers = key(&report, "customers");
+        assert_eq!(
+            (
+                customers.total,
+                customers.imported,
+                customers.skipped,
+                customers.repaired
+            ),
+            (4, 2, 2, 1)
+        );
+        let repaired = report
+            .issues
+            .iter()
+            .find(|issue| issue.key == "customers" && !issue.skipped)
+            .unwrap();
+        assert_eq!(repaired.id.as_deref(), Some("42"));
+        assert!(repaired
+            .reasons
+            .iter()
+            .any(|r| r == "id 为数字，已转为文本"));
+        assert!(repaired
+            .reasons
+            .iter()
+            .any(|r| r == "initialDebt 为文本，已转为数值"));
+        assert!(repaired
+            .reasons
+            .iter()
+            .any(|r| r == "images 中有无效元素，已移除"));
+        let blank = report
+            .issues
+            .iter()
+            .find(|issue| issue.key == "customers" && issue.index == 2)
+            .unwrap();
+        assert!(blank.skipped);
+        assert_eq!(blank.id.as_deref(), Some("c3"));
+        assert_eq!(blank.reasons, ["客户名称为空"]);
+
+        let stored = business_db::get_customer_by_id(&conn, "42")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.initial_debt.to_string(), "1200.5");
+        assert!(!stored.is_active);
+        assert_eq!(stored.images, ["a.jpg"]);
+
+        let invoices = key(&report, "invoice_history");
+        assert_eq!(
+            (invoices.imported, invoices.skipped, invoices.repaired),
+            (1, 1, 1)
+        );
+        let invoice = business_db::get_invoice_by_id(&conn, "s1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(invoice.invoice_number, "LEGACY-S1");
+        assert_eq!(invoice.date, "2026-01-05");
+        assert_eq!(invoice.items.len(), 1);
+        assert_eq!(invoice.items[0].amount.to_string(), "2.5");
+        assert_eq!(invoice.total_amount.to_string(), "2.5");
+        assert_eq!(invoice.created_at, "2026-01-05T00:00:00.000Z");
+        let missing_date = report
+            .issues
+            .iter()
+            .find(|issue| issue.id.as_deref() == Some("s2"))
+            .unwrap();
+        assert!(missing_date.skipped);
+        assert!(missing_date.reasons.iter().any(|r| r == "开单日期无效"));
+
+        let history = key(&report, "customer_product_history");
+        assert_eq!((history.imported, history.skipped), (1, 1));
+
+        let products = key(&report, "products");
+        assert_eq!(products.total, 0);
+        assert!(products
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("JSON 解析失败"));
+
+        assert_eq!(
+            business_db::preference(&conn, "last_selected_sales_company")
+                .unwrap()
+                .as_deref(),
+            Some("公司A")
+        );
+        assert!(business_db::preference(&conn, IMPORTED_AT_KEY)
+            .unwrap()
+            .is_some());
+        assert_eq!(report.imported, 2 + 1 + 1 + 1);
+        assert_eq!(report.skipped, 2 + 1 + 1);
+        assert_eq!(report.repaired, 2);
+    }
+
+    #[test]
+    fn repeated_import_only_writes_newer_records() {
+        let mut conn = setup();
+        let customer = |name: &str, updated_at: &str| {
+            payload(json!({ "customers": [{
+                "id": "c1", "name": name, "createdAt": "2026-01-01T00:00:00.000Z",
+                "updatedAt": updated_at,
+            }] }))
+        };
+        let first = import_payload(&mut conn, &customer("张三", "2026-01-02T00:00:00.000Z"));
+        assert_eq!(first.unwrap().imported, 1);
+
+        let again =
+            import_payload(&mut conn, &customer("张三", "2026-01-02T00:00:00.000Z")).unwrap();
+        assert_eq!((again.imported, again.skipped), (0, 1));
+        assert_eq!(again.issues[0].reasons, ["数据库中已有相同或更新的记录"]);
+
+        let older =
+            import_payload(&mut conn, &customer("旧名", "2026-01-01T00:00:00.000Z")).unwrap();
+        assert_eq!(older.skipped, 1);
+
+        let newer =
+            import_payload(&mut conn, &customer("新名", "2026-02-01T00:00:00.000Z")).unwrap();
+        assert_eq!(newer.imported, 1);
+        let stored = business_db::get_customer_by_id(&conn, "c1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.name, "新名");
+    }
+
+    #[test]
+    fn invoices_are_validated_like_saved_invoices() {
+        let mut conn = setup();
+        let invoice = |id: &str, number: &str, extra: Value| {
+            let mut invoice = json!({
+                "id": id, "invoiceNumber": number, "date": "2026-01-05", "customerId": "c1",
+                "items": [{ "id": "i1", "quantity": 2, "unitPrice": 5 }],
+                "totalAmount": 999, "paidAmount": 0, "paymentStatus": "paid",
+                "updatedAt": "2026-01-05T00:00:00.000Z",
+            });
+            invoice
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            invoice
+        };
+        import_payload(
+            &mut conn,
+            &payload(json!({
+                "customers": [{ "id": "c1", "name": "张三" }],
+                "invoice_history": [invoice("s1", "XS-1", json!({}))],
+            })),
+        )
+        .unwrap();
+        let stored = business_db::get_invoice_by_id(&conn, "s1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.total_amount.to_string(), "10");
+        assert_eq!(stored.payment_status.as_str(), "unpaid");
+
+        // 收款分配写入后，已付金额由后端维护
+        let payment: Payment = serde_json::from_value(json!({
+            "id": "p1", "customerId": "c1", "date": "2026-01-06", "amount": 4,
+            "allocations": [{ "invoiceId": "s1", "amount": 4 }],
+        }))
+        .unwrap();
+        receivables::upsert_payment(&conn, &payment).unwrap();
+        conn.execute("UPDATE invoices SET paid_amount = 4 WHERE id = 's1'", [])
+            .unwrap();
+
+        let report = import_payload(
+            &mut conn,
+            &payload(json!({
+                "invoice_history": [
+                    invoice("s1", "XS-1", json!({ "notes": "改过", "updatedAt": "2026-02-01T00:00:00.000Z" })),
+                    invoice("s2", "XS-1", json!({})),
+                    invoice("s3", "XS-3", json!({ "status": "cancelled" })),
+                ],
+            })),
+        )
+        .unwrap();
+        let updated = business_db::get_invoice_by_id(&conn, "s1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.notes.as_deref(), Some("改过"));
+        assert_eq!(updated.paid_amount.to_string(), "4");
+        assert_eq!(updated.payment_status.as_str(), "partial");
+        let repaired = report
+            .issues
+            .iter()
+            .find(|issue| issue.id.as_deref() == Some("s2"))
+            .unwrap();
+        assert!(!repaired.skipped);
+        assert!(repaired
+            .reasons
+            .iter()
+            .any(|r| r == "单号与其他单据重复，已改为 XS-1-2"));
+        assert_eq!(
+            business_db::get_invoice_by_id(&conn, "s2")
+                .unwrap()
+                .unwrap()
+                .invoice_number,
+            "XS-1-2"
+        );
+        assert!(business_db::get_invoice_by_id(&conn, "s3")
+            .unwrap()
+            .is_some());
+
+        // 已有收款的销售单不能被导入的旧记录作废
+        let conflict = import_payload(
+            &mut conn,
+            &payload(json!({
+                "invoice_history": [invoice("s1", "XS-1", json!({
+                    "status": "cancelled", "updatedAt": "2026-03-01T00:00:00.000Z",
+                }))],
+            })),
+        )
+        .unwrap();
+        assert_eq!(conflict.skipped, 1);
+        assert!(conflict.issues[0]
+            .reasons
+            .last()
+            .unwrap()
+            .starts_with("该销售单已有收款记录"));
+    }
+
+    #[test]
+    fn accepts_parsed_arrays_and_rejects_non_arrays() {
+        let mut conn = setup();
+        let mut payload = Map::new();
+        payload.insert(
+            "quotation_products".to_string(),
+            json!([{ "name": "螺母", "defaultPrice": "2.5" }]),
+        );
+        payload.insert("quotations".to_string(), json!("{\"id\": \"q1\"}"));
+        payload.insert("last_selected_sales_address".to_string(), json!(1));
+        let report = import_payload(&mut conn, &payload).unwrap();
+
+        let products = key(&report, "quotation_products");
+        assert_eq!((products.imported, products.repaired), (1, 1));
+        let stored = business_db::all_quotation_products(&conn).unwrap();
+        assert_eq!(stored[0].default_price, Some(2.5));
+        // 缺少 ID 时生成 UUID
+        assert_eq!(stored[0].id.len(), 36);
+
+        assert_eq!(
+            key(&report, "quotations").error.as_deref(),
+            Some("内容不是数组")
+        );
+        let preference = key(&report, "last_selected_sales_address");
+        assert_eq!(preference.skipped, 1);
+        assert_eq!(preference.error.as_deref(), Some("内容不是文本"));
+    }
+}
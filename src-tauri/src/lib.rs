@@ -1,10 +1,38 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::fs;
-use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::time::Duration;
+use tauri::Manager;
 
 mod access_control;
+mod activation;
+mod auto_backup;
+mod backup_container;
+mod business_db;
+mod business_models;
+mod clipboard_watch;
+mod clock_guard;
+mod db_migrations;
+mod download_integrity;
+mod encrypted_backup;
+mod entitlements;
+mod image_archive;
+mod image_convert;
+mod install_stamp;
+mod legacy_import;
+mod license;
+mod media_metadata;
+mod media_probe;
+mod media_protocol;
+mod media_store;
+mod money;
+mod mp4;
+mod mp4_mux;
+mod numbering;
+mod receivables;
+mod secret_store;
+mod statement;
+mod thumbnail_cache;
 
 const MOBILE_USER_AGENT: &str =
     "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
@@ -92,12 +120,10 @@ fn get_array_first_str(root: &Value, path: &[&str]) -> Option<String> {
 }
 
 fn first_non_empty(candidates: Vec<Option<String>>) -> Option<String> {
-    for candidate in candidates {
-        if let Some(value) = candidate {
-            let trimmed = value.trim();
-            if !trimmed.is_empty() {
-                return Some(trimmed.to_string());
-            }
+    for value in candidates.into_iter().flatten() {
+        let trimmed = value.trim();
+        if !trimmed.is_empty() {
+            return Some(trimmed.to_string());
         }
     }
     None
@@ -171,7 +197,7 @@ fn extract_router_data_json(html: &str) -> Option<String> {
     }
 }
 
-fn extract_douyin_item_from_router_data<'a>(router_data: &'a Value) -> Option<&'a Value> {
+fn extract_douyin_item_from_router_data(router_data: &Value) -> Option<&Value> {
     let loader_data = router_data.get("loaderData")?.as_object()?;
     for value in loader_data.values() {
         if let Some(item) = value
@@ -868,40 +894,106 @@ async fn parse_video_via_providers_inner(
     Err(format!("所有解析源均失败: {}", errors.join(" | ")))
 }
 
+/// 抓取视频文件头探测真实时长，覆盖解析源给出的（可能单位有误的）时长；探测失败时保留原值
+async fn refine_with_probe(mut info: VideoInfo) -> VideoInfo {
+    if info.content_type != "video" {
+        return info;
+    }
+    if let Some(video_url) = info.video_url.as_deref() {
+        if let Ok(probe) = media_probe::probe_remote(video_url).await {
+            if let Some(duration) = probe.duration.filter(|d| *d > 0.0) {
+                info.duration = Some(duration.round() as u32);
+            }
+        }
+    }
+    info
+}
+
+/// 登记解析结果中的媒体地址，之后才能通过 `media://` 协议预览
+fn allow_media_hosts(app: &tauri::AppHandle, info: &VideoInfo) {
+    let urls = [&info.video_url, &info.music_url]
+        .into_iter()
+        .flatten()
+        .chain(info.images.iter().flatten())
+        .chain(std::iter::once(&info.cover))
+        .map(String::as_str);
+    app.state::<media_protocol::MediaHosts>().allow(urls);
+}
+
 #[tauri::command]
-async fn parse_video_via_providers(url: String) -> Result<VideoInfo, String> {
+async fn parse_video_via_providers(
+    app: tauri::AppHandle,
+    url: String,
+    probe: Option<bool>,
+) -> Result<VideoInfo, String> {
     let client = build_http_client()?;
-    parse_video_via_providers_inner(&client, &url).await
+    let mut info = parse_video_via_providers_inner(&client, &url).await?;
+
+    if probe.unwrap_or(false) {
+        info = refine_with_probe(info).await;
+    }
+    allow_media_hosts(&app, &info);
+    Ok(info)
 }
 
 /// 解析抖音视频/图文链接（兼容旧命令）
 #[tauri::command]
-async fn parse_douyin_video(url: String) -> Result<VideoInfo, String> {
+async fn parse_douyin_video(
+    app: tauri::AppHandle,
+    url: String,
+    probe: Option<bool>,
+) -> Result<VideoInfo, String> {
     let client = build_http_client()?;
 
-    match try_parse_douyin_share_page(&client, &url).await {
-        Ok(info) => Ok(info),
+    let info = match try_parse_douyin_share_page(&client, &url).await {
+        Ok(info) => info,
         Err(share_error) => match parse_douyin_via_official_api(&client, &url).await {
-            Ok(info) => Ok(info),
+            Ok(info) => info,
             Err(official_error) => match parse_video_via_providers_inner(&client, &url).await {
-                Ok(info) => Ok(info),
-                Err(provider_error) => Err(format!(
-                    "抖音解析失败（分享页 + 官方接口 + 备用接口）: {} | {} | {}",
-                    share_error, official_error, provider_error
-                )),
+                Ok(info) => info,
+                Err(provider_error) => {
+                    return Err(format!(
+                        "抖音解析失败（分享页 + 官方接口 + 备用接口）: {} | {} | {}",
+                        share_error, official_error, provider_error
+                    ))
+                }
             },
         },
+    };
+
+    let info = if probe.unwrap_or(false) {
+        refine_with_probe(info).await
+    } else {
+        info
+    };
+    allow_media_hosts(&app, &info);
+    Ok(info)
+}
+
+/// 下载媒体文件用的客户端：大文件不设总超时，只限制连接时间与两次读到数据的间隔，
+/// 避免网络中断后下载永远挂起
+fn media_client() -> Result<&'static reqwest::Client, String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
     }
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(10))
+        .connect_timeout(Duration::from_secs(15))
+        .read_timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("创建客户端失败: {}", e))?;
+    Ok(CLIENT.get_or_init(|| client))
 }
 
-#[tauri::command]
-async fn download_video(url: String, filename: String) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let referer = build_referer(&url);
+/// 带上来源站点的 Referer/Origin/User-Agent 请求媒体文件
+async fn request_media(url: &str) -> Result<reqwest::Response, String> {
+    let client = media_client()?;
+    let referer = build_referer(url);
     let origin = referer.trim_end_matches('/').to_string();
 
     let response = client
-        .get(&url)
+        .get(url)
         .header("Referer", &referer)
         .header("Origin", &origin)
         .header("User-Agent", MOBILE_USER_AGENT)
@@ -912,62 +1004,26 @@ async fn download_video(url: String, filename: String) -> Result<String, String>
     if !response.status().is_success() {
         return Err(format!("下载失败: HTTP {}", response.status()));
     }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("读取数据失败: {}", e))?;
-
-    #[cfg(target_os = "android")]
-    let download_dir = PathBuf::from("/storage/emulated/0/Download");
-
-    #[cfg(target_os = "ios")]
-    let download_dir = std::env::temp_dir();
-
-    #[cfg(not(any(target_os = "android", target_os = "ios")))]
-    let download_dir = dirs::download_dir().ok_or_else(|| "无法获取下载目录".to_string())?;
-
-    fs::create_dir_all(&download_dir).map_err(|e| format!("创建目录失败: {}", e))?;
-
-    let file_path = download_dir.join(&filename);
-    fs::write(&file_path, bytes).map_err(|e| format!("保存文件失败: {}", e))?;
-
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(response)
 }
 
-/// 获取视频数据 (Base64 编码)
 #[tauri::command]
-async fn fetch_video_base64(url: String) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
-
-    let referer = build_referer(&url);
-    let origin = referer.trim_end_matches('/').to_string();
-
-    let response = client
-        .get(&url)
-        .header("Referer", &referer)
-        .header("Origin", &origin)
-        .header("User-Agent", MOBILE_USER_AGENT)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!("获取失败: HTTP {}", response.status()));
-    }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("读取数据失败: {}", e))?;
-
-    use base64::{engine::general_purpose, Engine as _};
-    let base64_data = general_purpose::STANDARD.encode(&bytes);
-
-    Ok(base64_data)
+async fn download_video(
+    app: tauri::AppHandle,
+    url: String,
+    filename: String,
+    metadata: Option<media_metadata::MediaMetadata>,
+    conversion: Option<image_convert::ImageConversion>,
+) -> Result<download_integrity::DownloadRecord, String> {
+    let response = request_media(&url).await?;
+    media_store::save_download(
+        &app,
+        response,
+        &filename,
+        metadata.as_ref(),
+        conversion.as_ref(),
+    )
+    .await
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -975,11 +1031,92 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_android_permissions::init())
+        .manage(clipboard_watch::ClipboardWatcher::default())
+        .manage(media_protocol::MediaHosts::default())
+        .manage(business_db::BusinessDb::default())
+        .setup(|app| {
+            // 数据库无法打开（如版本高于应用）时不阻止启动，各数据命令会返回同样的错误
+            let _ = business_db::init(app.handle());
+            auto_backup::start(app.handle());
+            Ok(())
+        })
+        .on_window_event(|_window, _event| {
+            // 移动端获得焦点时检查剪贴板中的分享链接
+            #[cfg(mobile)]
+            if let tauri::WindowEvent::Focused(true) = _event {
+                clipboard_watch::on_focus(_window.app_handle());
+            }
+        })
+        .register_asynchronous_uri_scheme_protocol(media_protocol::SCHEME, media_protocol::handle)
+        .register_uri_scheme_protocol(thumbnail_cache::SCHEME, thumbnail_cache::handle)
         .invoke_handler(tauri::generate_handler![
             download_video,
             parse_douyin_video,
             parse_video_via_providers,
-            fetch_video_base64,
+            image_archive::download_images_archive,
+            media_store::verify_download,
+            media_metadata::read_media_metadata,
+            media_probe::probe_media,
+            mp4_mux::merge_audio_video,
+            mp4_mux::download_dash_video,
+            thumbnail_cache::cache_thumbnail,
+            thumbnail_cache::clear_thumbnail_cache,
+            clipboard_watch::read_clipboard_text,
+            clipboard_watch::check_clipboard,
+            clipboard_watch::take_share_link,
+            clipboard_watch::start_clipboard_watch,
+            license::import_license,
+            activation::activate_code,
+            license::get_device_id,
+            entitlements::get_entitlements,
+            business_db::list_customers,
+            business_db::get_customer,
+            business_db::save_customer,
+            business_db::delete_customer,
+            business_db::list_products,
+            business_db::get_product,
+            business_db::save_product,
+            business_db::delete_product,
+            business_db::list_invoices,
+            business_db::get_invoice,
+            business_db::save_invoice,
+            business_db::delete_invoice,
+            business_db::list_quotations,
+            business_db::get_quotation,
+            business_db::save_quotation,
+            business_db::delete_quotation,
+            business_db::list_quotation_products,
+            business_db::save_quotation_product,
+            business_db::delete_quotation_product,
+            business_db::get_customer_product_history,
+            business_db::list_customer_product_history,
+            business_db::get_preference,
+            legacy_import::import_legacy_data,
+            encrypted_backup::export_encrypted_backup,
+            encrypted_backup::export_backup,
+            encrypted_backup::import_encrypted_backup,
+            encrypted_backup::backup_transfer_dir,
+            auto_backup::get_auto_backup_status,
+            auto_backup::save_auto_backup_settings,
+            auto_backup::create_backup_snapshot,
+            auto_backup::list_backup_snapshots,
+            auto_backup::restore_backup_snapshot,
+            money::calculate_invoice,
+            numbering::get_numbering_rules,
+            numbering::save_numbering_rule,
+            numbering::next_document_number,
+            numbering::preview_document_number,
+            numbering::check_document_number,
+            receivables::list_payments,
+            receivables::get_payment,
+            receivables::list_invoice_payments,
+            receivables::save_payment,
+            receivables::delete_payment,
+            receivables::collect_invoice_payment,
+            receivables::get_customer_balance,
+            receivables::list_customer_balances,
+            statement::generate_customer_statement,
             access_control::check_access_status
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,240 @@
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+// 许可证签名公钥（Ed25519，hex）。私钥由发布负责人保管并用 examples/sign_license.rs 签发许可证，
+// 发布构建必须通过环境变量 LICENSE_PUBLIC_KEY 提供对应公钥，未设置时编译失败
+#[cfg(not(debug_assertions))]
+const PUBLIC_KEY: &str = env!(
+    "LICENSE_PUBLIC_KEY",
+    "发布构建需要设置环境变量 LICENSE_PUBLIC_KEY（许可证签名公钥，hex）"
+);
+// 调试构建未设置时使用开发公钥，仅供本地测试，私钥随仓库公开
+#[cfg(debug_assertions)]
+const PUBLIC_KEY: &str = match option_env!("LICENSE_PUBLIC_KEY") {
+    Some(key) => key,
+    None => "6658b4366613801d4306cc6730fce7a072876c05e5e7c6fa0417a4b5ea1505be",
+};
+// 已导入的许可证文件名
+const LICENSE_FILENAME: &str = "license.json";
+// 设备标识文件名，许可证按此标识绑定设备
+const DEVICE_ID_FILENAME: &str = ".device_id";
+// 目前支持的许可证格式版本
+const LICENSE_VERSION: u32 = 1;
+
+/// 许可证文件：`payload` 为许可证内容的 JSON 字符串，`signature` 为对其 UTF-8 字节的 Ed25519 签名（hex）
+#[derive(Debug, Deserialize)]
+struct LicenseFile {
+    payload: String,
+    signature: String,
+}
+
+/// 许可证内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct License {
+    pub version: u32,
+    pub license_id: String,
+    pub licensee: String,
+    /// 绑定的设备标识，为空表示不限设备
+    #[serde(default)]
+    pub device_id: Option<String>,
+    pub issued_at: u64,
+    /// 到期时间（Unix 秒），为空表示永久有效
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// 允许的系统时钟回拨幅度（秒），为空时使用默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_tolerance_secs: Option<u64>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+pub(crate) fn now_secs() -> Result<u64, String> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .map_err(|e| e.to_string())
+}
+
+fn public_key() -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(PUBLIC_KEY.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "许可证公钥配置错误".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "许可证公钥配置错误".to_string())
+}
+
+/// 校验签名、格式版本与设备绑定，全部通过才返回许可证内容。
+/// 到期后仍可只读访问，是否过期由访问控制按宽限期判断
+pub fn verify_license(content: &str, device_id: &str) -> Result<License, String> {
+    verify_with_key(content, device_id, &public_key()?)
+}
+
+fn verify_with_key(content: &str, device_id: &str, key: &VerifyingKey) -> Result<License, String> {
+    let file: LicenseFile =
+        serde_json::from_str(content.trim()).map_err(|_| "许可证文件格式错误".to_string())?;
+    let signature = hex::decode(file.signature.trim())
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| "许可证签名格式错误".to_string())?;
+    key.verify(file.payload.as_bytes(), &signature)
+        .map_err(|_| "许可证签名无效".to_string())?;
+
+    let license: License =
+        serde_json::from_str(&file.payload).map_err(|_| "许可证内容格式错误".to_string())?;
+    if license.version != LICENSE_VERSION {
+        return Err(format!("不支持的许可证版本: {}", license.version));
+    }
+    if let Some(bound) = license.device_id.as_deref() {
+        if bound != device_id {
+            return Err("许可证不属于本设备".to_string());
+        }
+    }
+    Ok(license)
+}
+
+pub(crate) fn data_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(dir)
+}
+
+pub(crate) fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<(), String> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    fs::rename(&temp, path).map_err(|e| format!("写入文件失败: {}", e))
+}
+
+/// 本机设备标识：首次调用时随机生成并保存在应用数据目录
+pub fn device_id<R: Runtime>(app: &AppHandle<R>) -> Result<String, String> {
+    let path = data_dir(app)?.join(DEVICE_ID_FILENAME);
+    if let Ok(content) = fs::read_to_string(&path) {
+        let id = content.trim();
+        if !id.is_empty() {
+            return Ok(id.to_string());
+        }
+    }
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    write_atomic(&path, &id)?;
+    Ok(id)
+}
+
+/// 读取并校验已导入的许可证；未导入时返回 None
+pub fn load_license<R: Runtime>(app: &AppHandle<R>) -> Result<Option<License>, String> {
+    let path = data_dir(app)?.join(LICENSE_FILENAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取许可证失败: {}", e))?;
+    verify_license(&content, &device_id(app)?).map(Some)
+}
+
+/// 导入许可证文件内容，校验通过后保存到应用数据目录
+#[tauri::command]
+pub fn import_license<R: Runtime>(app: AppHandle<R>, content: String) -> Result<License, String> {
+    let license = verify_license(&content, &device_id(&app)?)?;
+    let now = crate::clock_guard::effective_now(&app)?;
+    if license
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return Err("许可证已过期".to_string());
+    }
+    write_atomic(&data_dir(&app)?.join(LICENSE_FILENAME), content.trim())?;
+    Ok(license)
+}
+
+/// 获取本机设备标识，申请绑定设备的许可证时提供给发放方
+#[tauri::command]
+pub fn get_device_id<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
+    device_id(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn payload(version: u32, device_id: Option<&str>) -> String {
+        serde_json::json!({
+            "version": version,
+            "licenseId": "L-001",
+            "licensee": "测试客户",
+            "deviceId": device_id,
+            "issuedAt": 1_700_000_000u64,
+            "expiresAt": 1_800_000_000u64,
+            "features": ["videoTools", "unknownFeature"],
+        })
+        .to_string()
+    }
+
+    fn sign(key: &SigningKey, payload: &str) -> String {
+        serde_json::json!({
+            "payload": payload,
+            "signature": hex::encode(key.sign(payload.as_bytes()).to_bytes()),
+        })
+        .to_string()
+    }
+
+    fn verify(content: &str, device_id: &str) -> Result<License, String> {
+        verify_with_key(content, device_id, &signing_key().verifying_key())
+    }
+
+    #[test]
+    fn accepts_a_signed_license() {
+        let license = verify(&sign(&signing_key(), &payload(1, None)), "any-device").unwrap();
+        assert_eq!(license.license_id, "L-001");
+        assert_eq!(license.expires_at, Some(1_800_000_000));
+        assert_eq!(license.features, vec!["videoTools", "unknownFeature"]);
+
+        let bound = sign(&signing_key(), &payload(1, Some("device-a")));
+        assert!(verify(&bound, "device-a").is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_or_foreign_signatures() {
+        let content = sign(&signing_key(), &payload(1, None));
+        let tampered = content.replace("L-001", "L-002");
+        assert_eq!(verify(&tampered, "d").unwrap_err(), "许可证签名无效");
+
+        let foreign = sign(&SigningKey::from_bytes(&[9u8; 32]), &payload(1, None));
+        assert_eq!(verify(&foreign, "d").unwrap_err(), "许可证签名无效");
+
+        let bad_signature = serde_json::json!({"payload": payload(1, None), "signature": "zz"});
+        assert_eq!(
+            verify(&bad_signature.to_string(), "d").unwrap_err(),
+            "许可证签名格式错误"
+        );
+        assert_eq!(verify("not json", "d").unwrap_err(), "许可证文件格式错误");
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_other_devices() {
+        let future = sign(&signing_key(), &payload(2, None));
+        assert_eq!(verify(&future, "d").unwrap_err(), "不支持的许可证版本: 2");
+
+        let bound = sign(&signing_key(), &payload(1, Some("device-a")));
+        assert_eq!(
+            verify(&bound, "device-b").unwrap_err(),
+            "许可证不属于本设备"
+        );
+
+        let garbage = sign(&signing_key(), "{}");
+        assert_eq!(verify(&garbage, "d").unwrap_err(), "许可证内容格式错误");
+    }
+
+    #[test]
+    fn development_public_key_is_valid() {
+        assert!(public_key().is_ok());
+    }
+}
@@ -0,0 +1,927 @@
+use crate::download_integrity::{self, MediaKind};
+use crate::media_store;
+use crate::mp4;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+
+// MP4 自定义（----）条目使用的命名空间
+const FREEFORM_MEAN: &str = "com.renteng.sales";
+// XMP 中自定义字段使用的命名空间
+const XMP_NAMESPACE: &str = "https://renteng.com/ns/media/1.0/";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+// 写入过程中使用的临时文件后缀
+const TEMP_SUFFIX: &str = ".meta-tmp";
+
+/// 随下载文件一起保存的来源信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub platform: Option<String>,
+    pub source_url: Option<String>,
+    pub parsed_at: Option<String>,
+}
+
+impl MediaMetadata {
+    fn with_defaults(&self) -> MediaMetadata {
+        let mut meta = self.clone();
+        if non_empty(&meta.parsed_at).is_none() {
+            meta.parsed_at = Some(chrono::Local::now().to_rfc3339());
+        }
+        meta
+    }
+
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.author.is_none()
+            && self.platform.is_none()
+            && self.source_url.is_none()
+            && self.parsed_at.is_none()
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(TEMP_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// 先写临时文件再整体替换，避免写到一半留下损坏的文件
+fn replace_file<F>(path: &Path, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut File) -> Result<(), String>,
+{
+    let temp_path = temp_path_for(path);
+    let result = File::create(&temp_path)
+        .map_err(|e| format!("创建临时文件失败: {}", e))
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+                .map_err(|e| format!("写入临时文件失败: {}", e))
+        })
+        .and_then(|_| fs::rename(&temp_path, path).map_err(|e| format!("替换文件失败: {}", e)));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn detect_kind(path: &Path) -> Result<MediaKind, String> {
+    let mut file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut head = [0u8; 32];
+    let read = file
+        .read(&mut head)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    download_integrity::sniff_media_kind(&head[..read])
+        .ok_or_else(|| "无法识别文件格式".to_string())
+}
+
+// ============================ MP4 (moov/udta/meta/ilst) ============================
+
+fn ilst_data_box(value: &str) -> Vec<u8> {
+    // 类型 1 表示 UTF-8 文本，随后 4 字节为 locale
+    let mut payload = Vec::with_capacity(value.len() + 8);
+    payload.extend_from_slice(&1u32.to_be_bytes());
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    payload.extend_from_slice(value.as_bytes());
+    let mut out = Vec::new();
+    mp4::write_box(&mut out, b"data", &payload);
+    out
+}
+
+fn write_ilst_text(ilst: &mut Vec<u8>, item: &[u8; 4], value: &str) {
+    mp4::write_box(ilst, item, &ilst_data_box(value));
+}
+
+fn write_ilst_freeform(ilst: &mut Vec<u8>, name: &str, value: &str) {
+    let mut item = Vec::new();
+    mp4::write_full_box(&mut item, b"mean", 0, 0, FREEFORM_MEAN.as_bytes());
+    mp4::write_full_box(&mut item, b"name", 0, 0, name.as_bytes());
+    item.extend_from_slice(&ilst_data_box(value));
+    mp4::write_box(ilst, b"----", &item);
+}
+
+fn build_meta_box(meta: &MediaMetadata) -> Vec<u8> {
+    let mut ilst = Vec::new();
+    if let Some(title) = non_empty(&meta.title) {
+        write_ilst_text(&mut ilst, b"\xA9nam", title);
+    }
+    if let Some(author) = non_empty(&meta.author) {
+        write_ilst_text(&mut ilst, b"\xA9ART", author);
+    }
+    if let Some(parsed_at) = non_empty(&meta.parsed_at) {
+        write_ilst_text(&mut ilst, b"\xA9day", parsed_at);
+    }
+    if let Some(source_url) = non_empty(&meta.source_url) {
+        write_ilst_text(&mut ilst, b"\xA9cmt", source_url);
+        write_ilst_freeform(&mut ilst, "source_url", source_url);
+    }
+    if let Some(platform) = non_empty(&meta.platform) {
+        write_ilst_freeform(&mut ilst, "platform", platform);
+    }
+    if let Some(parsed_at) = non_empty(&meta.parsed_at) {
+        write_ilst_freeform(&mut ilst, "parsed_at", parsed_at);
+    }
+
+    let mut hdlr = Vec::new();
+    hdlr.extend_from_slice(&0u32.to_be_bytes());
+    hdlr.extend_from_slice(b"mdir");
+    hdlr.extend_from_slice(b"appl");
+    hdlr.extend_from_slice(&[0u8; 8]);
+    hdlr.push(0);
+
+    let mut body = Vec::new();
+    mp4::write_full_box(&mut body, b"hdlr", 0, 0, &hdlr);
+    mp4::write_box(&mut body, b"ilst", &ilst);
+
+    let mut out = Vec::new();
+    mp4::write_full_box(&mut out, b"meta", 0, 0, &body);
+    out
+}
+
+/// 修正 stco/co64 中位于 `threshold` 之后的块偏移
+fn shift_chunk_offsets(data: &mut [u8], threshold: u64, delta: i64) -> Result<(), String> {
+    for info in mp4::parse_boxes(data)? {
+        let start = info.payload_offset() as usize;
+        let end = info.end() as usize;
+        let body = &mut data[start..end];
+
+        match &info.box_type {
+            b"trak" | b"mdia" | b"minf" | b"stbl" => shift_chunk_offsets(body, threshold, delta)?,
+            b"stco" => {
+                let count = mp4::be_u32(body, 4).unwrap_or(0) as usize;
+                for i in 0..count {
+                    let pos = 8 + i * 4;
+                    let value = mp4::be_u32(body, pos).ok_or("stco 表被截断")? as u64;
+                    if value >= threshold {
+                        let shifted = u32::try_from(value as i64 + delta)
+                            .map_err(|_| "写入元数据后块偏移超出 32 位范围".to_string())?;
+                        body[pos..pos + 4].copy_from_slice(&shifted.to_be_bytes());
+                    }
+                }
+            }
+            b"co64" => {
+                let count = mp4::be_u32(body, 4).unwrap_or(0) as usize;
+                for i in 0..count {
+                    let pos = 8 + i * 8;
+                    let value = mp4::be_u64(body, pos).ok_or("co64 表被截断")?;
+                    if value >= threshold {
+                        let shifted = (value as i64 + delta) as u64;
+                        body[pos..pos + 8].copy_from_slice(&shifted.to_be_bytes());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn copy_range(src: &mut File, dst: &mut File, start: u64, len: u64) -> Result<(), String> {
+    src.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let copied = io::copy(&mut src.take(len), dst).map_err(|e| format!("写入文件失败: {}", e))?;
+    if copied != len {
+        return Err("复制文件内容时数据不足".to_string());
+    }
+    Ok(())
+}
+
+/// 重写 moov 下的 udta/meta，不动媒体数据；moov 位于 mdat 之前时同步修正块偏移
+fn embed_mp4(path: &Path, meta: &MediaMetadata) -> Result<(), String> {
+    let mut source = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let len = source
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let boxes = mp4::read_top_level_boxes(&mut source, len)?;
+    let moov_info = *boxes
+        .iter()
+        .find(|info| info.is(b"moov"))
+        .ok_or("MP4 缺少 moov 盒子")?;
+
+    let moov = mp4::read_box_bytes(&mut source, &moov_info)?;
+    let moov_payload = &moov[moov_info.header_len as usize..];
+
+    let mut children = Vec::new();
+    let mut udta_payload = Vec::new();
+    for child in mp4::parse_boxes(moov_payload)? {
+        let bytes = &moov_payload[child.offset as usize..child.end() as usize];
+        if child.is(b"udta") {
+            // 保留 udta 中已有的其它条目，只替换 meta
+            let old = mp4::payload(moov_payload, &child);
+            for item in mp4::parse_boxes(old)? {
+                if !item.is(b"meta") {
+                    udta_payload.extend_from_slice(&old[item.offset as usize..item.end() as usize]);
+                }
+            }
+        } else {
+            children.extend_from_slice(bytes);
+        }
+    }
+    udta_payload.extend_from_slice(&build_meta_box(meta));
+    mp4::write_box(&mut children, b"udta", &udta_payload);
+
+    let mut new_moov = Vec::with_capacity(children.len() + 16);
+    mp4::write_box(&mut new_moov, b"moov", &children);
+    let delta = new_moov.len() as i64 - moov_info.size as i64;
+
+    let data_after_moov = boxes
+        .iter()
+        .any(|info| info.is(b"mdat") && info.offset > moov_info.offset);
+    if delta != 0 && data_after_moov {
+        let header_len = if new_moov.len() as u64 > u32::MAX as u64 {
+            16
+        } else {
+            8
+        };
+        shift_chunk_offsets(&mut new_moov[header_len..], moov_info.end(), delta)?;
+    }
+
+    replace_file(path, |out| {
+        copy_range(&mut source, out, 0, moov_info.offset)?;
+        out.write_all(&new_moov)
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        copy_range(&mut source, out, moov_info.end(), len - moov_info.end())
+    })
+}
+
+fn read_mp4(path: &Path) -> Result<MediaMetadata, String> {
+    let mut file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let boxes = mp4::read_top_level_boxes(&mut file, len)?;
+    let moov_info = boxes
+        .iter()
+        .find(|info| info.is(b"moov"))
+        .ok_or("MP4 缺少 moov 盒子")?;
+    let moov = mp4::read_box_bytes(&mut file, moov_info)?;
+    let moov_payload = &moov[moov_info.header_len as usize..];
+
+    let mut meta = MediaMetadata::default();
+    let ilst = mp4::find_path(moov_payload, &[b"udta", b"meta"])
+        .and_then(|m| m.get(4..))
+        .and_then(|m| mp4::find_child(m, b"ilst"));
+    let ilst = match ilst {
+        Some(ilst) => ilst,
+        None => return Ok(meta),
+    };
+
+    let text_of = |item: &[u8]| -> Option<String> {
+        mp4::find_child(item, b"data")
+            .and_then(|data| data.get(8..))
+            .map(|v| String::from_utf8_lossy(v).to_string())
+    };
+
+    for item in mp4::parse_boxes(ilst)? {
+        let body = mp4::payload(ilst, &item);
+        match &item.box_type {
+            b"\xA9nam" => meta.title = text_of(body),
+            b"\xA9ART" => meta.author = text_of(body),
+            b"\xA9day" => meta.parsed_at = meta.parsed_at.take().or_else(|| text_of(body)),
+            b"\xA9cmt" => meta.source_url = meta.source_url.take().or_else(|| text_of(body)),
+            b"----" => {
+                let name = mp4::find_child(body, b"name")
+                    .and_then(|n| n.get(4..))
+                    .map(|n| String::from_utf8_lossy(n).to_string());
+                let value = text_of(body);
+                match name.as_deref() {
+                    Some("platform") => meta.platform = value,
+                    Some("source_url") => meta.source_url = value,
+                    Some("parsed_at") => meta.parsed_at = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(meta)
+}
+
+// ============================ XMP（JPEG / PNG / WebP） ============================
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+fn build_xmp_packet(meta: &MediaMetadata) -> String {
+    let mut fields = String::new();
+    if let Some(title) = non_empty(&meta.title) {
+        fields.push_str(&format!(
+            "   <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+            xml_escape(title)
+        ));
+    }
+    if let Some(author) = non_empty(&meta.author) {
+        fields.push_str(&format!(
+            "   <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+            xml_escape(author)
+        ));
+    }
+    if let Some(source_url) = non_empty(&meta.source_url) {
+        fields.push_str(&format!(
+            "   <dc:source>{}</dc:source>\n",
+            xml_escape(source_url)
+        ));
+        fields.push_str(&format!(
+            "   <renteng:sourceUrl>{}</renteng:sourceUrl>\n",
+            xml_escape(source_url)
+        ));
+    }
+    if let Some(platform) = non_empty(&meta.platform) {
+        fields.push_str(&format!(
+            "   <renteng:platform>{}</renteng:platform>\n",
+            xml_escape(platform)
+        ));
+    }
+    if let Some(parsed_at) = non_empty(&meta.parsed_at) {
+        fields.push_str(&format!(
+            "   <renteng:parsedAt>{}</renteng:parsedAt>\n",
+            xml_escape(parsed_at)
+        ));
+    }
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+  <rdf:Description rdf:about=\"\"\n\
+    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n\
+    xmlns:renteng=\"{}\">\n\
+{}  </rdf:Description>\n\
+ </rdf:RDF>\n\
+</x:xmpmeta>\n\
+<?xpacket end=\"w\"?>",
+        XMP_NAMESPACE, fields
+    )
+}
+
+fn xmp_tag(xmp: &str, tag: &str) -> Option<String> {
+    let pattern = format!(r"(?s)<{tag}>(.*?)</{tag}>", tag = regex::escape(tag));
+    let re = regex::Regex::new(&pattern).ok()?;
+    let inner = re.captures(xmp)?.get(1)?.as_str();
+    // dc:title / dc:creator 的值包在 rdf:li 中
+    let li = regex::Regex::new(r"(?s)<rdf:li[^>]*>(.*?)</rdf:li>").ok()?;
+    let value = li
+        .captures(inner)
+        .and_then(|c| c.get(1))
+        .map_or(inner, |m| m.as_str());
+    let value = xml_unescape(value.trim());
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn parse_xmp_packet(xmp: &str) -> MediaMetadata {
+    MediaMetadata {
+        title: xmp_tag(xmp, "dc:title"),
+        author: xmp_tag(xmp, "dc:creator"),
+        platform: xmp_tag(xmp, "renteng:platform"),
+        source_url: xmp_tag(xmp, "renteng:sourceUrl").or_else(|| xmp_tag(xmp, "dc:source")),
+        parsed_at: xmp_tag(xmp, "renteng:parsedAt"),
+    }
+}
+
+/// 遍历 JPEG 头部段，返回 (marker, 段起点, 段终点)，遇到 SOS 停止
+fn jpeg_segments(data: &[u8]) -> Result<Vec<(u8, usize, usize)>, String> {
+    let mut segments = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return Err("JPEG 结构损坏".to_string());
+        }
+        let marker = data[pos + 1];
+        if marker == 0xDA {
+            break;
+        }
+        let len = mp4::be_u16(data, pos + 2).ok_or("JPEG 结构损坏")? as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > data.len() {
+            return Err("JPEG 结构损坏".to_string());
+        }
+        segments.push((marker, pos, end));
+        pos = end;
+    }
+    Ok(segments)
+}
+
+fn is_jpeg_xmp(data: &[u8], marker: u8, start: usize, end: usize) -> bool {
+    marker == 0xE1 && data[start + 4..end].starts_with(JPEG_XMP_HEADER)
+}
+
+fn embed_jpeg(data: &[u8], xmp: &str) -> Result<Vec<u8>, String> {
+    let segments = jpeg_segments(data)?;
+    let payload_len = JPEG_XMP_HEADER.len() + xmp.len() + 2;
+    if payload_len > u16::MAX as usize {
+        return Err("XMP 数据过大".to_string());
+    }
+
+    // 新的 XMP 段放在开头的 APP0/APP1 段之后
+    let insert_at = segments
+        .iter()
+        .take_while(|(marker, _, _)| *marker == 0xE0 || *marker == 0xE1)
+        .last()
+        .map_or(2, |(_, _, end)| *end);
+
+    let mut out = Vec::with_capacity(data.len() + payload_len + 2);
+    out.extend_from_slice(&data[..2]);
+    let mut cursor = 2;
+    for (marker, start, end) in &segments {
+        if *start == insert_at {
+            break;
+        }
+        if !is_jpeg_xmp(data, *marker, *start, *end) {
+            out.extend_from_slice(&data[*start..*end]);
+        }
+        cursor = *end;
+    }
+
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&(payload_len as u16).to_be_bytes());
+    out.extend_from_slice(JPEG_XMP_HEADER);
+    out.extend_from_slice(xmp.as_bytes());
+
+    for (marker, start, end) in &segments {
+        if *start < cursor {
+            continue;
+        }
+        if !is_jpeg_xmp(data, *marker, *start, *end) {
+            out.extend_from_slice(&data[*start..*end]);
+        }
+        cursor = *end;
+    }
+    out.extend_from_slice(&data[cursor..]);
+    Ok(out)
+}
+
+fn read_jpeg_xmp(data: &[u8]) -> Result<Option<String>, String> {
+    for (marker, start, end) in jpeg_segments(data)? {
+        if is_jpeg_xmp(data, marker, start, end) {
+            let body = &data[start + 4 + JPEG_XMP_HEADER.len()..end];
+            return Ok(Some(String::from_utf8_lossy(body).to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// PNG 块位置：(块类型, 块起点, 数据起点, 块终点)
+type PngChunk = ([u8; 4], usize, usize, usize);
+
+fn png_chunks(data: &[u8]) -> Result<Vec<PngChunk>, String> {
+    let mut chunks = Vec::new();
+    let mut pos = 8;
+    while pos + 12 <= data.len() {
+        let len = mp4::be_u32(data, pos).ok_or("PNG 结构损坏")? as usize;
+        let end = pos + 12 + len;
+        if end > data.len() {
+            return Err("PNG 结构损坏".to_string());
+        }
+        let mut chunk_type = [0u8; 4];
+        chunk_type.copy_from_slice(&data[pos + 4..pos + 8]);
+        chunks.push((chunk_type, pos, pos + 8, end));
+        pos = end;
+    }
+    Ok(chunks)
+}
+
+fn is_png_xmp(data: &[u8], chunk_type: &[u8; 4], data_start: usize, end: usize) -> bool {
+    chunk_type == b"iTXt" && data[data_start..end - 4].starts_with(PNG_XMP_KEYWORD)
+}
+
+fn embed_png(data: &[u8], xmp: &str) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    body.extend_from_slice(PNG_XMP_KEYWORD);
+    // 关键字结束符、未压缩标记、压缩方法、空语言标签、空翻译关键字
+    body.extend_from_slice(&[0, 0, 0, 0, 0]);
+    body.extend_from_slice(xmp.as_bytes());
+
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(b"iTXt");
+    crc.update(&body);
+
+    let mut chunk = Vec::with_capacity(body.len() + 12);
+    chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(b"iTXt");
+    chunk.extend_from_slice(&body);
+    chunk.extend_from_slice(&crc.finalize().to_be_bytes());
+
+    let mut out = Vec::with_capacity(data.len() + chunk.len());
+    out.extend_from_slice(&data[..8]);
+    for (chunk_type, start, data_start, end) in png_chunks(data)? {
+        if is_png_xmp(data, &chunk_type, data_start, end) {
+            continue;
+        }
+        if &chunk_type == b"IEND" {
+            out.extend_from_slice(&chunk);
+        }
+        out.extend_from_slice(&data[start..end]);
+    }
+    Ok(out)
+}
+
+fn read_png_xmp(data: &[u8]) -> Result<Option<String>, String> {
+    for (chunk_type, _, data_start, end) in png_chunks(data)? {
+        if is_png_xmp(data, &chunk_type, data_start, end) {
+            let text = &data[data_start + PNG_XMP_KEYWORD.len() + 5..end - 4];
+            return Ok(Some(String::from_utf8_lossy(text).to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// WebP（RIFF）块位置：(块类型, 块起点, 数据起点, 数据长度, 块终点)
+type WebpChunk = ([u8; 4], usize, usize, usize, usize);
+
+fn webp_chunks(data: &[u8]) -> Result<Vec<WebpChunk>, String> {
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let mut fourcc = [0u8; 4];
+        fourcc.copy_from_slice(&data[pos..pos + 4]);
+        let len = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+            as usize;
+        let end = (pos + 8 + len + (len & 1)).min(data.len());
+        if pos + 8 + len > data.len() {
+            return Err("WebP 结构损坏".to_string());
+        }
+        chunks.push((fourcc, pos, pos + 8, len, end));
+        pos = end;
+    }
+    Ok(chunks)
+}
+
+fn push_webp_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(body);
+    if body.len() & 1 == 1 {
+        out.push(0);
+    }
+}
+
+/// 简单格式的 WebP 需要补一个 VP8X 头才能携带 XMP，画布尺寸取自 VP8/VP8L 位流
+fn webp_vp8x_from_bitstream(fourcc: &[u8; 4], body: &[u8]) -> Result<Vec<u8>, String> {
+    let (width, height, alpha) = match fourcc {
+        b"VP8 " => {
+            // 帧头 3 字节 + 起始码 3 字节之后是 14 位宽高（小端）
+            let dims = body.get(6..10).ok_or("WebP 结构损坏")?;
+            let w = u16::from_le_bytes([dims[0], dims[1]]) & 0x3FFF;
+            let h = u16::from_le_bytes([dims[2], dims[3]]) & 0x3FFF;
+            (w as u32, h as u32, false)
+        }
+        b"VP8L" => {
+            let bits = body
+                .get(1..5)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or("WebP 结构损坏")?;
+            (
+                (bits & 0x3FFF) + 1,
+                ((bits >> 14) & 0x3FFF) + 1,
+                (bits >> 28) & 1 == 1,
+            )
+        }
+        _ => return Err("WebP 缺少图像数据块".to_string()),
+    };
+
+    if width == 0 || height == 0 {
+        return Err("无法读取 WebP 画布尺寸".to_string());
+    }
+
+    let mut vp8x = vec![0u8; 10];
+    vp8x[0] = 0x04 | if alpha { 0x10 } else { 0 };
+    vp8x[4..7].copy_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x[7..10].copy_from_slice(&(height - 1).to_le_bytes()[..3]);
+    Ok(vp8x)
+}
+
+fn embed_webp(data: &[u8], xmp: &str) -> Result<Vec<u8>, String> {
+    let chunks = webp_chunks(data)?;
+    let mut body = Vec::with_capacity(data.len() + xmp.len() + 32);
+    body.extend_from_slice(b"WEBP");
+
+    let has_vp8x = chunks.iter().any(|(fourcc, ..)| fourcc == b"VP8X");
+    if !has_vp8x {
+        let (fourcc, _, start, len, _) = chunks
+            .iter()
+            .find(|(fourcc, ..)| fourcc == b"VP8 " || fourcc == b"VP8L")
+            .ok_or("WebP 缺少图像数据块")?;
+        let vp8x = webp_vp8x_from_bitstream(fourcc, &data[*start..*start + *len])?;
+        push_webp_chunk(&mut body, b"VP8X", &vp8x);
+    }
+
+    for (fourcc, chunk_start, start, len, end) in &chunks {
+        match fourcc {
+            b"XMP " => continue,
+            b"VP8X" => {
+                let mut vp8x = data[*start..*start + *len].to_vec();
+                if let Some(flags) = vp8x.first_mut() {
+                    *flags |= 0x04;
+                }
+                push_webp_chunk(&mut body, b"VP8X", &vp8x);
+            }
+            _ => body.extend_from_slice(&data[*chunk_start..*end]),
+        }
+    }
+    push_webp_chunk(&mut body, b"XMP ", xmp.as_bytes());
+
+    let mut out = Vec::with_capacity(body.len() + 8);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+fn read_webp_xmp(data: &[u8]) -> Result<Option<String>, String> {
+    for (fourcc, _, start, len, _) in webp_chunks(data)? {
+        if &fourcc == b"XMP " {
+            return Ok(Some(
+                String::from_utf8_lossy(&data[start..start + len]).to_string(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// 把来源信息写入已下载的文件（MP4 写 moov/udta，图片写 XMP），不重新编码
+pub fn embed_metadata(path: &Path, meta: &MediaMetadata) -> Result<(), String> {
+    let meta = meta.with_defaults();
+    let kind = detect_kind(path)?;
+
+    if kind == MediaKind::Mp4 {
+        return embed_mp4(path, &meta);
+    }
+
+    let data = fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let xmp = build_xmp_packet(&meta);
+    let updated = match kind {
+        MediaKind::Jpeg => embed_jpeg(&data, &xmp)?,
+        MediaKind::Png => embed_png(&data, &xmp)?,
+        MediaKind::Webp => embed_webp(&data, &xmp)?,
+        other => return Err(format!("暂不支持写入 {:?} 格式的元数据", other)),
+    };
+
+    replace_file(path, |out| {
+        out.write_all(&updated)
+            .map_err(|e| format!("写入文件失败: {}", e))
+    })
+}
+
+/// 读取 [`embed_metadata`] 写入的来源信息
+pub fn read_metadata(path: &Path) -> Result<MediaMetadata, String> {
+    let kind = detect_kind(path)?;
+    if kind == MediaKind::Mp4 {
+        return read_mp4(path);
+    }
+
+    let data = fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let xmp = match kind {
+        MediaKind::Jpeg => read_jpeg_xmp(&data)?,
+        MediaKind::Png => read_png_xmp(&data)?,
+        MediaKind::Webp => read_webp_xmp(&data)?,
+        _ => None,
+    };
+    Ok(xmp.map(|x| parse_xmp_packet(&x)).unwrap_or_default())
+}
+
+/// 读取已下载文件中的来源信息（标题、作者、平台、来源链接、解析时间）。
+/// Android 上的 `content://` 地址会先复制到临时文件再读取。
+#[tauri::command]
+pub async fn read_media_metadata<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+) -> Result<MediaMetadata, String> {
+    let local = media_store::local_file(&app, &path).await?;
+    tauri::async_runtime::spawn_blocking(move || read_metadata(local.path()))
+        .await
+        .map_err(|e| format!("读取元数据失败: {}", e))?
+        .and_then(|meta| {
+            if meta.is_empty() {
+                Err("文件中没有来源信息".to_string())
+            } else {
+                Ok(meta)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("media-metadata-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn sample_meta() -> MediaMetadata {
+        MediaMetadata {
+            title: Some("春季新品 <限时> & 包邮".to_string()),
+            author: Some("\"仁腾\"小店".to_string()),
+            platform: Some("抖音".to_string()),
+            source_url: Some("https://v.douyin.com/abc/?a=1&b=2".to_string()),
+            parsed_at: Some("2026-03-01T08:00:00+08:00".to_string()),
+        }
+    }
+
+    fn assert_same(read: &MediaMetadata, expected: &MediaMetadata) {
+        assert_eq!(read.title, expected.title);
+        assert_eq!(read.author, expected.author);
+        assert_eq!(read.platform, expected.platform);
+        assert_eq!(read.source_url, expected.source_url);
+        assert_eq!(read.parsed_at, expected.parsed_at);
+    }
+
+    /// ftyp + moov(trak/mdia/minf/stbl/stco) + mdat，或 mdat 在 moov 之前
+    fn sample_mp4(moov_first: bool) -> Vec<u8> {
+        let mut ftyp = Vec::new();
+        mp4::write_box(&mut ftyp, b"ftyp", b"isom\0\0\x02\0isomiso2mp41");
+
+        let build_moov = |chunk_offset: u32| {
+            let mut stco_body = 1u32.to_be_bytes().to_vec();
+            stco_body.extend_from_slice(&chunk_offset.to_be_bytes());
+            let mut stco = Vec::new();
+            mp4::write_full_box(&mut stco, b"stco", 0, 0, &stco_body);
+            let mut boxed = stco;
+            for box_type in [b"stbl", b"minf", b"mdia", b"trak"] {
+                let mut outer = Vec::new();
+                mp4::write_box(&mut outer, box_type, &boxed);
+                boxed = outer;
+            }
+            let mut udta_payload = Vec::new();
+            mp4::write_box(&mut udta_payload, b"cprt", b"keep");
+            mp4::write_box(&mut boxed, b"udta", &udta_payload);
+            let mut moov = Vec::new();
+            mp4::write_box(&mut moov, b"moov", &boxed);
+            moov
+        };
+
+        let mut mdat = Vec::new();
+        mp4::write_box(&mut mdat, b"mdat", &[7u8; 64]);
+
+        let mut data = ftyp.clone();
+        if moov_first {
+            let moov_len = build_moov(0).len();
+            let chunk = (ftyp.len() + moov_len + 8) as u32;
+            data.extend(build_moov(chunk));
+            data.extend(mdat);
+        } else {
+            let chunk = (ftyp.len() + 8) as u32;
+            data.extend(mdat);
+            data.extend(build_moov(chunk));
+        }
+        data
+    }
+
+    fn chunk_offset_and_mdat(data: &[u8]) -> (u64, u64) {
+        let boxes = mp4::parse_boxes(data).unwrap();
+        let mdat = boxes.iter().find(|b| b.is(b"mdat")).unwrap();
+        let moov = boxes.iter().find(|b| b.is(b"moov")).unwrap();
+        let stco = mp4::find_path(
+            mp4::payload(data, moov),
+            &[b"trak", b"mdia", b"minf", b"stbl", b"stco"],
+        )
+        .unwrap();
+        (mp4::be_u32(stco, 8).unwrap() as u64, mdat.payload_offset())
+    }
+
+    #[test]
+    fn mp4_round_trip_shifts_chunk_offsets_when_moov_comes_first() {
+        let path = temp_file("clip.mp4", &sample_mp4(true));
+        let meta = sample_meta();
+        embed_metadata(&path, &meta).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        let (chunk, mdat_payload) = chunk_offset_and_mdat(&data);
+        assert_eq!(chunk, mdat_payload);
+        assert_eq!(
+            &data[mdat_payload as usize..mdat_payload as usize + 4],
+            &[7u8; 4]
+        );
+        assert_same(&read_metadata(&path).unwrap(), &meta);
+
+        // 再次写入只替换 meta，udta 中其它条目保留
+        let mut updated = meta.clone();
+        updated.title = Some("改名后".to_string());
+        embed_metadata(&path, &updated).unwrap();
+        let data = fs::read(&path).unwrap();
+        let (chunk, mdat_payload) = chunk_offset_and_mdat(&data);
+        assert_eq!(chunk, mdat_payload);
+        let boxes = mp4::parse_boxes(&data).unwrap();
+        let moov = boxes.iter().find(|b| b.is(b"moov")).unwrap();
+        let udta = mp4::find_child(mp4::payload(&data, moov), b"udta").unwrap();
+        let items: Vec<_> = mp4::parse_boxes(udta)
+            .unwrap()
+            .iter()
+            .map(|b| b.box_type)
+            .collect();
+        assert_eq!(items, vec![*b"cprt", *b"meta"]);
+        assert_eq!(
+            read_metadata(&path).unwrap().title.as_deref(),
+            Some("改名后")
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn mp4_round_trip_keeps_offsets_when_moov_is_last() {
+        let original = sample_mp4(false);
+        let (chunk_before, _) = chunk_offset_and_mdat(&original);
+        let path = temp_file("clip.mp4", &original);
+        embed_metadata(&path, &sample_meta()).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        let (chunk, mdat_payload) = chunk_offset_and_mdat(&data);
+        assert_eq!(chunk, chunk_before);
+        assert_eq!(chunk, mdat_payload);
+        assert_same(&read_metadata(&path).unwrap(), &sample_meta());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    fn encoded_image(format: image::ImageFormat) -> Vec<u8> {
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            4,
+            4,
+            image::Rgb([200, 30, 30]),
+        ));
+        let mut out = Cursor::new(Vec::new());
+        img.write_to(&mut out, format).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn images_round_trip_xmp_and_stay_decodable() {
+        for (format, name) in [
+            (image::ImageFormat::Jpeg, "photo.jpg"),
+            (image::ImageFormat::Png, "photo.png"),
+            (image::ImageFormat::WebP, "photo.webp"),
+        ] {
+            let path = temp_file(name, &encoded_image(format));
+            let meta = sample_meta();
+
+            embed_metadata(&path, &meta).unwrap();
+            // 重复写入不应产生多份 XMP
+            embed_metadata(&path, &meta).unwrap();
+
+            let data = fs::read(&path).unwrap();
+            let packet = String::from_utf8_lossy(&data).matches("<x:xmpmeta").count();
+            assert_eq!(packet, 1, "{:?} 中 XMP 数量不对", format);
+            assert_same(&read_metadata(&path).unwrap(), &meta);
+
+            let decoded = image::load_from_memory_with_format(&data, format).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (4, 4));
+
+            fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn xmp_packet_escapes_markup() {
+        let meta = sample_meta();
+        let packet = build_xmp_packet(&meta);
+        assert!(!packet.contains("<限时>"));
+        assert!(packet.contains("&amp;"));
+        assert_same(&parse_xmp_packet(&packet), &meta);
+    }
+
+    #[test]
+    fn files_without_metadata_read_as_empty() {
+        let path = temp_file("plain.png", &encoded_image(image::ImageFormat::Png));
+        assert!(read_metadata(&path).unwrap().is_empty());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,594 @@
+use crate::{build_referer, media_store, mp4, mp4_mux, MOBILE_USER_AGENT};
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+// 解析阶段抓取的文件头大小，faststart 的 MP4 其 moov 通常在这个范围内
+pub const PROBE_HEAD_BYTES: u64 = 512 * 1024;
+// moov 不在文件头时，最多再沿顶层盒子跳转的次数
+const MAX_REMOTE_HOPS: usize = 4;
+// 远程探测时允许读取的 moov 上限，防止异常文件占用过多内存
+const MAX_MOOV_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 从 MP4/MOV 盒子中读出的真实媒体参数
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaProbe {
+    /// 时长（秒）
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// 顺时针旋转角度：0 / 90 / 180 / 270
+    pub rotation: u32,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    /// 平均码率（bit/s）
+    pub bitrate: Option<u64>,
+    pub frame_rate: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub file_size: Option<u64>,
+    pub fragmented: bool,
+}
+
+fn mvhd_duration(moov: &[u8]) -> Option<f64> {
+    let mvhd = mp4::find_child(moov, b"mvhd")?;
+    let (timescale, duration) = match mvhd.first()? {
+        1 => (mp4::be_u32(mvhd, 20)?, mp4::be_u64(mvhd, 24)?),
+        _ => (mp4::be_u32(mvhd, 12)?, mp4::be_u32(mvhd, 16)? as u64),
+    };
+    // 分片文件的 mvhd 时长常为 0，未知时长为全 1
+    if timescale == 0 || duration == 0 || duration == u32::MAX as u64 || duration == u64::MAX {
+        return None;
+    }
+    Some(duration as f64 / timescale as f64)
+}
+
+/// 分片文件在 mvex/mehd 中声明的总时长
+fn mehd_duration(moov: &[u8]) -> Option<f64> {
+    let mehd = mp4::find_path(moov, &[b"mvex", b"mehd"])?;
+    let duration = match mehd.first()? {
+        1 => mp4::be_u64(mehd, 4)?,
+        _ => mp4::be_u32(mehd, 4)? as u64,
+    };
+    let mvhd = mp4::find_child(moov, b"mvhd")?;
+    let timescale = match mvhd.first()? {
+        1 => mp4::be_u32(mvhd, 20)?,
+        _ => mp4::be_u32(mvhd, 12)?,
+    };
+    (timescale > 0 && duration > 0).then(|| duration as f64 / timescale as f64)
+}
+
+/// 由 tkhd 矩阵计算旋转角度
+fn tkhd_rotation(tkhd: &[u8]) -> u32 {
+    let matrix_pos = if tkhd.first() == Some(&1) { 52 } else { 40 };
+    let a = mp4::be_u32(tkhd, matrix_pos).unwrap_or(0x0001_0000) as i32;
+    let b = mp4::be_u32(tkhd, matrix_pos + 4).unwrap_or(0) as i32;
+    let degrees = (b as f64).atan2(a as f64).to_degrees().round() as i32;
+    degrees.rem_euclid(360) as u32
+}
+
+fn tkhd_dimensions(tkhd: &[u8]) -> Option<(u32, u32)> {
+    let pos = tkhd.len().checked_sub(8)?;
+    let width = mp4::be_u32(tkhd, pos)? >> 16;
+    let height = mp4::be_u32(tkhd, pos + 4)? >> 16;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+fn mdhd_duration(mdhd: &[u8]) -> Option<(u32, u64)> {
+    let (timescale, duration) = match mdhd.first()? {
+        1 => (mp4::be_u32(mdhd, 20)?, mp4::be_u64(mdhd, 24)?),
+        _ => (mp4::be_u32(mdhd, 12)?, mp4::be_u32(mdhd, 16)? as u64),
+    };
+    (timescale > 0).then_some((timescale, duration))
+}
+
+/// stsd 中第一个样本描述：(四字符码, 描述内容)
+fn first_sample_entry(stbl: &[u8]) -> Option<([u8; 4], &[u8])> {
+    let stsd = mp4::find_child(stbl, b"stsd")?;
+    let entries = stsd.get(8..)?;
+    let info = mp4::parse_boxes(entries).ok()?.into_iter().next()?;
+    Some((info.box_type, mp4::payload(entries, &info)))
+}
+
+fn fourcc(code: &[u8; 4]) -> String {
+    String::from_utf8_lossy(code).trim().to_string()
+}
+
+/// 视频编码字符串，H.264 附带 profile/level（如 avc1.64001F）
+fn video_codec_name(code: &[u8; 4], entry: &[u8]) -> String {
+    // VisualSampleEntry 固定字段共 78 字节，其后为 avcC 等子盒子
+    let avcc = entry
+        .get(78..)
+        .and_then(|children| mp4::find_child(children, b"avcC"));
+    match (code, avcc) {
+        (b"avc1" | b"avc3", Some(avcc)) if avcc.len() >= 4 => format!(
+            "{}.{:02X}{:02X}{:02X}",
+            fourcc(code),
+            avcc[1],
+            avcc[2],
+            avcc[3]
+        ),
+        _ => fourcc(code),
+    }
+}
+
+/// 从 esds 读取 AAC 的 objectType 与 audioObjectType（如 mp4a.40.2）
+fn audio_codec_name(code: &[u8; 4], entry: &[u8]) -> String {
+    // AudioSampleEntry 固定字段共 28 字节
+    let esds = entry
+        .get(28..)
+        .and_then(|children| mp4::find_child(children, b"esds"));
+    let Some(esds) = esds.and_then(|e| e.get(4..)) else {
+        return fourcc(code);
+    };
+
+    // 描述符：tag(1) + 可变长度(1~4 字节)
+    let read_descriptor = |data: &[u8], pos: usize| -> Option<(u8, usize, usize)> {
+        let tag = *data.get(pos)?;
+        let mut len = 0usize;
+        let mut cursor = pos + 1;
+        for _ in 0..4 {
+            let byte = *data.get(cursor)?;
+            cursor += 1;
+            len = (len << 7) | (byte & 0x7F) as usize;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Some((tag, cursor, len))
+    };
+
+    let mut object_type = None;
+    let mut audio_object_type = None;
+    if let Some((0x03, start, _)) = read_descriptor(esds, 0) {
+        let flags = esds.get(start + 2).copied().unwrap_or(0);
+        let mut pos = start + 3;
+        if flags & 0x80 != 0 {
+            pos += 2;
+        }
+        if flags & 0x40 != 0 {
+            pos += 1 + esds.get(pos).copied().unwrap_or(0) as usize;
+        }
+        if flags & 0x20 != 0 {
+            pos += 2;
+        }
+        if let Some((0x04, config, _)) = read_descriptor(esds, pos) {
+            object_type = esds.get(config).copied();
+            if let Some((0x05, specific, _)) = read_descriptor(esds, config + 13) {
+                audio_object_type = esds.get(specific).map(|b| b >> 3);
+            }
+        }
+    }
+
+    match (object_type, audio_object_type) {
+        (Some(oti), Some(aot)) if aot > 0 => format!("{}.{:X}.{}", fourcc(code), oti, aot),
+        (Some(oti), _) => format!("{}.{:X}", fourcc(code), oti),
+        _ => fourcc(code),
+    }
+}
+
+/// 统计 stsz 中全部样本的字节数与样本数
+fn stsz_totals(stbl: &[u8]) -> Option<(u64, u64)> {
+    let stsz = mp4::find_child(stbl, b"stsz")?;
+    let uniform = mp4::be_u32(stsz, 4)? as u64;
+    let count = mp4::be_u32(stsz, 8)? as u64;
+    if uniform != 0 {
+        return Some((uniform * count, count));
+    }
+    let total = (0..count as usize)
+        .map_while(|i| mp4::be_u32(stsz, 12 + i * 4))
+        .map(u64::from)
+        .sum();
+    Some((total, count))
+}
+
+/// 解析 moov 内容（不含盒子头）得到媒体参数
+pub fn probe_moov(moov: &[u8], file_size: Option<u64>) -> Result<MediaProbe, String> {
+    let mut probe = MediaProbe {
+        duration: mvhd_duration(moov),
+        file_size,
+        fragmented: mp4::find_child(moov, b"mvex").is_some(),
+        ..Default::default()
+    };
+    let mut track_duration: Option<f64> = None;
+    let mut media_bytes = 0u64;
+
+    for info in mp4::parse_boxes(moov)? {
+        if !info.is(b"trak") {
+            continue;
+        }
+        let trak = mp4::payload(moov, &info);
+        let Some(stbl) = mp4::find_path(trak, &[b"mdia", b"minf", b"stbl"]) else {
+            continue;
+        };
+        let handler = mp4::find_path(trak, &[b"mdia", b"hdlr"]).and_then(|h| h.get(8..12));
+        let timing = mp4::find_path(trak, &[b"mdia", b"mdhd"]).and_then(mdhd_duration);
+        let seconds = timing
+            .filter(|(_, duration)| *duration > 0)
+            .map(|(timescale, duration)| duration as f64 / timescale as f64);
+        if let Some(seconds) = seconds {
+            track_duration = Some(track_duration.map_or(seconds, |d| d.max(seconds)));
+        }
+        let totals = stsz_totals(stbl);
+        media_bytes += totals.map_or(0, |(bytes, _)| bytes);
+
+        match handler {
+            Some(b"vide") if probe.video_codec.is_none() => {
+                if let Some((code, entry)) = first_sample_entry(stbl) {
+                    probe.video_codec = Some(video_codec_name(&code, entry));
+                    // tkhd 中的尺寸可能为 0，以样本描述中的编码尺寸兜底
+                    if let (Some(w), Some(h)) = (mp4::be_u16(entry, 24), mp4::be_u16(entry, 26)) {
+                        if w > 0 && h > 0 {
+                            probe.width = Some(w as u32);
+                            probe.height = Some(h as u32);
+                        }
+                    }
+                }
+                if let Some(tkhd) = mp4::find_child(trak, b"tkhd") {
+                    probe.rotation = tkhd_rotation(tkhd);
+                    if let Some((w, h)) = tkhd_dimensions(tkhd) {
+                        probe.width = Some(w);
+                        probe.height = Some(h);
+                    }
+                }
+                if let (Some(seconds), Some((_, count))) = (seconds, totals) {
+                    if seconds > 0.0 && count > 0 {
+                        probe.frame_rate = Some((count as f64 / seconds * 100.0).round() / 100.0);
+                    }
+                }
+            }
+            Some(b"soun") if probe.audio_codec.is_none() => {
+                if let Some((code, entry)) = first_sample_entry(stbl) {
+                    probe.audio_codec = Some(audio_codec_name(&code, entry));
+                    probe.channels = mp4::be_u16(entry, 16).filter(|c| *c > 0);
+                    probe.sample_rate = mp4::be_u32(entry, 24).map(|r| r >> 16).filter(|r| *r > 0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    probe.duration = probe
+        .duration
+        .or(track_duration)
+        .or_else(|| mehd_duration(moov));
+
+    if let Some(duration) = probe.duration.filter(|d| *d > 0.0) {
+        let bytes = if media_bytes > 0 {
+            Some(media_bytes)
+        } else {
+            file_size
+        };
+        probe.bitrate = bytes.map(|b| (b as f64 * 8.0 / duration).round() as u64);
+    }
+
+    if probe.video_codec.is_none() && probe.audio_codec.is_none() {
+        return Err("文件中没有可识别的音视频轨道".to_string());
+    }
+    Ok(probe)
+}
+
+/// 探测本地已下载的 MP4/MOV 文件
+pub fn probe_file(path: &Path) -> Result<MediaProbe, String> {
+    let mut file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let boxes = mp4::read_top_level_boxes(&mut file, len)?;
+    let moov_info = boxes
+        .iter()
+        .find(|info| info.is(b"moov"))
+        .ok_or("文件缺少 moov 盒子，无法探测")?;
+    let moov = mp4::read_box_bytes(&mut file, moov_info)?;
+    let mut probe = probe_moov(&moov[moov_info.header_len as usize..], Some(len))?;
+
+    if boxes.iter().any(|info| info.is(b"moof")) {
+        probe.fragmented = true;
+        // 分片文件的时长需要累加全部 trun
+        let fragmented = mp4_mux::fragmented_duration_secs(path, b"vide")
+            .or_else(|| mp4_mux::fragmented_duration_secs(path, b"soun"));
+        if let Some(duration) = fragmented.filter(|d| *d > 0.0) {
+            probe.duration = Some(duration);
+            probe.bitrate = Some((len as f64 * 8.0 / duration).round() as u64);
+        }
+    }
+    Ok(probe)
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(10))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("创建客户端失败: {}", e))
+}
+
+/// 按 Range 读取远程文件的一段，返回 (数据, 文件总长度)
+async fn fetch_range(
+    client: &reqwest::Client,
+    url: &str,
+    start: u64,
+    len: u64,
+) -> Result<(Vec<u8>, Option<u64>), String> {
+    let referer = build_referer(url);
+    let origin = referer.trim_end_matches('/').to_string();
+    let mut response = client
+        .get(url)
+        .header("Referer", &referer)
+        .header("Origin", &origin)
+        .header("User-Agent", MOBILE_USER_AGENT)
+        .header("Range", format!("bytes={}-{}", start, start + len - 1))
+        .send()
+        .await
+        .map_err(|e| format!("请求媒体失败: {}", e))?;
+
+    let status = response.status();
+    if status != reqwest::StatusCode::PARTIAL_CONTENT {
+        // 源站忽略 Range 时只能从头读取，仅在请求文件头时可用
+        if !status.is_success() || start > 0 {
+            return Err(format!("媒体源不支持分段读取: HTTP {}", status));
+        }
+    }
+
+    let total = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit('/').next())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| {
+            (status == reqwest::StatusCode::OK)
+                .then(|| response.content_length())
+                .flatten()
+        });
+
+    let mut data = Vec::with_capacity(len.min(MAX_MOOV_SIZE) as usize);
+    while (data.len() as u64) < len {
+        match response
+            .chunk()
+            .await
+            .map_err(|e| format!("读取媒体数据失败: {}", e))?
+        {
+            Some(chunk) => data.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    data.truncate(len as usize);
+    Ok((data, total))
+}
+
+/// 只抓取远程文件的头部（必要时沿顶层盒子跳转到 moov）完成探测，不下载整个文件
+pub async fn probe_remote(url: &str) -> Result<MediaProbe, String> {
+    let client = http_client()?;
+    let (head, total) = fetch_range(&client, url, 0, PROBE_HEAD_BYTES).await?;
+
+    let mut offset = 0u64;
+    let mut hops = 0usize;
+    let mut header = head;
+    let mut header_offset = 0u64;
+    loop {
+        // 当前已知数据中 offset 处的盒子头
+        let local = (offset - header_offset) as usize;
+        let bytes = match header.get(local..) {
+            Some(bytes) if bytes.len() >= 8 => bytes,
+            _ => {
+                if hops >= MAX_REMOTE_HOPS || total.is_some_and(|t| offset + 8 > t) {
+                    return Err("在文件中没有找到 moov 盒子".to_string());
+                }
+                hops += 1;
+                let (next, _) = fetch_range(&client, url, offset, 16).await?;
+                header = next;
+                header_offset = offset;
+                continue;
+            }
+        };
+
+        let size32 = mp4::be_u32(bytes, 0).unwrap_or(0) as u64;
+        let (size, header_len) = match size32 {
+            1 => (mp4::be_u64(bytes, 8).ok_or("MP4 盒子头被截断")?, 16),
+            0 => (total.map_or(u64::MAX, |t| t - offset), 8),
+            size => (size, 8),
+        };
+        if size < header_len {
+            return Err(format!("MP4 结构损坏: 偏移 {} 处盒子长度无效", offset));
+        }
+
+        if &bytes[4..8] == b"moov" {
+            if size > MAX_MOOV_SIZE {
+                return Err("moov 盒子过大，无法远程探测".to_string());
+            }
+            let moov = match bytes.get(..size as usize) {
+                Some(moov) => moov.to_vec(),
+                None => fetch_range(&client, url, offset, size).await?.0,
+            };
+            if (moov.len() as u64) < size {
+                return Err("moov 盒子数据不完整".to_string());
+            }
+            return probe_moov(&moov[header_len as usize..], total);
+        }
+
+        offset = offset.checked_add(size).ok_or("MP4 结构损坏")?;
+    }
+}
+
+fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// 探测媒体的真实时长、分辨率、编码、码率与旋转角度。
+/// `source` 可以是本地文件路径、Android 下载返回的 `content://` 地址（先复制到临时文件），
+/// 也可以是远程地址（只读取文件头与 moov）。
+#[tauri::command]
+pub async fn probe_media<R: Runtime>(
+    app: AppHandle<R>,
+    source: String,
+) -> Result<MediaProbe, String> {
+    if is_remote(&source) {
+        return probe_remote(&source).await;
+    }
+
+    let local = media_store::local_file(&app, &source).await?;
+    tauri::async_runtime::spawn_blocking(move || probe_file(local.path()))
+        .await
+        .map_err(|e| format!("探测任务异常: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp4::test_boxes::{boxed, full, u32s};
+
+    fn mvhd(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut body = u32s(&[0, 0, timescale, duration]);
+        body.extend_from_slice(&[0u8; 80]);
+        full(b"mvhd", 0, &body)
+    }
+
+    /// 1920x1080、旋转 90°、10 秒 300 帧的 H.264 轨道
+    fn video_trak() -> Vec<u8> {
+        let mut tkhd = u32s(&[0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        tkhd.extend(u32s(&[
+            0,
+            0x0001_0000,
+            0,
+            0xFFFF_0000,
+            0,
+            0,
+            0,
+            0,
+            0x4000_0000,
+        ]));
+        tkhd.extend(u32s(&[1920 << 16, 1080 << 16]));
+
+        let mut entry = vec![0u8; 24];
+        entry.extend_from_slice(&1920u16.to_be_bytes());
+        entry.extend_from_slice(&1080u16.to_be_bytes());
+        entry.resize(78, 0);
+        entry.extend(boxed(b"avcC", &[1, 0x64, 0x00, 0x1F, 0xFF]));
+
+        stream_trak(&tkhd, b"vide", 90_000, 900_000, b"avc1", &entry, 1000, 300)
+    }
+
+    /// 44.1kHz 双声道 AAC-LC 轨道
+    fn audio_trak() -> Vec<u8> {
+        let tkhd = vec![0u8; 80];
+        let mut entry = vec![0u8; 16];
+        entry.extend_from_slice(&2u16.to_be_bytes());
+        entry.extend_from_slice(&16u16.to_be_bytes());
+        entry.extend_from_slice(&[0u8; 4]);
+        entry.extend(u32s(&[44_100 << 16]));
+        let descriptor = [
+            0x03, 0x19, 0x00, 0x01, 0x00, // ES_Descriptor
+            0x04, 0x11, 0x40, 0x15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // DecoderConfig
+            0x05, 0x02, 0x12, 0x10, // AudioSpecificConfig
+        ];
+        entry.extend(full(b"esds", 0, &descriptor));
+
+        stream_trak(&tkhd, b"soun", 44_100, 441_000, b"mp4a", &entry, 200, 430)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn stream_trak(
+        tkhd: &[u8],
+        handler: &[u8; 4],
+        timescale: u32,
+        duration: u32,
+        code: &[u8; 4],
+        entry: &[u8],
+        sample_size: u32,
+        samples: u32,
+    ) -> Vec<u8> {
+        let mut stbl = full(b"stsd", 0, &[u32s(&[1]), boxed(code, entry)].concat());
+        stbl.extend(full(b"stsz", 0, &u32s(&[sample_size, samples])));
+
+        let mut hdlr = u32s(&[0]);
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0u8; 13]);
+
+        let mut mdia = full(b"mdhd", 0, &u32s(&[0, 0, timescale, duration, 0]));
+        mdia.extend(full(b"hdlr", 0, &hdlr));
+        mdia.extend(boxed(b"minf", &boxed(b"stbl", &stbl)));
+
+        let mut body = full(b"tkhd", 0, tkhd);
+        body.extend(boxed(b"mdia", &mdia));
+        boxed(b"trak", &body)
+    }
+
+    #[test]
+    fn reads_tracks_codecs_and_bitrate() {
+        let mut moov = mvhd(1000, 10_000);
+        moov.extend(video_trak());
+        moov.extend(audio_trak());
+
+        let probe = probe_moov(&moov, Some(500_000)).unwrap();
+        assert_eq!(probe.duration, Some(10.0));
+        assert_eq!((probe.width, probe.height), (Some(1920), Some(1080)));
+        assert_eq!(probe.rotation, 90);
+        assert_eq!(probe.video_codec.as_deref(), Some("avc1.64001F"));
+        assert_eq!(probe.audio_codec.as_deref(), Some("mp4a.40.2"));
+        assert_eq!(probe.frame_rate, Some(30.0));
+        assert_eq!(probe.sample_rate, Some(44_100));
+        assert_eq!(probe.channels, Some(2));
+        // 码率按样本总字节计算，不含容器开销
+        assert_eq!(probe.bitrate, Some((300_000 + 86_000) * 8 / 10));
+        assert!(!probe.fragmented);
+    }
+
+    #[test]
+    fn falls_back_to_track_or_fragment_duration() {
+        // mvhd 时长为 0 时取最长的轨道时长
+        let mut moov = mvhd(1000, 0);
+        moov.extend(audio_trak());
+        let probe = probe_moov(&moov, None).unwrap();
+        assert_eq!(probe.duration, Some(10.0));
+        assert!(probe.video_codec.is_none());
+
+        // 分片初始化段只有 mehd 声明的时长
+        let mut moov = mvhd(1000, 0);
+        moov.extend(stream_trak(
+            &[0u8; 80], b"vide", 90_000, 0, b"hvc1", &[0u8; 78], 0, 0,
+        ));
+        moov.extend(boxed(b"mvex", &full(b"mehd", 0, &u32s(&[12_500]))));
+        let probe = probe_moov(&moov, None).unwrap();
+        assert!(probe.fragmented);
+        assert_eq!(probe.duration, Some(12.5));
+        assert_eq!(probe.video_codec.as_deref(), Some("hvc1"));
+    }
+
+    #[test]
+    fn rejects_files_without_media_tracks() {
+        assert!(probe_moov(&mvhd(1000, 1000), None).is_err());
+
+        let dir = std::env::temp_dir().join(format!("media-probe-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("no-moov.mp4");
+        let mut data = boxed(b"ftyp", b"isom\0\0\0\0isom");
+        data.extend(boxed(b"mdat", &[0u8; 32]));
+        std::fs::write(&path, &data).unwrap();
+        assert!(probe_file(&path).unwrap_err().contains("moov"));
+
+        let mut data = boxed(b"ftyp", b"isom\0\0\0\0isom");
+        let mut moov = mvhd(1000, 10_000);
+        moov.extend(video_trak());
+        data.extend(boxed(b"moov", &moov));
+        data.extend(boxed(b"mdat", &[0u8; 32]));
+        std::fs::write(&path, &data).unwrap();
+        let probe = probe_file(&path).unwrap();
+        assert_eq!(probe.file_size, Some(data.len() as u64));
+        assert_eq!(probe.duration, Some(10.0));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recognizes_remote_sources() {
+        assert!(is_remote("https://v.example.com/a.mp4"));
+        assert!(is_remote("http://v.example.com/a.mp4"));
+        assert!(!is_remote("/storage/emulated/0/Download/a.mp4"));
+        assert!(!is_remote("content://media/external/video/1"));
+    }
+}
@@ -0,0 +1,400 @@
+use crate::{build_referer, MOBILE_USER_AGENT};
+use percent_encoding::percent_decode_str;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{Manager, Runtime, UriSchemeContext, UriSchemeResponder};
+
+/// 前端通过 `convertFileSrc(remoteUrl, 'media')` 生成地址，路径部分即为编码后的远程地址
+pub const SCHEME: &str = "media";
+// 单次响应的最大字节数，播放器会按需继续发起后续 Range 请求
+const MAX_CHUNK_SIZE: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+enum ByteRange {
+    /// `bytes=start-end` 或 `bytes=start-`
+    From { start: u64, end: Option<u64> },
+    /// `bytes=-len`，取文件末尾 len 字节
+    Suffix(u64),
+}
+
+/// 允许通过 `media://` 代理的主机，由解析命令在返回结果时登记，由 `run()` 中 `manage` 注册。
+/// 协议只代理解析结果中出现过的媒体地址，不能被页面用来请求任意网址
+#[derive(Default)]
+pub struct MediaHosts {
+    hosts: Mutex<HashSet<String>>,
+}
+
+impl MediaHosts {
+    /// 登记若干媒体地址的主机，无法解析或非 http(s) 的地址忽略
+    pub fn allow<'a>(&self, urls: impl IntoIterator<Item = &'a str>) {
+        let Ok(mut hosts) = self.hosts.lock() else {
+            return;
+        };
+        for url in urls {
+            if let Some(host) = media_host(url) {
+                hosts.insert(host);
+            }
+        }
+    }
+
+    fn allows(&self, url: &str) -> bool {
+        let Some(host) = media_host(url) else {
+            return false;
+        };
+        self.hosts
+            .lock()
+            .map(|hosts| hosts.contains(&host))
+            .unwrap_or(false)
+    }
+}
+
+fn media_host(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    parsed.host_str().map(|host| host.to_ascii_lowercase())
+}
+
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::limited(10))
+            .connect_timeout(Duration::from_secs(20))
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+fn parse_range(value: &str) -> Option<ByteRange> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    // 多段 Range 只取第一段，播放器实际不会发出多段请求
+    let first = spec.split(',').next()?.trim();
+    let (start, end) = first.split_once('-')?;
+
+    if start.is_empty() {
+        return end.trim().parse::<u64>().ok().map(ByteRange::Suffix);
+    }
+
+    let start = start.trim().parse::<u64>().ok()?;
+    let end = match end.trim() {
+        "" => None,
+        value => Some(value.parse::<u64>().ok()?),
+    };
+    Some(ByteRange::From { start, end })
+}
+
+/// 把播放器的请求范围限制在单块大小以内，避免一次拉取整个文件
+fn clamp_range(range: ByteRange) -> ByteRange {
+    match range {
+        ByteRange::From { start, end } => {
+            let limit = start.saturating_add(MAX_CHUNK_SIZE - 1);
+            ByteRange::From {
+                start,
+                end: Some(end.map_or(limit, |end| end.min(limit))),
+            }
+        }
+        ByteRange::Suffix(len) => ByteRange::Suffix(len.min(MAX_CHUNK_SIZE)),
+    }
+}
+
+/// `bytes=10-5`、`bytes=-0` 这类无法满足的范围，应直接返回 416
+fn is_satisfiable(range: ByteRange) -> bool {
+    match range {
+        ByteRange::From {
+            start,
+            end: Some(end),
+        } => end >= start,
+        ByteRange::From { end: None, .. } => true,
+        ByteRange::Suffix(len) => len > 0,
+    }
+}
+
+fn range_header(range: ByteRange) -> String {
+    match range {
+        ByteRange::From {
+            start,
+            end: Some(end),
+        } => format!("bytes={}-{}", start, end),
+        ByteRange::From { start, end: None } => format!("bytes={}-", start),
+        ByteRange::Suffix(len) => format!("bytes=-{}", len),
+    }
+}
+
+fn decode_target(path: &str) -> Result<String, String> {
+    let encoded = path.trim_start_matches('/');
+    let target = percent_decode_str(encoded)
+        .decode_utf8()
+        .map_err(|e| format!("媒体地址编码无效: {}", e))?
+        .to_string();
+
+    let parsed = reqwest::Url::parse(&target).map_err(|e| format!("媒体地址无效: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(target),
+        scheme => Err(format!("不支持的媒体地址协议: {}", scheme)),
+    }
+}
+
+/// 逐块读取响应体，跳过前 `skip` 字节后最多保留 `take` 字节
+async fn read_window(
+    mut response: reqwest::Response,
+    skip: u64,
+    take: u64,
+) -> Result<Vec<u8>, String> {
+    let mut skipped = 0u64;
+    let mut body = Vec::with_capacity(take.min(MAX_CHUNK_SIZE) as usize);
+
+    while (body.len() as u64) < take {
+        let chunk = match response
+            .chunk()
+            .await
+            .map_err(|e| format!("读取媒体数据失败: {}", e))?
+        {
+            Some(chunk) => chunk,
+            None => break,
+        };
+
+        let mut data = &chunk[..];
+        if skipped < skip {
+            let drop_len = (skip - skipped).min(data.len() as u64) as usize;
+            skipped += drop_len as u64;
+            data = &data[drop_len..];
+        }
+
+        let remaining = (take - body.len() as u64).min(data.len() as u64) as usize;
+        body.extend_from_slice(&data[..remaining]);
+    }
+
+    Ok(body)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+fn range_not_satisfiable(content_range: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(header::CONTENT_RANGE, content_range)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+fn upstream_request(target: &str) -> reqwest::RequestBuilder {
+    let referer = build_referer(target);
+    let origin = referer.trim_end_matches('/').to_string();
+    http_client()
+        .get(target)
+        .header("Referer", &referer)
+        .header("Origin", &origin)
+        .header("User-Agent", MOBILE_USER_AGENT)
+}
+
+fn upstream_content_type(upstream: &reqwest::Response) -> String {
+    upstream
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+fn upstream_error(status: reqwest::StatusCode) -> Response<Vec<u8>> {
+    error_response(
+        StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+        &format!("媒体源返回异常: HTTP {}", status),
+    )
+}
+
+async fn proxy_range(target: &str, requested: ByteRange) -> Result<Response<Vec<u8>>, String> {
+    let range = clamp_range(requested);
+    let upstream = upstream_request(target)
+        .header("Range", range_header(range))
+        .send()
+        .await
+        .map_err(|e| format!("请求媒体失败: {}", e))?;
+
+    let status = upstream.status();
+    let content_type = upstream_content_type(&upstream);
+
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        let content_range = upstream
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("bytes */*")
+            .to_string();
+        return Ok(range_not_satisfiable(&content_range));
+    }
+
+    if !status.is_success() {
+        return Ok(upstream_error(status));
+    }
+
+    let (body, content_range) = if status == reqwest::StatusCode::PARTIAL_CONTENT {
+        let content_range = upstream
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let body = read_window(upstream, 0, MAX_CHUNK_SIZE).await?;
+        let content_range =
+            content_range.unwrap_or_else(|| format!("bytes 0-{}/*", body.len().saturating_sub(1)));
+        (body, content_range)
+    } else {
+        // 源站忽略了 Range，自行截取所需区间
+        let total = upstream.content_length();
+        let (skip, take) = match range {
+            ByteRange::From { start, end } => (
+                start,
+                end.map_or(MAX_CHUNK_SIZE, |end| {
+                    end.saturating_sub(start).saturating_add(1)
+                }),
+            ),
+            ByteRange::Suffix(len) => match total {
+                Some(total) => (total.saturating_sub(len), len.min(total)),
+                None => return Err("源站不支持 Range 且未返回文件长度".to_string()),
+            },
+        };
+        if total.is_some_and(|total| skip >= total) {
+            return Ok(range_not_satisfiable(&format!(
+                "bytes */{}",
+                total.unwrap_or_default()
+            )));
+        }
+        let body = read_window(upstream, skip, take).await?;
+        let last = (skip + body.len() as u64).saturating_sub(1);
+        let total = total.map_or("*".to_string(), |t| t.to_string());
+        (body, format!("bytes {}-{}/{}", skip, last, total))
+    };
+
+    Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_RANGE, content_range)
+        .header(header::CONTENT_LENGTH, body.len().to_string())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(body)
+        .map_err(|e| e.to_string())
+}
+
+/// `media://` 协议处理入口：带上正确的 Referer/User-Agent 代理远程媒体，并按 Range 分块返回，
+/// 让 `<video>` 可以拖动进度而无需把整个文件读入内存。
+pub fn handle<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let target = match decode_target(request.uri().path()) {
+        Ok(target) => target,
+        Err(err) => return responder.respond(error_response(StatusCode::BAD_REQUEST, &err)),
+    };
+    if !ctx.app_handle().state::<MediaHosts>().allows(&target) {
+        return responder.respond(error_response(
+            StatusCode::FORBIDDEN,
+            "只能播放解析结果中的媒体地址",
+        ));
+    }
+
+    // 无法识别的 Range 头按规范忽略，当作普通请求
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_range);
+    if range.is_some_and(|range| !is_satisfiable(range)) {
+        return responder.respond(range_not_satisfiable("bytes */*"));
+    }
+
+    tauri::async_runtime::spawn(async move {
+        // 没有 Range 头时同样只返回第一块（206），不把整个文件读入内存；
+        // 播放器会根据 Content-Range 继续请求后面的部分
+        let range = range.unwrap_or(ByteRange::From {
+            start: 0,
+            end: None,
+        });
+        let response = match proxy_range(&target, range).await {
+            Ok(response) => response,
+            Err(err) => error_response(StatusCode::BAD_GATEWAY, &err),
+        };
+        responder.respond(response);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_range_headers() {
+        assert!(matches!(
+            parse_range("bytes=0-"),
+            Some(ByteRange::From {
+                start: 0,
+                end: None
+            })
+        ));
+        assert!(matches!(
+            parse_range("bytes=10-20, 30-40"),
+            Some(ByteRange::From {
+                start: 10,
+                end: Some(20)
+            })
+        ));
+        assert!(matches!(
+            parse_range("bytes=-500"),
+            Some(ByteRange::Suffix(500))
+        ));
+        assert!(parse_range("items=0-1").is_none());
+        assert!(parse_range("bytes=abc-").is_none());
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_ranges() {
+        assert!(!is_satisfiable(parse_range("bytes=10-5").unwrap()));
+        assert!(!is_satisfiable(parse_range("bytes=-0").unwrap()));
+        assert!(is_satisfiable(parse_range("bytes=5-5").unwrap()));
+    }
+
+    #[test]
+    fn clamps_without_overflow() {
+        let huge = ByteRange::From {
+            start: u64::MAX - 1,
+            end: None,
+        };
+        assert!(matches!(
+            clamp_range(huge),
+            ByteRange::From {
+                start,
+                end: Some(u64::MAX)
+            } if start == u64::MAX - 1
+        ));
+        assert!(matches!(
+            clamp_range(parse_range("bytes=0-").unwrap()),
+            ByteRange::From {
+                end: Some(end),
+                ..
+            } if end == MAX_CHUNK_SIZE - 1
+        ));
+    }
+
+    #[test]
+    fn only_allows_registered_media_hosts() {
+        let hosts = MediaHosts::default();
+        hosts.allow(["https://v26.douyinvod.com/a/b.mp4", "not a url"]);
+        assert!(hosts.allows("https://V26.douyinvod.com/other.mp4"));
+        assert!(!hosts.allows("https://example.com/a.mp4"));
+        assert!(!hosts.allows("http://127.0.0.1:8080/"));
+        assert!(!hosts.allows("file:///etc/passwd"));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::business_db;
+use crate::download_integrity::{
+    self, DownloadRecord, DownloadVerification, LoggedDownload, MediaCategory, MediaKind,
+};
+use crate::image_convert::{self, ImageConversion};
+use crate::media_metadata::{self, MediaMetadata};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+#[cfg(target_os = "android")]
+use tauri_plugin_android_permissions::{
+    AndroidPermissionsExt, CopyFromMediaStoreRequest, MediaCollection, SaveToMediaStoreRequest,
+};
+
+// Android 上先落盘到应用缓存目录，校验通过后再写入 MediaStore
+#[cfg(target_os = "android")]
+const STAGING_DIRNAME: &str = "downloads";
+
+#[cfg(target_os = "android")]
+fn download_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(STAGING_DIRNAME))
+        .map_err(|e| format!("无法获取缓存目录: {}", e))
+}
+
+#[cfg(target_os = "ios")]
+fn download_dir<R: Runtime>(_app: &AppHandle<R>) -> Result<PathBuf, String> {
+    Ok(std::env::temp_dir())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn download_dir<R: Runtime>(_app: &AppHandle<R>) -> Result<PathBuf, String> {
+    dirs::download_dir().ok_or_else(|| "无法获取下载目录".to_string())
+}
+
+/// 在 `dir` 中为 `filename` 选择未被占用的路径，重名时依次尝试 `名称 (1).ext`、`名称 (2).ext`……
+/// 正在下载的同名 `.part` 文件也视为占用
+pub(crate) fn unique_path(dir: &Path, filename: &str) -> PathBuf {
+    let taken = |path: &Path| {
+        let mut part = path.as_os_str().to_os_string();
+        part.push(".part");
+        path.exists() || Path::new(&part).exists()
+    };
+
+    let candidate = dir.join(filename);
+    if !taken(&candidate) {
+        return candidate;
+    }
+
+    let name = Path::new(filename);
+    let stem = name
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = name
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| !taken(path))
+        .expect("无限序列总能找到未占用的文件名")
+}
+
+/// 返回写入 `filename` 的落盘位置：桌面端即系统下载目录，Android 端为缓存中转目录。
+/// 已有同名文件时追加序号，不覆盖用户之前保存的文件
+pub fn staging_path<R: Runtime>(app: &AppHandle<R>, filename: &str) -> Result<PathBuf, String> {
+    let dir = download_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    Ok(unique_path(&dir, filename))
+}
+
+/// 按 MIME 类型选择 MediaStore 集合，非媒体文件放入 Download
+#[cfg(target_os = "android")]
+fn media_collection_for(mime_type: &str) -> MediaCollection {
+    if mime_type.starts_with("video/") {
+        MediaCollection::Movies
+    } else if mime_type.starts_with("image/") {
+        MediaCollection::Pictures
+    } else if mime_type.starts_with("audio/") {
+        MediaCollection::Music
+    } else {
+        MediaCollection::Downloads
+    }
+}
+
+/// 把已落盘、已校验的文件发布到用户可见的位置，返回最终路径。
+/// Android 端通过插件插入 MediaStore（以应用名作为子目录），返回 content:// URI 并删除中转文件。
+#[cfg(target_os = "android")]
+pub async fn publish<R: Runtime>(
+    app: &AppHandle<R>,
+    staged: &Path,
+    filename: &str,
+    mime_type: &str,
+) -> Result<String, String> {
+    let request = SaveToMediaStoreRequest {
+        source_path: staged.to_string_lossy().to_string(),
+        display_name: filename.to_string(),
+        mime_type: mime_type.to_string(),
+        collection: media_collection_for(mime_type),
+        folder: app.package_info().name.replace(['/', '\\'], "_"),
+    };
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        handle.android_permissions().save_to_media_store(request)
+    })
+    .await;
+    let _ = fs::remove_file(staged);
+
+    result
+        .map_err(|e| format!("保存到媒体库失败: {}", e))?
+        .map(|response| response.uri)
+        .map_err(|e| format!("保存到媒体库失败: {}", e))
+}
+
+/// 把已落盘、已校验的文件发布到用户可见的位置，返回最终路径。
+/// 桌面端文件已经在下载目录中，直接返回路径。
+#[cfg(not(target_os = "android"))]
+pub async fn publish<R: Runtime>(
+    _app: &AppHandle<R>,
+    staged: &Path,
+    _filename: &str,
+    _mime_type: &str,
+) -> Result<String, String> {
+    Ok(staged.to_string_lossy().to_string())
+}
+
+/// 可直接读取的本地文件；Android 上的 content:// URI 会先复制到缓存目录，丢弃时删除副本
+pub struct LocalFile {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl LocalFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LocalFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 把 `publish` 返回的路径解析为可读取的本地文件
+#[cfg(target_os = "android")]
+pub async fn local_file<R: Runtime>(app: &AppHandle<R>, path: &str) -> Result<LocalFile, String> {
+    if !path.starts_with("content://") {
+        return Ok(LocalFile {
+            path: PathBuf::from(path),
+            temporary: false,
+        });
+    }
+
+    let target = staging_path(app, &format!("{}.copy", uuid::Uuid::new_v4()))?;
+    let request = CopyFromMediaStoreRequest {
+        uri: path.to_string(),
+        target_path: target.to_string_lossy().to_string(),
+    };
+    let handle = app.clone();
+    let local = LocalFile {
+        path: target,
+        temporary: true,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        handle.android_permissions().copy_from_media_store(request)
+    })
+    .await
+    .map_err(|e| format!("读取媒体库文件失败: {}", e))?
+    .map_err(|e| format!("读取媒体库文件失败: {}", e))?;
+    Ok(local)
+}
+
+/// 把 `publish` 返回的路径解析为可读取的本地文件
+#[cfg(not(target_os = "android"))]
+pub async fn local_file<R: Runtime>(_app: &AppHandle<R>, path: &str) -> Result<LocalFile, String> {
+    Ok(LocalFile {
+        path: PathBuf::from(path),
+        temporary: false,
+    })
+}
+
+/// 文件名后缀是否与实际格式一致（同一格式的常见别名都算一致）
+fn extension_matches(filename: &str, kind: MediaKind) -> bool {
+    let ext = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match kind {
+        MediaKind::Jpeg => matches!(ext.as_str(), "jpg" | "jpeg"),
+        MediaKind::Heif => matches!(ext.as_str(), "heic" | "heif" | "avif"),
+        other => ext == other.extension(),
+    }
+}
+
+/// 把最终保存结果写入应用数据目录下的下载日志，供 `verify_download` 重新校验。
+/// 日志写入失败不影响已完成的下载。
+pub fn remember<R: Runtime>(app: &AppHandle<R>, path: &str, size: u64, sha256: &str) {
+    if let Ok(dir) = app.path().app_data_dir() {
+        let entry = LoggedDownload {
+            path: path.to_string(),
+            size,
+            sha256: sha256.to_string(),
+            saved_at: business_db::now_iso(),
+        };
+        let _ = download_integrity::append_download_log(&dir, &entry);
+    }
+}
+
+/// 按下载日志重新计算已保存文件的长度与 SHA-256，判断文件是否在保存后被改动或损坏
+#[tauri::command]
+pub async fn verify_download<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+) -> Result<DownloadVerification, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("无法获取应用数据目录: {}", e))?;
+    let entry = download_integrity::find_download_log(&dir, &path)?
+        .ok_or_else(|| "没有该文件的下载记录".to_string())?;
+
+    let local = local_file(&app, &path).await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        download_integrity::verify_against_log(entry, local.path())
+    })
+    .await
+    .map_err(|e| format!("校验任务异常: {}", e))?
+}
+
+/// 把下载响应保存到用户可见的位置。
+/// 桌面端写入系统下载目录；Android 端写入 MediaStore，返回记录中的 `path` 为 content:// URI。
+pub async fn save_download<R: Runtime>(
+    app: &AppHandle<R>,
+    response: reqwest::Response,
+    filename: &str,
+    metadata: Option<&MediaMetadata>,
+    conversion: Option<&ImageConversion>,
+) -> Result<DownloadRecord, String> {
+    let quarantine_root = app.path().app_cache_dir().ok();
+    let mut staged = staging_path(app, filename)?;
+    let mut filename = filename.to_string();
+
+    let mut record =
+        download_integrity::save_response_verified(response, &staged, quarantine_root.as_deref())
+            .await?;
+    staged = PathBuf::from(&record.path);
+    let mut modified = false;
+
+    // 图片按需转码，失败时保留原格式并记录原因
+    if let Some(conversion) = conversion {
+        if record.media_kind.matches(MediaCategory::Image)
+            && record.media_kind != conversion.target_kind()
+        {
+            match image_convert::convert_file(&staged, conversion) {
+                Ok(converted) => {
+                    staged = converted;
+                    record.media_kind = conversion.target_kind();
+                    filename = image_convert::converted_filename(&filename, record.media_kind);
+                    modified = true;
+                }
+                Err(err) => record.conversion_error = Some(err),
+            }
+        }
+    }
+
+    // 保持原格式（或转码失败）时按实际内容修正扩展名，避免 WebP 被存成 .jpg
+    if record.media_kind.matches(MediaCategory::Image)
+        && !extension_matches(&filename, record.media_kind)
+    {
+        let renamed = image_convert::converted_filename(&filename, record.media_kind);
+        let target = match staged.parent() {
+            Some(dir) => unique_path(dir, &renamed),
+            None => PathBuf::from(&renamed),
+        };
+        fs::rename(&staged, &target).map_err(|e| format!("重命名文件失败: {}", e))?;
+        staged = target;
+        filename = renamed;
+    }
+
+    // 写入来源信息失败不影响下载本身，只记录原因
+    if let Some(meta) = metadata {
+        match media_metadata::embed_metadata(&staged, meta) {
+            Ok(()) => modified = true,
+            Err(err) => record.metadata_error = Some(err),
+        }
+    }
+
+    if modified {
+        record.size = fs::metadata(&staged)
+            .map_err(|e| format!("读取文件信息失败: {}", e))?
+            .len();
+        record.sha256 = download_integrity::sha256_file(&staged)?;
+    }
+
+    let is_audio = download_integrity::expected_category(&filename) == Some(MediaCategory::Audio);
+    let mime_type = if record.media_kind == MediaKind::Mp4 && is_audio {
+        "audio/mp4"
+    } else {
+        record.media_kind.mime_type()
+    };
+
+    record.path = publish(app, &staged, &filename, mime_type).await?;
+    remember(app, &record.path, record.size, &record.sha256);
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_unused_name_instead_of_overwriting() {
+        let dir = std::env::temp_dir().join(format!("media-store-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "a.zip"), dir.join("a.zip"));
+        fs::write(dir.join("a.zip"), b"old").unwrap();
+        assert_eq!(unique_path(&dir, "a.zip"), dir.join("a (1).zip"));
+        fs::write(dir.join("a (1).zip.part"), b"").unwrap();
+        assert_eq!(unique_path(&dir, "a.zip"), dir.join("a (2).zip"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn matches_extensions_against_sniffed_kind() {
+        assert!(extension_matches("a_1.jpg", MediaKind::Jpeg));
+        assert!(extension_matches("a_1.JPEG", MediaKind::Jpeg));
+        assert!(!extension_matches("a_1.jpg", MediaKind::Webp));
+        assert!(extension_matches("a_1.avif", MediaKind::Heif));
+        assert!(!extension_matches("a_1", MediaKind::Png));
+    }
+}
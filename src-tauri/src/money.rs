@@ -0,0 +1,291 @@
+//! 金额计算：销售单的数量、单价、行金额、合计、已付与未付一律用定点小数（`Decimal`）计算。
+//! JSON 与数据库中仍以数字保存（数据库为 REAL 列），读写时按最短十进制表示转换（0.1 读作 0.1）。
+//! 有效数字不超过 15 位的十进制数经 f64 往返是精确的（IEEE 754 双精度保证 15 位），
+//! 金额（分）与数量在这一范围内（金额可到万亿元），所以存为 REAL 不会引入浮点误差。
+//!
+//! 舍入规则（`RoundingRule`）：
+//! - 金额保留到分，四舍五入（0.005 → 0.01）；
+//! - `level = line`（默认）：每行金额先舍入到分，合计为各行之和；
+//!   `level = total`：按未舍入的数量 × 单价累加后一次舍入到分，行金额仍舍入到分用于显示；
+//! - 抹零在合计之后进行：`wipe = jiao` 抹到角、`yuan` 抹到元，
+//!   `wipeMode = truncate`（默认）直接舍去零头，`round` 四舍五入。
+
+use crate::business_models::{
+    Invoice, PaymentStatus, RoundingLevel, RoundingRule, WipeMode, WipeUnit,
+};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+const CENT_SCALE: u32 = 2;
+
+pub fn from_f64(value: f64) -> Result<Decimal, String> {
+    if !value.is_finite() {
+        return Err(format!("金额无效: {}", value));
+    }
+    // f64 的 Display 是能还原该值的最短十进制表示
+    value
+        .to_string()
+        .parse()
+        .map_err(|_| format!("金额超出范围: {}", value))
+}
+
+/// 写入 JSON 或 REAL 列；有效数字不超过 15 位时 `from_f64(to_f64(x)) == x`
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_string().parse().unwrap_or(0.0)
+}
+
+/// 从数据库 REAL 列读取金额
+pub(crate) fn column(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Decimal> {
+    from_f64(row.get(index)?).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Real, e.into())
+    })
+}
+
+/// 以 JSON 数字读写 `Decimal`：`#[serde(with = "crate::money::number")]`，也接受数字字符串
+pub mod number {
+    use rust_decimal::Decimal;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(super::to_f64(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+
+    struct DecimalVisitor;
+
+    impl Visitor<'_> for DecimalVisitor {
+        type Value = Decimal;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("数字")
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Decimal, E> {
+            Ok(Decimal::from(value))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Decimal, E> {
+            Ok(Decimal::from(value))
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Decimal, E> {
+            super::from_f64(value).map_err(E::custom)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Decimal, E> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("金额无效: {}", value)))
+        }
+    }
+}
+
+pub(crate) fn round_cents(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(CENT_SCALE, RoundingStrategy::MidpointAwayFromZero)
+}
+
+fn wipe(amount: Decimal, rule: RoundingRule) -> Decimal {
+    let scale = match rule.wipe {
+        WipeUnit::None => return amount,
+        WipeUnit::Jiao => 1,
+        WipeUnit::Yuan => 0,
+    };
+    let strategy = match rule.wipe_mode {
+        WipeMode::Truncate => RoundingStrategy::ToZero,
+        WipeMode::Round => RoundingStrategy::MidpointAwayFromZero,
+    };
+    amount.round_dp_with_strategy(scale, strategy)
+}
+
+fn overflow() -> String {
+    "金额超出范围".to_string()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineAmount {
+    pub id: String,
+    #[serde(with = "number")]
+    pub amount: Decimal,
+}
+
+/// 销售单金额计算结果
+/// - subtotal: 抹零前的合计
+/// - wiped: 抹去的零头（subtotal - totalAmount）
+/// - unpaid_amount: 未付金额，多付时为 0
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceTotals {
+    pub items: Vec<LineAmount>,
+    #[serde(with = "number")]
+    pub subtotal: Decimal,
+    #[serde(with = "number")]
+    pub wiped: Decimal,
+    #[serde(with = "number")]
+    pub total_amount: Decimal,
+    #[serde(with = "number")]
+    pub paid_amount: Decimal,
+    #[serde(with = "number")]
+    pub unpaid_amount: Decimal,
+    pub payment_status: PaymentStatus,
+    pub rounding: RoundingRule,
+}
+
+/// 付款状态：付清（含合计为 0）、部分付款、未付款
+pub fn payment_status(total: Decimal, paid: Decimal) -> PaymentStatus {
+    if paid >= total {
+        PaymentStatus::Paid
+    } else if paid > Decimal::ZERO {
+        PaymentStatus::Partial
+    } else {
+        PaymentStatus::Unpaid
+    }
+}
+
+pub fn calculate(invoice: &Invoice) -> Result<InvoiceTotals, String> {
+    let rule = invoice.rounding.unwrap_or_default();
+    let mut exact_sum = Decimal::ZERO;
+    let mut rounded_sum = Decimal::ZERO;
+    let mut items = Vec::with_capacity(invoice.items.len());
+    for item in &invoice.items {
+        let exact = item
+            .quantity
+            .checked_mul(item.unit_price)
+            .ok_or_else(overflow)?;
+        let amount = round_cents(exact);
+        exact_sum = exact_sum.checked_add(exact).ok_or_else(overflow)?;
+        rounded_sum = rounded_sum.checked_add(amount).ok_or_else(overflow)?;
+        items.push(LineAmount {
+            id: item.id.clone(),
+            amount,
+        });
+    }
+    let subtotal = match rule.level {
+        RoundingLevel::Line => rounded_sum,
+        RoundingLevel::Total => round_cents(exact_sum),
+    };
+    let total_amount = wipe(subtotal, rule);
+    let paid_amount = round_cents(invoice.paid_amount);
+    Ok(InvoiceTotals {
+        items,
+        subtotal,
+        wiped: subtotal - total_amount,
+        total_amount,
+        paid_amount,
+        unpaid_amount: (total_amount - paid_amount).max(Decimal::ZERO),
+        payment_status: payment_status(total_amount, paid_amount),
+        rounding: rule,
+    })
+}
+
+/// 按计算结果改写销售单的行金额、合计、已付金额与付款状态
+pub fn apply(invoice: &mut Invoice) -> Result<InvoiceTotals, String> {
+    let totals = calculate(invoice)?;
+    for (item, line) in invoice.items.iter_mut().zip(&totals.items) {
+        item.amount = line.amount;
+    }
+    invoice.total_amount = totals.total_amount;
+    invoice.paid_amount = totals.paid_amount;
+    invoice.payment_status = totals.payment_status;
+    Ok(totals)
+}
+
+/// 计算销售单金额，返回以后端为准的行金额、合计、已付与未付金额
+#[tauri::command]
+pub async fn calculate_invoice(invoice: Invoice) -> Result<InvoiceTotals, String> {
+    calculate(&invoice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn invoice(items: serde_json::Value, paid: f64, rounding: serde_json::Value) -> Invoice {
+        serde_json::from_value(json!({
+            "id": "inv-1",
+            "invoiceNumber": "XS-1",
+            "items": items,
+            "paidAmount": paid,
+            "rounding": rounding,
+        }))
+        .unwrap()
+    }
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_floats_by_shortest_representation() {
+        assert_eq!(from_f64(0.1).unwrap() + from_f64(0.2).unwrap(), dec("0.3"));
+        assert_eq!(to_f64(dec("19.90")), 19.9);
+        assert!(from_f64(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn round_trips_cents_through_real_columns() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE t (amount REAL NOT NULL)", [])
+            .unwrap();
+        let values = [
+            "0.1",
+            "0.01",
+            "3.3",
+            "19.99",
+            "1234567.89",
+            "9999999999999.99",
+            "-0.07",
+            "0.335",
+            "12.3456",
+        ];
+        for value in values {
+            conn.execute("DELETE FROM t", []).unwrap();
+            conn.execute("INSERT INTO t (amount) VALUES (?1)", [to_f64(dec(value))])
+                .unwrap();
+            let read = conn
+                .query_row("SELECT amount FROM t", [], |row| column(row, 0))
+                .unwrap();
+            assert_eq!(read, dec(value).normalize(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn rounds_lines_or_total_to_cents() {
+        // 3 × 0.335 = 1.005：逐行舍入为 1.01，三行合计 3.03；按合计舍入为 3.015 → 3.02
+        let items = json!([
+            { "id": "a", "quantity": 3, "unitPrice": 0.335 },
+            { "id": "b", "quantity": 3, "unitPrice": 0.335 },
+            { "id": "c", "quantity": 3, "unitPrice": 0.335 },
+        ]);
+        let line = calculate(&invoice(items.clone(), 0.0, json!(null))).unwrap();
+        assert_eq!(line.items[0].amount, dec("1.01"));
+        assert_eq!(line.total_amount, dec("3.03"));
+
+        let total = calculate(&invoice(items, 0.0, json!({ "level": "total" }))).unwrap();
+        assert_eq!(total.items[0].amount, dec("1.01"));
+        assert_eq!(total.total_amount, dec("3.02"));
+    }
+
+    #[test]
+    fn wipes_to_jiao_or_yuan_and_derives_payment_status() {
+        let items = json!([{ "id": "a", "quantity": 7, "unitPrice": 12.38 }]);
+        let jiao = calculate(&invoice(items.clone(), 0.0, json!({ "wipe": "jiao" }))).unwrap();
+        assert_eq!(jiao.subtotal, dec("86.66"));
+        assert_eq!(jiao.total_amount, dec("86.6"));
+        assert_eq!(jiao.wiped, dec("0.06"));
+        assert_eq!(jiao.payment_status, PaymentStatus::Unpaid);
+
+        let rule = json!({ "wipe": "yuan", "wipeMode": "round" });
+        let yuan = calculate(&invoice(items, 50.0, rule)).unwrap();
+        assert_eq!(yuan.total_amount, dec("87"));
+        assert_eq!(yuan.unpaid_amount, dec("37"));
+        assert_eq!(yuan.payment_status, PaymentStatus::Partial);
+    }
+}
@@ -0,0 +1,292 @@
+use std::io::{Read, Seek, SeekFrom};
+
+/// 一个 ISO-BMFF 盒子的位置信息，`offset` 相对于所在的数据起点
+#[derive(Debug, Clone, Copy)]
+pub struct BoxInfo {
+    pub box_type: [u8; 4],
+    pub offset: u64,
+    pub header_len: u64,
+    pub size: u64,
+}
+
+impl BoxInfo {
+    pub fn is(&self, box_type: &[u8; 4]) -> bool {
+        &self.box_type == box_type
+    }
+
+    pub fn payload_offset(&self) -> u64 {
+        self.offset + self.header_len
+    }
+
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+}
+
+fn parse_header(header: &[u8], offset: u64, limit: u64) -> Result<BoxInfo, String> {
+    if header.len() < 8 {
+        return Err(format!("MP4 结构不完整: 偏移 {} 处盒子头被截断", offset));
+    }
+
+    let mut box_type = [0u8; 4];
+    box_type.copy_from_slice(&header[4..8]);
+    let size32 = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+
+    let (size, header_len) = match size32 {
+        0 => (limit - offset, 8),
+        1 => {
+            if header.len() < 16 {
+                return Err(format!(
+                    "MP4 结构不完整: 偏移 {} 处扩展盒子头被截断",
+                    offset
+                ));
+            }
+            let mut large = [0u8; 8];
+            large.copy_from_slice(&header[8..16]);
+            (u64::from_be_bytes(large), 16)
+        }
+        size => (size, 8),
+    };
+
+    let end = offset.checked_add(size);
+    if size < header_len || end.is_none_or(|end| end > limit) {
+        return Err(format!(
+            "MP4 结构损坏: 偏移 {} 处 {} 盒子长度无效",
+            offset,
+            String::from_utf8_lossy(&box_type)
+        ));
+    }
+
+    Ok(BoxInfo {
+        box_type,
+        offset,
+        header_len,
+        size,
+    })
+}
+
+/// 读取文件的顶层盒子列表（只读盒子头，不读内容）
+pub fn read_top_level_boxes<R: Read + Seek>(
+    reader: &mut R,
+    len: u64,
+) -> Result<Vec<BoxInfo>, String> {
+    let mut boxes = Vec::new();
+    let mut offset = 0u64;
+
+    while offset < len {
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        let mut header = [0u8; 16];
+        let available = (len - offset).min(16) as usize;
+        reader
+            .read_exact(&mut header[..available])
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+
+        let info = parse_header(&header[..available], offset, len)?;
+        offset = info.end();
+        boxes.push(info);
+    }
+
+    Ok(boxes)
+}
+
+/// 读取某个盒子的完整内容（含盒子头），适用于 moov 等体积较小的盒子
+pub fn read_box_bytes<R: Read + Seek>(reader: &mut R, info: &BoxInfo) -> Result<Vec<u8>, String> {
+    reader
+        .seek(SeekFrom::Start(info.offset))
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let mut data = vec![0u8; info.size as usize];
+    reader
+        .read_exact(&mut data)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(data)
+}
+
+/// 解析内存中连续排列的盒子
+pub fn parse_boxes(data: &[u8]) -> Result<Vec<BoxInfo>, String> {
+    let len = data.len() as u64;
+    let mut boxes = Vec::new();
+    let mut offset = 0u64;
+
+    while offset < len {
+        let start = offset as usize;
+        let end = (start + 16).min(data.len());
+        let info = parse_header(&data[start..end], offset, len)?;
+        offset = info.end();
+        boxes.push(info);
+    }
+
+    Ok(boxes)
+}
+
+/// 取盒子的内容部分（不含盒子头）
+pub fn payload<'a>(data: &'a [u8], info: &BoxInfo) -> &'a [u8] {
+    &data[info.payload_offset() as usize..info.end() as usize]
+}
+
+/// 在一串盒子中查找第一个指定类型的子盒子内容
+pub fn find_child<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    let boxes = parse_boxes(data).ok()?;
+    boxes
+        .iter()
+        .find(|info| info.is(box_type))
+        .map(|info| payload(data, info))
+}
+
+/// 按路径逐层查找盒子内容，例如 `[b"trak", b"mdia", b"mdhd"]`
+pub fn find_path<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    let mut current = data;
+    for box_type in path {
+        current = find_child(current, box_type)?;
+    }
+    Some(current)
+}
+
+pub fn be_u16(data: &[u8], pos: usize) -> Option<u16> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub fn be_u32(data: &[u8], pos: usize) -> Option<u32> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub fn be_u64(data: &[u8], pos: usize) -> Option<u64> {
+    data.get(pos..pos + 8).map(|b| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(b);
+        u64::from_be_bytes(buf)
+    })
+}
+
+/// 追加一个普通盒子，内容超过 4GB 时使用 64 位长度
+pub fn write_box(out: &mut Vec<u8>, box_type: &[u8; 4], payload: &[u8]) {
+    let size = payload.len() as u64 + 8;
+    if size > u32::MAX as u64 {
+        out.extend_from_slice(&1u32.to_be_bytes());
+        out.extend_from_slice(box_type);
+        out.extend_from_slice(&(size + 8).to_be_bytes());
+    } else {
+        out.extend_from_slice(&(size as u32).to_be_bytes());
+        out.extend_from_slice(box_type);
+    }
+    out.extend_from_slice(payload);
+}
+
+/// 追加一个 FullBox（带 version 与 flags）
+pub fn write_full_box(
+    out: &mut Vec<u8>,
+    box_type: &[u8; 4],
+    version: u8,
+    flags: u32,
+    payload: &[u8],
+) {
+    let mut body = Vec::with_capacity(payload.len() + 4);
+    body.push(version);
+    body.extend_from_slice(&flags.to_be_bytes()[1..]);
+    body.extend_from_slice(payload);
+    write_box(out, box_type, &body);
+}
+
+/// 测试中构造盒子的辅助函数，供各 MP4 相关模块的测试共用
+#[cfg(test)]
+pub(crate) mod test_boxes {
+    pub(crate) fn boxed(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        super::write_box(&mut out, box_type, payload);
+        out
+    }
+
+    pub(crate) fn full(box_type: &[u8; 4], flags: u32, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        super::write_full_box(&mut out, box_type, 0, flags, payload);
+        out
+    }
+
+    pub(crate) fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parses_nested_boxes_and_paths() {
+        let mut mdhd = Vec::new();
+        write_full_box(&mut mdhd, b"mdhd", 0, 0, &[0u8; 20]);
+        let mut mdia = Vec::new();
+        write_box(&mut mdia, b"mdia", &mdhd);
+        let mut trak = Vec::new();
+        write_box(&mut trak, b"trak", &mdia);
+        write_box(&mut trak, b"free", &[]);
+
+        let boxes = parse_boxes(&trak).unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes[0].is(b"trak"));
+        assert_eq!(boxes[1].offset, boxes[0].end());
+
+        let mdhd_payload = find_path(&trak, &[b"trak", b"mdia", b"mdhd"]).unwrap();
+        assert_eq!(mdhd_payload.len(), 24);
+        assert!(find_path(&trak, &[b"trak", b"minf"]).is_none());
+    }
+
+    #[test]
+    fn handles_large_and_open_ended_sizes() {
+        // size == 1：64 位长度
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&20u64.to_be_bytes());
+        data.extend_from_slice(&[9u8; 4]);
+        // size == 0：延伸到数据末尾
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(b"free");
+        data.extend_from_slice(&[0u8; 6]);
+
+        let boxes = parse_boxes(&data).unwrap();
+        assert_eq!(boxes[0].header_len, 16);
+        assert_eq!(payload(&data, &boxes[0]), &[9u8; 4]);
+        assert!(boxes[1].is(b"free"));
+        assert_eq!(boxes[1].end(), data.len() as u64);
+
+        let mut reader = Cursor::new(&data);
+        let top = read_top_level_boxes(&mut reader, data.len() as u64).unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(read_box_bytes(&mut reader, &top[0]).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn rejects_truncated_or_oversized_boxes() {
+        let mut data = Vec::new();
+        write_box(&mut data, b"moov", &[0u8; 16]);
+        assert!(parse_boxes(&data[..data.len() - 1]).is_err());
+        assert!(parse_boxes(&data[..5]).is_err());
+
+        let mut bad = 4u32.to_be_bytes().to_vec();
+        bad.extend_from_slice(b"moov");
+        assert!(parse_boxes(&bad).is_err());
+
+        // 64 位长度接近 u64::MAX 时偏移相加会溢出
+        let mut huge = Vec::new();
+        write_box(&mut huge, b"free", &[]);
+        huge.extend_from_slice(&1u32.to_be_bytes());
+        huge.extend_from_slice(b"mdat");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(parse_boxes(&huge).is_err());
+        let mut reader = Cursor::new(&huge);
+        assert!(read_top_level_boxes(&mut reader, huge.len() as u64).is_err());
+    }
+
+    #[test]
+    fn reads_big_endian_fields_within_bounds() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
+        assert_eq!(be_u16(&data, 0), Some(0x1234));
+        assert_eq!(be_u32(&data, 4), Some(0x9ABC_DEF0));
+        assert_eq!(be_u64(&data, 0), Some(0x1234_5678_9ABC_DEF0));
+        assert_eq!(be_u32(&data, 6), None);
+    }
+}
@@ -0,0 +1,1259 @@
+use crate::download_integrity::{self, MediaCategory};
+use crate::media_metadata::{self, MediaMetadata};
+use crate::{media_store, mp4};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+// 音视频时长允许的最大差值（秒），DASH 分片的音频通常比视频长或短几十毫秒
+const MAX_DURATION_DRIFT_SECS: f64 = 1.0;
+// 交织写入时每个块覆盖的时长（秒）
+const CHUNK_DURATION_SECS: f64 = 0.5;
+const MOVIE_TIMESCALE: u32 = 1000;
+// DASH 分片下载的中转目录（位于应用缓存目录下）
+const DASH_DIRNAME: &str = "dash";
+
+// trun / tfhd 标志位
+const TFHD_BASE_DATA_OFFSET: u32 = 0x01;
+const TFHD_SAMPLE_DESCRIPTION_INDEX: u32 = 0x02;
+const TFHD_DEFAULT_DURATION: u32 = 0x08;
+const TFHD_DEFAULT_SIZE: u32 = 0x10;
+const TFHD_DEFAULT_FLAGS: u32 = 0x20;
+const TRUN_DATA_OFFSET: u32 = 0x01;
+const TRUN_FIRST_SAMPLE_FLAGS: u32 = 0x04;
+const TRUN_SAMPLE_DURATION: u32 = 0x100;
+const TRUN_SAMPLE_SIZE: u32 = 0x200;
+const TRUN_SAMPLE_FLAGS: u32 = 0x400;
+const TRUN_SAMPLE_CTS: u32 = 0x800;
+const SAMPLE_IS_NON_SYNC: u32 = 0x10000;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    offset: u64,
+    size: u32,
+    duration: u32,
+    cts_offset: i32,
+    is_sync: bool,
+}
+
+/// 从输入文件中取出的一条轨道：原始 trak 内容 + 展开后的样本表
+struct Track {
+    source: PathBuf,
+    trak: Vec<u8>,
+    timescale: u32,
+    samples: Vec<Sample>,
+}
+
+impl Track {
+    fn duration(&self) -> u64 {
+        self.samples.iter().map(|s| s.duration as u64).sum()
+    }
+
+    fn duration_secs(&self) -> f64 {
+        self.duration() as f64 / self.timescale.max(1) as f64
+    }
+
+    fn movie_duration(&self) -> u64 {
+        self.duration() * MOVIE_TIMESCALE as u64 / self.timescale.max(1) as u64
+    }
+}
+
+/// 一个输出块：同一轨道中连续的若干样本
+struct Chunk {
+    track: usize,
+    first_sample: usize,
+    count: usize,
+    start_secs: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct TrexDefaults {
+    duration: u32,
+    size: u32,
+    flags: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuxRecord {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub video_duration: f64,
+    pub audio_duration: f64,
+    /// 写入来源信息失败时的原因（不影响合并结果）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_error: Option<String>,
+}
+
+fn handler_type(trak: &[u8]) -> Option<[u8; 4]> {
+    let hdlr = mp4::find_path(trak, &[b"mdia", b"hdlr"])?;
+    let mut handler = [0u8; 4];
+    handler.copy_from_slice(hdlr.get(8..12)?);
+    Some(handler)
+}
+
+fn track_id(trak: &[u8]) -> Option<u32> {
+    let tkhd = mp4::find_child(trak, b"tkhd")?;
+    match tkhd.first()? {
+        1 => mp4::be_u32(tkhd, 20),
+        _ => mp4::be_u32(tkhd, 12),
+    }
+}
+
+fn media_timescale(trak: &[u8]) -> Option<u32> {
+    let mdhd = mp4::find_path(trak, &[b"mdia", b"mdhd"])?;
+    match mdhd.first()? {
+        1 => mp4::be_u32(mdhd, 20),
+        _ => mp4::be_u32(mdhd, 12),
+    }
+}
+
+fn find_trex(moov: &[u8], track_id: u32) -> TrexDefaults {
+    mp4::find_child(moov, b"mvex")
+        .and_then(|mvex| {
+            mp4::parse_boxes(mvex).ok()?.into_iter().find_map(|info| {
+                let trex = mp4::payload(mvex, &info);
+                if !info.is(b"trex") || mp4::be_u32(trex, 4)? != track_id {
+                    return None;
+                }
+                Some(TrexDefaults {
+                    duration: mp4::be_u32(trex, 12)?,
+                    size: mp4::be_u32(trex, 16)?,
+                    flags: mp4::be_u32(trex, 20)?,
+                })
+            })
+        })
+        .unwrap_or_default()
+}
+
+fn full_box_entries(data: &[u8], entry_size: usize, name: &str) -> Result<usize, String> {
+    let count = mp4::be_u32(data, 4).ok_or(format!("{} 表被截断", name))? as usize;
+    if 8 + count.saturating_mul(entry_size) > data.len() {
+        return Err(format!("{} 表被截断", name));
+    }
+    Ok(count)
+}
+
+/// 样本数来自文件内容，分配前先确认这些样本能放进 `available` 字节
+fn check_sample_count(
+    count: usize,
+    bytes_per_sample: u64,
+    available: u64,
+    name: &str,
+) -> Result<(), String> {
+    if count > 0
+        && (bytes_per_sample == 0 || (count as u64).saturating_mul(bytes_per_sample) > available)
+    {
+        return Err(format!("{} 中的样本数无效", name));
+    }
+    Ok(())
+}
+
+/// 展开普通（非分片）MP4 的 stbl 样本表；分片文件的初始化段中这些表为空。
+/// `data_len` 为文件长度，用于限制样本数
+fn progressive_samples(stbl: &[u8], data_len: u64) -> Result<Vec<Sample>, String> {
+    let stsz = match mp4::find_child(stbl, b"stsz") {
+        Some(stsz) => stsz,
+        None => return Ok(Vec::new()),
+    };
+    let uniform_size = mp4::be_u32(stsz, 4).ok_or("stsz 表被截断")?;
+    let count = mp4::be_u32(stsz, 8).ok_or("stsz 表被截断")? as usize;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    if uniform_size != 0 {
+        check_sample_count(count, uniform_size as u64, data_len, "stsz")?;
+    } else {
+        check_sample_count(count, 4, stsz.len().saturating_sub(12) as u64, "stsz")?;
+    }
+    let sizes: Vec<u32> = if uniform_size != 0 {
+        vec![uniform_size; count]
+    } else {
+        (0..count)
+            .map(|i| mp4::be_u32(stsz, 12 + i * 4).ok_or("stsz 表被截断"))
+            .collect::<Result<_, _>>()?
+    };
+
+    let chunk_offsets: Vec<u64> = if let Some(stco) = mp4::find_child(stbl, b"stco") {
+        let n = full_box_entries(stco, 4, "stco")?;
+        (0..n)
+            .filter_map(|i| mp4::be_u32(stco, 8 + i * 4).map(u64::from))
+            .collect()
+    } else if let Some(co64) = mp4::find_child(stbl, b"co64") {
+        let n = full_box_entries(co64, 8, "co64")?;
+        (0..n)
+            .filter_map(|i| mp4::be_u64(co64, 8 + i * 8))
+            .collect()
+    } else {
+        return Err("MP4 缺少块偏移表".to_string());
+    };
+
+    let stsc = mp4::find_child(stbl, b"stsc").ok_or("MP4 缺少 stsc 表")?;
+    let stsc_count = full_box_entries(stsc, 12, "stsc")?;
+    let stsc_entries: Vec<(u32, u32)> = (0..stsc_count)
+        .filter_map(|i| {
+            let pos = 8 + i * 12;
+            Some((mp4::be_u32(stsc, pos)?, mp4::be_u32(stsc, pos + 4)?))
+        })
+        .collect();
+
+    let mut samples = Vec::with_capacity(count);
+    let mut sample_index = 0usize;
+    for (chunk_index, chunk_offset) in chunk_offsets.iter().enumerate() {
+        let chunk_number = chunk_index as u32 + 1;
+        let per_chunk = stsc_entries
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= chunk_number)
+            .map_or(0, |(_, n)| *n);
+        let mut offset = *chunk_offset;
+        for _ in 0..per_chunk {
+            if sample_index >= count {
+                break;
+            }
+            let size = sizes[sample_index];
+            samples.push(Sample {
+                offset,
+                size,
+                duration: 0,
+                cts_offset: 0,
+                is_sync: true,
+            });
+            offset += size as u64;
+            sample_index += 1;
+        }
+    }
+    if samples.len() != count {
+        return Err("MP4 样本表不一致: 块内样本数与 stsz 不符".to_string());
+    }
+
+    let stts = mp4::find_child(stbl, b"stts").ok_or("MP4 缺少 stts 表")?;
+    let stts_count = full_box_entries(stts, 8, "stts")?;
+    let mut index = 0usize;
+    for i in 0..stts_count {
+        let run = mp4::be_u32(stts, 8 + i * 8).unwrap_or(0) as usize;
+        let delta = mp4::be_u32(stts, 12 + i * 8).unwrap_or(0);
+        for sample in samples.iter_mut().skip(index).take(run) {
+            sample.duration = delta;
+        }
+        index += run;
+    }
+
+    if let Some(ctts) = mp4::find_child(stbl, b"ctts") {
+        let ctts_count = full_box_entries(ctts, 8, "ctts")?;
+        let mut index = 0usize;
+        for i in 0..ctts_count {
+            let run = mp4::be_u32(ctts, 8 + i * 8).unwrap_or(0) as usize;
+            let offset = mp4::be_u32(ctts, 12 + i * 8).unwrap_or(0) as i32;
+            for sample in samples.iter_mut().skip(index).take(run) {
+                sample.cts_offset = offset;
+            }
+            index += run;
+        }
+    }
+
+    if let Some(stss) = mp4::find_child(stbl, b"stss") {
+        let stss_count = full_box_entries(stss, 4, "stss")?;
+        for sample in samples.iter_mut() {
+            sample.is_sync = false;
+        }
+        for i in 0..stss_count {
+            let number = mp4::be_u32(stss, 8 + i * 4).unwrap_or(0) as usize;
+            if let Some(sample) = number.checked_sub(1).and_then(|n| samples.get_mut(n)) {
+                sample.is_sync = true;
+            }
+        }
+    }
+
+    Ok(samples)
+}
+
+/// 解析一个 moof 中属于 `track_id` 的 traf/trun，把样本追加到 `samples`；
+/// `data_len` 为文件长度，用于限制样本数
+fn fragment_samples(
+    moof: &[u8],
+    moof_offset: u64,
+    track_id: u32,
+    trex: TrexDefaults,
+    data_len: u64,
+    samples: &mut Vec<Sample>,
+) -> Result<(), String> {
+    for traf_info in mp4::parse_boxes(moof)? {
+        if !traf_info.is(b"traf") {
+            continue;
+        }
+        let traf = mp4::payload(moof, &traf_info);
+        let tfhd = mp4::find_child(traf, b"tfhd").ok_or("分片缺少 tfhd")?;
+        let flags = mp4::be_u32(tfhd, 0).ok_or("tfhd 被截断")? & 0x00FF_FFFF;
+        if mp4::be_u32(tfhd, 4) != Some(track_id) {
+            continue;
+        }
+
+        let mut pos = 8;
+        let mut base = moof_offset;
+        let mut defaults = trex;
+        if flags & TFHD_BASE_DATA_OFFSET != 0 {
+            base = mp4::be_u64(tfhd, pos).ok_or("tfhd 被截断")?;
+            pos += 8;
+        }
+        if flags & TFHD_SAMPLE_DESCRIPTION_INDEX != 0 {
+            pos += 4;
+        }
+        if flags & TFHD_DEFAULT_DURATION != 0 {
+            defaults.duration = mp4::be_u32(tfhd, pos).ok_or("tfhd 被截断")?;
+            pos += 4;
+        }
+        if flags & TFHD_DEFAULT_SIZE != 0 {
+            defaults.size = mp4::be_u32(tfhd, pos).ok_or("tfhd 被截断")?;
+            pos += 4;
+        }
+        if flags & TFHD_DEFAULT_FLAGS != 0 {
+            defaults.flags = mp4::be_u32(tfhd, pos).ok_or("tfhd 被截断")?;
+        }
+
+        // 没有 data_offset 的 trun 紧接在上一个 trun 的数据之后
+        let mut next_offset = base;
+        for trun_info in mp4::parse_boxes(traf)? {
+            if !trun_info.is(b"trun") {
+                continue;
+            }
+            let trun = mp4::payload(traf, &trun_info);
+            let trun_flags = mp4::be_u32(trun, 0).ok_or("trun 被截断")? & 0x00FF_FFFF;
+            let count = mp4::be_u32(trun, 4).ok_or("trun 被截断")? as usize;
+            let mut pos = 8;
+            let mut offset = next_offset;
+            if trun_flags & TRUN_DATA_OFFSET != 0 {
+                let data_offset = mp4::be_u32(trun, pos).ok_or("trun 被截断")? as i32;
+                offset = (base as i64 + data_offset as i64) as u64;
+                pos += 4;
+            }
+            let mut first_flags = None;
+            if trun_flags & TRUN_FIRST_SAMPLE_FLAGS != 0 {
+                first_flags = Some(mp4::be_u32(trun, pos).ok_or("trun 被截断")?);
+                pos += 4;
+            }
+            // 每个样本的字段都在 trun 中时按 trun 长度限制，否则按默认样本大小与文件长度限制
+            let field_bytes = [
+                TRUN_SAMPLE_DURATION,
+                TRUN_SAMPLE_SIZE,
+                TRUN_SAMPLE_FLAGS,
+                TRUN_SAMPLE_CTS,
+            ]
+            .iter()
+            .filter(|flag| trun_flags & **flag != 0)
+            .count() as u64
+                * 4;
+            if field_bytes > 0 {
+                check_sample_count(
+                    count,
+                    field_bytes,
+                    trun.len().saturating_sub(pos) as u64,
+                    "trun",
+                )?;
+            } else {
+                check_sample_count(count, defaults.size as u64, data_len, "trun")?;
+            }
+            samples.reserve(count);
+
+            for i in 0..count {
+                let mut read = |present: bool, default: u32| -> Result<u32, String> {
+                    if !present {
+                        return Ok(default);
+                    }
+                    let value = mp4::be_u32(trun, pos).ok_or("trun 被截断")?;
+                    pos += 4;
+                    Ok(value)
+                };
+                let duration = read(trun_flags & TRUN_SAMPLE_DURATION != 0, defaults.duration)?;
+                let size = read(trun_flags & TRUN_SAMPLE_SIZE != 0, defaults.size)?;
+                let mut sample_flags = read(trun_flags & TRUN_SAMPLE_FLAGS != 0, defaults.flags)?;
+                let cts_offset = read(trun_flags & TRUN_SAMPLE_CTS != 0, 0)? as i32;
+                if i == 0 {
+                    sample_flags = first_flags.unwrap_or(sample_flags);
+                }
+
+                samples.push(Sample {
+                    offset,
+                    size,
+                    duration,
+                    cts_offset,
+                    is_sync: sample_flags & SAMPLE_IS_NON_SYNC == 0,
+                });
+                offset += size as u64;
+            }
+            next_offset = offset;
+        }
+    }
+    Ok(())
+}
+
+/// 从文件中读出指定类型（vide / soun）的第一条轨道，支持分片与普通 MP4
+fn load_track(path: &Path, handler: &[u8; 4]) -> Result<Track, String> {
+    let kind_name = if handler == b"vide" {
+        "视频"
+    } else {
+        "音频"
+    };
+    let mut file = File::open(path).map_err(|e| format!("打开{}文件失败: {}", kind_name, e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let boxes = mp4::read_top_level_boxes(&mut file, len)?;
+    // 单独的 DASH 媒体分段（styp + moof）不含编码参数，必须与初始化段一起下载才能合并
+    if boxes.first().is_some_and(|info| info.is(b"styp"))
+        && !boxes.iter().any(|info| info.is(b"moov"))
+    {
+        return Err(format!(
+            "{}文件是单独的媒体分段，缺少初始化段（moov），无法合并",
+            kind_name
+        ));
+    }
+    let moov_info = boxes
+        .iter()
+        .find(|info| info.is(b"moov"))
+        .ok_or(format!("{}文件缺少 moov 盒子", kind_name))?;
+    let moov_bytes = mp4::read_box_bytes(&mut file, moov_info)?;
+    let moov = &moov_bytes[moov_info.header_len as usize..];
+
+    let trak = mp4::parse_boxes(moov)?
+        .into_iter()
+        .filter(|info| info.is(b"trak"))
+        .map(|info| mp4::payload(moov, &info))
+        .find(|trak| handler_type(trak).as_ref() == Some(handler))
+        .ok_or(format!("{}文件中没有{}轨道", kind_name, kind_name))?
+        .to_vec();
+
+    let id = track_id(&trak).ok_or("轨道缺少 tkhd")?;
+    let timescale = media_timescale(&trak).ok_or("轨道缺少 mdhd")?;
+    let stbl = mp4::find_path(&trak, &[b"mdia", b"minf", b"stbl"]).ok_or("轨道缺少 stbl")?;
+    let mut samples = progressive_samples(stbl, len)?;
+
+    let trex = find_trex(moov, id);
+    for moof_info in boxes.iter().filter(|info| info.is(b"moof")) {
+        let moof = mp4::read_box_bytes(&mut file, moof_info)?;
+        fragment_samples(
+            &moof[moof_info.header_len as usize..],
+            moof_info.offset,
+            id,
+            trex,
+            len,
+            &mut samples,
+        )?;
+    }
+
+    if samples.is_empty() {
+        return Err(format!("{}轨道中没有任何样本", kind_name));
+    }
+    if samples.iter().any(|s| s.offset + s.size as u64 > len) {
+        return Err(format!("{}样本数据超出文件范围，文件可能不完整", kind_name));
+    }
+
+    Ok(Track {
+        source: path.to_path_buf(),
+        trak,
+        timescale,
+        samples,
+    })
+}
+
+/// 分片文件中某类轨道（vide / soun）的实际时长，供探测使用
+pub(crate) fn fragmented_duration_secs(path: &Path, handler: &[u8; 4]) -> Option<f64> {
+    load_track(path, handler)
+        .ok()
+        .map(|track| track.duration_secs())
+}
+
+fn plan_chunks(track_index: usize, track: &Track) -> Vec<Chunk> {
+    let limit = (track.timescale as f64 * CHUNK_DURATION_SECS) as u64;
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut elapsed = 0u64;
+    let mut chunk_elapsed = 0u64;
+
+    for (index, sample) in track.samples.iter().enumerate() {
+        match chunks.last_mut() {
+            Some(chunk) if chunk_elapsed < limit => chunk.count += 1,
+            _ => {
+                chunks.push(Chunk {
+                    track: track_index,
+                    first_sample: index,
+                    count: 1,
+                    start_secs: elapsed as f64 / track.timescale.max(1) as f64,
+                });
+                chunk_elapsed = 0;
+            }
+        }
+        elapsed += sample.duration as u64;
+        chunk_elapsed += sample.duration as u64;
+    }
+    chunks
+}
+
+fn run_length<T: PartialEq + Copy>(values: impl Iterator<Item = T>) -> Vec<(u32, T)> {
+    let mut runs: Vec<(u32, T)> = Vec::new();
+    for value in values {
+        match runs.last_mut() {
+            Some((count, last)) if *last == value => *count += 1,
+            _ => runs.push((1, value)),
+        }
+    }
+    runs
+}
+
+fn build_stbl(stsd: &[u8], track: &Track, chunks: &[(&Chunk, u64)], co64: bool) -> Vec<u8> {
+    let mut stbl = Vec::new();
+    stbl.extend_from_slice(stsd);
+
+    let stts_runs = run_length(track.samples.iter().map(|s| s.duration));
+    let mut stts = (stts_runs.len() as u32).to_be_bytes().to_vec();
+    for (count, delta) in &stts_runs {
+        stts.extend_from_slice(&count.to_be_bytes());
+        stts.extend_from_slice(&delta.to_be_bytes());
+    }
+    mp4::write_full_box(&mut stbl, b"stts", 0, 0, &stts);
+
+    if track.samples.iter().any(|s| s.cts_offset != 0) {
+        let ctts_runs = run_length(track.samples.iter().map(|s| s.cts_offset));
+        let version = u8::from(ctts_runs.iter().any(|(_, offset)| *offset < 0));
+        let mut ctts = (ctts_runs.len() as u32).to_be_bytes().to_vec();
+        for (count, offset) in &ctts_runs {
+            ctts.extend_from_slice(&count.to_be_bytes());
+            ctts.extend_from_slice(&offset.to_be_bytes());
+        }
+        mp4::write_full_box(&mut stbl, b"ctts", version, 0, &ctts);
+    }
+
+    if track.samples.iter().any(|s| !s.is_sync) {
+        let sync: Vec<u32> = track
+            .samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.is_sync)
+            .map(|(i, _)| i as u32 + 1)
+            .collect();
+        let mut stss = (sync.len() as u32).to_be_bytes().to_vec();
+        for number in sync {
+            stss.extend_from_slice(&number.to_be_bytes());
+        }
+        mp4::write_full_box(&mut stbl, b"stss", 0, 0, &stss);
+    }
+
+    let mut stsc_entries: Vec<(u32, u32)> = Vec::new();
+    for (index, (chunk, _)) in chunks.iter().enumerate() {
+        if stsc_entries.last().map(|(_, n)| *n) != Some(chunk.count as u32) {
+            stsc_entries.push((index as u32 + 1, chunk.count as u32));
+        }
+    }
+    let mut stsc = (stsc_entries.len() as u32).to_be_bytes().to_vec();
+    for (first_chunk, per_chunk) in stsc_entries {
+        stsc.extend_from_slice(&first_chunk.to_be_bytes());
+        stsc.extend_from_slice(&per_chunk.to_be_bytes());
+        stsc.extend_from_slice(&1u32.to_be_bytes());
+    }
+    mp4::write_full_box(&mut stbl, b"stsc", 0, 0, &stsc);
+
+    let mut stsz = 0u32.to_be_bytes().to_vec();
+    stsz.extend_from_slice(&(track.samples.len() as u32).to_be_bytes());
+    for sample in &track.samples {
+        stsz.extend_from_slice(&sample.size.to_be_bytes());
+    }
+    mp4::write_full_box(&mut stbl, b"stsz", 0, 0, &stsz);
+
+    let mut offsets = (chunks.len() as u32).to_be_bytes().to_vec();
+    for (_, offset) in chunks {
+        if co64 {
+            offsets.extend_from_slice(&offset.to_be_bytes());
+        } else {
+            offsets.extend_from_slice(&(*offset as u32).to_be_bytes());
+        }
+    }
+    mp4::write_full_box(
+        &mut stbl,
+        if co64 { b"co64" } else { b"stco" },
+        0,
+        0,
+        &offsets,
+    );
+
+    let mut out = Vec::new();
+    mp4::write_box(&mut out, b"stbl", &stbl);
+    out
+}
+
+/// 按路径重建容器盒子：`rebuild` 返回 Some 时替换该子盒子，返回 None 时原样保留
+fn rebuild_container<F>(data: &[u8], skip: &[&[u8; 4]], mut rebuild: F) -> Result<Vec<u8>, String>
+where
+    F: FnMut(&mp4::BoxInfo, &[u8]) -> Result<Option<Vec<u8>>, String>,
+{
+    let mut out = Vec::new();
+    for info in mp4::parse_boxes(data)? {
+        if skip.iter().any(|t| info.is(t)) {
+            continue;
+        }
+        let bytes = &data[info.offset as usize..info.end() as usize];
+        match rebuild(&info, mp4::payload(data, &info))? {
+            Some(replaced) => out.extend_from_slice(&replaced),
+            None => out.extend_from_slice(bytes),
+        }
+    }
+    Ok(out)
+}
+
+fn patch_duration(payload: &[u8], v0_pos: usize, v1_pos: usize, duration: u64) -> Vec<u8> {
+    let mut patched = payload.to_vec();
+    if patched.first() == Some(&1) {
+        if let Some(slot) = patched.get_mut(v1_pos..v1_pos + 8) {
+            slot.copy_from_slice(&duration.to_be_bytes());
+        }
+    } else if let Some(slot) = patched.get_mut(v0_pos..v0_pos + 4) {
+        slot.copy_from_slice(&(duration.min(u32::MAX as u64) as u32).to_be_bytes());
+    }
+    patched
+}
+
+fn build_trak(
+    track: &Track,
+    new_track_id: u32,
+    chunks: &[(&Chunk, u64)],
+    co64: bool,
+) -> Result<Vec<u8>, String> {
+    let stsd = mp4::find_path(&track.trak, &[b"mdia", b"minf", b"stbl"])
+        .and_then(|stbl| {
+            let info = mp4::parse_boxes(stbl)
+                .ok()?
+                .into_iter()
+                .find(|info| info.is(b"stsd"))?;
+            Some(stbl[info.offset as usize..info.end() as usize].to_vec())
+        })
+        .ok_or("轨道缺少 stsd")?;
+
+    // 分片文件的编辑列表以分片为基准，合并后不再适用，直接丢弃
+    let body = rebuild_container(
+        &track.trak,
+        &[b"edts", b"tref", b"udta"],
+        |info, payload| {
+            let mut out = Vec::new();
+            match &info.box_type {
+                b"tkhd" => {
+                    let mut tkhd = patch_duration(payload, 20, 28, track.movie_duration());
+                    let id_pos = if tkhd.first() == Some(&1) { 20 } else { 12 };
+                    if let Some(slot) = tkhd.get_mut(id_pos..id_pos + 4) {
+                        slot.copy_from_slice(&new_track_id.to_be_bytes());
+                    }
+                    // 确保轨道处于启用状态
+                    if let Some(flags) = tkhd.get_mut(3) {
+                        *flags |= 0x03;
+                    }
+                    mp4::write_box(&mut out, b"tkhd", &tkhd);
+                }
+                b"mdia" => {
+                    let mdia = rebuild_container(payload, &[], |info, payload| {
+                        let mut out = Vec::new();
+                        match &info.box_type {
+                            b"mdhd" => {
+                                let mdhd = patch_duration(payload, 16, 24, track.duration());
+                                mp4::write_box(&mut out, b"mdhd", &mdhd);
+                            }
+                            b"minf" => {
+                                let minf = rebuild_container(payload, &[], |info, _| {
+                                    Ok(info
+                                        .is(b"stbl")
+                                        .then(|| build_stbl(&stsd, track, chunks, co64)))
+                                })?;
+                                mp4::write_box(&mut out, b"minf", &minf);
+                            }
+                            _ => return Ok(None),
+                        }
+                        Ok(Some(out))
+                    })?;
+                    mp4::write_box(&mut out, b"mdia", &mdia);
+                }
+                _ => return Ok(None),
+            }
+            Ok(Some(out))
+        },
+    )?;
+
+    let mut out = Vec::new();
+    mp4::write_box(&mut out, b"trak", &body);
+    Ok(out)
+}
+
+fn build_mvhd(duration: u64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(96);
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    payload.extend_from_slice(&MOVIE_TIMESCALE.to_be_bytes());
+    payload.extend_from_slice(&(duration.min(u32::MAX as u64) as u32).to_be_bytes());
+    payload.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    payload.extend_from_slice(&0x0100u16.to_be_bytes());
+    payload.extend_from_slice(&[0u8; 10]);
+    for value in [0x0001_0000u32, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000] {
+        payload.extend_from_slice(&value.to_be_bytes());
+    }
+    payload.extend_from_slice(&[0u8; 24]);
+    payload.extend_from_slice(&3u32.to_be_bytes());
+
+    let mut out = Vec::new();
+    mp4::write_full_box(&mut out, b"mvhd", 0, 0, &payload);
+    out
+}
+
+fn build_moov(
+    tracks: &[Track],
+    chunks: &[Chunk],
+    offsets: &[u64],
+    co64: bool,
+) -> Result<Vec<u8>, String> {
+    let duration = tracks.iter().map(Track::movie_duration).max().unwrap_or(0);
+    let mut body = build_mvhd(duration);
+
+    for (index, track) in tracks.iter().enumerate() {
+        let track_chunks: Vec<(&Chunk, u64)> = chunks
+            .iter()
+            .zip(offsets.iter().copied())
+            .filter(|(chunk, _)| chunk.track == index)
+            .collect();
+        body.extend_from_slice(&build_trak(track, index as u32 + 1, &track_chunks, co64)?);
+    }
+
+    let mut out = Vec::new();
+    mp4::write_box(&mut out, b"moov", &body);
+    Ok(out)
+}
+
+fn build_ftyp() -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(b"isom");
+    payload.extend_from_slice(&0x200u32.to_be_bytes());
+    for brand in [b"isom", b"iso2", b"avc1", b"mp41"] {
+        payload.extend_from_slice(brand);
+    }
+    let mut out = Vec::new();
+    mp4::write_box(&mut out, b"ftyp", &payload);
+    out
+}
+
+fn write_muxed(output: &Path, tracks: &[Track]) -> Result<(), String> {
+    let mut chunks: Vec<Chunk> = tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(index, track)| plan_chunks(index, track))
+        .collect();
+    // 按时间交织音视频块，播放时无需在文件中来回跳转
+    chunks.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+
+    let chunk_sizes: Vec<u64> = chunks
+        .iter()
+        .map(|chunk| {
+            tracks[chunk.track].samples[chunk.first_sample..chunk.first_sample + chunk.count]
+                .iter()
+                .map(|s| s.size as u64)
+                .sum()
+        })
+        .collect();
+    let mdat_payload: u64 = chunk_sizes.iter().sum();
+    let mdat_header = if mdat_payload + 8 > u32::MAX as u64 {
+        16
+    } else {
+        8
+    };
+
+    let ftyp = build_ftyp();
+    let zero_offsets = vec![0u64; chunks.len()];
+    let estimate = build_moov(tracks, &chunks, &zero_offsets, false)?;
+    let co64 =
+        ftyp.len() as u64 + estimate.len() as u64 + mdat_header + mdat_payload > u32::MAX as u64;
+    let moov_len = if co64 {
+        build_moov(tracks, &chunks, &zero_offsets, true)?.len()
+    } else {
+        estimate.len()
+    };
+
+    let mut offsets = Vec::with_capacity(chunks.len());
+    let mut position = (ftyp.len() + moov_len) as u64 + mdat_header;
+    for size in &chunk_sizes {
+        offsets.push(position);
+        position += size;
+    }
+    let moov = build_moov(tracks, &chunks, &offsets, co64)?;
+    if moov.len() != moov_len {
+        return Err("生成 moov 时长度不一致".to_string());
+    }
+
+    let file = File::create(output).map_err(|e| format!("创建文件失败: {}", e))?;
+    let mut out = BufWriter::new(file);
+    let write_err = |e: io::Error| format!("写入文件失败: {}", e);
+    out.write_all(&ftyp).map_err(write_err)?;
+    out.write_all(&moov).map_err(write_err)?;
+    if mdat_header == 16 {
+        out.write_all(&1u32.to_be_bytes()).map_err(write_err)?;
+        out.write_all(b"mdat").map_err(write_err)?;
+        out.write_all(&(mdat_payload + 16).to_be_bytes())
+            .map_err(write_err)?;
+    } else {
+        out.write_all(&((mdat_payload + 8) as u32).to_be_bytes())
+            .map_err(write_err)?;
+        out.write_all(b"mdat").map_err(write_err)?;
+    }
+
+    let mut sources = tracks
+        .iter()
+        .map(|track| File::open(&track.source).map_err(|e| format!("打开文件失败: {}", e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for chunk in &chunks {
+        let source = &mut sources[chunk.track];
+        let samples =
+            &tracks[chunk.track].samples[chunk.first_sample..chunk.first_sample + chunk.count];
+        // 相邻样本在源文件中通常是连续的，合并后一次复制
+        let mut index = 0;
+        while index < samples.len() {
+            let start = samples[index].offset;
+            let mut end = start + samples[index].size as u64;
+            index += 1;
+            while index < samples.len() && samples[index].offset == end {
+                end += samples[index].size as u64;
+                index += 1;
+            }
+            source
+                .seek(SeekFrom::Start(start))
+                .map_err(|e| format!("读取文件失败: {}", e))?;
+            let copied = io::copy(&mut io::Read::take(&mut *source, end - start), &mut out)
+                .map_err(write_err)?;
+            if copied != end - start {
+                return Err("复制样本数据时数据不足".to_string());
+            }
+        }
+    }
+
+    let file = out
+        .into_inner()
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+    file.sync_all().map_err(write_err)?;
+    Ok(())
+}
+
+fn check_durations(video: &Track, audio: &Track) -> Result<(), String> {
+    let drift = (video.duration_secs() - audio.duration_secs()).abs();
+    if drift > MAX_DURATION_DRIFT_SECS {
+        return Err(format!(
+            "音视频时长不一致: 视频 {:.2} 秒，音频 {:.2} 秒",
+            video.duration_secs(),
+            audio.duration_secs()
+        ));
+    }
+    Ok(())
+}
+
+/// 把一路视频与一路音频（分片 .m4s 或普通 MP4）无转码合并为一个 MP4，返回 (视频时长, 音频时长)
+pub fn mux_audio_video(video: &Path, audio: &Path, output: &Path) -> Result<(f64, f64), String> {
+    let video_track = load_track(video, b"vide")?;
+    let audio_track = load_track(audio, b"soun")?;
+    check_durations(&video_track, &audio_track)?;
+
+    let tracks = [video_track, audio_track];
+    if let Err(err) = write_muxed(output, &tracks) {
+        let _ = fs::remove_file(output);
+        return Err(err);
+    }
+
+    // 重新解析输出文件，确认样本数与时长和输入一致
+    let verified = download_integrity::verify_media_file(output, Some(MediaCategory::Video))
+        .and_then(|_| Ok((load_track(output, b"vide")?, load_track(output, b"soun")?)))
+        .and_then(|(video_out, audio_out)| {
+            for (input, merged) in tracks.iter().zip([&video_out, &audio_out]) {
+                if input.samples.len() != merged.samples.len()
+                    || input.duration() != merged.duration()
+                {
+                    return Err("合并后的文件与输入轨道不一致".to_string());
+                }
+            }
+            check_durations(&video_out, &audio_out)?;
+            Ok((video_out.duration_secs(), audio_out.duration_secs()))
+        });
+
+    if verified.is_err() {
+        let _ = fs::remove_file(output);
+    }
+    verified
+}
+
+async fn mux_and_publish<R: Runtime>(
+    app: &AppHandle<R>,
+    video: PathBuf,
+    audio: PathBuf,
+    filename: &str,
+    metadata: Option<MediaMetadata>,
+) -> Result<MuxRecord, String> {
+    let staged = media_store::staging_path(app, filename)?;
+    let output = staged.clone();
+    let (video_duration, audio_duration) =
+        tauri::async_runtime::spawn_blocking(move || mux_audio_video(&video, &audio, &output))
+            .await
+            .map_err(|e| format!("合并任务异常: {}", e))??;
+
+    let metadata_error =
+        metadata.and_then(|meta| media_metadata::embed_metadata(&staged, &meta).err());
+    let size = fs::metadata(&staged)
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let sha256 = download_integrity::sha256_file(&staged)?;
+    let path = media_store::publish(app, &staged, filename, "video/mp4").await?;
+    media_store::remember(app, &path, size, &sha256);
+
+    Ok(MuxRecord {
+        path,
+        size,
+        sha256,
+        video_duration,
+        audio_duration,
+        metadata_error,
+    })
+}
+
+/// 合并本地已有的视频与音频文件（分片 .m4s 或普通 MP4），不重新编码
+#[tauri::command]
+pub async fn merge_audio_video<R: Runtime>(
+    app: AppHandle<R>,
+    video_path: String,
+    audio_path: String,
+    filename: String,
+    metadata: Option<MediaMetadata>,
+) -> Result<MuxRecord, String> {
+    mux_and_publish(
+        &app,
+        PathBuf::from(video_path),
+        PathBuf::from(audio_path),
+        &filename,
+        metadata,
+    )
+    .await
+}
+
+/// 下载 DASH 平台分离的视频流与音频流，合并为一个可直接播放的 MP4
+#[tauri::command]
+pub async fn download_dash_video<R: Runtime>(
+    app: AppHandle<R>,
+    video_url: String,
+    audio_url: String,
+    filename: String,
+    metadata: Option<MediaMetadata>,
+) -> Result<MuxRecord, String> {
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("无法获取缓存目录: {}", e))?;
+    let dash_dir = cache_dir.join(DASH_DIRNAME);
+    fs::create_dir_all(&dash_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+
+    // 临时文件按任务取名，同名作品同时下载时互不覆盖
+    let task = uuid::Uuid::new_v4();
+    let video_path = dash_dir.join(format!("{}.video.m4s", task));
+    let audio_path = dash_dir.join(format!("{}.audio.m4s", task));
+
+    let fetch = |url: String, dest: PathBuf| {
+        let cache_dir = cache_dir.clone();
+        async move {
+            let response = crate::request_media(&url).await?;
+            download_integrity::save_response_verified(response, &dest, Some(&cache_dir)).await
+        }
+    };
+
+    let downloaded = tokio::try_join!(
+        fetch(video_url, video_path.clone()),
+        fetch(audio_url, audio_path.clone())
+    );
+    let result = match downloaded {
+        Ok(_) => {
+            mux_and_publish(
+                &app,
+                video_path.clone(),
+                audio_path.clone(),
+                &filename,
+                metadata,
+            )
+            .await
+        }
+        Err(err) => Err(err),
+    };
+
+    let _ = fs::remove_file(&video_path);
+    let _ = fs::remove_file(&audio_path);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp4::test_boxes::{boxed, full, u32s};
+
+    const SAMPLES: usize = 10;
+    const SAMPLE_DURATION: u32 = 100;
+    const TIMESCALE: u32 = 1000;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mp4-mux-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// trak 骨架，`stbl` 为样本表内容（不含 stsd）
+    fn trak(track_id: u32, handler: &[u8; 4], stbl: &[u8]) -> Vec<u8> {
+        let mut tkhd = vec![0u8; 8];
+        tkhd.extend_from_slice(&track_id.to_be_bytes());
+        tkhd.extend_from_slice(&[0u8; 68]);
+
+        let mut mdhd = vec![0u8; 8];
+        mdhd.extend(u32s(&[TIMESCALE, 0]));
+        mdhd.extend_from_slice(&[0u8; 4]);
+
+        let mut hdlr = vec![0u8; 4];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0u8; 13]);
+
+        let mut stsd_body = u32s(&[1]);
+        stsd_body.extend(boxed(b"mp4v", &[0u8; 8]));
+        let mut stbl_body = full(b"stsd", 0, &stsd_body);
+        stbl_body.extend_from_slice(stbl);
+
+        let minf = boxed(b"stbl", &stbl_body);
+        let mut mdia = full(b"mdhd", 0, &mdhd);
+        mdia.extend(full(b"hdlr", 0, &hdlr));
+        mdia.extend(boxed(b"minf", &minf));
+
+        let mut body = full(b"tkhd", 3, &tkhd);
+        body.extend(boxed(b"mdia", &mdia));
+        boxed(b"trak", &body)
+    }
+
+    fn video_sample(index: usize) -> Vec<u8> {
+        vec![index as u8 + 1; 10 + index]
+    }
+
+    fn audio_sample(index: usize) -> Vec<u8> {
+        vec![0xA0 + index as u8; 5]
+    }
+
+    /// 分片视频：初始化段 + 一个 moof/mdat，首帧为关键帧
+    fn fragmented_video() -> Vec<u8> {
+        let empty_tables = [
+            full(b"stts", 0, &u32s(&[0])),
+            full(b"stsc", 0, &u32s(&[0])),
+            full(b"stsz", 0, &u32s(&[0, 0])),
+            full(b"stco", 0, &u32s(&[0])),
+        ]
+        .concat();
+        let mut moov_body = trak(1, b"vide", &empty_tables);
+        let trex = full(b"trex", 0, &u32s(&[1, 1, 0, 0, 0]));
+        moov_body.extend(boxed(b"mvex", &trex));
+
+        let mut data = boxed(b"ftyp", b"iso6\0\0\0\0iso6dash");
+        data.extend(boxed(b"moov", &moov_body));
+
+        let build_moof = |data_offset: u32| {
+            let flags =
+                TRUN_DATA_OFFSET | TRUN_SAMPLE_DURATION | TRUN_SAMPLE_SIZE | TRUN_SAMPLE_FLAGS;
+            let mut trun = u32s(&[SAMPLES as u32, data_offset]);
+            for i in 0..SAMPLES {
+                let sample_flags = if i == 0 { 0 } else { SAMPLE_IS_NON_SYNC };
+                trun.extend(u32s(&[
+                    SAMPLE_DURATION,
+                    video_sample(i).len() as u32,
+                    sample_flags,
+                ]));
+            }
+            let mut traf = full(b"tfhd", 0, &u32s(&[1]));
+            traf.extend(full(b"trun", flags, &trun));
+            let mut moof = full(b"mfhd", 0, &u32s(&[1]));
+            moof.extend(boxed(b"traf", &traf));
+            boxed(b"moof", &moof)
+        };
+        let moof_len = build_moof(0).len();
+        data.extend(build_moof(moof_len as u32 + 8));
+
+        let payload: Vec<u8> = (0..SAMPLES).flat_map(video_sample).collect();
+        data.extend(boxed(b"mdat", &payload));
+        data
+    }
+
+    /// 普通 MP4 音频：所有样本放在一个块中
+    fn progressive_audio(samples: usize) -> Vec<u8> {
+        let build = |chunk_offset: u32| {
+            let mut sizes = vec![0, samples as u32];
+            sizes.extend((0..samples).map(|i| audio_sample(i).len() as u32));
+            let tables = [
+                full(b"stts", 0, &u32s(&[1, samples as u32, SAMPLE_DURATION])),
+                full(b"stsc", 0, &u32s(&[1, 1, samples as u32, 1])),
+                full(b"stsz", 0, &u32s(&sizes)),
+                full(b"stco", 0, &u32s(&[1, chunk_offset])),
+            ]
+            .concat();
+            boxed(b"moov", &trak(1, b"soun", &tables))
+        };
+
+        let ftyp = boxed(b"ftyp", b"M4A \0\0\0\0M4A isom");
+        let moov_len = build(0).len();
+        let mut data = ftyp.clone();
+        data.extend(build((ftyp.len() + moov_len + 8) as u32));
+        let payload: Vec<u8> = (0..samples).flat_map(audio_sample).collect();
+        data.extend(boxed(b"mdat", &payload));
+        data
+    }
+
+    fn sample_bytes(path: &Path, track: &Track) -> Vec<Vec<u8>> {
+        let data = fs::read(path).unwrap();
+        track
+            .samples
+            .iter()
+            .map(|s| data[s.offset as usize..s.offset as usize + s.size as usize].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn loads_fragmented_and_progressive_tracks() {
+        let dir = temp_dir();
+        let video = dir.join("v.m4s");
+        let audio = dir.join("a.m4a");
+        fs::write(&video, fragmented_video()).unwrap();
+        fs::write(&audio, progressive_audio(SAMPLES)).unwrap();
+
+        let video_track = load_track(&video, b"vide").unwrap();
+        assert_eq!(video_track.samples.len(), SAMPLES);
+        assert_eq!(video_track.duration_secs(), 1.0);
+        assert!(video_track.samples[0].is_sync);
+        assert!(!video_track.samples[1].is_sync);
+        let expected: Vec<_> = (0..SAMPLES).map(video_sample).collect();
+        assert_eq!(sample_bytes(&video, &video_track), expected);
+
+        let audio_track = load_track(&audio, b"soun").unwrap();
+        let expected: Vec<_> = (0..SAMPLES).map(audio_sample).collect();
+        assert_eq!(sample_bytes(&audio, &audio_track), expected);
+
+        assert!(load_track(&audio, b"vide").is_err());
+        assert_eq!(fragmented_duration_secs(&video, b"vide"), Some(1.0));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn muxes_without_reencoding() {
+        let dir = temp_dir();
+        let video = dir.join("v.m4s");
+        let audio = dir.join("a.m4a");
+        let output = dir.join("out.mp4");
+        fs::write(&video, fragmented_video()).unwrap();
+        fs::write(&audio, progressive_audio(SAMPLES)).unwrap();
+
+        let (video_secs, audio_secs) = mux_audio_video(&video, &audio, &output).unwrap();
+        assert_eq!((video_secs, audio_secs), (1.0, 1.0));
+
+        let video_out = load_track(&output, b"vide").unwrap();
+        let audio_out = load_track(&output, b"soun").unwrap();
+        let expected: Vec<_> = (0..SAMPLES).map(video_sample).collect();
+        assert_eq!(sample_bytes(&output, &video_out), expected);
+        let expected: Vec<_> = (0..SAMPLES).map(audio_sample).collect();
+        assert_eq!(sample_bytes(&output, &audio_out), expected);
+
+        // 关键帧信息写入 stss，轨道号重新编排
+        assert!(video_out.samples[0].is_sync);
+        assert!(video_out.samples[1..].iter().all(|s| !s.is_sync));
+        assert_eq!(track_id(&video_out.trak), Some(1));
+        assert_eq!(track_id(&audio_out.trak), Some(2));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_mismatched_durations_and_truncated_input() {
+        let dir = temp_dir();
+        let video = dir.join("v.m4s");
+        let audio = dir.join("a.m4a");
+        let output = dir.join("out.mp4");
+        fs::write(&video, fragmented_video()).unwrap();
+        fs::write(&audio, progressive_audio(SAMPLES * 3)).unwrap();
+
+        let err = mux_audio_video(&video, &audio, &output).unwrap_err();
+        assert!(err.contains("时长不一致"), "{}", err);
+        assert!(!output.exists());
+
+        let mut truncated = fragmented_video();
+        truncated.truncate(truncated.len() - 4);
+        fs::write(&video, truncated).unwrap();
+        let err = load_track(&video, b"vide").map(|_| ()).unwrap_err();
+        assert!(
+            err.contains("mdat") || err.contains("超出文件范围"),
+            "{}",
+            err
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_bare_segments_and_impossible_sample_counts() {
+        let dir = temp_dir();
+        let segment = dir.join("seg.m4s");
+        let mut data = boxed(b"styp", b"msdh\0\0\0\0msdhmsix");
+        data.extend(boxed(b"moof", &full(b"mfhd", 0, &u32s(&[1]))));
+        data.extend(boxed(b"mdat", &[0; 16]));
+        fs::write(&segment, data).unwrap();
+        let err = load_track(&segment, b"vide").map(|_| ()).unwrap_err();
+        assert!(err.contains("初始化段"), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+
+        // stsz 声明的样本数超出文件或表的长度
+        let uniform = full(b"stsz", 0, &u32s(&[4, u32::MAX]));
+        assert!(progressive_samples(&uniform, 1024).is_err());
+        let listed = full(b"stsz", 0, &u32s(&[0, 1000, 4, 4]));
+        assert!(progressive_samples(&listed, 1024).is_err());
+
+        // trun 声明的样本数超出 trun 或文件的长度
+        let trex = TrexDefaults {
+            duration: 1,
+            size: 1,
+            flags: 0,
+        };
+        for (flags, count) in [(0, u32::MAX), (TRUN_SAMPLE_SIZE, 1000)] {
+            let mut traf = full(b"tfhd", 0, &u32s(&[1]));
+            traf.extend(full(b"trun", flags, &u32s(&[count, 4])));
+            let moof = boxed(b"traf", &traf);
+            let mut samples = Vec::new();
+            let err = fragment_samples(&moof, 0, 1, trex, 1024, &mut samples).unwrap_err();
+            assert!(err.contains("样本数无效"), "{}", err);
+            assert!(samples.is_empty());
+        }
+    }
+
+    #[test]
+    fn groups_samples_into_half_second_chunks() {
+        let track = Track {
+            source: PathBuf::new(),
+            trak: Vec::new(),
+            timescale: TIMESCALE,
+            samples: vec![
+                Sample {
+                    offset: 0,
+                    size: 1,
+                    duration: SAMPLE_DURATION,
+                    cts_offset: 0,
+                    is_sync: true,
+                };
+                SAMPLES
+            ],
+        };
+        let chunks = plan_chunks(0, &track);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].count, chunks[1].count), (5, 5));
+        assert_eq!(chunks[1].start_secs, 0.5);
+
+        assert_eq!(
+            run_length([1, 1, 2, 2, 2, 1].into_iter()),
+            vec![(2, 1), (3, 2), (1, 1)]
+        );
+    }
+}
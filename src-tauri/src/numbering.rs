@@ -0,0 +1,459 @@
+//! 单据编号：按单据类型配置编号规则，如 `XS{yyyyMMdd}-{seq:4}`，流水号在数据库中原子分配。
+//!
+//! 规则中 `{...}` 内为占位符：
+//! - 日期：`yyyy`、`yy`、`MM`、`dd` 可任意组合，也可夹带 `-`、`_`、`.` 等分隔符，如 `{yyyy-MM}`；
+//! - 流水号：`{seq}` 或 `{seq:N}`（补零到 N 位），每条规则必须且只能有一个。
+//!
+//! 流水号按日、月、年重置或永不重置，计数保存在 `number_sequences` 表，以（单据类型、周期）为键。
+//! 分配时跳过已被占用的编号（如从备份恢复或其他设备录入的单据），手工输入的编号在保存时查重。
+
+use crate::business_db::{self, db_err, with_db, with_db_write};
+use crate::business_models::InvoiceType;
+use chrono::{Datelike, Local, NaiveDate};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Runtime};
+
+const RULES_KEY: &str = "numbering_rules";
+const MAX_SEQ_WIDTH: usize = 12;
+// 连续遇到这么多已占用的编号时放弃，通常说明规则与现有单号冲突
+const MAX_ATTEMPTS: usize = 1000;
+
+/// 单据类型：销售单、采购单、退货单、报价单、送货单
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Sale,
+    Purchase,
+    Return,
+    Quotation,
+    Delivery,
+}
+
+impl DocumentKind {
+    const ALL: [DocumentKind; 5] = [
+        DocumentKind::Sale,
+        DocumentKind::Purchase,
+        DocumentKind::Return,
+        DocumentKind::Quotation,
+        DocumentKind::Delivery,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DocumentKind::Sale => "sale",
+            DocumentKind::Purchase => "purchase",
+            DocumentKind::Return => "return",
+            DocumentKind::Quotation => "quotation",
+            DocumentKind::Delivery => "delivery",
+        }
+    }
+
+    pub fn of_invoice(invoice_type: InvoiceType) -> DocumentKind {
+        match invoice_type {
+            InvoiceType::Sale => DocumentKind::Sale,
+            InvoiceType::Purchase => DocumentKind::Purchase,
+            InvoiceType::Return => DocumentKind::Return,
+        }
+    }
+
+    fn default_rule(self) -> NumberingRule {
+        let (prefix, name) = match self {
+            DocumentKind::Sale => ("XS", "销售单"),
+            DocumentKind::Purchase => ("CG", "采购单"),
+            DocumentKind::Return => ("TH", "退货单"),
+            DocumentKind::Quotation => ("BJ", "报价单"),
+            DocumentKind::Delivery => ("SH", "送货单"),
+        };
+        NumberingRule {
+            kind: self,
+            name: name.to_string(),
+            pattern: format!("{}{{yyyyMMdd}}-{{seq:4}}", prefix),
+            reset: ResetPeriod::Day,
+        }
+    }
+}
+
+/// 流水号重置周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetPeriod {
+    Never,
+    #[default]
+    Day,
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberingRule {
+    pub kind: DocumentKind,
+    #[serde(default)]
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub reset: ResetPeriod,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    /// chrono 格式串
+    Date(String),
+    Seq(usize),
+}
+
+/// 解析编号规则，同时检查重置周期与日期占位符是否匹配（按日重置的规则必须包含年月日，否则会重号）
+fn parse_pattern(pattern: &str, reset: ResetPeriod) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let (mut year, mut month, mut day, mut seqs) = (false, false, false, 0);
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('{') else {
+            if rest.contains('}') {
+                return Err("编号规则中的括号不匹配".to_string());
+            }
+            tokens.push(Token::Literal(rest.to_string()));
+            break;
+        };
+        if start > 0 {
+            if rest[..start].contains('}') {
+                return Err("编号规则中的括号不匹配".to_string());
+            }
+            tokens.push(Token::Literal(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| "编号规则中的括号不匹配".to_string())?;
+        let placeholder = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(width) = placeholder.strip_prefix("seq") {
+            let width = match width.strip_prefix(':') {
+                Some(width) => width
+                    .parse()
+                    .ok()
+                    .filter(|width| (1..=MAX_SEQ_WIDTH).contains(width))
+                    .ok_or_else(|| format!("流水号位数应为 1～{}", MAX_SEQ_WIDTH))?,
+                None if width.is_empty() => 0,
+                None => return Err(format!("编号规则中的占位符无效：{{{}}}", placeholder)),
+            };
+            seqs += 1;
+            tokens.push(Token::Seq(width));
+            continue;
+        }
+
+        let mut format = String::new();
+        let mut chars = placeholder;
+        while !chars.is_empty() {
+            let (part, len) = if chars.starts_with("yyyy") {
+                year = true;
+                ("%Y", 4)
+            } else if chars.starts_with("yy") {
+                year = true;
+                ("%y", 2)
+            } else if chars.starts_with("MM") {
+                month = true;
+                ("%m", 2)
+            } else if chars.starts_with("dd") {
+                day = true;
+                ("%d", 2)
+            } else {
+                let c = chars.chars().next().unwrap_or_default();
+                if c.is_alphanumeric() || c == '%' {
+                    return Err(format!("编号规则中的占位符无效：{{{}}}", placeholder));
+                }
+                format.push(c);
+                chars = &chars[c.len_utf8()..];
+                continue;
+            };
+            format.push_str(part);
+            chars = &chars[len..];
+        }
+        if format.is_empty() {
+            return Err("编号规则中有空的占位符".to_string());
+        }
+        tokens.push(Token::Date(format));
+    }
+
+    if seqs != 1 {
+        return Err("编号规则必须包含且只能包含一个 {seq} 流水号".to_string());
+    }
+    let covered = match reset {
+        ResetPeriod::Never => true,
+        ResetPeriod::Year => year,
+        ResetPeriod::Month => year && month,
+        ResetPeriod::Day => year && month && day,
+    };
+    if !covered {
+        return Err("编号规则缺少与重置周期对应的日期（如按日重置需包含年、月、日）".to_string());
+    }
+    Ok(tokens)
+}
+
+fn render(tokens: &[Token], date: NaiveDate, seq: u64) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Literal(text) => text.clone(),
+            Token::Date(format) => date.format(format).to_string(),
+            Token::Seq(width) => format!("{:0width$}", seq, width = *width),
+        })
+        .collect()
+}
+
+fn period_key(reset: ResetPeriod, date: NaiveDate) -> String {
+    match reset {
+        ResetPeriod::Never => String::new(),
+        ResetPeriod::Day => date.format("%Y%m%d").to_string(),
+        ResetPeriod::Month => date.format("%Y%m").to_string(),
+        ResetPeriod::Year => date.year().to_string(),
+    }
+}
+
+pub(crate) fn parse_date(date: Option<&str>) -> Result<NaiveDate, String> {
+    match date.map(str::trim).filter(|date| !date.is_empty()) {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("日期格式应为 YYYY-MM-DD：{}", date)),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+fn load_rules(conn: &Connection) -> Result<HashMap<DocumentKind, NumberingRule>, String> {
+    let saved: Vec<NumberingRule> = business_db::preference(conn, RULES_KEY)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let mut rules: HashMap<_, _> = DocumentKind::ALL
+        .into_iter()
+        .map(|kind| (kind, kind.default_rule()))
+        .collect();
+    rules.extend(saved.into_iter().map(|rule| (rule.kind, rule)));
+    Ok(rules)
+}
+
+fn rule_for(conn: &Connection, kind: DocumentKind) -> Result<NumberingRule, String> {
+    load_rules(conn)?
+        .remove(&kind)
+        .ok_or_else(|| "未找到编号规则".to_string())
+}
+
+/// 编号是否已被同类单据（除 `exclude_id` 外）占用；送货单不单独保存，不查重。
+/// `exclude_id` 对应的单据已经使用该编号时（未改单号）视为未占用，旧数据中已重复的编号不影响后续保存
+pub(crate) fn is_taken(
+    conn: &Connection,
+    kind: DocumentKind,
+    number: &str,
+    exclude_id: Option<&str>,
+) -> Result<bool, String> {
+    let (table, column) = match kind {
+        DocumentKind::Sale | DocumentKind::Purchase | DocumentKind::Return => {
+            ("invoices", "invoice_number")
+        }
+        DocumentKind::Quotation => ("quotations", "quotation_number"),
+        DocumentKind::Delivery => return Ok(false),
+    };
+    let sql = format!(
+        "SELECT EXISTS(SELECT 1 FROM {table} WHERE {column} = ?1 AND id != ?2) \
+         AND NOT EXISTS(SELECT 1 FROM {table} WHERE {column} = ?1 AND id = ?2)"
+    );
+    conn.query_row(&sql, [number, exclude_id.unwrap_or_default()], |row| {
+        row.get(0)
+    })
+    .map_err(db_err)
+}
+
+/// 分配下一个编号：流水号在单条语句中自增，遇到已占用的编号继续往后分配
+fn allocate(conn: &Connection, kind: DocumentKind, date: NaiveDate) -> Result<String, String> {
+    let rule = rule_for(conn, kind)?;
+    let tokens = parse_pattern(&rule.pattern, rule.reset)?;
+    let period = period_key(rule.reset, date);
+    for _ in 0..MAX_ATTEMPTS {
+        let seq: i64 = conn
+            .query_row(
+                "INSERT INTO number_sequences (kind, period, value) VALUES (?, ?, 1) \
+                 ON CONFLICT(kind, period) DO UPDATE SET value = value + 1 \
+                 RETURNING value",
+                [kind.as_str(), period.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(db_err)?;
+        let number = render(&tokens, date, seq as u64);
+        if !is_taken(conn, kind, &number, None)? {
+            return Ok(number);
+        }
+    }
+    Err("无法生成不重复的单号，请检查编号规则".to_string())
+}
+
+/// 读取各类单据的编号规则（未设置的使用默认规则）
+#[tauri::command]
+pub async fn get_numbering_rules<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<NumberingRule>, String> {
+    let mut rules = with_db(&app, |conn| load_rules(conn))?;
+    Ok(DocumentKind::ALL
+        .iter()
+        .filter_map(|kind| rules.remove(kind))
+        .collect())
+}
+
+/// 保存一类单据的编号规则；修改规则不会影响已分配的流水号
+#[tauri::command]
+pub async fn save_numbering_rule<R: Runtime>(
+    app: AppHandle<R>,
+    mut rule: NumberingRule,
+) -> Result<NumberingRule, String> {
+    rule.pattern = rule.pattern.trim().to_string();
+    parse_pattern(&rule.pattern, rule.reset)?;
+    with_db_write(&app, |conn| {
+        let mut rules = load_rules(conn)?;
+        if rule.name.trim().is_empty() {
+            rule.name = rules
+                .get(&rule.kind)
+                .map(|old| old.name.clone())
+                .unwrap_or_default();
+        }
+        rules.insert(rule.kind, rule.clone());
+        let rules: Vec<_> = DocumentKind::ALL
+            .iter()
+            .filter_map(|kind| rules.remove(kind))
+            .collect();
+        let json = serde_json::to_string(&rules).map_err(|e| format!("序列化失败: {}", e))?;
+        business_db::set_preference(conn, RULES_KEY, &json)
+    })?;
+    Ok(rule)
+}
+
+/// 分配下一个单号；`date` 为单据日期（YYYY-MM-DD），省略时为今天
+#[tauri::command]
+pub async fn next_document_number<R: Runtime>(
+    app: AppHandle<R>,
+    kind: DocumentKind,
+    date: Option<String>,
+) -> Result<String, String> {
+    let date = parse_date(date.as_deref())?;
+    with_db_write(&app, |conn| {
+        let tx = conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(db_err)?;
+        let number = allocate(&tx, kind, date)?;
+        tx.commit().map_err(db_err)?;
+        Ok(number)
+    })
+}
+
+/// 预览下一个单号，不占用流水号（仅供显示，实际单号以保存时分配的为准）
+#[tauri::command]
+pub async fn preview_document_number<R: Runtime>(
+    app: AppHandle<R>,
+    kind: DocumentKind,
+    date: Option<String>,
+) -> Result<String, String> {
+    let date = parse_date(date.as_deref())?;
+    with_db(&app, |conn| {
+        let rule = rule_for(conn, kind)?;
+        let tokens = parse_pattern(&rule.pattern, rule.reset)?;
+        let current: Option<i64> = conn
+            .query_row(
+                "SELECT value FROM number_sequences WHERE kind = ? AND period = ?",
+                [kind.as_str(), period_key(rule.reset, date).as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        Ok(render(&tokens, date, current.unwrap_or(0) as u64 + 1))
+    })
+}
+
+/// 检查编号是否已被其他单据使用；`exclude_id` 为正在编辑的单据
+#[tauri::command]
+pub async fn check_document_number<R: Runtime>(
+    app: AppHandle<R>,
+    kind: DocumentKind,
+    number: String,
+    exclude_id: Option<String>,
+) -> Result<bool, String> {
+    with_db(&app, |conn| {
+        is_taken(conn, kind, number.trim(), exclude_id.as_deref())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 9).unwrap()
+    }
+
+    #[test]
+    fn renders_date_and_padded_sequence() {
+        let tokens = parse_pattern("XS{yyyyMMdd}-{seq:4}", ResetPeriod::Day).unwrap();
+        assert_eq!(render(&tokens, date(), 7), "XS20260309-0007");
+        assert_eq!(render(&tokens, date(), 12345), "XS20260309-12345");
+
+        let tokens = parse_pattern("{yy-MM}/{seq}", ResetPeriod::Month).unwrap();
+        assert_eq!(render(&tokens, date(), 3), "26-03/3");
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(parse_pattern("XS{yyyyMMdd}", ResetPeriod::Day).is_err());
+        assert!(parse_pattern("XS{seq}{seq}", ResetPeriod::Never).is_err());
+        assert!(parse_pattern("XS{yyyyMM}-{seq:4}", ResetPeriod::Day).is_err());
+        assert!(parse_pattern("XS{yyyyMMdd-{seq:4}", ResetPeriod::Day).is_err());
+        assert!(parse_pattern("XS{HHmm}-{seq:4}", ResetPeriod::Never).is_err());
+        assert!(parse_pattern("XS{seq:0}", ResetPeriod::Never).is_err());
+        assert!(parse_pattern("XS-{seq:6}", ResetPeriod::Never).is_ok());
+    }
+
+    #[test]
+    fn allocates_sequences_per_period_and_skips_taken_numbers() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE preferences (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             CREATE TABLE invoices (id TEXT PRIMARY KEY, invoice_number TEXT NOT NULL);
+             CREATE TABLE quotations (id TEXT PRIMARY KEY, quotation_number TEXT NOT NULL);
+             CREATE TABLE number_sequences (
+                 kind TEXT NOT NULL, period TEXT NOT NULL, value INTEGER NOT NULL,
+                 PRIMARY KEY (kind, period)
+             );
+             INSERT INTO invoices VALUES ('restored', 'XS20260309-0002');",
+        )
+        .unwrap();
+
+        let next = |kind, date| allocate(&conn, kind, date).unwrap();
+        assert_eq!(next(DocumentKind::Sale, date()), "XS20260309-0001");
+        assert_eq!(next(DocumentKind::Sale, date()), "XS20260309-0003");
+        assert_eq!(next(DocumentKind::Return, date()), "TH20260309-0001");
+        let tomorrow = date().succ_opt().unwrap();
+        assert_eq!(next(DocumentKind::Sale, tomorrow), "XS20260310-0001");
+        assert!(is_taken(&conn, DocumentKind::Sale, "XS20260309-0002", None).unwrap());
+        assert!(!is_taken(
+            &conn,
+            DocumentKind::Sale,
+            "XS20260309-0002",
+            Some("restored")
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn duplicate_numbers_only_block_a_change_of_number() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE invoices (id TEXT PRIMARY KEY, invoice_number TEXT NOT NULL);
+             INSERT INTO invoices VALUES ('a', 'XS-1'), ('b', 'XS-1'), ('c', 'XS-2');",
+        )
+        .unwrap();
+        let taken = |number, id| is_taken(&conn, DocumentKind::Sale, number, Some(id)).unwrap();
+        assert!(!taken("XS-1", "a"));
+        assert!(!taken("XS-1", "b"));
+        assert!(taken("XS-1", "c"));
+        assert!(taken("XS-2", "a"));
+        assert!(!taken("XS-3", "a"));
+    }
+}
@@ -0,0 +1,1012 @@
+//! 应收账款：收款记录、收款分配与客户余额。
+//!
+//! 一笔收款可以分配到同一客户的一张或多张销售单，未分配的部分作为预收款，以后再分配。
+//! 销售单的 `paidAmount` 由后端按收款分配合计得出，保存销售单时忽略前端传入的值；
+//! 开单时收到的款项也记为一笔分配到该销售单的收款（未选客户的散客销售单，收款的客户为空）。
+//! 保存或删除收款后重算涉及的销售单并返回，前端据此更新本地副本。
+//!
+//! 客户余额 = 期初欠款 + 销售合计 − 销售单已付 − 退货冲减 − 预收款，为负表示客户有余款。
+//! 退货冲减为退货单合计减去已退现金（退货单的 `paidAmount`）；作废的单据不计入，采购单与应收无关。
+
+use crate::business_db::{
+    self, db_err, non_empty, now_iso, query_rows, select_sql, upsert_sql, with_db, with_db_write,
+    Query,
+};
+use crate::business_models::{
+    Customer, Invoice, InvoiceStatus, InvoiceType, Payment, PaymentAllocation, SearchFilter,
+};
+use crate::money;
+use crate::numbering;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use tauri::{AppHandle, Runtime};
+
+// 旧版开单时填写的已收金额转成的收款记录，ID 为此前缀加销售单 ID
+const LEGACY_PAID_PREFIX: &str = "legacy-paid-";
+// 开单收款的默认收款方式
+const CHECKOUT_METHOD: &str = "开单收款";
+
+const PAYMENT_COLUMNS: &[&str] = &[
+    "id",
+    "customer_id",
+    "date",
+    "amount",
+    "method",
+    "note",
+    "created_at",
+    "updated_at",
+];
+
+fn payment_from_row(row: &Row) -> rusqlite::Result<Payment> {
+    Ok(Payment {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        date: row.get(2)?,
+        amount: money::column(row, 3)?,
+        method: row.get(4)?,
+        note: row.get(5)?,
+        allocations: Vec::new(),
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn load_allocations(conn: &Connection, payment: &mut Payment) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT a.invoice_id, a.amount, i.invoice_number FROM payment_allocations a \
+             LEFT JOIN invoices i ON i.id = a.invoice_id \
+             WHERE a.payment_id = ? ORDER BY a.position",
+        )
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([&payment.id], |row| {
+            Ok(PaymentAllocation {
+                invoice_id: row.get(0)?,
+                amount: money::column(row, 1)?,
+                invoice_number: row.get(2)?,
+            })
+        })
+        .map_err(db_err)?;
+    payment.allocations = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(db_err)?;
+    Ok(())
+}
+
+fn load_all_allocations(conn: &Connection, payments: &mut [Payment]) -> Result<(), String> {
+    for payment in payments {
+        load_allocations(conn, payment)?;
+    }
+    Ok(())
+}
+
+/// 按原样写入收款及其分配（已存在则整体替换），不调整销售单，调用方负责事务。
+/// 引用的销售单不存在时跳过该分配（从备份导入时销售单可能已被跳过）
+pub(crate) fn upsert_payment(conn: &Connection, payment: &Payment) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare_cached(&upsert_sql("payments", PAYMENT_COLUMNS))
+        .map_err(db_err)?;
+    stmt.execute(params![
+        payment.id,
+        payment.customer_id,
+        payment.date,
+        money::to_f64(payment.amount),
+        payment.method,
+        payment.note,
+        payment.created_at,
+        payment.updated_at,
+    ])
+    .map_err(db_err)?;
+
+    conn.execute(
+        "DELETE FROM payment_allocations WHERE payment_id = ?",
+        [&payment.id],
+    )
+    .map_err(db_err)?;
+    let mut insert = conn
+        .prepare_cached(
+            "INSERT INTO payment_allocations (payment_id, invoice_id, position, amount) \
+             SELECT ?1, ?2, ?3, ?4 WHERE EXISTS (SELECT 1 FROM invoices WHERE id = ?2)",
+        )
+        .map_err(db_err)?;
+    for (position, allocation) in payment.allocations.iter().enumerate() {
+        insert
+            .execute(params![
+                payment.id,
+                allocation.invoice_id,
+                position as i64,
+                money::to_f64(allocation.amount),
+            ])
+            .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn get_payment_by_id(conn: &Connection, id: &str) -> Result<Option<Payment>, String> {
+    let payment = conn
+        .query_row(
+            &format!("{} WHERE id = ?", select_sql("payments", PAYMENT_COLUMNS)),
+            [id],
+            payment_from_row,
+        )
+        .optional()
+        .map_err(db_err)?;
+    match payment {
+        Some(mut payment) => {
+            load_allocations(conn, &mut payment)?;
+            Ok(Some(payment))
+        }
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn all_payments(conn: &Connection) -> Result<Vec<Payment>, String> {
+    let sql = format!(
+        "{} ORDER BY date, created_at",
+        select_sql("payments", PAYMENT_COLUMNS)
+    );
+    let mut payments = query_rows(conn, &sql, Vec::new(), payment_from_row)?;
+    load_all_allocations(conn, &mut payments)?;
+    Ok(payments)
+}
+
+fn allocated_to(conn: &Connection, invoice_id: &str) -> Result<Decimal, String> {
+    let amounts = query_rows(
+        conn,
+        "SELECT amount FROM payment_allocations WHERE invoice_id = ?",
+        vec![SqlValue::Text(invoice_id.to_string())],
+        |row| money::column(row, 0),
+    )?;
+    Ok(amounts.into_iter().sum())
+}
+
+/// 按收款分配合计改写销售单的已付金额与付款状态；采购单与退货单不能分配收款，保留填写的金额
+pub(crate) fn settle(conn: &Connection, invoice: &mut Invoice) -> Result<(), String> {
+    if invoice.invoice_type != InvoiceType::Sale {
+        return Ok(());
+    }
+    invoice.paid_amount = allocated_to(conn, &invoice.id)?;
+    invoice.payment_status = money::payment_status(invoice.total_amount, invoice.paid_amount);
+    Ok(())
+}
+
+/// 重算库中一张销售单的已付金额与付款状态，返回重算后的销售单
+fn settle_stored(
+    conn: &Connection,
+    invoice_id: &str,
+    now: &str,
+) -> Result<Option<Invoice>, String> {
+    let Some(mut invoice) = business_db::get_invoice_by_id(conn, invoice_id)? else {
+        return Ok(None);
+    };
+    let before = (invoice.paid_amount, invoice.payment_status);
+    settle(conn, &mut invoice)?;
+    if (invoice.paid_amount, invoice.payment_status) != before {
+        invoice.updated_at = now.to_string();
+        conn.execute(
+            "UPDATE invoices SET paid_amount = ?, payment_status = ?, updated_at = ? WHERE id = ?",
+            params![
+                money::to_f64(invoice.paid_amount),
+                invoice.payment_status.as_str(),
+                now,
+                invoice_id
+            ],
+        )
+        .map_err(db_err)?;
+    }
+    Ok(Some(invoice))
+}
+
+/// 导入或恢复备份后统一结算：销售单已付金额高于收款分配的部分（旧版开单时填写的已收金额）
+/// 转为一笔「开单收款」收款记录，然后按收款分配重算全部销售单
+pub(crate) fn settle_imported(conn: &Connection) -> Result<(), String> {
+    let now = now_iso();
+    let invoices = query_rows(
+        conn,
+        "SELECT i.id, i.customer_id, i.date, i.paid_amount, \
+         COALESCE((SELECT SUM(a.amount) FROM payment_allocations a WHERE a.invoice_id = i.id), 0) \
+         FROM invoices i WHERE i.type = 'sale' AND i.status != 'cancelled'",
+        Vec::new(),
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                money::column(row, 3)?,
+                money::column(row, 4)?,
+            ))
+        },
+    )?;
+    for (invoice_id, customer_id, date, paid, allocated) in invoices {
+        let surplus = money::round_cents(paid - allocated);
+        if surplus <= Decimal::ZERO {
+            continue;
+        }
+        let id = format!("{}{}", LEGACY_PAID_PREFIX, invoice_id);
+        let mut payment = get_payment_by_id(conn, &id)?.unwrap_or_else(|| Payment {
+            id: id.clone(),
+            customer_id: customer_id.unwrap_or_default(),
+            date,
+            amount: Decimal::ZERO,
+            method: CHECKOUT_METHOD.to_string(),
+            note: None,
+            allocations: Vec::new(),
+            created_at: now.clone(),
+            updated_at: now.clone(),
+        });
+        payment.amount += surplus;
+        payment.updated_at = now.clone();
+        match payment
+            .allocations
+            .iter_mut()
+            .find(|allocation| allocation.invoice_id == invoice_id)
+        {
+            Some(allocation) => allocation.amount += surplus,
+            None => payment.allocations.push(PaymentAllocation {
+                invoice_id: invoice_id.clone(),
+                amount: surplus,
+                invoice_number: None,
+            }),
+        }
+        upsert_payment(conn, &payment)?;
+    }
+    let ids = query_rows(
+        conn,
+        "SELECT id FROM invoices WHERE type = 'sale'",
+        Vec::new(),
+        |row| row.get::<_, String>(0),
+    )?;
+    for id in ids {
+        settle_stored(conn, &id, &now)?;
+    }
+    Ok(())
+}
+
+/// 保存销售单前检查与收款分配是否冲突：已有分配的销售单不能改客户、改类型或作废
+pub(crate) fn check_invoice(conn: &Connection, invoice: &Invoice) -> Result<(), String> {
+    let allocated = allocated_to(conn, &invoice.id)?;
+    if allocated.is_zero() {
+        return Ok(());
+    }
+    let other_customer: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM payment_allocations a \
+             JOIN payments p ON p.id = a.payment_id \
+             WHERE a.invoice_id = ? AND p.customer_id IS NOT COALESCE(?, ''))",
+            params![invoice.id, invoice.customer_id],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+    if other_customer
+        || invoice.invoice_type != InvoiceType::Sale
+        || invoice.status == InvoiceStatus::Cancelled
+    {
+        return Err(
+            "该销售单已有收款记录，不能更改客户、单据类型或作废，请先调整收款分配".to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn check_allocatable(invoice: &Invoice, customer_id: &str) -> Result<(), String> {
+    if invoice.invoice_type != InvoiceType::Sale {
+        return Err(format!("只能分配到销售单：{}", invoice.invoice_number));
+    }
+    if invoice.status == InvoiceStatus::Cancelled {
+        return Err(format!("销售单已作废：{}", invoice.invoice_number));
+    }
+    if invoice.customer_id.as_deref().unwrap_or_default() != customer_id {
+        return Err(format!("销售单不属于该客户：{}", invoice.invoice_number));
+    }
+    Ok(())
+}
+
+/// 校验并规范化收款：日期默认今天，金额舍入到分，分配合计不超过收款金额
+fn normalize(payment: &mut Payment) -> Result<(), String> {
+    if payment.id.trim().is_empty() {
+        return Err("收款记录 ID 不能为空".to_string());
+    }
+    if payment.customer_id.trim().is_empty() {
+        return Err("请选择客户".to_string());
+    }
+    payment.date = numbering::parse_date(Some(&payment.date))?
+        .format("%Y-%m-%d")
+        .to_string();
+    payment.method = payment.method.trim().to_string();
+    payment.amount = money::round_cents(payment.amount);
+    if payment.amount <= Decimal::ZERO {
+        return Err("收款金额必须大于 0".to_string());
+    }
+    let mut seen = HashSet::new();
+    for allocation in &mut payment.allocations {
+        allocation.amount = money::round_cents(allocation.amount);
+        allocation.invoice_number = None;
+        if allocation.amount <= Decimal::ZERO {
+            return Err("分配金额必须大于 0".to_string());
+        }
+        if !seen.insert(allocation.invoice_id.clone()) {
+            return Err("同一张销售单不能重复分配".to_string());
+        }
+    }
+    let allocated: Decimal = payment.allocations.iter().map(|a| a.amount).sum();
+    if allocated > payment.amount {
+        return Err(format!(
+            "分配合计 {} 元超过收款金额 {} 元",
+            allocated, payment.amount
+        ));
+    }
+    Ok(())
+}
+
+/// 把剩余金额按顺序分配到未付清的销售单，每张最多分配其未付金额
+fn allocate_in_order(mut remaining: Decimal, open: &[(String, Decimal)]) -> Vec<PaymentAllocation> {
+    let mut allocations = Vec::new();
+    for (invoice_id, outstanding) in open {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        if *outstanding <= Decimal::ZERO {
+            continue;
+        }
+        let amount = remaining.min(*outstanding);
+        allocations.push(PaymentAllocation {
+            invoice_id: invoice_id.clone(),
+            amount,
+            invoice_number: None,
+        });
+        remaining -= amount;
+    }
+    allocations
+}
+
+/// 客户未付清的销售单（按开单日期从早到晚）及其未付金额；`previous` 为本笔收款原有的分配，先加回
+fn open_invoices(
+    conn: &Connection,
+    customer_id: &str,
+    previous: &BTreeMap<String, Decimal>,
+) -> Result<Vec<(String, Decimal)>, String> {
+    let rows = query_rows(
+        conn,
+        "SELECT id, total_amount, paid_amount FROM invoices \
+         WHERE customer_id = ? AND type = 'sale' AND status != 'cancelled' \
+         ORDER BY date, created_at",
+        vec![SqlValue::Text(customer_id.to_string())],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                money::column(row, 1)?,
+                money::column(row, 2)?,
+            ))
+        },
+    )?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, total, paid)| {
+            let outstanding = total - paid + previous.get(&id).copied().unwrap_or_default();
+            (id, outstanding)
+        })
+        .filter(|(_, outstanding)| *outstanding > Decimal::ZERO)
+        .collect())
+}
+
+/// 收款保存或删除后的结果：收款记录（删除时为被删除的记录，记录不存在时为空）
+/// 与已付金额随之重算的销售单，前端用这些销售单更新本地副本
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentUpdate {
+    pub payment: Option<Payment>,
+    pub invoices: Vec<Invoice>,
+}
+
+/// 重算涉及的销售单
+fn settle_all(
+    conn: &Connection,
+    invoice_ids: BTreeSet<String>,
+    now: &str,
+) -> Result<Vec<Invoice>, String> {
+    let mut invoices = Vec::new();
+    for id in invoice_ids {
+        invoices.extend(settle_stored(conn, &id, now)?);
+    }
+    Ok(invoices)
+}
+
+/// 保存收款并同步重算涉及的销售单；`auto_allocate` 为 true 时把未分配的金额按开单先后冲抵未付清的销售单
+fn save_payment_in(
+    conn: &mut Connection,
+    mut payment: Payment,
+    auto_allocate: bool,
+) -> Result<PaymentUpdate, String> {
+    normalize(&mut payment)?;
+    let tx = conn.transaction().map_err(db_err)?;
+    if business_db::get_customer_by_id(&tx, &payment.customer_id)?.is_none() {
+        return Err("客户不存在".to_string());
+    }
+    let previous = get_payment_by_id(&tx, &payment.id)?;
+    let old: BTreeMap<String, Decimal> = previous
+        .as_ref()
+        .map(|previous| {
+            previous
+                .allocations
+                .iter()
+                .map(|a| (a.invoice_id.clone(), a.amount))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for allocation in &payment.allocations {
+        let invoice = business_db::get_invoice_by_id(&tx, &allocation.invoice_id)?
+            .ok_or_else(|| format!("销售单不存在：{}", allocation.invoice_id))?;
+        check_allocatable(&invoice, &payment.customer_id)?;
+        let outstanding = invoice.total_amount - invoice.paid_amount
+            + old.get(&invoice.id).copied().unwrap_or_default();
+        if allocation.amount > outstanding {
+            return Err(format!(
+                "销售单 {} 未付金额为 {} 元，分配金额超出",
+                invoice.invoice_number,
+                outstanding.max(Decimal::ZERO)
+            ));
+        }
+    }
+    if auto_allocate {
+        let allocated: Decimal = payment.allocations.iter().map(|a| a.amount).sum();
+        let chosen: HashSet<_> = payment
+            .allocations
+            .iter()
+            .map(|a| a.invoice_id.clone())
+            .collect();
+        let open: Vec<_> = open_invoices(&tx, &payment.customer_id, &old)?
+            .into_iter()
+            .filter(|(id, _)| !chosen.contains(id))
+            .collect();
+        let extra = allocate_in_order(payment.amount - allocated, &open);
+        payment.allocations.extend(extra);
+    }
+
+    let now = now_iso();
+    payment.created_at = previous
+        .map(|previous| previous.created_at)
+        .filter(|created_at| !created_at.is_empty())
+        .unwrap_or_else(|| now.clone());
+    payment.updated_at = now.clone();
+
+    upsert_payment(&tx, &payment)?;
+    let affected = old
+        .into_keys()
+        .chain(payment.allocations.iter().map(|a| a.invoice_id.clone()))
+        .collect();
+    let invoices = settle_all(&tx, affected, &now)?;
+    let saved =
+        get_payment_by_id(&tx, &payment.id)?.ok_or_else(|| "收款记录保存失败".to_string())?;
+    tx.commit().map_err(db_err)?;
+    Ok(PaymentUpdate {
+        payment: Some(saved),
+        invoices,
+    })
+}
+
+/// 删除收款并撤回其分配
+fn delete_payment_in(conn: &mut Connection, id: &str) -> Result<PaymentUpdate, String> {
+    let tx = conn.transaction().map_err(db_err)?;
+    let Some(payment) = get_payment_by_id(&tx, id)? else {
+        return Ok(PaymentUpdate {
+            payment: None,
+            invoices: Vec::new(),
+        });
+    };
+    tx.execute("DELETE FROM payment_allocations WHERE payment_id = ?", [id])
+        .map_err(db_err)?;
+    tx.execute("DELETE FROM payments WHERE id = ?", [id])
+        .map_err(db_err)?;
+    let affected = payment
+        .allocations
+        .iter()
+        .map(|a| a.invoice_id.clone())
+        .collect();
+    let invoices = settle_all(&tx, affected, &now_iso())?;
+    tx.commit().map_err(db_err)?;
+    Ok(PaymentUpdate {
+        payment: Some(payment),
+        invoices,
+    })
+}
+
+/// 为一张销售单登记收款（开单收款或之后补收），整笔分配到该销售单；散客销售单的收款不归属任何客户
+fn collect_in(
+    conn: &mut Connection,
+    invoice_id: &str,
+    amount: Decimal,
+    method: &str,
+    date: Option<&str>,
+) -> Result<PaymentUpdate, String> {
+    let amount = money::round_cents(amount);
+    if amount <= Decimal::ZERO {
+        return Err("收款金额必须大于 0".to_string());
+    }
+    let date = numbering::parse_date(date)?.format("%Y-%m-%d").to_string();
+    let tx = conn.transaction().map_err(db_err)?;
+    let invoice = business_db::get_invoice_by_id(&tx, invoice_id)?
+        .ok_or_else(|| "销售单不存在，请稍后重试".to_string())?;
+    let customer_id = invoice.customer_id.clone().unwrap_or_default();
+    check_allocatable(&invoice, &customer_id)?;
+    let outstanding = invoice.total_amount - invoice.paid_amount;
+    if amount > outstanding {
+        return Err(format!(
+            "销售单 {} 未付金额为 {} 元，收款金额超出",
+            invoice.invoice_number,
+            outstanding.max(Decimal::ZERO)
+        ));
+    }
+    let method = match method.trim() {
+        "" => CHECKOUT_METHOD,
+        method => method,
+    };
+    let now = now_iso();
+    let payment = Payment {
+        id: uuid::Uuid::new_v4().to_string(),
+        customer_id,
+        date,
+        amount,
+        method: method.to_string(),
+        note: None,
+        allocations: vec![PaymentAllocation {
+            invoice_id: invoice.id.clone(),
+            amount,
+            invoice_number: None,
+        }],
+        created_at: now.clone(),
+        updated_at: now.clone(),
+    };
+    upsert_payment(&tx, &payment)?;
+    let invoices = settle_all(&tx, BTreeSet::from([invoice.id]), &now)?;
+    let saved = get_payment_by_id(&tx, &payment.id)?;
+    tx.commit().map_err(db_err)?;
+    Ok(PaymentUpdate {
+        payment: saved,
+        invoices,
+    })
+}
+
+/// 客户余额
+/// - paid_amount: 销售单已付（含收款分配）
+/// - returns_amount: 退货冲减，退货单合计减去已退现金
+/// - prepayment: 预收款，即各笔收款中未分配到销售单的部分
+/// - balance: 正数为客户欠款，负数为客户余款
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerBalance {
+    pub customer_id: String,
+    pub customer_name: String,
+    #[serde(with = "money::number")]
+    pub initial_debt: Decimal,
+    #[serde(with = "money::number")]
+    pub sales_amount: Decimal,
+    #[serde(with = "money::number")]
+    pub paid_amount: Decimal,
+    #[serde(with = "money::number")]
+    pub returns_amount: Decimal,
+    #[serde(with = "money::number")]
+    pub prepayment: Decimal,
+    #[serde(with = "money::number")]
+    pub balance: Decimal,
+    /// 未付清的销售单张数
+    pub unpaid_invoices: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_payment_date: Option<String>,
+}
+
+fn balance_of(conn: &Connection, customer: &Customer) -> Result<CustomerBalance, String> {
+    let id = || vec![SqlValue::Text(customer.id.clone())];
+    let invoices = query_rows(
+        conn,
+        "SELECT type, total_amount, paid_amount FROM invoices \
+         WHERE customer_id = ? AND status != 'cancelled'",
+        id(),
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                money::column(row, 1)?,
+                money::column(row, 2)?,
+            ))
+        },
+    )?;
+    let mut sales_amount = Decimal::ZERO;
+    let mut paid_amount = Decimal::ZERO;
+    let mut returns_amount = Decimal::ZERO;
+    let mut unpaid_invoices = 0;
+    for (invoice_type, total, paid) in invoices {
+        match InvoiceType::parse(&invoice_type) {
+            Some(InvoiceType::Sale) => {
+                sales_amount += total;
+                paid_amount += paid;
+                if paid < total {
+                    unpaid_invoices += 1;
+                }
+            }
+            Some(InvoiceType::Return) => returns_amount += total - paid,
+            _ => {}
+        }
+    }
+
+    let received: Decimal = query_rows(
+        conn,
+        "SELECT amount FROM payments WHERE customer_id = ?",
+        id(),
+        |row| money::column(row, 0),
+    )?
+    .into_iter()
+    .sum();
+    let allocated: Decimal = query_rows(
+        conn,
+        "SELECT a.amount FROM payment_allocations a \
+         JOIN payments p ON p.id = a.payment_id WHERE p.customer_id = ?",
+        id(),
+        |row| money::column(row, 0),
+    )?
+    .into_iter()
+    .sum();
+    let prepayment = received - allocated;
+    let last_payment_date = conn
+        .query_row(
+            "SELECT MAX(date) FROM payments WHERE customer_id = ?",
+            [&customer.id],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+
+    Ok(CustomerBalance {
+        customer_id: customer.id.clone(),
+        customer_name: customer.name.clone(),
+        initial_debt: customer.initial_debt,
+        sales_amount,
+        paid_amount,
+        returns_amount,
+        prepayment,
+        balance: customer.initial_debt + sales_amount - paid_amount - returns_amount - prepayment,
+        unpaid_invoices,
+        last_payment_date,
+    })
+}
+
+/// 查询收款记录（含分配）：关键字匹配收款方式与备注，`customer_id` 与日期范围按收款日期筛选
+#[tauri::command]
+pub async fn list_payments<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<Payment>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut query = Query::default();
+    query.keyword(non_empty(&filter.keyword), &["method", "note"]);
+    if let Some(customer_id) = non_empty(&filter.customer_id) {
+        query.filter("customer_id = ?", [SqlValue::Text(customer_id.to_string())]);
+    }
+    if let Some(date_from) = non_empty(&filter.date_from) {
+        query.filter("date >= ?", [SqlValue::Text(date_from.to_string())]);
+    }
+    if let Some(date_to) = non_empty(&filter.date_to) {
+        query.filter("date <= ?", [SqlValue::Text(date_to.to_string())]);
+    }
+    query.order_by(
+        &filter,
+        &[
+            ("date", "date"),
+            ("amount", "amount"),
+            ("createdAt", "created_at"),
+        ],
+        "date DESC, created_at DESC",
+    );
+    let sql = query.sql(&select_sql("payments", PAYMENT_COLUMNS), &filter);
+    with_db(&app, |conn| {
+        let mut payments = query_rows(conn, &sql, query.values, payment_from_row)?;
+        load_all_allocations(conn, &mut payments)?;
+        Ok(payments)
+    })
+}
+
+#[tauri::command]
+pub async fn get_payment<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<Payment>, String> {
+    with_db(&app, |conn| get_payment_by_id(conn, &id))
+}
+
+/// 分配到某张销售单的收款记录，按收款日期排列
+#[tauri::command]
+pub async fn list_invoice_payments<R: Runtime>(
+    app: AppHandle<R>,
+    invoice_id: String,
+) -> Result<Vec<Payment>, String> {
+    let sql = format!(
+        "{} WHERE id IN (SELECT payment_id FROM payment_allocations WHERE invoice_id = ?) \
+         ORDER BY date, created_at",
+        select_sql("payments", PAYMENT_COLUMNS)
+    );
+    with_db(&app, |conn| {
+        let mut payments = query_rows(
+            conn,
+            &sql,
+            vec![SqlValue::Text(invoice_id)],
+            payment_from_row,
+        )?;
+        load_all_allocations(conn, &mut payments)?;
+        Ok(payments)
+    })
+}
+
+/// 新增或更新收款，返回写入后的记录与重算后的销售单。
+/// `auto_allocate` 为 true 时，未分配的金额按开单先后自动冲抵该客户未付清的销售单，其余作为预收款
+#[tauri::command]
+pub async fn save_payment<R: Runtime>(
+    app: AppHandle<R>,
+    payment: Payment,
+    auto_allocate: Option<bool>,
+) -> Result<PaymentUpdate, String> {
+    with_db_write(&app, |conn| {
+        save_payment_in(conn, payment, auto_allocate.unwrap_or(false))
+    })
+}
+
+/// 删除收款，对应销售单的已付金额随之重算
+#[tauri::command]
+pub async fn delete_payment<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<PaymentUpdate, String> {
+    with_db_write(&app, |conn| delete_payment_in(conn, &id))
+}
+
+/// 为一张销售单登记收款；`date` 为空时取今天，`method` 为空时记为「开单收款」
+#[tauri::command]
+pub async fn collect_invoice_payment<R: Runtime>(
+    app: AppHandle<R>,
+    invoice_id: String,
+    amount: f64,
+    method: Option<String>,
+    date: Option<String>,
+) -> Result<PaymentUpdate, String> {
+    let amount = money::from_f64(amount)?;
+    with_db_write(&app, |conn| {
+        collect_in(
+            conn,
+            &invoice_id,
+            amount,
+            method.as_deref().unwrap_or_default(),
+            date.as_deref(),
+        )
+    })
+}
+
+#[tauri::command]
+pub async fn get_customer_balance<R: Runtime>(
+    app: AppHandle<R>,
+    customer_id: String,
+) -> Result<CustomerBalance, String> {
+    with_db(&app, |conn| {
+        let customer = business_db::get_customer_by_id(conn, &customer_id)?
+            .ok_or_else(|| "客户不存在".to_string())?;
+        balance_of(conn, &customer)
+    })
+}
+
+/// 各客户余额，按余额从高到低排列；默认不含已停用的客户
+#[tauri::command]
+pub async fn list_customer_balances<R: Runtime>(
+    app: AppHandle<R>,
+    filter: Option<SearchFilter>,
+) -> Result<Vec<CustomerBalance>, String> {
+    let include_inactive = filter.is_some_and(|filter| filter.include_inactive);
+    with_db(&app, |conn| {
+        let mut balances = business_db::all_customers(conn)?
+            .iter()
+            .filter(|customer| include_inactive || customer.is_active)
+            .map(|customer| balance_of(conn, customer))
+            .collect::<Result<Vec<_>, _>>()?;
+        balances.sort_by_key(|balance| std::cmp::Reverse(balance.balance));
+        Ok(balances)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business_models::PaymentStatus;
+    use crate::db_migrations;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // 空库不做迁移前备份，目录不会被创建
+        db_migrations::migrate(&mut conn, Path::new("unused")).unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        let customer: Customer = serde_json::from_value(json!({
+            "id": "c1", "name": "张三", "initialDebt": 100,
+        }))
+        .unwrap();
+        business_db::upsert_customer(&conn, &customer).unwrap();
+        for (id, date, invoice_type, total, paid) in [
+            ("s1", "2026-01-05", "sale", 300, 0),
+            ("s2", "2026-02-10", "sale", 200, 50),
+            ("r1", "2026-02-20", "return", 40, 0),
+        ] {
+            let mut invoice: Invoice = serde_json::from_value(json!({
+                "id": id, "invoiceNumber": id.to_uppercase(), "date": date,
+                "customerId": "c1", "type": invoice_type,
+                "items": [{ "id": "i", "quantity": 1, "unitPrice": total }],
+                "paidAmount": paid,
+            }))
+            .unwrap();
+            money::apply(&mut invoice).unwrap();
+            business_db::upsert_invoice(&conn, &invoice).unwrap();
+        }
+        // s2 开单时已收的 50 转为收款记录
+        settle_imported(&conn).unwrap();
+        conn
+    }
+
+    fn payment(amount: f64, allocations: serde_json::Value) -> Payment {
+        serde_json::from_value(json!({
+            "id": "p1", "customerId": "c1", "date": "2026-03-01",
+            "amount": amount, "method": "微信", "allocations": allocations,
+        }))
+        .unwrap()
+    }
+
+    fn invoice(conn: &Connection, id: &str) -> Invoice {
+        business_db::get_invoice_by_id(conn, id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn allocates_oldest_first_and_keeps_remainder_as_credit() {
+        let open = vec![("a".to_string(), dec("30")), ("b".to_string(), dec("50"))];
+        let allocations = allocate_in_order(dec("45.5"), &open);
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(allocations[0].amount, dec("30"));
+        assert_eq!(allocations[1].amount, dec("15.5"));
+        assert!(allocate_in_order(Decimal::ZERO, &open).is_empty());
+    }
+
+    #[test]
+    fn payments_update_invoice_status_and_are_reverted_on_delete() {
+        let mut conn = setup();
+        let saved = save_payment_in(
+            &mut conn,
+            payment(400.0, json!([{ "invoiceId": "s2", "amount": 150 }])),
+            true,
+        )
+        .unwrap();
+        let mut returned: Vec<_> = saved.invoices.iter().map(|i| i.id.as_str()).collect();
+        returned.sort();
+        assert_eq!(returned, ["s1", "s2"]);
+        let saved = saved.payment.unwrap();
+        assert_eq!(saved.allocations.len(), 2);
+        assert_eq!(saved.allocations[1].invoice_number.as_deref(), Some("S1"));
+        assert_eq!(saved.allocations[1].amount, dec("250"));
+        assert_eq!(invoice(&conn, "s2").payment_status, PaymentStatus::Paid);
+        assert_eq!(invoice(&conn, "s1").payment_status, PaymentStatus::Partial);
+
+        // 期初 100 + 销售 500 − 已付 450 − 退货 40 − 预收 0
+        let customer = business_db::get_customer_by_id(&conn, "c1")
+            .unwrap()
+            .unwrap();
+        let balance = balance_of(&conn, &customer).unwrap();
+        assert_eq!(balance.balance, dec("110"));
+        assert_eq!(balance.unpaid_invoices, 1);
+
+        // 改为只分配 100 到 s1，其余 300 作为预收款
+        let saved = save_payment_in(
+            &mut conn,
+            payment(400.0, json!([{ "invoiceId": "s1", "amount": 100 }])),
+            false,
+        )
+        .unwrap();
+        assert_eq!(saved.payment.unwrap().allocations.len(), 1);
+        assert_eq!(invoice(&conn, "s2").paid_amount, dec("50"));
+        let balance = balance_of(&conn, &customer).unwrap();
+        assert_eq!(balance.prepayment, dec("300"));
+        assert_eq!(balance.balance, dec("110"));
+
+        let deleted = delete_payment_in(&mut conn, "p1").unwrap();
+        assert!(deleted.payment.is_some());
+        assert_eq!(deleted.invoices[0].payment_status, PaymentStatus::Unpaid);
+        assert_eq!(invoice(&conn, "s1").paid_amount, Decimal::ZERO);
+        assert_eq!(invoice(&conn, "s1").payment_status, PaymentStatus::Unpaid);
+        assert!(delete_payment_in(&mut conn, "p1")
+            .unwrap()
+            .payment
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_allocations() {
+        let mut conn = setup();
+        let over = payment(500.0, json!([{ "invoiceId": "s2", "amount": 200 }]));
+        assert!(save_payment_in(&mut conn, over, false).is_err());
+        let to_return = payment(10.0, json!([{ "invoiceId": "r1", "amount": 10 }]));
+        assert!(save_payment_in(&mut conn, to_return, false).is_err());
+        let exceeds = payment(10.0, json!([{ "invoiceId": "s1", "amount": 20 }]));
+        assert!(save_payment_in(&mut conn, exceeds, false).is_err());
+        assert!(get_payment_by_id(&conn, "p1").unwrap().is_none());
+
+        save_payment_in(
+            &mut conn,
+            payment(100.0, json!([{ "invoiceId": "s1", "amount": 100 }])),
+            false,
+        )
+        .unwrap();
+        // 已付金额只由收款分配得出，传入的值被忽略
+        let mut edited = invoice(&conn, "s1");
+        edited.paid_amount = dec("20");
+        settle(&conn, &mut edited).unwrap();
+        assert_eq!(edited.paid_amount, dec("100"));
+        assert!(check_invoice(&conn, &edited).is_ok());
+        edited.customer_id = Some("c2".to_string());
+        assert!(check_invoice(&conn, &edited).is_err());
+    }
+
+    #[test]
+    fn collects_payments_for_walk_in_invoices() {
+        let mut conn = setup();
+        let mut walk_in: Invoice = serde_json::from_value(json!({
+            "id": "w1", "invoiceNumber": "W1", "date": "2026-03-01",
+            "items": [{ "id": "i", "quantity": 1, "unitPrice": 80 }],
+        }))
+        .unwrap();
+        money::apply(&mut walk_in).unwrap();
+        business_db::upsert_invoice(&conn, &walk_in).unwrap();
+
+        let collected = collect_in(&mut conn, "w1", dec("30"), "", None).unwrap();
+        let payment = collected.payment.unwrap();
+        assert_eq!(payment.customer_id, "");
+        assert_eq!(payment.method, CHECKOUT_METHOD);
+        assert_eq!(collected.invoices[0].paid_amount, dec("30"));
+        assert_eq!(collected.invoices[0].payment_status, PaymentStatus::Partial);
+        assert!(collect_in(&mut conn, "w1", dec("60"), "现金", None).is_err());
+        assert!(collect_in(&mut conn, "r1", dec("10"), "现金", None).is_err());
+
+        // 散客销售单有收款后仍可正常保存，但不能再指定客户
+        let mut saved = invoice(&conn, "w1");
+        assert!(check_invoice(&conn, &saved).is_ok());
+        saved.customer_id = Some("c1".to_string());
+        assert!(check_invoice(&conn, &saved).is_err());
+    }
+
+    #[test]
+    fn converts_unallocated_paid_amounts_once() {
+        let conn = setup();
+        let legacy = get_payment_by_id(&conn, "legacy-paid-s2").unwrap().unwrap();
+        assert_eq!(legacy.amount, dec("50"));
+        assert_eq!(legacy.customer_id, "c1");
+        assert_eq!(legacy.method, CHECKOUT_METHOD);
+        assert_eq!(invoice(&conn, "s2").paid_amount, dec("50"));
+        assert_eq!(invoice(&conn, "s2").payment_status, PaymentStatus::Partial);
+        // 退货单的已退现金不转为收款
+        assert!(get_payment_by_id(&conn, "legacy-paid-r1")
+            .unwrap()
+            .is_none());
+
+        settle_imported(&conn).unwrap();
+        assert_eq!(all_payments(&conn).unwrap().len(), 1);
+
+        // 导入的新数据中已付金额又增加时补记差额，低于分配合计时按分配重算
+        conn.execute("UPDATE invoices SET paid_amount = 70 WHERE id = 's2'", [])
+            .unwrap();
+        settle_imported(&conn).unwrap();
+        let legacy = get_payment_by_id(&conn, "legacy-paid-s2").unwrap().unwrap();
+        assert_eq!(legacy.amount, dec("70"));
+        assert_eq!(legacy.allocations[0].amount, dec("70"));
+
+        conn.execute("UPDATE invoices SET paid_amount = 10 WHERE id = 's2'", [])
+            .unwrap();
+        settle_imported(&conn).unwrap();
+        assert_eq!(invoice(&conn, "s2").paid_amount, dec("70"));
+        assert_eq!(all_payments(&conn).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,70 @@
+//! 敏感设置（如自动备份密码）的安全存储，不再明文写入业务数据库。
+//! 桌面端与 iOS 使用系统钥匙串（Windows 凭据管理器、macOS/iOS 钥匙串、Linux Secret Service），
+//! Android 用 Keystore 中不可导出的密钥加密后保存在应用私有目录。
+
+use tauri::{AppHandle, Runtime};
+
+#[cfg(target_os = "android")]
+use tauri_plugin_android_permissions::{AndroidPermissionsExt, SecretRequest};
+
+// 钥匙串中的服务名，与应用标识一致
+#[cfg(not(target_os = "android"))]
+const SERVICE: &str = "com.renteng.sales";
+
+#[cfg(not(target_os = "android"))]
+fn entry(key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, key).map_err(|e| format!("无法访问系统钥匙串: {}", e))
+}
+
+/// 读取保存的值，没有保存过时返回 `None`
+#[cfg(not(target_os = "android"))]
+pub(crate) fn load<R: Runtime>(_app: &AppHandle<R>, key: &str) -> Result<Option<String>, String> {
+    match entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("读取系统钥匙串失败: {}", e)),
+    }
+}
+
+/// 保存值，`value` 为 `None` 时删除
+#[cfg(not(target_os = "android"))]
+pub(crate) fn store<R: Runtime>(
+    _app: &AppHandle<R>,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), String> {
+    let entry = entry(key)?;
+    let result = match value {
+        Some(value) => entry.set_password(value),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        },
+    };
+    result.map_err(|e| format!("写入系统钥匙串失败: {}", e))
+}
+
+#[cfg(target_os = "android")]
+pub(crate) fn load<R: Runtime>(app: &AppHandle<R>, key: &str) -> Result<Option<String>, String> {
+    app.android_permissions()
+        .load_secret(SecretRequest {
+            key: key.to_string(),
+            value: None,
+        })
+        .map(|response| response.value)
+        .map_err(|e| format!("读取安全存储失败: {}", e))
+}
+
+#[cfg(target_os = "android")]
+pub(crate) fn store<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), String> {
+    app.android_permissions()
+        .store_secret(SecretRequest {
+            key: key.to_string(),
+            value: value.map(str::to_string),
+        })
+        .map_err(|e| format!("写入安全存储失败: {}", e))
+}
@@ -0,0 +1,345 @@
+//! 客户对账单：指定期间内的期初余额、逐笔销售单、退货单与收款明细（含滚动余额）以及期末余额。
+//!
+//! 每一行的借方增加客户欠款、贷方减少欠款，口径与 `receivables` 中的客户余额一致：
+//! - 销售单：借方为合计，贷方为未记入收款的已付（开单收款已记为收款，通常为 0；分配的部分计入对应的收款行）；
+//! - 退货单：贷方为合计，借方为已退现金；
+//! - 收款：贷方为收款金额（含未分配的预收款）。
+//!
+//! 期初余额 = 期初欠款 + 开始日期之前各行的借方 − 贷方。作废的单据与采购单不计入。
+
+use crate::business_db::{self, db_err, query_rows, with_db};
+use crate::business_models::{CompanyInfo, Customer, InvoiceType};
+use crate::money;
+use crate::numbering;
+use chrono::{Datelike, Local, NaiveDate};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Runtime};
+
+/// 对账单行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementLineKind {
+    Sale,
+    Return,
+    Payment,
+}
+
+/// 对账单明细行
+/// - document_number: 销售单/退货单的单号；收款行为其冲抵的单号，以顿号分隔
+/// - summary: 摘要，如“销售”“退货”“收款（微信）”
+/// - balance: 本行之后的滚动余额
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementLine {
+    pub date: String,
+    pub kind: StatementLineKind,
+    pub document_id: String,
+    pub document_number: String,
+    pub summary: String,
+    #[serde(with = "money::number")]
+    pub debit: Decimal,
+    #[serde(with = "money::number")]
+    pub credit: Decimal,
+    #[serde(with = "money::number")]
+    pub balance: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip)]
+    created_at: String,
+}
+
+/// 客户对账单；`company_info` 用于抬头，与销售单样式一致
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerStatement {
+    pub customer: Customer,
+    pub company_info: CompanyInfo,
+    pub date_from: String,
+    pub date_to: String,
+    #[serde(with = "money::number")]
+    pub opening_balance: Decimal,
+    pub lines: Vec<StatementLine>,
+    #[serde(with = "money::number")]
+    pub total_debit: Decimal,
+    #[serde(with = "money::number")]
+    pub total_credit: Decimal,
+    #[serde(with = "money::number")]
+    pub closing_balance: Decimal,
+    pub generated_at: String,
+}
+
+/// 客户截至 `date_to`（含）的全部对账行，按日期与创建时间排列，余额未计算
+fn ledger_lines(
+    conn: &Connection,
+    customer_id: &str,
+    date_to: &str,
+) -> Result<Vec<StatementLine>, String> {
+    let params = || {
+        vec![
+            SqlValue::Text(customer_id.to_string()),
+            SqlValue::Text(date_to.to_string()),
+        ]
+    };
+    // 各销售单上的收款分配合计，开单时已付 = 已付金额 − 分配合计
+    let mut allocated: HashMap<String, Decimal> = HashMap::new();
+    let allocations = query_rows(
+        conn,
+        "SELECT a.invoice_id, a.amount FROM payment_allocations a \
+         JOIN payments p ON p.id = a.payment_id WHERE p.customer_id = ?",
+        vec![SqlValue::Text(customer_id.to_string())],
+        |row| Ok((row.get::<_, String>(0)?, money::column(row, 1)?)),
+    )?;
+    for (invoice_id, amount) in allocations {
+        *allocated.entry(invoice_id).or_default() += amount;
+    }
+
+    let mut lines = query_rows(
+        conn,
+        "SELECT id, invoice_number, date, type, total_amount, paid_amount, notes, created_at \
+         FROM invoices WHERE customer_id = ? AND date <= ? \
+         AND status != 'cancelled' AND type IN ('sale', 'return')",
+        params(),
+        |row| {
+            let id: String = row.get(0)?;
+            let invoice_type: String = row.get(3)?;
+            let total = money::column(row, 4)?;
+            let paid = money::column(row, 5)?;
+            let (kind, summary, debit, credit) = match InvoiceType::parse(&invoice_type) {
+                Some(InvoiceType::Return) => (StatementLineKind::Return, "退货", paid, total),
+                _ => {
+                    let at_sale = paid - allocated.get(&id).copied().unwrap_or_default();
+                    (
+                        StatementLineKind::Sale,
+                        "销售",
+                        total,
+                        at_sale.max(Decimal::ZERO),
+                    )
+                }
+            };
+            Ok(StatementLine {
+                date: row.get(2)?,
+                kind,
+                document_id: id,
+                document_number: row.get(1)?,
+                summary: summary.to_string(),
+                debit,
+                credit,
+                balance: Decimal::ZERO,
+                note: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        },
+    )?;
+
+    let payments = query_rows(
+        conn,
+        "SELECT p.id, p.date, p.amount, p.method, p.note, p.created_at, \
+         (SELECT group_concat(i.invoice_number, '、') FROM payment_allocations a \
+         JOIN invoices i ON i.id = a.invoice_id WHERE a.payment_id = p.id) \
+         FROM payments p WHERE p.customer_id = ? AND p.date <= ?",
+        params(),
+        |row| {
+            let method: String = row.get(3)?;
+            let summary = if method.is_empty() {
+                "收款".to_string()
+            } else {
+                format!("收款（{}）", method)
+            };
+            Ok(StatementLine {
+                date: row.get(1)?,
+                kind: StatementLineKind::Payment,
+                document_id: row.get(0)?,
+                document_number: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                summary,
+                debit: Decimal::ZERO,
+                credit: money::column(row, 2)?,
+                balance: Decimal::ZERO,
+                note: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        },
+    )?;
+    lines.extend(payments);
+    lines.sort_by(|a, b| (&a.date, &a.created_at).cmp(&(&b.date, &b.created_at)));
+    Ok(lines)
+}
+
+/// 按期间拆分对账行：期初余额累计开始日期之前的行，期间内的行填写滚动余额
+fn build_statement(
+    customer: Customer,
+    company_info: CompanyInfo,
+    date_from: String,
+    date_to: String,
+    all_lines: Vec<StatementLine>,
+) -> CustomerStatement {
+    let mut opening_balance = customer.initial_debt;
+    let mut lines = Vec::new();
+    for line in all_lines {
+        if line.date < date_from {
+            opening_balance += line.debit - line.credit;
+        } else {
+            lines.push(line);
+        }
+    }
+    let mut balance = opening_balance;
+    let mut total_debit = Decimal::ZERO;
+    let mut total_credit = Decimal::ZERO;
+    for line in &mut lines {
+        balance += line.debit - line.credit;
+        line.balance = balance;
+        total_debit += line.debit;
+        total_credit += line.credit;
+    }
+    CustomerStatement {
+        customer,
+        company_info,
+        date_from,
+        date_to,
+        opening_balance,
+        lines,
+        total_debit,
+        total_credit,
+        closing_balance: balance,
+        generated_at: business_db::now_iso(),
+    }
+}
+
+/// 客户最近一张销售单上的公司信息快照，作为对账单抬头的默认值
+fn latest_company_info(conn: &Connection, customer_id: &str) -> Result<CompanyInfo, String> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT company_info FROM invoices WHERE customer_id = ? \
+             ORDER BY date DESC, created_at DESC LIMIT 1",
+            [customer_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_err)?;
+    Ok(json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// 生成客户对账单。`date_from` / `date_to` 为 YYYY-MM-DD（含），省略时为本月初至今天；
+/// `company_info` 为抬头使用的公司信息，省略时取该客户最近一张销售单上的快照
+#[tauri::command]
+pub async fn generate_customer_statement<R: Runtime>(
+    app: AppHandle<R>,
+    customer_id: String,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    company_info: Option<CompanyInfo>,
+) -> Result<CustomerStatement, String> {
+    let today = Local::now().date_naive();
+    let date_to = match date_to.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => numbering::parse_date(Some(date))?,
+        None => today,
+    };
+    let date_from = match date_from
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+    {
+        Some(date) => numbering::parse_date(Some(date))?,
+        None => NaiveDate::from_ymd_opt(date_to.year(), date_to.month(), 1).unwrap_or(date_to),
+    };
+    if date_from > date_to {
+        return Err("开始日期不能晚于结束日期".to_string());
+    }
+    let date_from = date_from.format("%Y-%m-%d").to_string();
+    let date_to = date_to.format("%Y-%m-%d").to_string();
+
+    with_db(&app, |conn| {
+        let customer = business_db::get_customer_by_id(conn, &customer_id)?
+            .ok_or_else(|| "客户不存在".to_string())?;
+        let company_info = match company_info {
+            Some(info) => info,
+            None => latest_company_info(conn, &customer_id)?,
+        };
+        let lines = ledger_lines(conn, &customer_id, &date_to)?;
+        Ok(build_statement(
+            customer,
+            company_info,
+            date_from,
+            date_to,
+            lines,
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business_models::{Invoice, Payment};
+    use crate::{db_migrations, receivables};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn splits_opening_balance_and_runs_balance_through_period() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db_migrations::migrate(&mut conn, Path::new("unused")).unwrap();
+        let customer: Customer =
+            serde_json::from_value(json!({ "id": "c1", "name": "张三", "initialDebt": 100 }))
+                .unwrap();
+        business_db::upsert_customer(&conn, &customer).unwrap();
+        for (id, date, invoice_type, status, total, paid) in [
+            ("s0", "2026-02-20", "sale", "sent", 50, 0),
+            ("s1", "2026-03-02", "sale", "sent", 300, 80),
+            ("s2", "2026-03-05", "sale", "cancelled", 999, 0),
+            ("r1", "2026-03-08", "return", "sent", 40, 10),
+            ("s3", "2026-04-01", "sale", "sent", 70, 0),
+        ] {
+            let mut invoice: Invoice = serde_json::from_value(json!({
+                "id": id, "invoiceNumber": id.to_uppercase(), "date": date,
+                "customerId": "c1", "type": invoice_type, "status": status,
+                "items": [{ "id": "i", "quantity": 1, "unitPrice": total }],
+                "paidAmount": paid,
+            }))
+            .unwrap();
+            money::apply(&mut invoice).unwrap();
+            business_db::upsert_invoice(&conn, &invoice).unwrap();
+        }
+        // s1 开单时付 30，收款分配 50；收款 120 中其余 70 为预收款
+        let payment: Payment = serde_json::from_value(json!({
+            "id": "p1", "customerId": "c1", "date": "2026-03-10", "amount": 120,
+            "method": "微信", "allocations": [{ "invoiceId": "s1", "amount": 50 }],
+        }))
+        .unwrap();
+        receivables::upsert_payment(&conn, &payment).unwrap();
+
+        let lines = ledger_lines(&conn, "c1", "2026-03-31").unwrap();
+        let statement = build_statement(
+            customer,
+            CompanyInfo::default(),
+            "2026-03-01".to_string(),
+            "2026-03-31".to_string(),
+            lines,
+        );
+        assert_eq!(statement.opening_balance, dec("150"));
+        let kinds: Vec<_> = statement.lines.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                StatementLineKind::Sale,
+                StatementLineKind::Return,
+                StatementLineKind::Payment
+            ]
+        );
+        assert_eq!(statement.lines[0].credit, dec("30"));
+        assert_eq!(statement.lines[0].balance, dec("420"));
+        assert_eq!(statement.lines[1].balance, dec("390"));
+        assert_eq!(statement.lines[2].document_number, "S1");
+        assert_eq!(statement.total_debit, dec("310"));
+        assert_eq!(statement.total_credit, dec("190"));
+        assert_eq!(statement.closing_balance, dec("270"));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::image_convert;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime, UriSchemeContext};
+
+/// 前端通过 `convertFileSrc('<key>/<size>', 'thumb')` 访问缩略图
+pub const SCHEME: &str = "thumb";
+// 缩略图缓存目录（位于应用缓存目录下）
+const CACHE_DIRNAME: &str = "thumbnails";
+// 缓存总大小上限，超出后按最近访问时间淘汰
+const MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+// 封面原图大小上限
+const MAX_SOURCE_BYTES: usize = 20 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 82;
+
+/// 固定的缩略图规格（长边像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl ThumbnailSize {
+    const ALL: [ThumbnailSize; 3] = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Large,
+    ];
+
+    fn max_edge(self) -> u32 {
+        match self {
+            ThumbnailSize::Small => 160,
+            ThumbnailSize::Medium => 320,
+            ThumbnailSize::Large => 640,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ThumbnailSize::Small => "small",
+            ThumbnailSize::Medium => "medium",
+            ThumbnailSize::Large => "large",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<ThumbnailSize> {
+        ThumbnailSize::ALL
+            .into_iter()
+            .find(|size| size.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailEntry {
+    /// 缓存键，历史记录保存它即可在封面链接过期后继续显示
+    pub key: String,
+    pub sizes: Vec<ThumbnailSize>,
+    /// 大图（large）缩略图的实际尺寸，可用于按比例预留占位
+    pub width: u32,
+    pub height: u32,
+}
+
+fn cache_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_DIRNAME))
+        .map_err(|e| format!("无法获取缓存目录: {}", e))
+}
+
+/// 封面链接的签名参数会变化，默认以去掉查询串后的地址作为缓存键
+fn default_key(url: &str) -> String {
+    let stable = url.split(['?', '#']).next().unwrap_or(url);
+    hex::encode(Sha256::digest(stable.as_bytes()))
+}
+
+/// 缓存键只允许字母数字与 `-_`，其它输入统一哈希，避免路径穿越
+fn normalize_key(key: &str) -> String {
+    let valid = !key.is_empty()
+        && key.len() <= 64
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        key.to_string()
+    } else {
+        hex::encode(Sha256::digest(key.as_bytes()))
+    }
+}
+
+fn thumbnail_path(dir: &Path, key: &str, size: ThumbnailSize) -> PathBuf {
+    dir.join(format!("{}_{}.jpg", key, size.name()))
+}
+
+/// 更新访问时间，作为 LRU 淘汰依据
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// 缓存超出上限时，按最近访问时间从旧到新删除
+fn evict(dir: &Path, max_bytes: u64) -> Result<(), String> {
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = fs::read_dir(dir)
+        .map_err(|e| format!("读取缓存目录失败: {}", e))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| {
+                (
+                    entry.path(),
+                    meta.len(),
+                    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                )
+            })
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return Ok(());
+    }
+
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(len);
+        }
+    }
+    Ok(())
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| format!("生成缩略图失败: {}", e))?;
+    Ok(out)
+}
+
+/// 解码封面并按固定规格写入缓存（不放大小图），返回大图缩略图尺寸
+fn write_thumbnails(dir: &Path, key: &str, data: &[u8]) -> Result<(u32, u32), String> {
+    let image = image_convert::decode_oriented(data)?;
+    let (width, height) = (image.width(), image.height());
+    let mut large = (width, height);
+
+    for size in ThumbnailSize::ALL {
+        let edge = size.max_edge();
+        let resized = if width.max(height) > edge {
+            image.resize(edge, edge, FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        if size == ThumbnailSize::Large {
+            large = (resized.width(), resized.height());
+        }
+        let bytes = encode_jpeg(&resized)?;
+
+        let path = thumbnail_path(dir, key, size);
+        let temp = path.with_extension("jpg.tmp");
+        fs::write(&temp, &bytes).map_err(|e| format!("写入缓存失败: {}", e))?;
+        fs::rename(&temp, &path).map_err(|e| format!("写入缓存失败: {}", e))?;
+    }
+    Ok(large)
+}
+
+/// 全部规格都已缓存时返回大图尺寸，用于命中缓存时直接返回
+fn cached_dimensions(dir: &Path, key: &str) -> Option<(u32, u32)> {
+    let all_cached = ThumbnailSize::ALL
+        .iter()
+        .all(|size| thumbnail_path(dir, key, *size).is_file());
+    if !all_cached {
+        return None;
+    }
+    let large = thumbnail_path(dir, key, ThumbnailSize::Large);
+    touch(&large);
+    image::image_dimensions(&large).ok()
+}
+
+/// 下载封面并生成缩略图缓存。`key` 为空时以封面地址（去掉查询串）生成；
+/// 已缓存时不再请求网络，除非 `refresh` 为 true。
+#[tauri::command]
+pub async fn cache_thumbnail<R: Runtime>(
+    app: AppHandle<R>,
+    url: String,
+    key: Option<String>,
+    refresh: Option<bool>,
+) -> Result<ThumbnailEntry, String> {
+    let key = match key.as_deref().map(str::trim) {
+        Some(key) if !key.is_empty() => normalize_key(key),
+        _ => default_key(&url),
+    };
+    let dir = cache_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
+
+    if !refresh.unwrap_or(false) {
+        if let Some((width, height)) = cached_dimensions(&dir, &key) {
+            return Ok(ThumbnailEntry {
+                key,
+                sizes: ThumbnailSize::ALL.to_vec(),
+                width,
+                height,
+            });
+        }
+    }
+
+    let mut response = crate::request_media(&url).await?;
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_SOURCE_BYTES as u64)
+    {
+        return Err("封面图片过大".to_string());
+    }
+    // 没有 Content-Length 时逐块读取，超过上限立即停止
+    let mut data = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("读取数据失败: {}", e))?
+    {
+        if data.len() + chunk.len() > MAX_SOURCE_BYTES {
+            return Err("封面图片过大".to_string());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    let task_dir = dir.clone();
+    let task_key = key.clone();
+    let (width, height) = tauri::async_runtime::spawn_blocking(move || {
+        let dimensions = write_thumbnails(&task_dir, &task_key, &data)?;
+        evict(&task_dir, MAX_CACHE_BYTES)?;
+        Ok::<_, String>(dimensions)
+    })
+    .await
+    .map_err(|e| format!("生成缩略图任务异常: {}", e))??;
+
+    Ok(ThumbnailEntry {
+        key,
+        sizes: ThumbnailSize::ALL.to_vec(),
+        width,
+        height,
+    })
+}
+
+/// 删除全部缩略图缓存
+#[tauri::command]
+pub fn clear_thumbnail_cache<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    let dir = cache_dir(&app)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("清理缓存失败: {}", e))?;
+    }
+    Ok(())
+}
+
+fn not_found() -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+/// `thumb://` 协议处理入口：路径为 `<key>/<size>`，返回缓存中的 JPEG 缩略图
+pub fn handle<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let path = percent_decode_str(request.uri().path().trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .to_string();
+    let (key, size) = match path.split_once('/') {
+        Some((key, size)) => (normalize_key(key), ThumbnailSize::from_name(size)),
+        None => (normalize_key(&path), Some(ThumbnailSize::Medium)),
+    };
+    let (Some(size), Ok(dir)) = (size, cache_dir(ctx.app_handle())) else {
+        return not_found();
+    };
+
+    let file = thumbnail_path(&dir, &key, size);
+    match fs::read(&file) {
+        Ok(bytes) => {
+            touch(&file);
+            Response::builder()
+                .header(header::CONTENT_TYPE, "image/jpeg")
+                .header(header::CACHE_CONTROL, "max-age=86400")
+                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                .body(bytes)
+                .unwrap_or_default()
+        }
+        Err(_) => not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("thumbnails-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::new(width, height));
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn parses_size_names() {
+        for size in ThumbnailSize::ALL {
+            assert_eq!(ThumbnailSize::from_name(size.name()), Some(size));
+        }
+        assert_eq!(ThumbnailSize::from_name("huge"), None);
+    }
+
+    #[test]
+    fn keys_ignore_signatures_and_never_escape_the_cache() {
+        assert_eq!(
+            default_key("https://p3.example.com/cover.jpeg?x-expires=1&sign=a"),
+            default_key("https://p3.example.com/cover.jpeg?x-expires=2&sign=b#top")
+        );
+        assert_ne!(
+            default_key("https://p3.example.com/a.jpeg"),
+            default_key("https://p3.example.com/b.jpeg")
+        );
+
+        assert_eq!(normalize_key("video_123-abc"), "video_123-abc");
+        for unsafe_key in ["../../etc/passwd", "a/b", "", &"k".repeat(65)] {
+            let key = normalize_key(unsafe_key);
+            assert_eq!(key.len(), 64);
+            assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        }
+    }
+
+    #[test]
+    fn writes_every_size_without_upscaling() {
+        let dir = temp_dir();
+        assert_eq!(cached_dimensions(&dir, "cover"), None);
+
+        let large = write_thumbnails(&dir, "cover", &png(1280, 720)).unwrap();
+        assert_eq!(large, (640, 360));
+        let small = image::image_dimensions(thumbnail_path(&dir, "cover", ThumbnailSize::Small));
+        assert_eq!(small.unwrap(), (160, 90));
+        assert_eq!(cached_dimensions(&dir, "cover"), Some((640, 360)));
+
+        // 小图保持原尺寸
+        assert_eq!(
+            write_thumbnails(&dir, "tiny", &png(100, 50)).unwrap(),
+            (100, 50)
+        );
+        let medium = image::image_dimensions(thumbnail_path(&dir, "tiny", ThumbnailSize::Medium));
+        assert_eq!(medium.unwrap(), (100, 50));
+
+        assert!(write_thumbnails(&dir, "broken", b"<html>404</html>").is_err());
+        assert_eq!(cached_dimensions(&dir, "broken"), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used_files_first() {
+        let dir = temp_dir();
+        let base = SystemTime::now() - Duration::from_secs(3600);
+        for (index, name) in ["old.jpg", "middle.jpg", "new.jpg"].iter().enumerate() {
+            let path = dir.join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            File::options()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(base + Duration::from_secs(index as u64 * 60))
+                .unwrap();
+        }
+        // 访问过的旧文件变为最新
+        touch(&dir.join("old.jpg"));
+
+        evict(&dir, 300).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        evict(&dir, 200).unwrap();
+        assert!(!dir.join("middle.jpg").exists());
+        assert!(dir.join("new.jpg").exists());
+        assert!(dir.join("old.jpg").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+// 生成 CYPRIDINA1 测试样本：直接调用前端导出所用的 src/lib/utils/crypto.ts 中的 encryptJsonToBinary
+// 用法（Node 22.6+，需直接加载 TypeScript）：node --experimental-strip-types make_cbin_fixture.mjs
+// Node 23.6 起默认支持，可省略该参数
+import { writeFileSync } from 'node:fs';
+import { encryptJsonToBinary } from '../../../src/lib/utils/crypto.ts';
+
+const PASSWORD = 'cypridina-测试';
+const data = {
+  customers: [
+    {
+      id: 'c-1',
+      name: '张三五金',
+      category: '批发',
+      initialDebt: 120.5,
+      phone: '13800000000',
+      createdAt: '2024-03-01T08:00:00.000Z',
+      updatedAt: '2024-03-02T08:00:00.000Z',
+      isActive: true
+    }
+  ],
+  products: [],
+  invoices: [
+    {
+      id: 'i-1',
+      invoiceNumber: 'INV202403021234',
+      date: '2024-03-02',
+      customerId: 'c-1',
+      customerInfo: { name: '张三五金' },
+      companyInfo: { name: '靓仔建材', address: '', phone: '' },
+      items: [
+        { id: 'it-1', productName: '板材', specification: '1220*2440', unit: '张', quantity: 3, unitPrice: 85, amount: 255 }
+      ],
+      totalAmount: 255,
+      createdBy: '小王',
+      status: 'sent',
+      type: 'sale',
+      paymentStatus: 'partial',
+      paidAmount: 100,
+      createdAt: '2024-03-02T08:00:00.000Z',
+      updatedAt: '2024-03-02T08:00:00.000Z'
+    }
+  ],
+  quotations: [],
+  customerHistory: [],
+  globalTags: ['热销'],
+  exportTime: '2024-03-02T09:00:00.000Z',
+  version: '1.0.0'
+};
+
+const blob = await encryptJsonToBinary(data, PASSWORD);
+writeFileSync(new URL('./js-export.cbin', import.meta.url), new Uint8Array(await blob.arrayBuffer()));
+writeFileSync(new URL('./js-export.json', import.meta.url), JSON.stringify(data, null, 2) + '\n');
@@ -0,0 +1,154 @@
+/**
+ * 访问控制与许可证服务
+ * 许可证由后端使用内置公钥校验，前端只负责展示与导入
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export interface License {
+	version: number;
+	licenseId: string;
+	licensee: string;
+	deviceId?: string | null;
+	issuedAt: number;
+	expiresAt?: number | null;
+	clockToleranceSecs?: number;
+	features: string[];
+}
+
+/**
+ * 安装时间记录的校验结果,tampered 表示记录被删改
+ */
+export type InstallState = 'first_run' | 'verified' | 'repaired' | 'migrated' | 'tampered';
+
+/**
+ * 可单独开通的功能,由后端根据许可证决定
+ * - videoTools: 视频去水印
+ * - profileEditing: “我的”页面编辑资料
+ * - exportScope: 导出前选择是否包含客户与销售单
+ */
+export type Feature = 'videoTools' | 'profileEditing' | 'exportScope';
+
+export interface Entitlements {
+	features: Feature[];
+}
+
+/**
+ * 已激活的激活码
+ */
+export interface Activation {
+	code: string;
+	features: Feature[];
+	expiresAt: number | null;
+	deviceBound: boolean;
+}
+
+export interface AccessStatus {
+	allowed: boolean;
+	install_time: number;
+	install_state: InstallState;
+	threshold_time: number;
+	license: License | null;
+	license_error: string | null;
+	activation: Activation | null;
+	activation_error: string | null;
+	/** 系统时钟是否可信,回拨超过容差或时间记录被删改时为 false */
+	clock_trusted: boolean;
+	last_seen_time: number;
+	clock_rollback_secs: number;
+	clock_tolerance_secs: number;
+	/** 许可证与激活码中最晚的到期时间,为空表示永久有效或未授权 */
+	expires_at: number | null;
+	/** 距到期天数,已到期为 0 或负数 */
+	days_remaining: number | null;
+	/** 到期前 7 天内为 true */
+	expiry_warning: boolean;
+	grace_period_days: number;
+	/** 已到期但仍在宽限期内 */
+	in_grace_period: boolean;
+	/** 宽限期已过,只能查看业务数据 */
+	read_only: boolean;
+	entitlements: Entitlements;
+}
+
+/**
+ * 检测是否在 Tauri 环境中
+ */
+export function isTauriEnvironment(): boolean {
+	// @ts-ignore
+	return typeof window !== 'undefined' && !!window.__TAURI_INTERNALS__;
+}
+
+/**
+ * 查询访问状态
+ */
+export async function checkAccessStatus(): Promise<AccessStatus> {
+	return await invoke<AccessStatus>('check_access_status');
+}
+
+/**
+ * 导入许可证文件内容,校验失败时抛出后端返回的错误信息
+ */
+export async function importLicense(content: string): Promise<License> {
+	return await invoke<License>('import_license', { content });
+}
+
+/**
+ * 输入离线激活码,校验失败时抛出后端返回的错误信息
+ */
+export async function activateCode(code: string): Promise<Activation> {
+	return await invoke<Activation>('activate_code', { code });
+}
+
+/**
+ * 弹窗输入激活码,激活成功后重新加载应用以刷新权限
+ */
+export async function promptActivationCode(): Promise<void> {
+	const code = prompt('请输入激活码（如 XXXX-XXXX-XXXX-XXXX）');
+	if (!code || !code.trim()) return;
+	try {
+		const activation = await activateCode(code);
+		const expiry = activation.expiresAt
+			? `有效期至 ${new Date(activation.expiresAt * 1000).toLocaleDateString()}`
+			: '永久有效';
+		alert(`激活成功，${expiry}`);
+		window.location.reload();
+	} catch (error) {
+		alert(`激活失败：${error}`);
+	}
+}
+
+/**
+ * 获取本机设备标识,申请绑定设备的许可证时提供给发放方
+ */
+export async function getDeviceId(): Promise<string> {
+	return await invoke<string>('get_device_id');
+}
+
+let entitlementsPromise: Promise<Entitlements> | null = null;
+
+/**
+ * 获取已开通的功能(同一会话内缓存,导入许可证后会重新加载应用)
+ * 非 Tauri 环境没有后端校验,不开通任何功能
+ */
+export async function getEntitlements(): Promise<Entitlements> {
+	if (!isTauriEnvironment()) {
+		return { features: [] };
+	}
+	if (!entitlementsPromise) {
+		entitlementsPromise = invoke<Entitlements>('get_entitlements').catch((error) => {
+			console.error('获取功能权限失败:', error);
+			entitlementsPromise = null;
+			return { features: [] };
+		});
+	}
+	return await entitlementsPromise;
+}
+
+/**
+ * 判断某个功能是否已开通
+ */
+export async function hasFeature(feature: Feature): Promise<boolean> {
+	const entitlements = await getEntitlements();
+	return entitlements.features.includes(feature);
+}
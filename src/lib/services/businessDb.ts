@@ -0,0 +1,396 @@
+/**
+ * 业务数据库服务
+ * 客户、产品、销售单与报价单保存在应用数据目录下的 SQLite 数据库中，
+ * 不受 WebView localStorage 容量限制，清除 WebView 缓存也不会丢失
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import type {
+	CompanyInfo,
+	Customer,
+	Invoice,
+	InvoiceRounding,
+	Payment,
+	Product,
+	Quotation,
+	QuotationProduct
+} from '$lib/types/invoice';
+import type { CustomerProductHistory } from '$lib/utils/customerHistory';
+
+/**
+ * 列表查询条件
+ * - keyword: 模糊匹配名称、电话、单号、备注等
+ * - category: 客户/产品分类；查询销售单时为单据类型（sale / purchase / return）
+ * - status: 销售单状态或付款状态
+ * - dateFrom / dateTo: 销售单开单日期范围（含），YYYY-MM-DD
+ * - includeInactive: 是否包含已停用的客户与产品
+ */
+export interface DbSearchFilter {
+	keyword?: string;
+	category?: string;
+	dateFrom?: string;
+	dateTo?: string;
+	status?: string;
+	sortBy?: string;
+	sortOrder?: 'asc' | 'desc';
+	customerId?: string;
+	includeInactive?: boolean;
+	limit?: number;
+	offset?: number;
+}
+
+export const listCustomers = (filter?: DbSearchFilter) =>
+	invoke<Customer[]>('list_customers', { filter });
+export const getCustomer = (id: string) => invoke<Customer | null>('get_customer', { id });
+export const saveCustomer = (customer: Customer) => invoke<Customer>('save_customer', { customer });
+export const deleteCustomer = (id: string) => invoke<boolean>('delete_customer', { id });
+
+export const listProducts = (filter?: DbSearchFilter) =>
+	invoke<Product[]>('list_products', { filter });
+export const getProduct = (id: string) => invoke<Product | null>('get_product', { id });
+export const saveProduct = (product: Product) => invoke<Product>('save_product', { product });
+export const deleteProduct = (id: string) => invoke<boolean>('delete_product', { id });
+
+export const listInvoices = (filter?: DbSearchFilter) =>
+	invoke<Invoice[]>('list_invoices', { filter });
+export const getInvoice = (id: string) => invoke<Invoice | null>('get_invoice', { id });
+export const saveInvoice = (invoice: Invoice) => invoke<Invoice>('save_invoice', { invoice });
+export const deleteInvoice = (id: string) => invoke<boolean>('delete_invoice', { id });
+
+/**
+ * 销售单金额计算结果（定点小数计算，以后端为准）
+ * - subtotal: 抹零前的合计；wiped: 抹去的零头
+ * - unpaidAmount: 未付金额，多付时为 0
+ */
+export interface InvoiceTotals {
+	items: { id: string; amount: number }[];
+	subtotal: number;
+	wiped: number;
+	totalAmount: number;
+	paidAmount: number;
+	unpaidAmount: number;
+	paymentStatus: Invoice['paymentStatus'];
+	rounding: Required<InvoiceRounding>;
+}
+
+export const calculateInvoice = (invoice: Invoice) =>
+	invoke<InvoiceTotals>('calculate_invoice', { invoice });
+
+/**
+ * 保存前用后端定点小数结果覆盖行金额、合计与付款状态，
+ * 使 localStorage 中的销售单与写入数据库的一致
+ */
+export async function applyInvoiceTotals(invoice: Invoice): Promise<void> {
+	const totals = await calculateInvoice(invoice);
+	const amounts = new Map(totals.items.map((item) => [item.id, item.amount]));
+	for (const item of invoice.items) {
+		const amount = amounts.get(item.id);
+		if (amount !== undefined) {
+			item.amount = amount;
+		}
+	}
+	invoice.totalAmount = totals.totalAmount;
+	invoice.paidAmount = totals.paidAmount;
+	invoice.paymentStatus = totals.paymentStatus;
+}
+
+/**
+ * 客户余额（应收账款）
+ * - paidAmount: 销售单已付（含收款分配）；returnsAmount: 退货冲减（退货单合计减去已退现金）
+ * - prepayment: 预收款，即收款中未分配到销售单的部分
+ * - balance: 期初欠款 + 销售合计 − 已付 − 退货冲减 − 预收款，负数表示客户有余款
+ */
+export interface CustomerBalance {
+	customerId: string;
+	customerName: string;
+	initialDebt: number;
+	salesAmount: number;
+	paidAmount: number;
+	returnsAmount: number;
+	prepayment: number;
+	balance: number;
+	unpaidInvoices: number;
+	lastPaymentDate?: string;
+}
+
+export const listPayments = (filter?: DbSearchFilter) =>
+	invoke<Payment[]>('list_payments', { filter });
+export const getPayment = (id: string) => invoke<Payment | null>('get_payment', { id });
+export const listInvoicePayments = (invoiceId: string) =>
+	invoke<Payment[]>('list_invoice_payments', { invoiceId });
+
+/**
+ * 收款保存或删除后的结果：payment 为写入后（删除时为被删除）的收款，记录不存在时为 null；
+ * invoices 为已付金额随之重算的销售单，需同步到本地副本
+ */
+export interface PaymentUpdate {
+	payment: Payment | null;
+	invoices: Invoice[];
+}
+
+/** autoAllocate 为 true 时，未分配的金额按开单先后冲抵该客户未付清的销售单 */
+export const savePayment = (payment: Payment, autoAllocate?: boolean) =>
+	invoke<PaymentUpdate>('save_payment', { payment, autoAllocate });
+export const deletePayment = (id: string) => invoke<PaymentUpdate>('delete_payment', { id });
+/** 为一张销售单登记收款并整笔分配到该单；method 为空时记为「开单收款」，date 为空时取今天 */
+export const collectInvoicePayment = (
+	invoiceId: string,
+	amount: number,
+	method?: string,
+	date?: string
+) => invoke<PaymentUpdate>('collect_invoice_payment', { invoiceId, amount, method, date });
+export const getCustomerBalance = (customerId: string) =>
+	invoke<CustomerBalance>('get_customer_balance', { customerId });
+export const listCustomerBalances = (filter?: DbSearchFilter) =>
+	invoke<CustomerBalance[]>('list_customer_balances', { filter });
+
+/**
+ * 客户对账单明细行：借方增加欠款，贷方减少欠款，balance 为本行之后的滚动余额
+ * - 销售：借方为合计，贷方为未记入收款的已付（开单收款已记为收款）；退货：贷方为合计，借方为已退现金
+ * - 收款：贷方为收款金额，documentNumber 为其冲抵的单号
+ */
+export interface StatementLine {
+	date: string;
+	kind: 'sale' | 'return' | 'payment';
+	documentId: string;
+	documentNumber: string;
+	summary: string;
+	debit: number;
+	credit: number;
+	balance: number;
+	note?: string;
+}
+
+export interface CustomerStatement {
+	customer: Customer;
+	companyInfo: CompanyInfo;
+	dateFrom: string;
+	dateTo: string;
+	openingBalance: number;
+	lines: StatementLine[];
+	totalDebit: number;
+	totalCredit: number;
+	closingBalance: number;
+	generatedAt: string;
+}
+
+/**
+ * 生成客户对账单；dateFrom / dateTo 为 YYYY-MM-DD（含），省略时为本月初至今天；
+ * companyInfo 省略时取该客户最近一张销售单上的公司信息
+ */
+export const generateCustomerStatement = (
+	customerId: string,
+	dateFrom?: string,
+	dateTo?: string,
+	companyInfo?: CompanyInfo
+) =>
+	invoke<CustomerStatement>('generate_customer_statement', {
+		customerId,
+		dateFrom,
+		dateTo,
+		companyInfo
+	});
+
+export const listQuotations = (filter?: DbSearchFilter) =>
+	invoke<Quotation[]>('list_quotations', { filter });
+export const getQuotation = (id: string) => invoke<Quotation | null>('get_quotation', { id });
+export const saveQuotation = (quotation: Quotation) =>
+	invoke<Quotation>('save_quotation', { quotation });
+export const deleteQuotation = (id: string) => invoke<boolean>('delete_quotation', { id });
+
+export const listQuotationProducts = (filter?: DbSearchFilter) =>
+	invoke<QuotationProduct[]>('list_quotation_products', { filter });
+export const saveQuotationProduct = (product: QuotationProduct) =>
+	invoke<QuotationProduct>('save_quotation_product', { product });
+export const deleteQuotationProduct = (id: string) =>
+	invoke<boolean>('delete_quotation_product', { id });
+export const getCustomerProductHistory = (customerId: string, productId: string) =>
+	invoke<CustomerProductHistory['lastPurchase'] | null>('get_customer_product_history', {
+		customerId,
+		productId
+	});
+export const listCustomerProductHistory = () =>
+	invoke<CustomerProductHistory[]>('list_customer_product_history');
+export const getPreference = (key: string) => invoke<string | null>('get_preference', { key });
+
+/**
+ * 旧数据导入结果：每个 localStorage 键的统计，以及被跳过或修复的记录明细
+ */
+export interface LegacyImportReport {
+	keys: {
+		key: string;
+		total: number;
+		imported: number;
+		skipped: number;
+		repaired: number;
+		error?: string;
+	}[];
+	imported: number;
+	skipped: number;
+	repaired: number;
+	issues: {
+		key: string;
+		index: number;
+		id?: string;
+		skipped: boolean;
+		reasons: string[];
+	}[];
+}
+
+// 需要迁移的 localStorage 键，另外还有所有 last_selected_* 键
+const LEGACY_KEYS = [
+	'customers',
+	'products',
+	'invoice_history',
+	'quotations',
+	'quotation_products',
+	'customer_product_history'
+];
+const LAST_SELECTED_PREFIX = 'last_selected_';
+
+/**
+ * 把 localStorage 中的旧业务数据导入数据库
+ * 原始值直接交给后端校验与修复；已导入过的记录不会重复写入，可以多次执行
+ */
+export async function importLegacyData(): Promise<LegacyImportReport> {
+	const payload: Record<string, string> = {};
+	for (let i = 0; i < localStorage.length; i++) {
+		const key = localStorage.key(i);
+		if (!key || !(LEGACY_KEYS.includes(key) || key.startsWith(LAST_SELECTED_PREFIX))) continue;
+		const value = localStorage.getItem(key);
+		if (value !== null) payload[key] = value;
+	}
+	return invoke<LegacyImportReport>('import_legacy_data', { payload });
+}
+
+/**
+ * 加密备份导出结果：文件路径、大小与各类记录条数
+ */
+export interface EncryptedBackupSummary {
+	path: string;
+	size: number;
+	formatVersion: number;
+	customers: number;
+	products: number;
+	invoices: number;
+	quotations: number;
+	quotationProducts: number;
+	customerHistory: number;
+	payments: number;
+	attachments: number;
+}
+
+/**
+ * 加密备份恢复结果；extra 为备份中数据库之外的内容（userInfo、settings 等），由调用方决定是否恢复
+ */
+export interface EncryptedBackupRestoreResult {
+	report: LegacyImportReport;
+	formatVersion: number;
+	attachments: number;
+	exportTime?: string;
+	extra: Record<string, unknown>;
+}
+
+/**
+ * 把数据库中的业务数据导出为 .cbin 加密文件，格式与 utils/crypto.ts 一致
+ */
+export const exportEncryptedBackup = (
+	path: string,
+	password: string,
+	extra?: Record<string, unknown>
+) => invoke<EncryptedBackupSummary>('export_encrypted_backup', { path, password, extra });
+
+/**
+ * 以 v2 格式导出加密备份（Argon2id + zstd + 校验清单），密码至少 8 位；
+ * includeAttachments 为 true 时一并打包客户与产品引用的本地图片
+ */
+export const exportBackup = (
+	path: string,
+	password: string,
+	extra?: Record<string, unknown>,
+	includeAttachments?: boolean
+) =>
+	invoke<EncryptedBackupSummary>('export_backup', { path, password, extra, includeAttachments });
+
+/**
+ * 从 .cbin 加密文件恢复业务数据，自动识别 v1 / v2 格式；overwrite 为 true 时先清空现有业务数据，默认合并
+ */
+export const importEncryptedBackup = (path: string, password: string, overwrite?: boolean) =>
+	invoke<EncryptedBackupRestoreResult>('import_encrypted_backup', { path, password, overwrite });
+
+/** 备份文件中转目录（应用数据目录下的 transfer/），前端只能读写其中的文件 */
+export const backupTransferDir = () => invoke<string>('backup_transfer_dir');
+
+/**
+ * 自动备份设置
+ * - intervalHours: 距上次快照满多少小时生成新快照
+ * - changeThreshold: 累计变更达到多少次时提前生成快照，0 表示不按变更次数触发
+ * - directory: 快照保存的文件夹，省略时为应用数据目录下的 snapshots/
+ * - keepDaily / keepWeekly / keepMonthly: 每日、每周、每月各保留的份数
+ */
+export interface AutoBackupSettings {
+	enabled: boolean;
+	intervalHours: number;
+	changeThreshold: number;
+	directory?: string;
+	keepDaily: number;
+	keepWeekly: number;
+	keepMonthly: number;
+	includeAttachments: boolean;
+}
+
+export interface AutoBackupStatus {
+	settings: AutoBackupSettings;
+	hasPassword: boolean;
+	directory: string;
+	pendingChanges: number;
+	lastSnapshotAt?: string;
+	lastError?: string;
+}
+
+export interface BackupSnapshot {
+	fileName: string;
+	path: string;
+	createdAt: string;
+	size: number;
+}
+
+export const getAutoBackupStatus = () => invoke<AutoBackupStatus>('get_auto_backup_status');
+// password 省略时沿用已保存的自动备份密码
+export const saveAutoBackupSettings = (settings: AutoBackupSettings, password?: string) =>
+	invoke<AutoBackupStatus>('save_auto_backup_settings', { settings, password });
+export const createBackupSnapshot = () => invoke<BackupSnapshot>('create_backup_snapshot');
+export const listBackupSnapshots = () => invoke<BackupSnapshot[]>('list_backup_snapshots');
+// 默认覆盖现有数据；password 省略时使用自动备份密码
+export const restoreBackupSnapshot = (fileName: string, password?: string, overwrite?: boolean) =>
+	invoke<EncryptedBackupRestoreResult>('restore_backup_snapshot', {
+		fileName,
+		password,
+		overwrite
+	});
+
+/**
+ * 单据编号规则：pattern 中 {yyyyMMdd} 等为日期，{seq:4} 为补零到 4 位的流水号；
+ * reset 为流水号重置周期
+ */
+export type DocumentKind = 'sale' | 'purchase' | 'return' | 'quotation' | 'delivery';
+
+export interface NumberingRule {
+	kind: DocumentKind;
+	name: string;
+	pattern: string;
+	reset: 'never' | 'day' | 'month' | 'year';
+}
+
+export const getNumberingRules = () => invoke<NumberingRule[]>('get_numbering_rules');
+export const saveNumberingRule = (rule: NumberingRule) =>
+	invoke<NumberingRule>('save_numbering_rule', { rule });
+// 分配下一个单号（占用流水号）；date 为单据日期 YYYY-MM-DD，省略时为今天
+export const nextDocumentNumber = (kind: DocumentKind, date?: string) =>
+	invoke<string>('next_document_number', { kind, date });
+// 预览下一个单号，不占用流水号
+export const previewDocumentNumber = (kind: DocumentKind, date?: string) =>
+	invoke<string>('preview_document_number', { kind, date });
+// 编号是否已被其他单据使用；excludeId 为正在编辑的单据
+export const checkDocumentNumber = (kind: DocumentKind, number: string, excludeId?: string) =>
+	invoke<boolean>('check_document_number', { kind, number, excludeId });
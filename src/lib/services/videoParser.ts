@@ -3,7 +3,8 @@
  * 支持抖音、快手、小红书、TikTok 等平台的视频解析
  */
 
-import { invoke } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 /**
  * 检测是否在 Tauri 环境中
@@ -159,8 +160,12 @@ function extractVideoUrl(text: string): string | null {
 /**
  * 解析视频
  * 会依次尝试多个 API,直到成功或全部失败
+ * options.probe 为 true 时（仅 Tauri 环境）读取视频文件头校正时长
  */
-export async function parseVideo(url: string): Promise<ParseResult> {
+export async function parseVideo(
+	url: string,
+	options: { probe?: boolean } = {}
+): Promise<ParseResult> {
 	if (!url || !url.trim()) {
 		return {
 			success: false,
@@ -188,7 +193,10 @@ export async function parseVideo(url: string): Promise<ParseResult> {
 	if (isTauriEnvironment()) {
 		try {
 			console.log('🦀 使用 Tauri Rust 解析命令...');
-			const videoInfo = await invoke<VideoInfo>('parse_video_via_providers', { url: videoUrl });
+			const videoInfo = await invoke<VideoInfo>('parse_video_via_providers', {
+				url: videoUrl,
+				probe: options.probe ?? false
+			});
 			return {
 				success: true,
 				data: videoInfo
@@ -201,7 +209,10 @@ export async function parseVideo(url: string): Promise<ParseResult> {
 		if (platform === '抖音') {
 			try {
 				console.log('🦀 回退到旧抖音解析命令...');
-				const videoInfo = await invoke<VideoInfo>('parse_douyin_video', { url: videoUrl });
+				const videoInfo = await invoke<VideoInfo>('parse_douyin_video', {
+					url: videoUrl,
+					probe: options.probe ?? false
+				});
 				return {
 					success: true,
 					data: videoInfo
@@ -323,14 +334,29 @@ function hasAndroidVideoDownloader(): boolean {
 
 /**
  * 下载视频到手机
+ * Tauri 环境由 Rust 后端下载、校验并写入 metadata 中的来源信息
  */
 export async function downloadVideo(
 	videoUrl: string,
 	title: string = 'video',
-	platform: string = '抖音'
+	platform: string = '抖音',
+	metadata?: MediaMetadata
 ): Promise<{ success: boolean; path?: string; error?: string }> {
 	try {
-		const filename = `${sanitizeFilename(title)}.mp4`;
+		const filename = sanitizeFilename(`${title}.mp4`);
+
+		if (isTauriEnvironment()) {
+			const data = await invoke<DownloadRecord>('download_video', {
+				url: videoUrl,
+				filename,
+				metadata: metadata ?? null,
+				conversion: null
+			});
+			if (data.metadataError) {
+				console.warn('写入来源信息失败:', data.metadataError);
+			}
+			return { success: true, path: data.path };
+		}
 
 		// 优先使用 Android 原生视频下载器 (直接在 Android 端下载,绕过 CORS 和防盗链)
 		if (hasAndroidVideoDownloader()) {
@@ -411,19 +437,362 @@ export async function downloadVideo(
 		console.error('下载失败:', error);
 		return {
 			success: false,
-			error: error instanceof Error ? error.message : '下载失败,请稍后重试'
+			error:
+				typeof error === 'string'
+					? error
+					: error instanceof Error
+						? error.message
+						: '下载失败,请稍后重试'
+		};
+	}
+}
+
+export interface ImageConversion {
+	format: 'jpeg' | 'png';
+	/** 仅 JPEG 生效,1-100 */
+	quality?: number;
+}
+
+export interface DownloadRecord {
+	path: string;
+	size: number;
+	sha256: string;
+	mediaKind: string;
+	metadataError?: string;
+	conversionError?: string;
+}
+
+/**
+ * 通过 Rust 后端保存单张图片,可选转为 JPEG/PNG（仅 Tauri 环境）
+ * metadata 中的来源信息会以 XMP 写入图片
+ */
+export async function downloadImageFile(
+	imageUrl: string,
+	filename: string,
+	conversion?: ImageConversion,
+	metadata?: MediaMetadata
+): Promise<{ success: boolean; data?: DownloadRecord; error?: string }> {
+	if (!isTauriEnvironment()) {
+		return { success: false, error: '仅支持 App 内使用' };
+	}
+
+	try {
+		const data = await invoke<DownloadRecord>('download_video', {
+			url: imageUrl,
+			filename: sanitizeFilename(filename),
+			metadata: metadata ?? null,
+			conversion: conversion ?? null
+		});
+		return { success: true, data };
+	} catch (error) {
+		console.error('保存图片失败:', error);
+		return {
+			success: false,
+			error: typeof error === 'string' ? error : '保存图片失败,请稍后重试'
+		};
+	}
+}
+
+export interface DownloadVerification {
+	path: string;
+	savedAt: string;
+	expectedSize: number;
+	expectedSha256: string;
+	actualSize: number;
+	actualSha256: string;
+	/** false 表示文件在保存后被修改或损坏 */
+	intact: boolean;
+}
+
+/**
+ * 按保存时记录的长度与 SHA-256 重新校验已下载的文件（仅 Tauri 环境）
+ * path 为下载结果中的 path（Android 上为 content:// URI）
+ */
+export async function verifyDownload(
+	path: string
+): Promise<{ success: boolean; data?: DownloadVerification; error?: string }> {
+	if (!isTauriEnvironment()) {
+		return { success: false, error: '仅支持 App 内使用' };
+	}
+
+	try {
+		const data = await invoke<DownloadVerification>('verify_download', { path });
+		return { success: true, data };
+	} catch (error) {
+		console.error('校验文件失败:', error);
+		return {
+			success: false,
+			error: typeof error === 'string' ? error : '校验文件失败,请稍后重试'
+		};
+	}
+}
+
+export interface ImageArchiveFailure {
+	index: number;
+	url: string;
+	error: string;
+}
+
+export interface ImageArchiveResult {
+	path: string;
+	size: number;
+	sha256: string;
+	imageCount: number;
+	failures: ImageArchiveFailure[];
+}
+
+/**
+ * 将图文作品的全部图片打包为一个 ZIP（仅 Tauri 环境）
+ * 单张图片失败不会中断打包，失败项在 failures 中返回
+ */
+export async function downloadImagesArchive(
+	info: VideoInfo,
+	sourceUrl?: string,
+	conversion?: ImageConversion
+): Promise<{ success: boolean; data?: ImageArchiveResult; error?: string }> {
+	if (!isTauriEnvironment()) {
+		return { success: false, error: '打包下载仅支持 App 内使用' };
+	}
+
+	try {
+		const data = await invoke<ImageArchiveResult>('download_images_archive', {
+			info,
+			sourceUrl: sourceUrl ?? null,
+			filename: sanitizeFilename(`${info.title}.zip`),
+			conversion: conversion ?? null
+		});
+		return { success: true, data };
+	} catch (error) {
+		console.error('打包下载失败:', error);
+		return {
+			success: false,
+			error: typeof error === 'string' ? error : '打包下载失败,请稍后重试'
+		};
+	}
+}
+
+export interface MediaMetadata {
+	title?: string | null;
+	author?: string | null;
+	platform?: string | null;
+	sourceUrl?: string | null;
+	parsedAt?: string | null;
+}
+
+/**
+ * 由解析结果生成随下载写入文件的来源信息
+ */
+export function sourceMetadata(info: VideoInfo, sourceUrl?: string): MediaMetadata {
+	return {
+		title: info.title,
+		author: info.author ?? null,
+		platform: info.platform,
+		sourceUrl: sourceUrl ?? null
+	};
+}
+
+/**
+ * 读取已下载文件中写入的来源信息（仅 Tauri 环境）
+ */
+export async function readMediaMetadata(path: string): Promise<MediaMetadata | null> {
+	if (!isTauriEnvironment()) {
+		return null;
+	}
+
+	try {
+		return await invoke<MediaMetadata>('read_media_metadata', { path });
+	} catch (error) {
+		console.error('读取来源信息失败:', error);
+		return null;
+	}
+}
+
+export interface MuxRecord {
+	path: string;
+	size: number;
+	sha256: string;
+	videoDuration: number;
+	audioDuration: number;
+	metadataError?: string;
+}
+
+/**
+ * 下载音视频分离（DASH）的作品并合并为一个 MP4（仅 Tauri 环境）
+ */
+export async function downloadDashVideo(
+	info: VideoInfo,
+	videoUrl: string,
+	audioUrl: string,
+	sourceUrl?: string
+): Promise<{ success: boolean; data?: MuxRecord; error?: string }> {
+	if (!isTauriEnvironment()) {
+		return { success: false, error: '音视频合并仅支持 App 内使用' };
+	}
+
+	try {
+		const data = await invoke<MuxRecord>('download_dash_video', {
+			videoUrl,
+			audioUrl,
+			filename: sanitizeFilename(`${info.title}.mp4`),
+			metadata: sourceMetadata(info, sourceUrl)
+		});
+		return { success: true, data };
+	} catch (error) {
+		console.error('合并下载失败:', error);
+		return {
+			success: false,
+			error: typeof error === 'string' ? error : '合并下载失败,请稍后重试'
 		};
 	}
 }
 
+export interface MediaProbe {
+	duration?: number | null;
+	width?: number | null;
+	height?: number | null;
+	rotation: number;
+	videoCodec?: string | null;
+	audioCodec?: string | null;
+	bitrate?: number | null;
+	frameRate?: number | null;
+	sampleRate?: number | null;
+	channels?: number | null;
+	fileSize?: number | null;
+	fragmented: boolean;
+}
+
+/**
+ * 探测本地文件或远程地址的真实时长、分辨率、编码等（仅 Tauri 环境）
+ */
+export async function probeMedia(source: string): Promise<MediaProbe | null> {
+	if (!isTauriEnvironment()) {
+		return null;
+	}
+
+	try {
+		return await invoke<MediaProbe>('probe_media', { source });
+	} catch (error) {
+		console.error('媒体探测失败:', error);
+		return null;
+	}
+}
+
+export type ThumbnailSize = 'small' | 'medium' | 'large';
+
+export interface ThumbnailEntry {
+	key: string;
+	sizes: ThumbnailSize[];
+	width: number;
+	height: number;
+}
+
+/**
+ * 由后端下载封面并生成缩略图缓存（仅 Tauri 环境）
+ * key 建议使用作品的分享链接，历史记录保存 key 即可在封面链接过期后继续显示
+ */
+export async function cacheCoverThumbnail(
+	coverUrl: string,
+	key?: string
+): Promise<ThumbnailEntry | null> {
+	if (!isTauriEnvironment() || !coverUrl) {
+		return null;
+	}
+
+	try {
+		return await invoke<ThumbnailEntry>('cache_thumbnail', { url: coverUrl, key: key ?? null });
+	} catch (error) {
+		console.warn('缓存封面失败:', error);
+		return null;
+	}
+}
+
+/**
+ * 缓存缩略图的本地访问地址
+ */
+export function thumbnailSrc(key: string, size: ThumbnailSize = 'medium'): string {
+	return convertFileSrc(`${key}/${size}`, 'thumb');
+}
+
+/**
+ * 解析记录：保存在 localStorage，最多保留最近 MAX_PARSE_HISTORY 条
+ * thumbnailKey 为缓存缩略图的键，封面链接过期后仍可通过 thumbnailSrc 显示
+ */
+export interface ParseHistoryEntry {
+	url: string;
+	title: string;
+	author?: string;
+	platform: string;
+	type: VideoInfo['type'];
+	thumbnailKey?: string;
+	parsedAt: string;
+}
+
+const PARSE_HISTORY_KEY = 'video_parse_history';
+const MAX_PARSE_HISTORY = 30;
+
+export function getParseHistory(): ParseHistoryEntry[] {
+	try {
+		const stored = JSON.parse(localStorage.getItem(PARSE_HISTORY_KEY) || '[]');
+		return Array.isArray(stored) ? stored : [];
+	} catch {
+		return [];
+	}
+}
+
+function saveParseHistory(entries: ParseHistoryEntry[]): ParseHistoryEntry[] {
+	const trimmed = entries.slice(0, MAX_PARSE_HISTORY);
+	try {
+		localStorage.setItem(PARSE_HISTORY_KEY, JSON.stringify(trimmed));
+	} catch (error) {
+		console.warn('保存解析记录失败:', error);
+	}
+	return trimmed;
+}
+
+/**
+ * 记录一次解析结果；同一链接只保留最新一条，已有的缩略图键会沿用
+ */
+export function addParseHistory(url: string, info: VideoInfo): ParseHistoryEntry[] {
+	const history = getParseHistory();
+	const previous = history.find((entry) => entry.url === url);
+	const entry: ParseHistoryEntry = {
+		url,
+		title: info.title,
+		author: info.author,
+		platform: info.platform,
+		type: info.type,
+		thumbnailKey: previous?.thumbnailKey,
+		parsedAt: new Date().toISOString()
+	};
+	return saveParseHistory([entry, ...history.filter((item) => item.url !== url)]);
+}
+
+export function setParseHistoryThumbnail(url: string, thumbnailKey: string): ParseHistoryEntry[] {
+	return saveParseHistory(
+		getParseHistory().map((entry) => (entry.url === url ? { ...entry, thumbnailKey } : entry))
+	);
+}
+
+export function clearParseHistory(): void {
+	localStorage.removeItem(PARSE_HISTORY_KEY);
+}
+
 /**
- * 清理文件名中的非法字符
+ * 清理文件名中的非法字符（与后端 image_archive::sanitize_filename 规则一致）
+ * 只截断文件名主体,扩展名保持不变
  */
 function sanitizeFilename(filename: string): string {
-	return filename
-		.replace(/[<>:"/\\|?*]/g, '') // 移除非法字符
-		.replace(/\s+/g, '_') // 空格替换为下划线
-		.substring(0, 100); // 限制长度
+	const match = filename.match(/^(.+)\.([A-Za-z0-9]{1,8})$/);
+	const stem = match ? match[1] : filename;
+	const ext = match ? `.${match[2]}` : '';
+	const cleaned = Array.from(
+		stem
+			.replace(/[<>:"/\\|?*]/g, '') // 移除非法字符
+			.replace(/\s+/g, '_') // 连续空白替换为一个下划线
+	)
+		.slice(0, 100) // 限制主体长度
+		.join('');
+	return (cleaned || 'images') + ext;
 }
 
 /**
@@ -460,29 +829,18 @@ export function formatDuration(seconds: number | undefined): string {
 	return `${mins.toString().padStart(2, '0')}:${secs.toString().padStart(2, '0')}`;
 }
 
-/**
- * 检查是否有 Android 剪贴板接口
- */
-function hasAndroidClipboard(): boolean {
-	const w: any = window as any;
-	return w.AndroidClipboard && typeof w.AndroidClipboard.readText === 'function';
-}
-
 /**
  * 从剪贴板读取文本
- * 优先使用 Android 原生接口,回退到浏览器 API
+ * Tauri 环境由后端读取(桌面端 arboard、Android 走原生插件),回退到浏览器 API
  */
 export async function getClipboardText(): Promise<string> {
-	// 优先使用 Android 原生剪贴板接口
-	if (hasAndroidClipboard()) {
-		console.log('📋 使用 Android 原生剪贴板接口...');
+	if (isTauriEnvironment()) {
 		try {
-			const w: any = window as any;
-			const text = w.AndroidClipboard.readText();
-			console.log('✅ Android 剪贴板读取成功:', text.substring(0, 100));
+			const text = await invoke<string>('read_clipboard_text');
+			console.log('✅ 原生剪贴板读取成功:', text.substring(0, 100));
 			return text || '';
 		} catch (error) {
-			console.error('❌ Android 剪贴板读取失败:', error);
+			console.error('❌ 原生剪贴板读取失败:', error);
 		}
 	}
 
@@ -527,3 +885,74 @@ export async function getClipboardUrl(): Promise<string | null> {
 		return null;
 	}
 }
+
+/**
+ * 剪贴板中检测到的分享链接
+ */
+export interface ShareLink {
+	platform: string;
+	url: string;
+}
+
+/**
+ * 后端检测到新分享链接时广播的事件
+ * 桌面端持续监听剪贴板,移动端在窗口获得焦点时检查;同一链接只提示一次
+ */
+export const SHARE_LINK_EVENT = 'share-link-detected';
+
+/**
+ * 订阅分享链接事件,返回取消订阅函数
+ * 订阅后由后端开始监听剪贴板(未开通视频去水印时不监听),并补收之前已发出的链接
+ */
+export async function onShareLinkDetected(
+	handler: (link: ShareLink) => void
+): Promise<() => void> {
+	if (!isTauriEnvironment()) {
+		return () => {};
+	}
+
+	const unlisten = await listen<ShareLink>(SHARE_LINK_EVENT, (event) => {
+		// 已通过事件收到,清掉后端的待处理记录,避免下次挂载重复提示
+		invoke('take_share_link').catch(() => {});
+		handler(event.payload);
+	});
+
+	try {
+		const watching = await invoke<boolean>('start_clipboard_watch');
+		if (!watching) {
+			unlisten();
+			return () => {};
+		}
+	} catch (error) {
+		console.error('❌ 启动剪贴板监听失败:', error);
+		unlisten();
+		return () => {};
+	}
+
+	try {
+		const pending = await invoke<ShareLink | null>('take_share_link');
+		if (pending) {
+			handler(pending);
+		}
+	} catch (error) {
+		console.error('❌ 获取待处理分享链接失败:', error);
+	}
+
+	return unlisten;
+}
+
+/**
+ * 立即检查一次剪贴板,发现新的分享链接时返回(同时会广播事件)
+ */
+export async function checkClipboardShareLink(): Promise<ShareLink | null> {
+	if (!isTauriEnvironment()) {
+		return null;
+	}
+
+	try {
+		return await invoke<ShareLink | null>('check_clipboard');
+	} catch (error) {
+		console.error('❌ 检查剪贴板失败:', error);
+		return null;
+	}
+}
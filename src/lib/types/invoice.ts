@@ -151,11 +151,41 @@ export interface Invoice {
   type: 'sale' | 'purchase' | 'return'; // 销售单、采购单、退货单
   deliveryDate?: string; // 送货日期
   paymentStatus: 'unpaid' | 'partial' | 'paid'; // 付款状态
-  paidAmount: number; // 已付金额
+  paidAmount: number; // 已付金额（含收款记录的分配）
+  rounding?: InvoiceRounding; // 金额舍入规则，省略时逐行舍入、不抹零
   createdAt: string; // 创建时间戳 (ISO 8601)
   updatedAt: string; // 更新时间戳 (ISO 8601)
 }
 
+// 销售单金额舍入规则
+// - level: line 每行舍入到分后相加；total 按未舍入的行金额相加后再舍入到分
+// - wipe: 抹零到角（jiao）或元（yuan）；wipeMode: truncate 直接舍去，round 四舍五入
+export interface InvoiceRounding {
+  level?: 'line' | 'total';
+  wipe?: 'none' | 'jiao' | 'yuan';
+  wipeMode?: 'truncate' | 'round';
+}
+
+// 收款分配：一笔收款冲抵某张销售单的金额
+export interface PaymentAllocation {
+  invoiceId: string;
+  amount: number;
+  invoiceNumber?: string; // 销售单号，读取时由后端填写
+}
+
+// 收款记录；未分配到销售单的部分作为预收款
+export interface Payment {
+  id: string;
+  customerId: string;
+  date: string; // 收款日期 YYYY-MM-DD
+  amount: number;
+  method: string; // 收款方式，如现金、微信、支付宝、银行转账
+  note?: string;
+  allocations: PaymentAllocation[];
+  createdAt: string;
+  updatedAt: string;
+}
+
 // 费用收入记录
 export interface ExpenseIncome {
   id: string;
@@ -247,7 +277,7 @@ export const createEmptyInvoice = (companyInfo: CompanyInfo): Invoice => {
   };
 };
 
-// 生成发票编号
+// 生成发票编号（仅作离线时的临时编号；Tauri 端开单保存时改用后端 next_document_number 分配的单号）
 export const generateInvoiceNumber = (): string => {
   const now = new Date();
   const year = now.getFullYear();
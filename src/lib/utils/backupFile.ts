@@ -0,0 +1,55 @@
+import { join } from '@tauri-apps/api/path';
+import { readFile, remove, writeFile } from '@tauri-apps/plugin-fs';
+import {
+  backupTransferDir,
+  exportEncryptedBackup,
+  importEncryptedBackup,
+  type EncryptedBackupRestoreResult,
+  type EncryptedBackupSummary
+} from '$lib/services/businessDb';
+import { saveFileWithAndroid, downloadBlobAsBrowser } from './androidHelpers';
+
+/**
+ * 数据库备份文件（仅 Tauri 环境）
+ * 后端只按路径读写备份文件，这里经应用数据目录下的中转目录与用户选择/保存的文件交换
+ */
+
+/**
+ * 由后端把数据库中的业务数据导出为 .cbin（与网页版导出格式相同），再按导出数据的方式保存
+ * extra 为数据库之外需要一并备份的内容（userInfo、settings、globalTags 等）
+ */
+export async function exportDbBackup(
+  fileName: string,
+  password: string,
+  extra: Record<string, unknown>
+): Promise<EncryptedBackupSummary> {
+  const fullFileName = `${fileName}.cbin`;
+  const path = await join(await backupTransferDir(), fullFileName);
+  try {
+    const summary = await exportEncryptedBackup(path, password, extra);
+    const blob = new Blob([await readFile(path)], { type: 'application/octet-stream' });
+    if (!(await saveFileWithAndroid(blob, fullFileName, 'application/octet-stream'))) {
+      downloadBlobAsBrowser(blob, fullFileName);
+    }
+    return summary;
+  } finally {
+    await remove(path).catch(() => {});
+  }
+}
+
+/**
+ * 把用户选择的 .cbin 文件交给后端恢复到数据库；overwrite 为 true 时先清空现有业务数据
+ */
+export async function importDbBackup(
+  file: File,
+  password: string,
+  overwrite: boolean
+): Promise<EncryptedBackupRestoreResult> {
+  const path = await join(await backupTransferDir(), `import-${crypto.randomUUID()}.cbin`);
+  try {
+    await writeFile(path, new Uint8Array(await file.arrayBuffer()));
+    return await importEncryptedBackup(path, password, overwrite);
+  } finally {
+    await remove(path).catch(() => {});
+  }
+}
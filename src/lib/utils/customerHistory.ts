@@ -148,6 +148,13 @@ export function clearCustomerHistory(customerId: string): void {
   console.log('🗑️ 清除客户购买历史:', customerId);
 }
 
+/**
+ * 用数据库中的购买历史整体替换本地记录（恢复备份后）
+ */
+export function replaceAllHistory(history: CustomerProductHistory[]): void {
+  saveAllHistory(history);
+}
+
 /**
  * 清除所有购买历史
  */
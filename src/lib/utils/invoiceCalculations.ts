@@ -5,6 +5,9 @@
  * - 统一销售单相关的计算逻辑
  * - 提供一致的计算方法
  * - 减少重复的计算代码
+ *
+ * 注意：这里用浮点数计算，仅用于界面即时显示；保存到数据库时行金额、合计与付款状态
+ * 以后端 calculate_invoice 的定点小数计算结果为准（见 services/businessDb.ts）
  */
 
 import type { Invoice, InvoiceItem } from '$lib/types/invoice';
@@ -7,6 +7,8 @@
  * - 统一数据类型转换
  * - 提供一致的 API 接口
  * - 集成缓存策略，减少 localStorage 读取
+ * - Tauri 环境下把客户、产品、销售单、报价单与报价产品的修改同步写入业务数据库（SQLite），
+ *   对账单、备份等后端功能读取数据库；启动时再用 importLegacyData 补齐同步失败的记录
  */
 
 import type {
@@ -17,9 +19,89 @@ import type {
   ExpenseIncome,
   CompanyInfo,
   Quotation,
-  QuotationProduct
+  QuotationProduct,
+  Payment
 } from '$lib/types/invoice';
 import { globalCache } from './cache';
+import { isTauriEnvironment } from '$lib/services/access';
+import * as businessDb from '$lib/services/businessDb';
+import { showToast } from '$lib/components/ui/toast';
+import { replaceAllHistory } from './customerHistory';
+
+/**
+ * 只读模式：授权到期且宽限期已过时由启动检查开启，业务数据只能查看不能修改
+ */
+let readOnlyMode = false;
+
+function assertWritable(): void {
+  if (readOnlyMode) {
+    throw new Error('授权已过期，当前为只读模式，无法保存修改。请输入新的激活码或导入许可证后再试。');
+  }
+}
+
+/**
+ * 业务数据库同步：新增或修改的记录调用 save，被删除的记录调用 remove
+ */
+interface DbMirror<T> {
+  /** 提示中使用的记录名称 */
+  label: string;
+  save(item: T): Promise<unknown>;
+  remove(id: string): Promise<unknown>;
+}
+
+/**
+ * 尚未完成的数据库写入（同步与启动导入）；读取数据库的页面先等待它们完成，
+ * 保证看到的数据与 localStorage 一致
+ */
+const pendingDbWrites = new Set<Promise<unknown>>();
+
+export function trackDbWrite<T>(write: Promise<T>): Promise<T> {
+  pendingDbWrites.add(write);
+  const settle = () => pendingDbWrites.delete(write);
+  write.then(settle, settle);
+  return write;
+}
+
+export async function waitForDbSync(): Promise<void> {
+  while (pendingDbWrites.size > 0) {
+    await Promise.allSettled([...pendingDbWrites]);
+  }
+}
+
+/**
+ * 比较保存前后的列表，把差异写入业务数据库；失败时提示用户（如单号重复、与收款记录冲突），
+ * 写入失败的记录下次启动时由 importLegacyData 补齐
+ */
+function mirrorToDb<T extends { id: string }>(
+  storageKey: string,
+  before: string | null,
+  items: T[],
+  mirror: DbMirror<T>
+): void {
+  let previous: T[] = [];
+  try {
+    previous = before ? JSON.parse(before) : [];
+  } catch {
+    previous = [];
+  }
+  const previousById = new Map(previous.map(item => [item.id, JSON.stringify(item)]));
+  const currentIds = new Set<string>();
+  for (const item of items) {
+    currentIds.add(item.id);
+    if (previousById.get(item.id) === JSON.stringify(item)) continue;
+    trackDbWrite(mirror.save(item)).catch(error => {
+      console.error(`同步 ${storageKey} 到数据库失败:`, item.id, error);
+      showToast(`${mirror.label}保存到数据库失败：${error}`, 'error', 4000);
+    });
+  }
+  for (const id of previousById.keys()) {
+    if (currentIds.has(id)) continue;
+    trackDbWrite(mirror.remove(id)).catch(error => {
+      console.error(`从数据库删除 ${storageKey} 失败:`, id, error);
+      showToast(`${mirror.label}从数据库删除失败：${error}`, 'error', 4000);
+    });
+  }
+}
 
 /**
  * 通用 CRUD 操作基类
@@ -28,7 +110,8 @@ class CRUDManager<T extends { id: string }> {
   constructor(
     private storageKey: string,
     private cacheKey: string,
-    private cacheDuration: number = 5 * 60 * 1000
+    private cacheDuration: number = 5 * 60 * 1000,
+    private mirror?: DbMirror<T>
   ) {}
 
   /**
@@ -50,9 +133,15 @@ class CRUDManager<T extends { id: string }> {
    * 保存所有数据（并清除缓存）
    */
   saveAll(items: T[]): void {
+    assertWritable();
     try {
+      // 缓存中的数组可能已被调用方原地修改，以 localStorage 中的旧值作为比较基准
+      const before = this.mirror && isTauriEnvironment() ? localStorage.getItem(this.storageKey) : null;
       localStorage.setItem(this.storageKey, JSON.stringify(items));
       globalCache.delete(this.cacheKey);
+      if (this.mirror && isTauriEnvironment()) {
+        mirrorToDb(this.storageKey, before, items, this.mirror);
+      }
     } catch (error) {
       const msg = (error as any)?.message || '';
       const name = (error as any)?.name || '';
@@ -65,6 +154,34 @@ class CRUDManager<T extends { id: string }> {
     }
   }
 
+  /**
+   * 用数据库中的记录更新 localStorage，不再回写数据库；replace 为 true 时整体替换，
+   * 否则按 ID 覆盖，数据库中没有的记录（如导入时被跳过的）原样保留
+   */
+  applyFromDb(records: T[], replace: boolean): void {
+    let items = records;
+    if (!replace) {
+      const fromDb = new Map(records.map(record => [record.id, record]));
+      items = this.getAll().map(item => fromDb.get(item.id) ?? item);
+      const existing = new Set(items.map(item => item.id));
+      items.push(...records.filter(record => !existing.has(record.id)));
+    }
+    localStorage.setItem(this.storageKey, JSON.stringify(items));
+    globalCache.delete(this.cacheKey);
+  }
+
+  /**
+   * 用数据库返回的字段更新单条记录（如后端重算的已付金额），不再回写数据库
+   */
+  patchFromDb(id: string, updates: Partial<T>): void {
+    const items = this.getAll();
+    const index = items.findIndex(item => item.id === id);
+    if (index < 0) return;
+    items[index] = { ...items[index], ...updates };
+    localStorage.setItem(this.storageKey, JSON.stringify(items));
+    globalCache.delete(this.cacheKey);
+  }
+
   /**
    * 获取单个数据
    */
@@ -105,13 +222,87 @@ class CRUDManager<T extends { id: string }> {
 }
 
 export class StorageManager {
+  // ==================== 只读模式 ====================
+
+  /**
+   * 开启或关闭只读模式（由启动时的访问检查设置）
+   */
+  static setReadOnly(readOnly: boolean): void {
+    readOnlyMode = readOnly;
+  }
+
+  static isReadOnly(): boolean {
+    return readOnlyMode;
+  }
+
+  // ==================== 数据库同步 ====================
+
+  /**
+   * 从业务数据库重新加载客户、产品、销售单、报价单与报价产品（仅 Tauri 环境）。
+   * 启动导入后按 ID 合并，使导入时修正的单号、金额等回到前端；覆盖式恢复备份后整体替换（replace），
+   * 连同客户购买历史，避免下次启动时 importLegacyData 把旧的本地数据重新写回数据库
+   */
+  static async reloadFromDb(replace = false): Promise<void> {
+    if (!isTauriEnvironment()) return;
+    await waitForDbSync();
+    const all = { includeInactive: true };
+    const [customers, products, invoices, quotations, quotationProducts] = await Promise.all([
+      businessDb.listCustomers(all),
+      businessDb.listProducts(all),
+      businessDb.listInvoices(),
+      businessDb.listQuotations(),
+      businessDb.listQuotationProducts()
+    ]);
+    this.customerManager.applyFromDb(customers, replace);
+    this.productManager.applyFromDb(products, replace);
+    this.invoiceManager.applyFromDb(invoices, replace);
+    this.quotationManager.applyFromDb(quotations, replace);
+    this.quotationProductManager.applyFromDb(quotationProducts, replace);
+    if (replace) {
+      replaceAllHistory(await businessDb.listCustomerProductHistory());
+    }
+  }
+
+  /**
+   * 用后端重算后的销售单更新本地的已付金额与付款状态（保存销售单、登记或删除收款之后）
+   */
+  static applyDbInvoices(invoices: Invoice[]): void {
+    for (const invoice of invoices) {
+      this.invoiceManager.patchFromDb(invoice.id, {
+        paidAmount: invoice.paidAmount,
+        paymentStatus: invoice.paymentStatus
+      });
+    }
+  }
+
   // ==================== 初始化 CRUD 管理器 ====================
 
-  private static invoiceManager = new CRUDManager<Invoice>('invoice_history', 'invoices', 5 * 60 * 1000);
-  private static customerManager = new CRUDManager<Customer>('customers', 'customers', 5 * 60 * 1000);
-  private static productManager = new CRUDManager<Product>('products', 'products', 5 * 60 * 1000);
-  private static quotationManager = new CRUDManager<Quotation>('quotations', 'quotations', 5 * 60 * 1000);
-  private static quotationProductManager = new CRUDManager<QuotationProduct>('quotation_products', 'quotation_products', 5 * 60 * 1000);
+  private static invoiceManager = new CRUDManager<Invoice>('invoice_history', 'invoices', 5 * 60 * 1000, {
+    label: '销售单',
+    // 已付金额由后端按收款分配得出，以保存结果为准
+    save: invoice => businessDb.saveInvoice(invoice).then(saved => StorageManager.applyDbInvoices([saved])),
+    remove: businessDb.deleteInvoice
+  });
+  private static customerManager = new CRUDManager<Customer>('customers', 'customers', 5 * 60 * 1000, {
+    label: '客户',
+    save: businessDb.saveCustomer,
+    remove: businessDb.deleteCustomer
+  });
+  private static productManager = new CRUDManager<Product>('products', 'products', 5 * 60 * 1000, {
+    label: '产品',
+    save: businessDb.saveProduct,
+    remove: businessDb.deleteProduct
+  });
+  private static quotationManager = new CRUDManager<Quotation>('quotations', 'quotations', 5 * 60 * 1000, {
+    label: '报价单',
+    save: businessDb.saveQuotation,
+    remove: businessDb.deleteQuotation
+  });
+  private static quotationProductManager = new CRUDManager<QuotationProduct>('quotation_products', 'quotation_products', 5 * 60 * 1000, {
+    label: '报价产品',
+    save: businessDb.saveQuotationProduct,
+    remove: businessDb.deleteQuotationProduct
+  });
 
   // ==================== 销售单相关 ====================
 
@@ -157,6 +348,41 @@ export class StorageManager {
     this.invoiceManager.delete(id);
   }
 
+  // ==================== 收款相关 ====================
+  // 收款只保存在业务数据库中（仅 Tauri 环境），登记前先等待销售单写入数据库，
+  // 之后用后端重算的已付金额更新本地销售单
+
+  /**
+   * 为销售单登记收款；method 为空时记为「开单收款」，date 为空时取今天
+   */
+  static async collectInvoicePayment(invoiceId: string, amount: number, method?: string, date?: string): Promise<void> {
+    assertWritable();
+    await waitForDbSync();
+    const update = await businessDb.collectInvoicePayment(invoiceId, amount, method, date);
+    this.applyDbInvoices(update.invoices);
+  }
+
+  /**
+   * 新增或修改收款；autoAllocate 为 true 时未分配的金额按开单先后冲抵未付清的销售单
+   */
+  static async savePayment(payment: Payment, autoAllocate?: boolean): Promise<Payment | null> {
+    assertWritable();
+    await waitForDbSync();
+    const update = await businessDb.savePayment(payment, autoAllocate);
+    this.applyDbInvoices(update.invoices);
+    return update.payment;
+  }
+
+  /**
+   * 删除收款，撤回其分配
+   */
+  static async deletePayment(id: string): Promise<void> {
+    assertWritable();
+    await waitForDbSync();
+    const update = await businessDb.deletePayment(id);
+    this.applyDbInvoices(update.invoices);
+  }
+
   // ==================== 客户相关 ====================
 
   /**
@@ -304,6 +530,7 @@ export class StorageManager {
    * 保存客户分类
    */
   static saveCustomerCategories(categories: string[]): void {
+    assertWritable();
     try {
       localStorage.setItem('customer_categories', JSON.stringify(categories));
     } catch (error) {
@@ -328,6 +555,7 @@ export class StorageManager {
    * 保存产品分类
    */
   static saveProductCategories(categories: string[]): void {
+    assertWritable();
     try {
       localStorage.setItem('product_categories', JSON.stringify(categories));
     } catch (error) {
@@ -356,6 +584,7 @@ export class StorageManager {
    * 保存用户信息（并清除缓存）
    */
   static saveUserInfo(userInfo: Record<string, any>): void {
+    assertWritable();
     try {
       localStorage.setItem('user_info', JSON.stringify(userInfo));
       globalCache.delete('userInfo');
@@ -399,6 +628,7 @@ export class StorageManager {
    * 清除所有数据
    */
   static clearAllData(): void {
+    assertWritable();
     try {
       localStorage.removeItem('customers');
       localStorage.removeItem('products');
@@ -4,7 +4,7 @@ plugins {
 }
 
 android {
-    namespace = "com.plugin.android-permissionscom.renteng.permissions"
+    namespace = "com.renteng.permissions"
     compileSdk = 36
 
     defaultConfig {
@@ -1,4 +1,4 @@
-package com.plugin.android-permissionscom.renteng.permissions
+package com.renteng.permissions
 
 import androidx.test.platform.app.InstrumentationRegistry
 import androidx.test.ext.junit.runners.AndroidJUnit4
@@ -19,6 +19,6 @@ class ExampleInstrumentedTest {
     fun useAppContext() {
         // Context of the app under test.
         val appContext = InstrumentationRegistry.getInstrumentation().targetContext
-        assertEquals("com.plugin.android-permissionscom.renteng.permissions", appContext.packageName)
+        assertEquals("com.renteng.permissions", appContext.packageName)
     }
 }
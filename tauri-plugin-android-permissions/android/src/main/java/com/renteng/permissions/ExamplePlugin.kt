@@ -0,0 +1,141 @@
+package com.renteng.permissions
+
+import android.app.Activity
+import android.content.ClipboardManager
+import android.content.Context
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import app.tauri.plugin.Invoke
+
+@InvokeArg
+class PingArgs {
+  var value: String? = null
+}
+
+@InvokeArg
+class SaveToMediaStoreArgs {
+  lateinit var sourcePath: String
+  lateinit var displayName: String
+  var mimeType: String = "application/octet-stream"
+  var collection: String = "movies"
+  var folder: String = ""
+}
+
+@InvokeArg
+class CopyFromMediaStoreArgs {
+  lateinit var uri: String
+  lateinit var targetPath: String
+}
+
+@InvokeArg
+class SecretArgs {
+  lateinit var key: String
+  var value: String? = null
+}
+
+@TauriPlugin
+class ExamplePlugin(private val activity: Activity): Plugin(activity) {
+    private val implementation = Example()
+    private val mediaStoreSaver = MediaStoreSaver(activity.applicationContext)
+    private val secretStore = SecretStore(activity.applicationContext)
+
+    @Command
+    fun ping(invoke: Invoke) {
+        val args = invoke.parseArgs(PingArgs::class.java)
+
+        val ret = JSObject()
+        ret.put("value", implementation.pong(args.value ?: "default value :("))
+        invoke.resolve(ret)
+    }
+
+    @Command
+    fun saveToMediaStore(invoke: Invoke) {
+        val args = invoke.parseArgs(SaveToMediaStoreArgs::class.java)
+
+        // 拷贝大文件较慢，放到后台线程执行，避免阻塞主线程
+        Thread {
+            try {
+                val uri = mediaStoreSaver.save(
+                    args.sourcePath,
+                    args.displayName,
+                    args.mimeType,
+                    args.collection,
+                    args.folder
+                )
+                val ret = JSObject()
+                ret.put("uri", uri.toString())
+                invoke.resolve(ret)
+            } catch (e: Exception) {
+                invoke.reject(e.message ?: "保存到媒体库失败")
+            }
+        }.start()
+    }
+
+    @Command
+    fun copyFromMediaStore(invoke: Invoke) {
+        val args = invoke.parseArgs(CopyFromMediaStoreArgs::class.java)
+
+        Thread {
+            try {
+                val size = mediaStoreSaver.copyTo(args.uri, args.targetPath)
+                val ret = JSObject()
+                ret.put("size", size)
+                invoke.resolve(ret)
+            } catch (e: Exception) {
+                invoke.reject(e.message ?: "读取媒体库文件失败")
+            }
+        }.start()
+    }
+
+    @Command
+    fun readClipboard(invoke: Invoke) {
+        // Android 10 起仅前台且持有焦点的应用可以读取剪贴板，需在 UI 线程访问
+        activity.runOnUiThread {
+            try {
+                val clipboard = activity.getSystemService(Context.CLIPBOARD_SERVICE) as ClipboardManager
+                val clip = clipboard.primaryClip
+                val text = if (clip != null && clip.itemCount > 0) {
+                    clip.getItemAt(0).coerceToText(activity)?.toString()
+                } else {
+                    null
+                }
+                val ret = JSObject()
+                ret.put("text", text)
+                invoke.resolve(ret)
+            } catch (e: Exception) {
+                invoke.reject(e.message ?: "读取剪贴板失败")
+            }
+        }
+    }
+
+    @Command
+    fun loadSecret(invoke: Invoke) {
+        val args = invoke.parseArgs(SecretArgs::class.java)
+        try {
+            val ret = JSObject()
+            ret.put("value", secretStore.load(args.key))
+            invoke.resolve(ret)
+        } catch (e: Exception) {
+            invoke.reject(e.message ?: "读取安全存储失败")
+        }
+    }
+
+    @Command
+    fun storeSecret(invoke: Invoke) {
+        val args = invoke.parseArgs(SecretArgs::class.java)
+        try {
+            val value = args.value
+            if (value == null) {
+                secretStore.delete(args.key)
+            } else {
+                secretStore.store(args.key, value)
+            }
+            invoke.resolve()
+        } catch (e: Exception) {
+            invoke.reject(e.message ?: "写入安全存储失败")
+        }
+    }
+}
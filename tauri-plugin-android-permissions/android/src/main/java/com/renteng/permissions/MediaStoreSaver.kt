@@ -0,0 +1,163 @@
+package com.renteng.permissions
+
+import android.content.ContentValues
+import android.content.Context
+import android.media.MediaScannerConnection
+import android.net.Uri
+import android.os.Build
+import android.os.Environment
+import android.provider.MediaStore
+import android.util.Log
+import java.io.File
+import java.io.FileInputStream
+import java.io.FileOutputStream
+
+class MediaStoreSaver(private val context: Context) {
+    fun save(
+        sourcePath: String,
+        displayName: String,
+        mimeType: String,
+        collection: String,
+        folder: String
+    ): Uri {
+        val source = File(sourcePath)
+        if (!source.isFile) {
+            throw IllegalArgumentException("源文件不存在: $sourcePath")
+        }
+
+        val baseDir = when (collection) {
+            "pictures" -> Environment.DIRECTORY_PICTURES
+            "music" -> Environment.DIRECTORY_MUSIC
+            "downloads" -> Environment.DIRECTORY_DOWNLOADS
+            else -> Environment.DIRECTORY_MOVIES
+        }
+
+        return if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.Q) {
+            insertScoped(source, displayName, mimeType, collection, "$baseDir/$folder")
+        } else {
+            insertLegacy(source, displayName, mimeType, collection, baseDir, folder)
+        }
+    }
+
+    // 把媒体库中的文件复制到应用沙盒内，供 Rust 端校验或读取
+    fun copyTo(uri: String, targetPath: String): Long {
+        val target = File(targetPath)
+        target.parentFile?.mkdirs()
+        val input = context.contentResolver.openInputStream(Uri.parse(uri))
+            ?: throw IllegalStateException("无法打开媒体库文件: $uri")
+        return input.use { src ->
+            FileOutputStream(target).use { dst -> src.copyTo(dst) }
+        }
+    }
+
+    private fun collectionUri(collection: String): Uri {
+        return if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.Q) {
+            val volume = MediaStore.VOLUME_EXTERNAL_PRIMARY
+            when (collection) {
+                "pictures" -> MediaStore.Images.Media.getContentUri(volume)
+                "music" -> MediaStore.Audio.Media.getContentUri(volume)
+                "downloads" -> MediaStore.Downloads.getContentUri(volume)
+                else -> MediaStore.Video.Media.getContentUri(volume)
+            }
+        } else {
+            when (collection) {
+                "pictures" -> MediaStore.Images.Media.EXTERNAL_CONTENT_URI
+                "music" -> MediaStore.Audio.Media.EXTERNAL_CONTENT_URI
+                "downloads" -> MediaStore.Files.getContentUri("external")
+                else -> MediaStore.Video.Media.EXTERNAL_CONTENT_URI
+            }
+        }
+    }
+
+    // Android 10+：通过 RELATIVE_PATH 写入共享媒体库，无需存储权限
+    private fun insertScoped(
+        source: File,
+        displayName: String,
+        mimeType: String,
+        collection: String,
+        relativePath: String
+    ): Uri {
+        val resolver = context.contentResolver
+        val values = ContentValues().apply {
+            put(MediaStore.MediaColumns.DISPLAY_NAME, displayName)
+            put(MediaStore.MediaColumns.MIME_TYPE, mimeType)
+            put(MediaStore.MediaColumns.RELATIVE_PATH, relativePath)
+            put(MediaStore.MediaColumns.IS_PENDING, 1)
+        }
+
+        val uri = resolver.insert(collectionUri(collection), values)
+            ?: throw IllegalStateException("MediaStore 插入失败")
+
+        try {
+            resolver.openOutputStream(uri)?.use { output ->
+                FileInputStream(source).use { input -> input.copyTo(output) }
+            } ?: throw IllegalStateException("无法打开 MediaStore 输出流")
+
+            val done = ContentValues().apply { put(MediaStore.MediaColumns.IS_PENDING, 0) }
+            resolver.update(uri, done, null, null)
+        } catch (e: Exception) {
+            resolver.delete(uri, null, null)
+            throw e
+        }
+
+        Log.i(TAG, "已写入 MediaStore: $uri ($relativePath/$displayName)")
+        return uri
+    }
+
+    // Android 9 及以下：写入公共目录后登记到 MediaStore 并触发媒体扫描
+    @Suppress("DEPRECATION")
+    private fun insertLegacy(
+        source: File,
+        displayName: String,
+        mimeType: String,
+        collection: String,
+        baseDir: String,
+        folder: String
+    ): Uri {
+        val targetDir = File(Environment.getExternalStoragePublicDirectory(baseDir), folder)
+        if (!targetDir.exists() && !targetDir.mkdirs()) {
+            throw IllegalStateException("创建目录失败: ${targetDir.absolutePath}")
+        }
+
+        val target = claimUniqueFile(targetDir, displayName)
+        FileInputStream(source).use { input ->
+            FileOutputStream(target).use { output -> input.copyTo(output) }
+        }
+
+        val values = ContentValues().apply {
+            put(MediaStore.MediaColumns.DISPLAY_NAME, target.name)
+            put(MediaStore.MediaColumns.MIME_TYPE, mimeType)
+            put(MediaStore.MediaColumns.DATA, target.absolutePath)
+        }
+        val uri = context.contentResolver.insert(collectionUri(collection), values)
+            ?: Uri.fromFile(target)
+
+        MediaScannerConnection.scanFile(
+            context,
+            arrayOf(target.absolutePath),
+            arrayOf(mimeType),
+            null
+        )
+
+        Log.i(TAG, "已写入公共目录: ${target.absolutePath}")
+        return uri
+    }
+
+    // 重名时依次尝试 "名称 (1).ext"、"名称 (2).ext"……；createNewFile 原子地占用文件名，不覆盖已有文件
+    private fun claimUniqueFile(dir: File, displayName: String): File {
+        val dot = displayName.lastIndexOf('.')
+        val stem = if (dot > 0) displayName.substring(0, dot) else displayName
+        val ext = if (dot > 0) displayName.substring(dot) else ""
+        var candidate = File(dir, displayName)
+        var n = 1
+        while (!candidate.createNewFile()) {
+            candidate = File(dir, "$stem ($n)$ext")
+            n++
+        }
+        return candidate
+    }
+
+    companion object {
+        private const val TAG = "MediaStoreSaver"
+    }
+}
@@ -0,0 +1,68 @@
+package com.renteng.permissions
+
+import android.content.Context
+import android.security.keystore.KeyGenParameterSpec
+import android.security.keystore.KeyProperties
+import android.util.Base64
+import java.security.KeyStore
+import javax.crypto.Cipher
+import javax.crypto.KeyGenerator
+import javax.crypto.SecretKey
+import javax.crypto.spec.GCMParameterSpec
+
+/**
+ * 敏感设置（如自动备份密码）的存储：值用 Android Keystore 中不可导出的 AES 密钥加密后
+ * 保存在应用私有的 SharedPreferences 中，拷走数据目录也无法解密。
+ */
+class SecretStore(context: Context) {
+    private val prefs = context.getSharedPreferences(PREFS_NAME, Context.MODE_PRIVATE)
+
+    fun load(key: String): String? {
+        val stored = prefs.getString(key, null) ?: return null
+        val data = Base64.decode(stored, Base64.NO_WRAP)
+        val cipher = Cipher.getInstance(TRANSFORMATION)
+        cipher.init(Cipher.DECRYPT_MODE, secretKey(), GCMParameterSpec(TAG_BITS, data, 0, IV_LEN))
+        val plain = cipher.doFinal(data, IV_LEN, data.size - IV_LEN)
+        return String(plain, Charsets.UTF_8)
+    }
+
+    fun store(key: String, value: String) {
+        val cipher = Cipher.getInstance(TRANSFORMATION)
+        cipher.init(Cipher.ENCRYPT_MODE, secretKey())
+        val encrypted = cipher.doFinal(value.toByteArray(Charsets.UTF_8))
+        val data = cipher.iv + encrypted
+        if (!prefs.edit().putString(key, Base64.encodeToString(data, Base64.NO_WRAP)).commit()) {
+            throw IllegalStateException("保存失败")
+        }
+    }
+
+    fun delete(key: String) {
+        prefs.edit().remove(key).commit()
+    }
+
+    private fun secretKey(): SecretKey {
+        val keyStore = KeyStore.getInstance(ANDROID_KEYSTORE).apply { load(null) }
+        (keyStore.getKey(KEY_ALIAS, null) as? SecretKey)?.let { return it }
+        val generator = KeyGenerator.getInstance(KeyProperties.KEY_ALGORITHM_AES, ANDROID_KEYSTORE)
+        generator.init(
+            KeyGenParameterSpec.Builder(
+                KEY_ALIAS,
+                KeyProperties.PURPOSE_ENCRYPT or KeyProperties.PURPOSE_DECRYPT
+            )
+                .setBlockModes(KeyProperties.BLOCK_MODE_GCM)
+                .setEncryptionPaddings(KeyProperties.ENCRYPTION_PADDING_NONE)
+                .setKeySize(256)
+                .build()
+        )
+        return generator.generateKey()
+    }
+
+    private companion object {
+        const val ANDROID_KEYSTORE = "AndroidKeyStore"
+        const val KEY_ALIAS = "renteng_secret_store"
+        const val PREFS_NAME = "renteng_secrets"
+        const val TRANSFORMATION = "AES/GCM/NoPadding"
+        const val IV_LEN = 12
+        const val TAG_BITS = 128
+    }
+}
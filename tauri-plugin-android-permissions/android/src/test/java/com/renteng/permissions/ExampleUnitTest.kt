@@ -1,4 +1,4 @@
-package com.plugin.android-permissionscom.renteng.permissions
+package com.renteng.permissions
 
 import org.junit.Test
 
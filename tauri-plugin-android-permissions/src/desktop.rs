@@ -19,4 +19,30 @@ impl<R: Runtime> AndroidPermissions<R> {
       value: payload.value,
     })
   }
+
+  pub fn save_to_media_store(
+    &self,
+    _payload: SaveToMediaStoreRequest,
+  ) -> crate::Result<SaveToMediaStoreResponse> {
+    Err(crate::Error::Unsupported("MediaStore is only available on Android"))
+  }
+
+  pub fn copy_from_media_store(
+    &self,
+    _payload: CopyFromMediaStoreRequest,
+  ) -> crate::Result<CopyFromMediaStoreResponse> {
+    Err(crate::Error::Unsupported("MediaStore is only available on Android"))
+  }
+
+  pub fn read_clipboard(&self) -> crate::Result<ReadClipboardResponse> {
+    Err(crate::Error::Unsupported("Clipboard bridge is only available on Android"))
+  }
+
+  pub fn load_secret(&self, _payload: SecretRequest) -> crate::Result<LoadSecretResponse> {
+    Err(crate::Error::Unsupported("Keystore is only available on Android"))
+  }
+
+  pub fn store_secret(&self, _payload: SecretRequest) -> crate::Result<()> {
+    Err(crate::Error::Unsupported("Keystore is only available on Android"))
+  }
 }
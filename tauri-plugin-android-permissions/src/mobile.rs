@@ -15,7 +15,7 @@ pub fn init<R: Runtime, C: DeserializeOwned>(
   api: PluginApi<R, C>,
 ) -> crate::Result<AndroidPermissions<R>> {
   #[cfg(target_os = "android")]
-  let handle = api.register_android_plugin("com.plugin.android-permissionscom.renteng.permissions", "ExamplePlugin")?;
+  let handle = api.register_android_plugin("com.renteng.permissions", "ExamplePlugin")?;
   #[cfg(target_os = "ios")]
   let handle = api.register_ios_plugin(init_plugin_android_permissions)?;
   Ok(AndroidPermissions(handle))
@@ -31,4 +31,47 @@ impl<R: Runtime> AndroidPermissions<R> {
       .run_mobile_plugin("ping", payload)
       .map_err(Into::into)
   }
+
+  pub fn save_to_media_store(
+    &self,
+    payload: SaveToMediaStoreRequest,
+  ) -> crate::Result<SaveToMediaStoreResponse> {
+    self
+      .0
+      .run_mobile_plugin("saveToMediaStore", payload)
+      .map_err(Into::into)
+  }
+
+  pub fn copy_from_media_store(
+    &self,
+    payload: CopyFromMediaStoreRequest,
+  ) -> crate::Result<CopyFromMediaStoreResponse> {
+    self
+      .0
+      .run_mobile_plugin("copyFromMediaStore", payload)
+      .map_err(Into::into)
+  }
+
+  pub fn read_clipboard(&self) -> crate::Result<ReadClipboardResponse> {
+    self
+      .0
+      .run_mobile_plugin("readClipboard", ())
+      .map_err(Into::into)
+  }
+
+  /// Reads a value encrypted with a non-exportable Android Keystore key.
+  pub fn load_secret(&self, payload: SecretRequest) -> crate::Result<LoadSecretResponse> {
+    self
+      .0
+      .run_mobile_plugin("loadSecret", payload)
+      .map_err(Into::into)
+  }
+
+  /// Stores (or removes, when `value` is `None`) a value in the Keystore-backed store.
+  pub fn store_secret(&self, payload: SecretRequest) -> crate::Result<()> {
+    self
+      .0
+      .run_mobile_plugin("storeSecret", payload)
+      .map_err(Into::into)
+  }
 }
@@ -11,3 +11,69 @@ pub struct PingRequest {
 pub struct PingResponse {
   pub value: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaCollection {
+  Movies,
+  Pictures,
+  Music,
+  Downloads,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveToMediaStoreRequest {
+  /// Absolute path of the already downloaded file inside the app sandbox.
+  pub source_path: String,
+  pub display_name: String,
+  pub mime_type: String,
+  pub collection: MediaCollection,
+  /// Sub folder created under the collection, e.g. `Movies/<folder>`.
+  pub folder: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveToMediaStoreResponse {
+  /// `content://` URI of the inserted MediaStore row.
+  pub uri: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFromMediaStoreRequest {
+  /// `content://` URI previously returned by `save_to_media_store`.
+  pub uri: String,
+  /// Absolute destination path inside the app sandbox.
+  pub target_path: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFromMediaStoreResponse {
+  /// Number of bytes copied.
+  pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadClipboardResponse {
+  /// Plain text of the primary clip, `None` when the clipboard is empty.
+  pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretRequest {
+  pub key: String,
+  /// Value to store; `None` removes the entry (ignored by `load_secret`).
+  pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadSecretResponse {
+  /// Decrypted value, `None` when nothing is stored under the key.
+  pub value: Option<String>,
+}